@@ -0,0 +1,56 @@
+use pulldown_cmark::{Options, Parser, html};
+
+use super::escape_html;
+
+/// Render Markdown (CommonMark with tables, strikethrough and task lists)
+/// into a single-column email layout that shrinks to the screen on mobile.
+pub(super) fn to_html(source: &str, title: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut content = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut content, Parser::new_ext(source, options));
+
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta http-equiv="Content-Type" content="text/html; charset=UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style type="text/css">
+body {{ margin:0; padding:0; -webkit-text-size-adjust:100%; -ms-text-size-adjust:100%; }}
+img {{ border:0; max-width:100%; height:auto; }}
+table {{ border-collapse:collapse; }}
+th, td {{ border:1px solid #dddddd; padding:6px 10px; }}
+pre {{ white-space:pre-wrap; background:#f4f4f4; padding:12px; }}
+blockquote {{ margin:0; padding-left:12px; border-left:3px solid #dddddd; color:#555555; }}
+</style>
+</head>
+<body style="margin:0;padding:0;background-color:#ffffff;">
+<table role="presentation" border="0" cellpadding="0" cellspacing="0" width="100%">
+<tr><td align="center">
+<div style="max-width:600px;margin:0 auto;padding:16px;text-align:left;font-family:Helvetica, Arial, sans-serif;font-size:16px;line-height:1.5;color:#222222;">
+{content}</div>
+</td></tr>
+</table>
+</body>
+</html>
+"#,
+        title = escape_html(title),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_extensions_inside_layout() {
+        let html = to_html("| a | b |\n|---|---|\n| 1 | 2 |\n\n~~old~~ new", "T");
+        assert!(html.contains("<table>"));
+        assert!(html.contains("<td>1</td>"));
+        assert!(html.contains("<del>old</del>"));
+        assert!(html.contains("max-width:600px"));
+        assert!(html.contains(r#"<meta name="viewport""#));
+    }
+}
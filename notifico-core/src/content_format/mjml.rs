@@ -0,0 +1,241 @@
+//! MJML compilation, backed by the `mrml` crate.
+//!
+//! `mrml` is a Rust port of the MJML reference compiler and covers every
+//! standard element. Like MJML's default validation level it is lenient
+//! about where elements are placed, so malformed markup and unknown `mj-*`
+//! elements (usually typos that `mrml` would pass through as HTML) are the
+//! errors. `mj-include` is not resolved: templates are self-contained.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use mrml::prelude::parser::{Error as ParseError, ParserOptions};
+use mrml::prelude::render::RenderOptions;
+
+/// Why MJML failed to compile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct MjmlError {
+    pub message: String,
+    pub line: Option<usize>,
+}
+
+/// Compile an MJML document to HTML.
+pub(super) fn to_html(source: &str) -> Result<String, MjmlError> {
+    // mrml rejects an XML declaration; blank it so positions stay the same
+    let source = blank_declaration(source);
+    let parsed = mrml::parse_with_options(&source, &ParserOptions::default())
+        .map_err(|e| parse_error(&source, e))?;
+    check_elements(&source)?;
+    parsed
+        .element
+        .render(&RenderOptions::default())
+        .map_err(|e| MjmlError {
+            message: e.to_string(),
+            line: None,
+        })
+}
+
+/// Elements `mrml` compiles; the `mj-attributes` children are included.
+const ELEMENTS: &[&str] = &[
+    "mj-accordion",
+    "mj-accordion-element",
+    "mj-accordion-text",
+    "mj-accordion-title",
+    "mj-all",
+    "mj-attributes",
+    "mj-body",
+    "mj-breakpoint",
+    "mj-button",
+    "mj-carousel",
+    "mj-carousel-image",
+    "mj-class",
+    "mj-column",
+    "mj-divider",
+    "mj-font",
+    "mj-group",
+    "mj-head",
+    "mj-hero",
+    "mj-image",
+    "mj-include",
+    "mj-navbar",
+    "mj-navbar-link",
+    "mj-preview",
+    "mj-raw",
+    "mj-section",
+    "mj-social",
+    "mj-social-element",
+    "mj-spacer",
+    "mj-style",
+    "mj-table",
+    "mj-text",
+    "mj-title",
+    "mj-wrapper",
+];
+
+fn check_elements(source: &str) -> Result<(), MjmlError> {
+    static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<(mj-[A-Za-z0-9-]+)").unwrap());
+    for caps in TAG.captures_iter(source) {
+        let name = &caps[1];
+        if !ELEMENTS.contains(&name) {
+            let start = caps.get(0).map_or(0, |m| m.start());
+            return Err(MjmlError {
+                message: format!("Unknown element <{name}>"),
+                line: Some(source[..start].matches('\n').count() + 1),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn blank_declaration(source: &str) -> String {
+    let start = source.len() - source.trim_start().len();
+    match source[start..]
+        .strip_prefix("<?xml")
+        .and_then(|rest| rest.find("?>"))
+    {
+        Some(end) => {
+            let end = start + "<?xml".len() + end + "?>".len();
+            let blank: String = source[start..end]
+                .chars()
+                .map(|c| if c == '\n' { c } else { ' ' })
+                .collect();
+            format!("{}{blank}{}", &source[..start], &source[end..])
+        }
+        None => source.to_string(),
+    }
+}
+
+fn parse_error(source: &str, e: ParseError) -> MjmlError {
+    let line_at = |pos: usize| Some(source[..pos.min(source.len())].matches('\n').count() + 1);
+    let (message, line) = match e {
+        ParseError::UnexpectedElement { position, .. } => {
+            ("Unexpected element".to_string(), line_at(position.start))
+        }
+        ParseError::UnexpectedToken { position, .. } => {
+            ("Unexpected markup".to_string(), line_at(position.start))
+        }
+        ParseError::MissingAttribute { name, position, .. } => (
+            format!("Missing attribute '{name}'"),
+            line_at(position.start),
+        ),
+        ParseError::InvalidAttribute { position, .. } => {
+            ("Invalid attribute".to_string(), line_at(position.start))
+        }
+        ParseError::InvalidFormat { position, .. } => {
+            ("Invalid value".to_string(), line_at(position.start))
+        }
+        ParseError::IncludeLoaderError { position, .. } => (
+            "<mj-include> is not supported".to_string(),
+            line_at(position.start),
+        ),
+        ParseError::ParserError { source, .. } => {
+            let line = Some(source.pos().row as usize);
+            (format!("Malformed markup: {source}"), line)
+        }
+        ParseError::EndOfStream { .. } => ("Unexpected end of document".to_string(), None),
+        ParseError::SizeLimit { .. } => ("Document is too large".to_string(), None),
+        ParseError::NoRootNode => ("Expected <mjml>".to_string(), None),
+    };
+    MjmlError { message, line }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(source: &str) -> String {
+        to_html(source).unwrap()
+    }
+
+    #[test]
+    fn compiles_sections_columns_and_content() {
+        let html = compile(
+            r##"<mjml>
+  <mj-head>
+    <mj-title>Welcome</mj-title>
+    <mj-preview>Your account is ready</mj-preview>
+    <mj-attributes>
+      <mj-all font-family="Arial" />
+      <mj-class name="muted" color="#888888" />
+    </mj-attributes>
+  </mj-head>
+  <mj-body background-color="#f0f0f0">
+    <mj-section background-color="#ffffff">
+      <mj-column>
+        <mj-image src="https://example.com/logo.png" alt="Logo" width="100px" href="https://example.com" />
+        <mj-text font-size="20px">Hi <b>Ann</b>,<br>welcome!</mj-text>
+        <mj-button href="https://example.com/start">Get started</mj-button>
+      </mj-column>
+      <mj-column width="200px">
+        <mj-text mj-class="muted">Side</mj-text>
+        <mj-divider border-width="1px" />
+        <mj-spacer height="10px" />
+      </mj-column>
+    </mj-section>
+  </mj-body>
+</mjml>"##,
+        );
+        assert!(html.contains("<title>Welcome</title>"), "{html}");
+        assert!(html.contains("Your account is ready"));
+        assert!(html.contains("background-color:#f0f0f0;"));
+        assert!(html.contains("mj-column-px-200"));
+        assert!(html.contains("@media only screen and (min-width:480px)"));
+        assert!(html.contains("Hi <b>Ann</b>,<br />welcome!"));
+        assert!(html.contains("color:#888888;"));
+        assert!(html.contains(r#"href="https://example.com/start""#));
+        assert!(html.contains("Get started"));
+        assert!(html.contains(r#"alt="Logo""#));
+        assert!(html.contains("height:10px;"));
+    }
+
+    #[test]
+    fn template_expressions_survive_compilation() {
+        let html = compile(
+            r#"<mjml><mj-body><mj-section><mj-column>
+               <mj-button href="{{ url }}">Hi {{ name }}</mj-button>
+               </mj-column></mj-section></mj-body></mjml>"#,
+        );
+        assert!(html.contains(r#"href="{{ url }}""#));
+        assert!(html.contains("Hi {{ name }}"));
+    }
+
+    #[test]
+    fn errors_have_lines() {
+        let cases = [
+            (
+                "<mjml><mj-body><mj-section><mj-column>\n<mj-text color=>x</mj-text></mj-column></mj-section></mj-body></mjml>",
+                Some(2),
+                "Malformed markup",
+            ),
+            (
+                "<mjml>\n<mj-body>\n<mj-section>",
+                None,
+                "Unexpected end of document",
+            ),
+            ("<html></html>", Some(1), "Unexpected markup"),
+            (
+                "<mjml><mj-body><mj-section>\n<mj-colum>\n</mj-colum></mj-section></mj-body></mjml>",
+                Some(2),
+                "Unknown element <mj-colum>",
+            ),
+        ];
+        for (source, line, message) in cases {
+            let e = to_html(source).unwrap_err();
+            assert_eq!(e.line, line, "{source}: {e:?}");
+            assert!(e.message.starts_with(message), "{source}: {e:?}");
+        }
+    }
+
+    #[test]
+    fn xml_declaration_is_skipped() {
+        let html = compile(
+            "<?xml version=\"1.0\"?>\n<!-- hi -->\n<mjml><mj-body><!-- c --><mj-section><mj-column><mj-text>ok</mj-text></mj-column></mj-section></mj-body></mjml>\n",
+        );
+        assert!(html.contains(">ok</div>"));
+
+        let e = to_html("<?xml version=\"1.0\"?>\n<mjml>\n<mj-body>\n<mj-text a=>x</mj-text>")
+            .unwrap_err();
+        assert_eq!(e.line, Some(4), "{e:?}");
+    }
+}
@@ -0,0 +1,312 @@
+//! Authoring formats for email template content.
+//!
+//! A template in the `html` format provides `html` and `text` directly. An
+//! `mjml` or `markdown` template instead provides its source in a field named
+//! after the format; after the template strings are rendered, the source is
+//! compiled to responsive `html`, and `text` is generated from it unless the
+//! content sets one.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::transport::{ContentField, ContentFieldType, ContentSchema};
+
+mod markdown;
+mod mjml;
+
+/// Width of the plain text generated from compiled HTML.
+const TEXT_WIDTH: usize = 80;
+
+/// How a template's content is authored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFormat {
+    /// `html`/`text` fields are sent as rendered.
+    #[default]
+    Html,
+    /// An `mjml` field compiled to responsive HTML.
+    Mjml,
+    /// A `markdown` field compiled to HTML in a responsive layout.
+    Markdown,
+}
+
+/// Compiling the source field of rendered content failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatError {
+    pub format: ContentFormat,
+    pub message: String,
+    /// 1-based line in the rendered source, when known.
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} line {line}: {}", self.format, self.message),
+            None => write!(f, "{}: {}", self.format, self.message),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl fmt::Display for ContentFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ContentFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "html" => Ok(Self::Html),
+            "mjml" => Ok(Self::Mjml),
+            "markdown" => Ok(Self::Markdown),
+            other => Err(format!(
+                "Unknown content format '{other}' (expected html, mjml or markdown)"
+            )),
+        }
+    }
+}
+
+impl ContentFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Mjml => "mjml",
+            Self::Markdown => "markdown",
+        }
+    }
+
+    /// Field holding the source to compile; `None` for `html`.
+    pub fn source_field(self) -> Option<&'static str> {
+        match self {
+            Self::Html => None,
+            Self::Mjml => Some("mjml"),
+            Self::Markdown => Some("markdown"),
+        }
+    }
+
+    /// Whether content for a channel with `schema` can use this format: the
+    /// compiled formats need a channel that takes an `html` field.
+    pub fn supports(self, schema: &ContentSchema) -> bool {
+        self == Self::Html || schema.fields.iter().any(|f| f.name == "html")
+    }
+
+    /// The fields content in this format must provide for a channel: the
+    /// source field is required, while `html` and `text` become optional
+    /// since they are generated.
+    pub fn content_schema(self, mut schema: ContentSchema) -> ContentSchema {
+        let Some(source) = self.source_field() else {
+            return schema;
+        };
+        for field in &mut schema.fields {
+            if field.name == "html" || field.name == "text" {
+                field.required = false;
+            }
+        }
+        schema.fields.insert(
+            0,
+            ContentField {
+                name: source.into(),
+                field_type: ContentFieldType::Text,
+                required: true,
+                description: match self {
+                    Self::Mjml => "MJML source, compiled to the HTML body".into(),
+                    _ => "Markdown source, compiled to the HTML body".into(),
+                },
+            },
+        );
+        schema
+    }
+
+    /// Compile the source field of rendered content into `html`, removing
+    /// the source, and generate `text` from the HTML when it is missing or
+    /// blank. Content in the `html` format is left as is.
+    pub fn compile(self, body: &mut Map<String, Value>) -> Result<(), FormatError> {
+        let Some(field) = self.source_field() else {
+            return Ok(());
+        };
+        let source = match body.remove(field) {
+            Some(Value::String(source)) => source,
+            Some(_) => return Err(self.error(format!("'{field}' must be a string"), None)),
+            None => return Err(self.error(format!("'{field}' is required"), None)),
+        };
+
+        let html = match self {
+            Self::Mjml => mjml::to_html(&source).map_err(|e| self.error(e.message, e.line))?,
+            _ => {
+                let title = body.get("subject").and_then(Value::as_str).unwrap_or("");
+                markdown::to_html(&source, title)
+            }
+        };
+
+        let has_text = matches!(body.get("text"), Some(Value::String(t)) if !t.trim().is_empty());
+        if !has_text {
+            // Email layouts are built from tables; borders would only add noise
+            let text = html2text::config::plain()
+                .no_table_borders()
+                .string_from_read(html.as_bytes(), TEXT_WIDTH)
+                .map_err(|e| self.error(format!("generating plain text: {e}"), None))?;
+            body.insert("text".into(), Value::String(text.trim_end().to_string()));
+        }
+        body.insert("html".into(), Value::String(html));
+        Ok(())
+    }
+
+    fn error(self, message: String, line: Option<usize>) -> FormatError {
+        FormatError {
+            format: self,
+            message,
+            line,
+        }
+    }
+}
+
+/// Escape text for use in HTML content and double-quoted attributes.
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn email_schema() -> ContentSchema {
+        ContentSchema {
+            fields: vec![
+                ContentField {
+                    name: "subject".into(),
+                    field_type: ContentFieldType::Text,
+                    required: true,
+                    description: String::new(),
+                },
+                ContentField {
+                    name: "text".into(),
+                    field_type: ContentFieldType::Text,
+                    required: true,
+                    description: String::new(),
+                },
+                ContentField {
+                    name: "html".into(),
+                    field_type: ContentFieldType::Html,
+                    required: false,
+                    description: String::new(),
+                },
+            ],
+        }
+    }
+
+    fn compile(format: ContentFormat, body: Value) -> Result<Map<String, Value>, FormatError> {
+        let Value::Object(mut body) = body else {
+            unreachable!()
+        };
+        format.compile(&mut body).map(|_| body)
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for format in [
+            ContentFormat::Html,
+            ContentFormat::Mjml,
+            ContentFormat::Markdown,
+        ] {
+            assert_eq!(format.to_string().parse::<ContentFormat>(), Ok(format));
+        }
+        assert!("docx".parse::<ContentFormat>().is_err());
+    }
+
+    #[test]
+    fn schema_requires_source_instead_of_text() {
+        let schema = ContentFormat::Markdown.content_schema(email_schema());
+        let errors = schema.validate(&json!({"subject": "Hi"}));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "markdown");
+        assert!(
+            schema
+                .validate(&json!({"subject": "Hi", "markdown": "# Hi"}))
+                .is_empty()
+        );
+
+        let html = ContentFormat::Html.content_schema(email_schema());
+        assert_eq!(html.validate(&json!({"subject": "Hi"}))[0].field, "text");
+    }
+
+    #[test]
+    fn compiled_formats_need_an_html_channel() {
+        assert!(ContentFormat::Mjml.supports(&email_schema()));
+        let sms = ContentSchema {
+            fields: vec![ContentField {
+                name: "text".into(),
+                field_type: ContentFieldType::Text,
+                required: true,
+                description: String::new(),
+            }],
+        };
+        assert!(!ContentFormat::Markdown.supports(&sms));
+        assert!(ContentFormat::Html.supports(&sms));
+    }
+
+    #[test]
+    fn markdown_compiles_to_html_and_text() {
+        let body = compile(
+            ContentFormat::Markdown,
+            json!({"subject": "Order <1>", "markdown": "# Shipped\n\nTrack it [here](https://t.example/1)."}),
+        )
+        .unwrap();
+        assert!(body.get("markdown").is_none());
+        let html = body["html"].as_str().unwrap();
+        assert!(html.contains("<title>Order &lt;1&gt;</title>"));
+        assert!(html.contains("<h1>Shipped</h1>"));
+        assert!(html.contains(r#"<a href="https://t.example/1">here</a>"#));
+        let text = body["text"].as_str().unwrap();
+        assert!(text.contains("Shipped"));
+        assert!(text.contains("https://t.example/1"));
+    }
+
+    #[test]
+    fn authored_text_is_kept() {
+        let body = compile(
+            ContentFormat::Markdown,
+            json!({"markdown": "**Hi**", "text": "Hi (plain)"}),
+        )
+        .unwrap();
+        assert_eq!(body["text"], "Hi (plain)");
+    }
+
+    #[test]
+    fn mjml_errors_carry_format_and_line() {
+        let err = compile(
+            ContentFormat::Mjml,
+            json!({"mjml": "<mjml>\n<mj-body>\n<mj-colum></mj-colum>\n</mj-body>\n</mjml>"}),
+        )
+        .unwrap_err();
+        assert_eq!(err.line, Some(3));
+        assert!(err.to_string().starts_with("mjml line 3: "), "{err}");
+
+        let err = compile(ContentFormat::Mjml, json!({"subject": "x"})).unwrap_err();
+        assert_eq!(err.to_string(), "mjml: 'mjml' is required");
+    }
+
+    #[test]
+    fn html_format_is_untouched() {
+        let body = compile(ContentFormat::Html, json!({"html": "<b>x</b>"})).unwrap();
+        assert_eq!(body, *json!({"html": "<b>x</b>"}).as_object().unwrap());
+    }
+}
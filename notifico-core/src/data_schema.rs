@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// JSON Schema declared for an event's `data`, checked at ingest time.
+#[derive(Debug)]
+pub struct DataSchema {
+    schema: Value,
+    validator: jsonschema::Validator,
+}
+
+/// A value in event data that does not match the event's schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataError {
+    /// JSON Pointer to the offending value, e.g. `/items/0/sku`; empty for
+    /// the data root.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl DataSchema {
+    /// Compile a schema. Fails if it is not a valid JSON Schema document.
+    pub fn compile(schema: &Value) -> Result<Self, String> {
+        let validator = jsonschema::validator_for(schema).map_err(|e| e.to_string())?;
+        Ok(Self {
+            schema: schema.clone(),
+            validator,
+        })
+    }
+
+    /// Validate event data, returning every violation found.
+    pub fn validate(&self, data: &Value) -> Vec<DataError> {
+        self.validator
+            .iter_errors(data)
+            .map(|e| DataError {
+                pointer: e.instance_path.as_str().to_string(),
+                message: e.to_string(),
+            })
+            .collect()
+    }
+
+    /// Whether a dotted variable path such as `order.total` is declared.
+    ///
+    /// Each segment must be listed under `properties` of the enclosing
+    /// object schema; once a level declares no `properties` (free-form
+    /// objects, arrays, scalars), anything below it is accepted.
+    pub fn defines(&self, path: &str) -> bool {
+        let mut node = &self.schema;
+        for segment in path.split('.') {
+            let Some(properties) = node.get("properties").and_then(Value::as_object) else {
+                return true;
+            };
+            match properties.get(segment) {
+                Some(next) => node = next,
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Compiled event schemas keyed by event ID, so ingest compiles a schema
+/// once rather than on every request. An entry is only reused while the
+/// event's schema is unchanged.
+///
+/// Cheap to clone; clones share the entries.
+#[derive(Debug, Clone, Default)]
+pub struct DataSchemaCache {
+    entries: Arc<Mutex<HashMap<Uuid, Arc<DataSchema>>>>,
+}
+
+impl DataSchemaCache {
+    /// The compiled form of `schema`, the current schema of `event_id`.
+    pub fn get_or_compile(
+        &self,
+        event_id: Uuid,
+        schema: &Value,
+    ) -> Result<Arc<DataSchema>, String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = entries.get(&event_id)
+            && cached.schema == *schema
+        {
+            return Ok(cached.clone());
+        }
+        let compiled = Arc::new(DataSchema::compile(schema)?);
+        entries.insert(event_id, compiled.clone());
+        Ok(compiled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn order_schema() -> DataSchema {
+        DataSchema::compile(&json!({
+            "type": "object",
+            "required": ["order_id"],
+            "properties": {
+                "order_id": {"type": "integer"},
+                "customer": {
+                    "type": "object",
+                    "properties": {"name": {"type": "string"}}
+                },
+                "meta": {"type": "object"},
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"sku": {"type": "string"}}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn valid_data_has_no_errors() {
+        let schema = order_schema();
+        let errors = schema.validate(&json!({"order_id": 1, "items": [{"sku": "A-1"}]}));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn errors_point_at_offending_values() {
+        let schema = order_schema();
+        let mut errors = schema.validate(&json!({"orderId": 1, "items": [{"sku": 5}]}));
+        errors.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+        let pointers: Vec<_> = errors.iter().map(|e| e.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["", "/items/0/sku"]);
+        assert!(errors[0].message.contains("order_id"));
+    }
+
+    #[test]
+    fn invalid_schema_is_rejected() {
+        assert!(DataSchema::compile(&json!({"type": "no-such-type"})).is_err());
+    }
+
+    #[test]
+    fn defines_follows_nested_properties() {
+        let schema = order_schema();
+        assert!(schema.defines("order_id"));
+        assert!(schema.defines("customer.name"));
+        assert!(!schema.defines("orderId"));
+        assert!(!schema.defines("customer.email"));
+        // Levels without `properties` accept anything below them
+        assert!(schema.defines("meta.source"));
+        assert!(schema.defines("items.length"));
+    }
+
+    #[test]
+    fn cache_recompiles_only_when_schema_changes() {
+        let cache = DataSchemaCache::default();
+        let event_id = Uuid::now_v7();
+        let v1 = json!({"type": "object", "required": ["a"]});
+
+        let first = cache.get_or_compile(event_id, &v1).unwrap();
+        assert!(Arc::ptr_eq(
+            &first,
+            &cache.get_or_compile(event_id, &v1).unwrap()
+        ));
+
+        let v2 = json!({"type": "object", "required": ["b"]});
+        let second = cache.get_or_compile(event_id, &v2).unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert!(
+            second.validate(&json!({"a": 1}))[0]
+                .message
+                .contains("\"b\"")
+        );
+        assert!(cache.get_or_compile(event_id, &json!({"type": 5})).is_err());
+    }
+}
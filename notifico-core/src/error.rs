@@ -16,4 +16,7 @@ pub enum CoreError {
 
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
 }
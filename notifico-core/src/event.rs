@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use uuid::Uuid;
 
 /// Notification category determines unsubscribe rules and delivery behavior.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +26,31 @@ pub struct IngestEvent {
     /// Optional idempotency key
     #[serde(default)]
     pub idempotency_key: Option<String>,
+    /// Files to attach to the message (channels without attachment support
+    /// ignore them)
+    #[serde(default)]
+    pub attachments: Vec<EventAttachment>,
+}
+
+/// Attachment on an ingest event: either base64 `content` sent inline, or
+/// the `id` of a file uploaded beforehand via `POST /api/v1/attachments`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventAttachment {
+    /// ID of a previously uploaded attachment
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    /// File name (required for inline content, overrides the uploaded name)
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// MIME type (guessed from the file name if omitted)
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Base64-encoded file content
+    #[serde(default)]
+    pub content: Option<String>,
+    /// Content-ID for inline images, referenced from HTML as `cid:<content_id>`
+    #[serde(default)]
+    pub content_id: Option<String>,
 }
 
 /// Recipient within an ingest event.
@@ -92,6 +118,25 @@ mod tests {
         assert_eq!(event.idempotency_key.as_deref(), Some("abc-123"));
     }
 
+    #[test]
+    fn ingest_event_with_attachments() {
+        let json = r#"{
+            "event": "invoice.ready",
+            "recipients": [{"id": "u-1"}],
+            "data": {},
+            "attachments": [
+                {"filename": "invoice.pdf", "content": "JVBERi0xLjQ="},
+                {"id": "0195b4a0-0000-7000-8000-000000000001", "content_id": "logo"}
+            ]
+        }"#;
+        let event: IngestEvent = serde_json::from_str(json).unwrap();
+        assert_eq!(event.attachments.len(), 2);
+        assert_eq!(event.attachments[0].filename.as_deref(), Some("invoice.pdf"));
+        assert!(event.attachments[0].id.is_none());
+        assert!(event.attachments[1].id.is_some());
+        assert_eq!(event.attachments[1].content_id.as_deref(), Some("logo"));
+    }
+
     #[test]
     fn event_recipient_empty_contacts() {
         let json = r#"{"id": "user-456"}"#;
@@ -1,8 +1,15 @@
 pub mod channel;
+pub mod content_format;
+pub mod data_schema;
 pub mod error;
 pub mod event;
+pub mod experiment;
+pub mod locale;
 pub mod middleware;
 pub mod pipeline;
 pub mod recipient;
 pub mod registry;
+pub mod sigv4;
+pub mod storage;
+pub mod tracking;
 pub mod transport;
@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Per-project locale fallback settings, stored under `locale_fallback` in
+/// the project's `settings` JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocaleFallbackConfig {
+    /// Extra locales to try right after a given locale, before its parent,
+    /// e.g. `{"pt-BR": ["pt-PT"]}` tries `pt-BR`, `pt-PT`, then `pt`.
+    #[serde(default)]
+    pub overrides: BTreeMap<String, Vec<String>>,
+    /// As a last resort, use any locale the template has content for.
+    #[serde(default = "default_any")]
+    pub any: bool,
+}
+
+fn default_any() -> bool {
+    true
+}
+
+impl Default for LocaleFallbackConfig {
+    fn default() -> Self {
+        Self {
+            overrides: BTreeMap::new(),
+            any: default_any(),
+        }
+    }
+}
+
+impl LocaleFallbackConfig {
+    /// Read the config from project settings, using defaults when absent or invalid.
+    pub fn from_settings(settings: &serde_json::Value) -> Self {
+        settings
+            .get("locale_fallback")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Ordered locales to try when resolving localized content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleChain {
+    /// Normalized BCP-47 tags, most specific first, without duplicates.
+    pub locales: Vec<String>,
+    /// Whether any available locale is acceptable once the chain is exhausted.
+    pub any: bool,
+}
+
+impl LocaleChain {
+    /// Pick the best match from the available locales (compared after
+    /// normalization). Returns the available locale as given.
+    pub fn select<'a, I>(&self, available: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut available: Vec<&str> = available.into_iter().collect();
+        available.sort_unstable();
+
+        self.locales
+            .iter()
+            .find_map(|wanted| available.iter().find(|a| normalize(a) == *wanted).copied())
+            .or_else(|| {
+                if self.any {
+                    available.first().copied()
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+/// Canonical casing for a BCP-47 tag: `pt_br` → `pt-BR`, `zh-hant-tw` →
+/// `zh-Hant-TW`. Subtags after a singleton (extensions, private use) are
+/// lowercased.
+pub fn normalize(tag: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut in_extension = false;
+
+    for (i, subtag) in tag
+        .trim()
+        .split(['-', '_'])
+        .filter(|s| !s.is_empty())
+        .enumerate()
+    {
+        let formatted = if i == 0 || in_extension {
+            subtag.to_ascii_lowercase()
+        } else if subtag.len() == 1 {
+            in_extension = true;
+            subtag.to_ascii_lowercase()
+        } else if subtag.len() == 4 && subtag.chars().all(|c| c.is_ascii_alphabetic()) {
+            let mut s = subtag.to_ascii_lowercase();
+            s[..1].make_ascii_uppercase();
+            s
+        } else if subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()) {
+            subtag.to_ascii_uppercase()
+        } else {
+            subtag.to_ascii_lowercase()
+        };
+        out.push(formatted);
+    }
+
+    out.join("-")
+}
+
+/// RFC 4647 lookup truncation: the tag itself, then each parent obtained by
+/// dropping the last subtag (and a singleton left dangling before it).
+/// `zh-Hant-TW` → `zh-Hant-TW`, `zh-Hant`, `zh`.
+pub fn truncations(tag: &str) -> Vec<String> {
+    let tag = normalize(tag);
+    let mut subtags: Vec<&str> = tag.split('-').filter(|s| !s.is_empty()).collect();
+    let mut result = Vec::new();
+
+    while !subtags.is_empty() {
+        result.push(subtags.join("-"));
+        subtags.pop();
+        if subtags.len() > 1 && subtags.last().is_some_and(|s| s.len() == 1) {
+            subtags.pop();
+        }
+    }
+
+    result
+}
+
+/// Build the fallback chain for a recipient locale: the locale and its
+/// parents (with per-project overrides spliced in), then each default
+/// locale and its parents.
+pub fn fallback_chain(
+    locale: &str,
+    defaults: &[&str],
+    config: &LocaleFallbackConfig,
+) -> LocaleChain {
+    let overrides: BTreeMap<String, &Vec<String>> = config
+        .overrides
+        .iter()
+        .map(|(k, v)| (normalize(k), v))
+        .collect();
+
+    let mut locales: Vec<String> = Vec::new();
+    let mut push = |tag: String| {
+        if !tag.is_empty() && !locales.contains(&tag) {
+            locales.push(tag);
+        }
+    };
+
+    for tag in truncations(locale) {
+        let extra = overrides.get(&tag);
+        push(tag);
+        for o in extra.into_iter().flat_map(|v| v.iter()) {
+            push(normalize(o));
+        }
+    }
+    for default in defaults {
+        for tag in truncations(default) {
+            push(tag);
+        }
+    }
+
+    LocaleChain {
+        locales,
+        any: config.any,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn normalize_casing_and_separators() {
+        assert_eq!(normalize("pt_br"), "pt-BR");
+        assert_eq!(normalize("ZH-hant-tw"), "zh-Hant-TW");
+        assert_eq!(normalize("es-419"), "es-419");
+        assert_eq!(normalize("en-US-x-Twain"), "en-US-x-twain");
+        assert_eq!(normalize("sl-rozaj-biske"), "sl-rozaj-biske");
+    }
+
+    #[test]
+    fn truncation_drops_region_script_and_singletons() {
+        assert_eq!(truncations("pt-BR"), vec!["pt-BR", "pt"]);
+        assert_eq!(
+            truncations("zh-Hant-TW"),
+            vec!["zh-Hant-TW", "zh-Hant", "zh"]
+        );
+        assert_eq!(truncations("sr-Latn"), vec!["sr-Latn", "sr"]);
+        assert_eq!(
+            truncations("en-US-x-twain"),
+            vec!["en-US-x-twain", "en-US", "en"]
+        );
+        assert_eq!(truncations("de"), vec!["de"]);
+        assert!(truncations("").is_empty());
+    }
+
+    #[test]
+    fn chain_appends_defaults_and_dedups() {
+        let chain = fallback_chain("pt-BR", &["en-GB", "en"], &LocaleFallbackConfig::default());
+        assert_eq!(chain.locales, vec!["pt-BR", "pt", "en-GB", "en"]);
+        assert!(chain.any);
+
+        let chain = fallback_chain("en-US", &["en"], &LocaleFallbackConfig::default());
+        assert_eq!(chain.locales, vec!["en-US", "en"]);
+    }
+
+    #[test]
+    fn chain_applies_overrides() {
+        let config = LocaleFallbackConfig::from_settings(&json!({
+            "locale_fallback": {"overrides": {"pt_br": ["pt-PT"], "zh-Hant": ["zh-TW"]}, "any": false}
+        }));
+        assert!(!config.any);
+
+        let chain = fallback_chain("pt-BR", &["en"], &config);
+        assert_eq!(chain.locales, vec!["pt-BR", "pt-PT", "pt", "en"]);
+
+        let chain = fallback_chain("zh-Hant-HK", &["en"], &config);
+        assert_eq!(
+            chain.locales,
+            vec!["zh-Hant-HK", "zh-Hant", "zh-TW", "zh", "en"]
+        );
+    }
+
+    #[test]
+    fn select_prefers_chain_order_then_any() {
+        let chain = fallback_chain("pt-BR", &["en"], &LocaleFallbackConfig::default());
+        assert_eq!(chain.select(["en", "pt", "de"]), Some("pt"));
+        assert_eq!(chain.select(["en", "pt_br"]), Some("pt_br"));
+        assert_eq!(chain.select(["fr", "de"]), Some("de"));
+        assert_eq!(chain.select([]), None);
+
+        let strict = LocaleChain {
+            any: false,
+            ..chain
+        };
+        assert_eq!(strict.select(["fr", "de"]), None);
+    }
+
+    #[test]
+    fn config_defaults_when_missing_or_invalid() {
+        assert_eq!(
+            LocaleFallbackConfig::from_settings(&json!({})),
+            LocaleFallbackConfig::default()
+        );
+        assert_eq!(
+            LocaleFallbackConfig::from_settings(&json!({"locale_fallback": 5})),
+            LocaleFallbackConfig::default()
+        );
+    }
+}
@@ -1,17 +1,27 @@
 use async_trait::async_trait;
-use base64::Engine;
-use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use regex::Regex;
 use serde_json::Value;
 
 use crate::error::CoreError;
 use crate::pipeline::PipelineOutput;
+use crate::tracking::create_tracking_token;
 
 use super::Middleware;
 
 /// Post-render middleware that rewrites URLs in HTML content with click-tracking
 /// redirect URLs.
-pub struct ClickTrackingMiddleware;
+///
+/// Redirect tokens are signed with the server key so the endpoint only
+/// redirects to URLs it rewrote; without a key links are left as they are.
+pub struct ClickTrackingMiddleware {
+    key: Option<[u8; 32]>,
+}
+
+impl ClickTrackingMiddleware {
+    pub fn new(key: Option<[u8; 32]>) -> Self {
+        Self { key }
+    }
+}
 
 #[async_trait]
 impl Middleware for ClickTrackingMiddleware {
@@ -31,10 +41,14 @@ impl Middleware for ClickTrackingMiddleware {
         if base_url.is_empty() {
             return Ok(());
         }
+        let Some(key) = &self.key else {
+            tracing::warn!("Click tracking needs an encryption key, skipping");
+            return Ok(());
+        };
 
         if let Value::Object(ref mut map) = output.rendered_body {
             if let Some(Value::String(html)) = map.get_mut("html") {
-                let delivery_id = output.id;
+                let delivery_id = output.id.to_string();
                 let base = base_url.to_string();
                 let re = Regex::new(r#"href="([^"]+)""#).unwrap();
                 let new_html = re
@@ -43,8 +57,7 @@ impl Middleware for ClickTrackingMiddleware {
                         if should_skip_url(url) {
                             return caps[0].to_string();
                         }
-                        let token =
-                            URL_SAFE_NO_PAD.encode(format!("{}:{}", delivery_id, url));
+                        let token = create_tracking_token(&delivery_id, Some(url), key);
                         format!(r#"href="{}/t/click/{}""#, base, token)
                     })
                     .to_string();
@@ -80,12 +93,13 @@ mod tests {
             contact_value: "user@example.com".into(),
             idempotency_key: None,
             max_attempts: 3,
+            stylesheets: Default::default(),
         }
     }
 
     #[tokio::test]
     async fn click_tracking_rewrites_urls() {
-        let mw = ClickTrackingMiddleware;
+        let mw = ClickTrackingMiddleware::new(Some([7; 32]));
         let config = json!({"base_url": "https://track.example.com"});
         let mut output = make_output(json!({
             "html": r#"<a href="https://example.com/page">Link</a>"#
@@ -96,11 +110,21 @@ mod tests {
         let html = output.rendered_body["html"].as_str().unwrap();
         assert!(html.contains("https://track.example.com/t/click/"));
         assert!(!html.contains("https://example.com/page"));
+
+        // The signed token carries the task ID and the original URL
+        let token = html
+            .split("/t/click/")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap();
+        let (delivery_id, url) = crate::tracking::verify_tracking_token(token, &[7; 32]).unwrap();
+        assert_eq!(delivery_id, output.id.to_string());
+        assert_eq!(url.as_deref(), Some("https://example.com/page"));
     }
 
     #[tokio::test]
     async fn click_tracking_skips_mailto() {
-        let mw = ClickTrackingMiddleware;
+        let mw = ClickTrackingMiddleware::new(Some([7; 32]));
         let config = json!({"base_url": "https://track.example.com"});
         let mut output = make_output(json!({
             "html": "<a href=\"mailto:test@example.com\">Email</a> <a href=\"tel:+1234\">Call</a> <a href=\"#top\">Top</a>"
@@ -116,7 +140,7 @@ mod tests {
 
     #[tokio::test]
     async fn click_tracking_noop_without_base_url() {
-        let mw = ClickTrackingMiddleware;
+        let mw = ClickTrackingMiddleware::new(Some([7; 32]));
         let config = json!({});
         let mut output = make_output(json!({
             "html": r#"<a href="https://example.com">Link</a>"#
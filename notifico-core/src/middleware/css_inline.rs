@@ -0,0 +1,727 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use async_trait::async_trait;
+use cssparser::{
+    AtRuleParser, CowRcStr, DeclarationParser, ParseError, ParserInput, ParserState,
+    QualifiedRuleParser, RuleBodyItemParser, RuleBodyParser, StyleSheetParser, Token,
+};
+use html5ever::{LocalName, QualName, ns};
+use scraper::node::Text;
+use scraper::{Html, Node, Selector};
+use selectors::parser::{ParseRelative, SelectorList};
+use serde_json::Value;
+
+use crate::error::CoreError;
+use crate::pipeline::PipelineOutput;
+
+use super::Middleware;
+
+/// Post-render middleware that inlines CSS into `style` attributes of the
+/// `html` field, since many email clients strip `<style>` blocks.
+///
+/// CSS comes from `<style>` tags and from `<link rel="stylesheet">` tags
+/// whose `href` names one of the project's stylesheets (partials of kind
+/// `stylesheet`); other links are left alone. Rules that cannot be inlined
+/// (`@media` and other at-rules, `:hover` and other dynamic pseudo-classes,
+/// pseudo-elements) are kept in a single `<style>` in the head.
+///
+/// Config:
+/// - `preserve_media_queries` (default `true`): keep `@media` rules;
+///   `false` drops them
+/// - `remove_unused_classes` (default `false`): drop class names the
+///   remaining CSS no longer references
+pub struct CssInlineMiddleware;
+
+#[async_trait]
+impl Middleware for CssInlineMiddleware {
+    fn name(&self) -> &str {
+        "css_inline"
+    }
+
+    async fn post_render(
+        &self,
+        output: &mut PipelineOutput,
+        config: &Value,
+    ) -> Result<(), CoreError> {
+        if let Value::Object(ref mut map) = output.rendered_body
+            && let Some(Value::String(html)) = map.get_mut("html")
+            && let Some(inlined) =
+                inline_css(html, &output.stylesheets, &Options::from_config(config))
+        {
+            *html = inlined;
+        }
+        Ok(())
+    }
+}
+
+struct Options {
+    preserve_media_queries: bool,
+    remove_unused_classes: bool,
+}
+
+impl Options {
+    fn from_config(config: &Value) -> Self {
+        let flag = |name: &str, default: bool| {
+            config
+                .get(name)
+                .and_then(|v| v.as_bool())
+                .unwrap_or(default)
+        };
+        Self {
+            preserve_media_queries: flag("preserve_media_queries", true),
+            remove_unused_classes: flag("remove_unused_classes", false),
+        }
+    }
+}
+
+/// Inline the document's CSS, resolving linked stylesheets by `href`
+/// against `stylesheets`. Returns `None` when there is nothing to inline,
+/// leaving the HTML byte-for-byte unchanged.
+fn inline_css(
+    source: &str,
+    stylesheets: &BTreeMap<String, String>,
+    options: &Options,
+) -> Option<String> {
+    let lower = source.to_ascii_lowercase();
+    if !lower.contains("<style") && !lower.contains("<link") {
+        return None;
+    }
+    let is_document = lower.contains("<html");
+    let mut html = if is_document {
+        Html::parse_document(source)
+    } else {
+        Html::parse_fragment(source)
+    };
+
+    // Gather CSS in document order, noting the tags it came from
+    let mut css = String::new();
+    let mut sources = Vec::new();
+    for node in html.tree.root().descendants() {
+        let Node::Element(element) = node.value() else {
+            continue;
+        };
+        match element.name() {
+            "style" => {
+                for child in node.children() {
+                    if let Node::Text(text) = child.value() {
+                        css.push_str(&text.text);
+                    }
+                }
+                css.push('\n');
+                sources.push(node.id());
+            }
+            "link"
+                if element
+                    .attr("rel")
+                    .is_some_and(|rel| rel.eq_ignore_ascii_case("stylesheet")) =>
+            {
+                if let Some(sheet) = element.attr("href").and_then(|h| stylesheets.get(h)) {
+                    css.push_str(sheet);
+                    css.push('\n');
+                    sources.push(node.id());
+                }
+            }
+            _ => {}
+        }
+    }
+    if sources.is_empty() {
+        return None;
+    }
+
+    let sheet = parse_stylesheet(&css, options.preserve_media_queries);
+
+    // Winning declaration per element and property
+    let mut styles: HashMap<_, BTreeMap<String, Declared>> = HashMap::new();
+    let mut order = 0usize;
+    for rule in &sheet.rules {
+        for element in html.select(&rule.selector) {
+            let entry = styles.entry(element.id()).or_default();
+            for decl in &rule.declarations {
+                order += 1;
+                let declared = Declared {
+                    rank: (decl.important, false, rule.specificity, order),
+                    value: decl.value.clone(),
+                };
+                entry
+                    .entry(decl.property.clone())
+                    .and_modify(|d| {
+                        if declared.rank > d.rank {
+                            *d = declared.clone();
+                        }
+                    })
+                    .or_insert(declared);
+            }
+        }
+    }
+
+    for (id, mut declared) in styles {
+        let mut node = html.tree.get_mut(id)?;
+        let Node::Element(element) = node.value() else {
+            continue;
+        };
+        // Inline styles beat the stylesheet unless it says !important
+        if let Some(inline) = element.attr("style") {
+            for decl in parse_declarations(inline) {
+                order += 1;
+                let declared_inline = Declared {
+                    rank: (decl.important, true, 0, order),
+                    value: decl.value,
+                };
+                declared
+                    .entry(decl.property)
+                    .and_modify(|d| {
+                        if declared_inline.rank > d.rank {
+                            *d = declared_inline.clone();
+                        }
+                    })
+                    .or_insert(declared_inline);
+            }
+        }
+        let mut ordered: Vec<_> = declared.into_iter().collect();
+        ordered.sort_by_key(|(_, d)| d.rank.3);
+        let style: String = ordered
+            .iter()
+            .map(|(property, d)| format!("{property}:{};", d.value))
+            .collect();
+        set_attr(element, "style", &style);
+    }
+
+    if options.remove_unused_classes {
+        let used = referenced_classes(&sheet.retained);
+        let ids: Vec<_> = html.tree.nodes().map(|n| n.id()).collect();
+        for id in ids {
+            let mut node = html.tree.get_mut(id)?;
+            let Node::Element(element) = node.value() else {
+                continue;
+            };
+            let Some(class) = element.attr("class") else {
+                continue;
+            };
+            let kept: Vec<&str> = class
+                .split_whitespace()
+                .filter(|c| used.contains(*c))
+                .collect();
+            if kept.is_empty() {
+                element.attrs.retain(|(name, _)| &*name.local != "class");
+            } else {
+                set_attr(element, "class", &kept.join(" "));
+            }
+        }
+    }
+
+    // Replace the sources with one <style> holding what was not inlined
+    let head = html
+        .tree
+        .root()
+        .descendants()
+        .find(|n| matches!(n.value(), Node::Element(e) if e.name() == "head"))
+        .map(|n| n.id());
+    for id in sources {
+        html.tree.get_mut(id)?.detach();
+    }
+    let retained = sheet.retained.trim();
+    if !retained.is_empty() {
+        let parent = match head {
+            Some(head) => head,
+            None => html.root_element().id(),
+        };
+        let mut parent = html.tree.get_mut(parent)?;
+        let mut style = if head.is_some() {
+            parent.append(style_element())
+        } else {
+            parent.prepend(style_element())
+        };
+        style.append(Node::Text(Text {
+            text: retained.into(),
+        }));
+    }
+
+    Some(if is_document {
+        html.html()
+    } else {
+        html.root_element().inner_html()
+    })
+}
+
+#[derive(Clone)]
+struct Declared {
+    /// Important, inline, specificity, source order: the highest wins.
+    rank: (bool, bool, u32, usize),
+    value: String,
+}
+
+/// An empty `<style>` element; scraper has no public constructor for one.
+fn style_element() -> Node {
+    Html::parse_fragment("<style></style>")
+        .tree
+        .nodes()
+        .find(|n| matches!(n.value(), Node::Element(e) if e.name() == "style"))
+        .map(|n| n.value().clone())
+        .expect("style element parses")
+}
+
+fn set_attr(element: &mut scraper::node::Element, name: &str, value: &str) {
+    match element.attrs.iter_mut().find(|(n, _)| &*n.local == name) {
+        Some((_, v)) => *v = value.into(),
+        None => element.attrs.push((
+            QualName::new(None, ns!(), LocalName::from(name)),
+            value.into(),
+        )),
+    }
+}
+
+// ── CSS ─────────────────────────────────────────────────────────────
+//
+// Tokenizing is left to cssparser and selector matching to `selectors`
+// (through scraper), so strings, comments, escapes and nested blocks are
+// read the way browsers read them.
+
+#[derive(Default)]
+struct Stylesheet {
+    /// Inlinable rules, one selector each.
+    rules: Vec<Rule>,
+    /// CSS that stays in a `<style>` block.
+    retained: String,
+}
+
+struct Rule {
+    selector: Selector,
+    specificity: u32,
+    declarations: Vec<Declaration>,
+}
+
+#[derive(Clone)]
+struct Declaration {
+    property: String,
+    value: String,
+    important: bool,
+}
+
+fn parse_stylesheet(css: &str, preserve_media_queries: bool) -> Stylesheet {
+    let mut input = ParserInput::new(css);
+    let mut input = cssparser::Parser::new(&mut input);
+    let mut sheet = Stylesheet::default();
+    for rule in StyleSheetParser::new(&mut input, &mut TopLevelParser).flatten() {
+        match rule {
+            CssRule::At { media, text } => {
+                if !media || preserve_media_queries {
+                    sheet.retained.push_str(&text);
+                    sheet.retained.push('\n');
+                }
+            }
+            CssRule::Style {
+                selectors,
+                body,
+                declarations,
+            } => {
+                let mut kept = Vec::new();
+                for selector in selectors.into_iter().filter(|s| !s.is_empty()) {
+                    match inlinable(selector) {
+                        Some((selector, specificity)) => sheet.rules.push(Rule {
+                            selector,
+                            specificity,
+                            declarations: declarations.clone(),
+                        }),
+                        None => kept.push(selector),
+                    }
+                }
+                if !kept.is_empty() {
+                    sheet
+                        .retained
+                        .push_str(&format!("{} {{{body}}}\n", kept.join(", ")));
+                }
+            }
+        }
+    }
+    sheet
+}
+
+/// A selector that matches statically, so its declarations can be
+/// written into `style` attributes, and its specificity. scraper's
+/// selector grammar has no dynamic pseudo-classes (`:hover`) and no
+/// pseudo-elements, so those fail to parse and stay in the stylesheet.
+fn inlinable(selector: &str) -> Option<(Selector, u32)> {
+    let mut input = ParserInput::new(selector);
+    let list = SelectorList::parse(
+        &scraper::selector::Parser,
+        &mut cssparser::Parser::new(&mut input),
+        ParseRelative::No,
+    )
+    .ok()?;
+    let specificity = list.slice().iter().map(|s| s.specificity()).max()?;
+    Some((Selector::parse(selector).ok()?, specificity))
+}
+
+fn parse_declarations(css: &str) -> Vec<Declaration> {
+    let mut input = ParserInput::new(css);
+    let mut input = cssparser::Parser::new(&mut input);
+    parse_declaration_list(&mut input)
+}
+
+fn parse_declaration_list(input: &mut cssparser::Parser) -> Vec<Declaration> {
+    RuleBodyParser::new(input, &mut DeclarationListParser)
+        .flatten()
+        .collect()
+}
+
+/// The rest of `input` as written, with leading and trailing whitespace
+/// removed.
+fn remaining<'i>(input: &mut cssparser::Parser<'i, '_>) -> &'i str {
+    let start = input.position();
+    while input.next().is_ok() {}
+    input.slice_from(start).trim()
+}
+
+enum CssRule<'i> {
+    Style {
+        /// Selectors as written, comma-separated in the source.
+        selectors: Vec<&'i str>,
+        /// The declaration block as written, without braces.
+        body: &'i str,
+        declarations: Vec<Declaration>,
+    },
+    /// An at-rule, kept as written.
+    At { media: bool, text: String },
+}
+
+struct TopLevelParser;
+
+impl<'i> QualifiedRuleParser<'i> for TopLevelParser {
+    type Prelude = Vec<&'i str>;
+    type QualifiedRule = CssRule<'i>;
+    type Error = ();
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut cssparser::Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, ()>> {
+        input.parse_comma_separated(|input| Ok(remaining(input)))
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        selectors: Self::Prelude,
+        _start: &ParserState,
+        input: &mut cssparser::Parser<'i, 't>,
+    ) -> Result<CssRule<'i>, ParseError<'i, ()>> {
+        let start = input.position();
+        let declarations = parse_declaration_list(input);
+        Ok(CssRule::Style {
+            selectors,
+            body: input.slice_from(start).trim(),
+            declarations,
+        })
+    }
+}
+
+impl<'i> AtRuleParser<'i> for TopLevelParser {
+    type Prelude = (CowRcStr<'i>, &'i str);
+    type AtRule = CssRule<'i>;
+    type Error = ();
+
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut cssparser::Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, ()>> {
+        Ok((name, remaining(input)))
+    }
+
+    fn rule_without_block(
+        &mut self,
+        (name, prelude): Self::Prelude,
+        _start: &ParserState,
+    ) -> Result<CssRule<'i>, ()> {
+        Ok(CssRule::At {
+            media: false,
+            text: format!("@{name} {prelude};"),
+        })
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        (name, prelude): Self::Prelude,
+        _start: &ParserState,
+        input: &mut cssparser::Parser<'i, 't>,
+    ) -> Result<CssRule<'i>, ParseError<'i, ()>> {
+        Ok(CssRule::At {
+            media: name.eq_ignore_ascii_case("media"),
+            text: format!("@{name} {prelude} {{{}}}", remaining(input)),
+        })
+    }
+}
+
+struct DeclarationListParser;
+
+impl<'i> DeclarationParser<'i> for DeclarationListParser {
+    type Declaration = Declaration;
+    type Error = ();
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut cssparser::Parser<'i, 't>,
+        _start: &ParserState,
+    ) -> Result<Declaration, ParseError<'i, ()>> {
+        let start = input.position();
+        let mut end = start;
+        let mut important = false;
+        while !input.is_exhausted() {
+            if input
+                .try_parse(|input| {
+                    cssparser::parse_important(input)?;
+                    input.expect_exhausted()
+                })
+                .is_ok()
+            {
+                important = true;
+                break;
+            }
+            input.next()?;
+            end = input.position();
+        }
+        let value = input.slice(start..end).trim();
+        if value.is_empty() {
+            return Err(input.new_custom_error(()));
+        }
+        Ok(Declaration {
+            property: name.to_ascii_lowercase(),
+            value: value.to_string(),
+            important,
+        })
+    }
+}
+
+impl<'i> AtRuleParser<'i> for DeclarationListParser {
+    type Prelude = ();
+    type AtRule = Declaration;
+    type Error = ();
+}
+
+impl<'i> QualifiedRuleParser<'i> for DeclarationListParser {
+    type Prelude = ();
+    type QualifiedRule = Declaration;
+    type Error = ();
+}
+
+impl<'i> RuleBodyItemParser<'i, Declaration, ()> for DeclarationListParser {
+    fn parse_declarations(&self) -> bool {
+        true
+    }
+
+    fn parse_qualified(&self) -> bool {
+        false
+    }
+}
+
+/// Class names the selectors in `css` use, inside at-rule blocks too.
+fn referenced_classes(css: &str) -> HashSet<String> {
+    fn walk(input: &mut cssparser::Parser, classes: &mut HashSet<String>) {
+        let mut after_dot = false;
+        while let Ok(token) = input.next_including_whitespace() {
+            match token.clone() {
+                Token::Delim('.') => {
+                    after_dot = true;
+                    continue;
+                }
+                Token::Ident(name) if after_dot => {
+                    classes.insert(name.to_string());
+                }
+                Token::CurlyBracketBlock
+                | Token::ParenthesisBlock
+                | Token::SquareBracketBlock
+                | Token::Function(_) => {
+                    let _ = input.parse_nested_block(|input| {
+                        walk(input, classes);
+                        Ok::<_, ParseError<()>>(())
+                    });
+                }
+                _ => {}
+            }
+            after_dot = false;
+        }
+    }
+
+    let mut classes = HashSet::new();
+    let mut input = ParserInput::new(css);
+    walk(&mut cssparser::Parser::new(&mut input), &mut classes);
+    classes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn make_output(rendered_body: Value) -> PipelineOutput {
+        PipelineOutput {
+            id: Uuid::now_v7(),
+            project_id: Uuid::now_v7(),
+            event_name: "test.event".into(),
+            recipient_id: Uuid::now_v7(),
+            channel: "email".into(),
+            rendered_body,
+            contact_value: "user@example.com".into(),
+            idempotency_key: None,
+            max_attempts: 3,
+            stylesheets: Default::default(),
+        }
+    }
+
+    async fn inline(html: &str, config: Value) -> String {
+        let mut output = make_output(json!({ "html": html }));
+        CssInlineMiddleware
+            .post_render(&mut output, &config)
+            .await
+            .unwrap();
+        output.rendered_body["html"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn inlines_by_specificity_and_keeps_inline_styles() {
+        let html = inline(
+            r#"<html><head><style>
+                p { color: red; margin: 0 }
+                .note { color: blue }
+                #first { font-weight: bold !important }
+                td, th { padding: 4px; }
+            </style></head><body>
+            <p id="first" class="note" style="font-weight: normal; color: green">A</p>
+            <p class="note">B</p><p>C</p>
+            <table><tr><td>x</td></tr></table>
+            </body></html>"#,
+            json!({}),
+        )
+        .await;
+        assert!(!html.contains("<style"), "{html}");
+        assert!(
+            html.contains(
+                r#"<p class="note" id="first" style="margin:0;font-weight:bold;color:green;">A</p>"#
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"<p class="note" style="margin:0;color:blue;">B</p>"#),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"<p style="color:red;margin:0;">C</p>"#),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"<td style="padding:4px;">x</td>"#),
+            "{html}"
+        );
+    }
+
+    #[tokio::test]
+    async fn media_queries_and_pseudo_classes_are_kept() {
+        let source = r#"<html><head><style>
+            a { color: red }
+            a:hover { color: blue }
+            @media (max-width: 480px) { .col { width: 100% !important } }
+        </style></head><body><a class="col" href="x">L</a></body></html>"#;
+
+        let html = inline(source, json!({})).await;
+        assert!(
+            html.contains(r#"<a class="col" href="x" style="color:red;">"#),
+            "{html}"
+        );
+        let head = &html[..html.find("</head>").unwrap()];
+        assert!(head.contains("a:hover {color: blue}"), "{html}");
+        assert!(head.contains("@media (max-width: 480px)"), "{html}");
+
+        let html = inline(source, json!({"preserve_media_queries": false})).await;
+        assert!(!html.contains("@media"), "{html}");
+        assert!(html.contains("a:hover"), "{html}");
+    }
+
+    #[tokio::test]
+    async fn linked_project_stylesheets_are_inlined() {
+        let mut output = make_output(json!({
+            "html": r#"<link rel="stylesheet" href="brand.css"><link rel="stylesheet" href="https://cdn.example/x.css"><h1>Hi</h1>"#,
+        }));
+        output.stylesheets = Arc::new(BTreeMap::from([(
+            "brand.css".to_string(),
+            "h1 { color: #333 }".to_string(),
+        )]));
+        CssInlineMiddleware
+            .post_render(&mut output, &json!({}))
+            .await
+            .unwrap();
+        assert_eq!(
+            output.rendered_body["html"],
+            r#"<link href="https://cdn.example/x.css" rel="stylesheet"><h1 style="color:#333;">Hi</h1>"#
+        );
+    }
+
+    #[tokio::test]
+    async fn unused_classes_are_removed() {
+        let source = r#"<html><head><style>
+            .title { font-size: 20px }
+            @media (min-width: 480px) { .wide { width: 50% } }
+        </style></head><body><div class="title wide extra">T</div><p class="extra">x</p></body></html>"#;
+        let html = inline(source, json!({"remove_unused_classes": true})).await;
+        assert!(
+            html.contains(r#"<div class="wide" style="font-size:20px;">T</div>"#),
+            "{html}"
+        );
+        assert!(html.contains("<p>x</p>"), "{html}");
+
+        let html = inline(source, json!({})).await;
+        assert!(html.contains(r#"class="title wide extra""#), "{html}");
+    }
+
+    #[tokio::test]
+    async fn html_without_css_is_untouched() {
+        let source = "<p>Hello &amp; <b>bye</b></p><br>";
+        assert_eq!(inline(source, json!({})).await, source);
+
+        let mut output = make_output(json!({"text": "plain"}));
+        CssInlineMiddleware
+            .post_render(&mut output, &json!({}))
+            .await
+            .unwrap();
+        assert_eq!(output.rendered_body, json!({"text": "plain"}));
+    }
+
+    #[tokio::test]
+    async fn strings_and_comments_do_not_end_rules() {
+        let html = inline(
+            r#"<html><head><style>
+                a::after { content: "}" }
+                /* p { color: red } */
+                p { background: url("/*x") no-repeat; color: green }
+                .x { content: ";{" }
+                b { font-weight: 700 }
+            </style></head><body><p class="x">A</p><b>B</b></body></html>"#,
+            json!({}),
+        )
+        .await;
+        assert!(
+            html.contains(
+                r#"<p class="x" style="background:url(&quot;/*x&quot;) no-repeat;color:green;content:&quot;;{&quot;;">A</p>"#
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains(r#"<b style="font-weight:700;">B</b>"#),
+            "{html}"
+        );
+        let head = &html[..html.find("</head>").unwrap()];
+        assert!(head.contains(r#"a::after {content: "}"}"#), "{html}");
+    }
+
+    #[test]
+    fn specificity_counts() {
+        let specificity = |s| inlinable(s).map(|(_, n)| n);
+        assert!(specificity("div p.note") > specificity("p.note"));
+        assert!(specificity("p.note") > specificity("div p"));
+        assert!(specificity("#a") > specificity(".b:first-child > li[x=\"1\"]"));
+        assert_eq!(specificity("*"), Some(0));
+        assert_eq!(specificity("a:hover"), None);
+        assert_eq!(specificity("p::before"), None);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod click_tracking;
+pub mod css_inline;
 pub mod open_tracking;
 pub mod plaintext_fallback;
 pub mod unsubscribe_link;
@@ -139,10 +140,18 @@ mod tests {
             event_name: "test.event".into(),
             recipient_id: uuid::Uuid::now_v7(),
             recipient_locale: "en".into(),
+            recipient_timezone: "UTC".into(),
             channel: "email".into(),
             contact_value: "user@example.com".into(),
             template_body: serde_json::json!({"text": "hello"}),
             context_data: serde_json::json!({}),
+            partials: Default::default(),
+            stylesheets: Default::default(),
+            translations: Default::default(),
+            render_options: Default::default(),
+            template_cache: Default::default(),
+            template_key: None,
+            content_format: Default::default(),
             idempotency_key: None,
             max_attempts: 3,
         };
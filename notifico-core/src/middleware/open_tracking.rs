@@ -1,15 +1,25 @@
 use async_trait::async_trait;
-use base64::Engine;
-use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use serde_json::Value;
 
 use crate::error::CoreError;
 use crate::pipeline::PipelineOutput;
+use crate::tracking::create_tracking_token;
 
 use super::Middleware;
 
 /// Post-render middleware that appends a 1x1 tracking pixel to HTML content.
-pub struct OpenTrackingMiddleware;
+///
+/// The pixel URL carries a token signed with the server key; without a key
+/// the tracking endpoint can't verify it, so nothing is added.
+pub struct OpenTrackingMiddleware {
+    key: Option<[u8; 32]>,
+}
+
+impl OpenTrackingMiddleware {
+    pub fn new(key: Option<[u8; 32]>) -> Self {
+        Self { key }
+    }
+}
 
 #[async_trait]
 impl Middleware for OpenTrackingMiddleware {
@@ -29,10 +39,14 @@ impl Middleware for OpenTrackingMiddleware {
         if base_url.is_empty() {
             return Ok(());
         }
+        let Some(key) = &self.key else {
+            tracing::warn!("Open tracking needs an encryption key, skipping");
+            return Ok(());
+        };
 
         if let Value::Object(ref mut map) = output.rendered_body {
             if let Some(Value::String(html)) = map.get_mut("html") {
-                let token = URL_SAFE_NO_PAD.encode(output.id.to_string());
+                let token = create_tracking_token(&output.id.to_string(), None, key);
                 let pixel = format!(
                     r#"<img src="{}/t/open/{}" width="1" height="1" style="display:none" alt="" />"#,
                     base_url, token
@@ -65,12 +79,13 @@ mod tests {
             contact_value: "user@example.com".into(),
             idempotency_key: None,
             max_attempts: 3,
+            stylesheets: Default::default(),
         }
     }
 
     #[tokio::test]
     async fn open_tracking_appends_pixel() {
-        let mw = OpenTrackingMiddleware;
+        let mw = OpenTrackingMiddleware::new(Some([7; 32]));
         let config = json!({"base_url": "https://track.example.com"});
         let mut output = make_output(json!({
             "html": "<html><body><p>Hello</p></body></html>"
@@ -86,7 +101,7 @@ mod tests {
 
     #[tokio::test]
     async fn open_tracking_no_html_is_noop() {
-        let mw = OpenTrackingMiddleware;
+        let mw = OpenTrackingMiddleware::new(Some([7; 32]));
         let config = json!({"base_url": "https://track.example.com"});
         let mut output = make_output(json!({"subject": "Hello"}));
         let original = output.rendered_body.clone();
@@ -98,7 +113,7 @@ mod tests {
 
     #[tokio::test]
     async fn open_tracking_noop_without_base_url() {
-        let mw = OpenTrackingMiddleware;
+        let mw = OpenTrackingMiddleware::new(Some([7; 32]));
         let config = json!({});
         let mut output = make_output(json!({"html": "<p>Hello</p>"}));
         let original = output.rendered_body.clone();
@@ -56,6 +56,7 @@ mod tests {
             contact_value: "user@example.com".into(),
             idempotency_key: None,
             max_attempts: 3,
+            stylesheets: Default::default(),
         }
     }
 
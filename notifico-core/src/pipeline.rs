@@ -1,6 +1,14 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use notifico_template::{
+    Localization, Partials, RenderOptions, RenderSettings, TemplateCache, TemplateKey, Translations,
+};
 use serde_json::Value;
 use uuid::Uuid;
 
+use crate::content_format::ContentFormat;
+
 /// Input for the pipeline: one recipient + one pipeline rule match.
 #[derive(Debug, Clone)]
 pub struct PipelineInput {
@@ -8,10 +16,29 @@ pub struct PipelineInput {
     pub event_name: String,
     pub recipient_id: Uuid,
     pub recipient_locale: String,
+    /// Recipient time zone for date filters; IANA name or fixed offset.
+    pub recipient_timezone: String,
     pub channel: String,
     pub contact_value: String,
     pub template_body: Value,
     pub context_data: Value,
+    /// Project layouts and partials the template can extend or include.
+    pub partials: Partials,
+    /// Project stylesheets by name, for `<link rel="stylesheet">` tags the
+    /// css_inline middleware resolves.
+    pub stylesheets: Arc<BTreeMap<String, String>>,
+    /// Project translation catalogs for `t()`.
+    pub translations: Translations,
+    /// Project undefined-variable mode and render limits.
+    pub render_options: RenderOptions,
+    /// Compiled templates shared across renders.
+    pub template_cache: TemplateCache,
+    /// Identifies `template_body` in `template_cache`; `None` renders
+    /// without caching.
+    pub template_key: Option<TemplateKey>,
+    /// How `template_body` is authored; compiled formats are turned into
+    /// `html` and `text` after rendering.
+    pub content_format: ContentFormat,
     pub idempotency_key: Option<String>,
     pub max_attempts: u32,
 }
@@ -28,15 +55,41 @@ pub struct PipelineOutput {
     pub contact_value: String,
     pub idempotency_key: Option<String>,
     pub max_attempts: u32,
+    /// Carried from [`PipelineInput::stylesheets`] for post-render
+    /// middleware.
+    pub stylesheets: Arc<BTreeMap<String, String>>,
 }
 
 /// Execute the rendering pipeline for one recipient + one channel.
 ///
 /// Steps:
-/// 1. Render template body fields via minijinja (notifico-template)
-/// 2. Return PipelineOutput ready for enqueuing
+/// 1. Render template body fields via minijinja (notifico-template), with
+///    locale filters driven by the recipient locale and time zone
+/// 2. Compile MJML or Markdown content to HTML and plain text
+/// 3. Return PipelineOutput ready for enqueuing
 pub fn execute_pipeline(input: PipelineInput) -> Result<PipelineOutput, crate::error::CoreError> {
-    let rendered = notifico_template::render_body(&input.template_body, &input.context_data)
+    let settings = RenderSettings {
+        partials: input.partials.clone(),
+        localization: Localization::new(input.recipient_locale.as_str())
+            .with_timezone(input.recipient_timezone.as_str())
+            .with_translations(input.translations.clone()),
+        options: input.render_options,
+    };
+    let mut rendered = match &input.template_key {
+        Some(key) => input.template_cache.render_body(
+            key,
+            &input.template_body,
+            &input.context_data,
+            &settings,
+        ),
+        None => {
+            notifico_template::render_body(&input.template_body, &input.context_data, &settings)
+        }
+    }
+    .map_err(|e| crate::error::CoreError::TemplateRender(e.to_string()))?;
+    input
+        .content_format
+        .compile(&mut rendered)
         .map_err(|e| crate::error::CoreError::TemplateRender(e.to_string()))?;
 
     Ok(PipelineOutput {
@@ -49,6 +102,7 @@ pub fn execute_pipeline(input: PipelineInput) -> Result<PipelineOutput, crate::e
         contact_value: input.contact_value,
         idempotency_key: input.idempotency_key,
         max_attempts: input.max_attempts,
+        stylesheets: input.stylesheets,
     })
 }
 
@@ -63,10 +117,18 @@ mod tests {
             event_name: "order.confirmed".into(),
             recipient_id: Uuid::now_v7(),
             recipient_locale: "en".into(),
+            recipient_timezone: "UTC".into(),
             channel: "email".into(),
             contact_value: "user@example.com".into(),
             template_body: body,
             context_data: data,
+            partials: Partials::default(),
+            stylesheets: Default::default(),
+            translations: Translations::default(),
+            render_options: RenderOptions::default(),
+            template_cache: TemplateCache::default(),
+            template_key: None,
+            content_format: ContentFormat::Html,
             idempotency_key: None,
             max_attempts: 5,
         }
@@ -120,6 +182,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn execute_pipeline_formats_for_recipient_locale() {
+        let mut input = make_input(
+            json!({
+                "subject": "{{ t('subject', n=order_id) }}",
+                "text": "{{ total | currency('EUR') }} · {{ at | datetime('short') }}"
+            }),
+            json!({"order_id": 42, "total": 1234.5, "at": "2026-01-05T14:04:05Z"}),
+        );
+        input.recipient_locale = "de-AT".into();
+        input.recipient_timezone = "+01:00".into();
+        input.translations = Translations::new(
+            [("de".to_string(), json!({"subject": "Bestellung #{n}"}))].into(),
+        );
+        let output = execute_pipeline(input).unwrap();
+        assert_eq!(output.rendered_body["subject"], "Bestellung #42");
+        // Austrian German puts the euro sign first and groups with a space
+        assert_eq!(
+            output.rendered_body["text"],
+            "€\u{a0}1\u{a0}234,50 · 05.01.26, 15:04"
+        );
+    }
+
+    #[test]
+    fn execute_pipeline_compiles_markdown_after_rendering() {
+        let mut input = make_input(
+            json!({"subject": "Hi", "markdown": "# Hello {{ name }}\n\n*Thanks*"}),
+            json!({"name": "Dana"}),
+        );
+        input.content_format = ContentFormat::Markdown;
+        let output = execute_pipeline(input).unwrap();
+        let body = output.rendered_body.as_object().unwrap();
+        assert!(!body.contains_key("markdown"));
+        assert!(body["html"].as_str().unwrap().contains("<h1>Hello Dana</h1>"));
+        assert!(body["text"].as_str().unwrap().contains("Hello Dana"));
+    }
+
     #[test]
     fn execute_pipeline_invalid_body_returns_error() {
         let input = make_input(json!("not an object"), json!({}));
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use crate::error::CoreError;
+
+/// Blob storage for uploaded assets such as message attachments.
+///
+/// Keys are `/`-separated relative paths; backends map them onto files or
+/// object keys.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Store `data` under `key`, overwriting any existing object.
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), CoreError>;
+
+    /// Load the object stored under `key`.
+    async fn get(&self, key: &str) -> Result<Vec<u8>, CoreError>;
+
+    /// Delete the object stored under `key`. Missing objects are not an error.
+    async fn delete(&self, key: &str) -> Result<(), CoreError>;
+}
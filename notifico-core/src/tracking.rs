@@ -0,0 +1,111 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Create a signed tracking token.
+///
+/// `delivery_id` is the ID of the delivery task the link was rendered for;
+/// every delivery log of the task records it.
+///
+/// Token format: `base64url(json_payload).base64url(hmac_signature)`
+pub fn create_tracking_token(delivery_id: &str, url: Option<&str>, key: &[u8; 32]) -> String {
+    let payload = match url {
+        Some(u) => serde_json::json!({"d": delivery_id, "u": u}),
+        None => serde_json::json!({"d": delivery_id}),
+    };
+    let payload_bytes = serde_json::to_vec(&payload).expect("JSON serialization cannot fail");
+    let encoded_payload = URL_SAFE_NO_PAD.encode(&payload_bytes);
+
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC key length is always valid");
+    mac.update(encoded_payload.as_bytes());
+    let signature = mac.finalize().into_bytes();
+    let encoded_sig = URL_SAFE_NO_PAD.encode(signature);
+
+    format!("{encoded_payload}.{encoded_sig}")
+}
+
+/// Verify a tracking token and return (delivery_id, optional_url).
+pub fn verify_tracking_token(token: &str, key: &[u8; 32]) -> Option<(String, Option<String>)> {
+    let (encoded_payload, encoded_sig) = token.split_once('.')?;
+
+    let signature = URL_SAFE_NO_PAD.decode(encoded_sig).ok()?;
+
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC key length is always valid");
+    mac.update(encoded_payload.as_bytes());
+    mac.verify_slice(&signature).ok()?;
+
+    let payload_bytes = URL_SAFE_NO_PAD.decode(encoded_payload).ok()?;
+    let payload: serde_json::Value = serde_json::from_slice(&payload_bytes).ok()?;
+
+    let delivery_id = payload.get("d")?.as_str()?.to_string();
+    let url = payload
+        .get("u")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    Some((delivery_id, url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> [u8; 32] {
+        [0xAB; 32]
+    }
+
+    #[test]
+    fn token_roundtrip_open() {
+        let key = test_key();
+        let token = create_tracking_token("delivery-123", None, &key);
+        let result = verify_tracking_token(&token, &key);
+        assert!(result.is_some());
+        let (d, u) = result.unwrap();
+        assert_eq!(d, "delivery-123");
+        assert!(u.is_none());
+    }
+
+    #[test]
+    fn token_roundtrip_click() {
+        let key = test_key();
+        let url = "https://example.com/landing?ref=abc";
+        let token = create_tracking_token("delivery-456", Some(url), &key);
+        let result = verify_tracking_token(&token, &key);
+        assert!(result.is_some());
+        let (d, u) = result.unwrap();
+        assert_eq!(d, "delivery-456");
+        assert_eq!(u.unwrap(), url);
+    }
+
+    #[test]
+    fn tampered_token_fails() {
+        let key = test_key();
+        let token = create_tracking_token("delivery-123", None, &key);
+        // Tamper with the payload
+        let tampered = format!("x{token}");
+        assert!(verify_tracking_token(&tampered, &key).is_none());
+    }
+
+    #[test]
+    fn wrong_key_fails() {
+        let key = test_key();
+        let token = create_tracking_token("delivery-123", None, &key);
+        let wrong_key = [0xCD; 32];
+        assert!(verify_tracking_token(&token, &wrong_key).is_none());
+    }
+
+    #[test]
+    fn missing_dot_fails() {
+        let key = test_key();
+        assert!(verify_tracking_token("nodothere", &key).is_none());
+    }
+
+    #[test]
+    fn empty_token_fails() {
+        let key = test_key();
+        assert!(verify_tracking_token("", &key).is_none());
+    }
+}
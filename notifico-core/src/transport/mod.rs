@@ -27,6 +27,89 @@ pub enum ContentFieldType {
     Json,
 }
 
+/// Why a template content field was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentErrorCode {
+    /// A required field is missing, null or blank.
+    Required,
+    /// The value does not match the declared field type.
+    InvalidType,
+    /// A template string does not compile.
+    Syntax,
+}
+
+/// A field-level problem found when validating template content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentError {
+    /// Field path, e.g. `subject` or `blocks[0].text`.
+    pub field: String,
+    pub code: ContentErrorCode,
+    pub message: String,
+}
+
+impl ContentSchema {
+    /// Validate a template body before saving: it must be an object with every
+    /// required field present, `text`/`html` fields must be template strings,
+    /// `json` fields objects or arrays, and every template string must compile.
+    /// Fields not in the schema are allowed.
+    pub fn validate(&self, body: &Value) -> Vec<ContentError> {
+        let Some(obj) = body.as_object() else {
+            return vec![ContentError {
+                field: String::new(),
+                code: ContentErrorCode::InvalidType,
+                message: "Content must be a JSON object".into(),
+            }];
+        };
+
+        let mut errors = Vec::new();
+        for field in &self.fields {
+            let value = obj.get(&field.name).filter(|v| !v.is_null());
+            let blank = matches!(value, Some(Value::String(s)) if s.trim().is_empty());
+            let Some(value) = value.filter(|_| !blank) else {
+                if field.required {
+                    errors.push(ContentError {
+                        field: field.name.clone(),
+                        code: ContentErrorCode::Required,
+                        message: format!("'{}' is required", field.name),
+                    });
+                }
+                continue;
+            };
+
+            let type_ok = match field.field_type {
+                ContentFieldType::Text | ContentFieldType::Html => value.is_string(),
+                ContentFieldType::Json => value.is_object() || value.is_array(),
+            };
+            if !type_ok {
+                let expected = match field.field_type {
+                    ContentFieldType::Text | ContentFieldType::Html => "a string",
+                    ContentFieldType::Json => "a JSON object or array",
+                };
+                errors.push(ContentError {
+                    field: field.name.clone(),
+                    code: ContentErrorCode::InvalidType,
+                    message: format!("'{}' must be {expected}", field.name),
+                });
+            }
+        }
+
+        errors.extend(
+            notifico_template::check_syntax(body)
+                .into_iter()
+                .map(|e| ContentError {
+                    message: match e.line {
+                        Some(line) => format!("{} (line {line})", e.message),
+                        None => e.message,
+                    },
+                    field: e.path,
+                    code: ContentErrorCode::Syntax,
+                }),
+        );
+        errors
+    }
+}
+
 /// Schema describing what credentials a transport needs.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CredentialSchema {
@@ -49,6 +132,30 @@ pub struct RenderedMessage {
     pub content: Value,
     pub credentials: Value,
     pub attachments: Vec<Attachment>,
+    /// Internal `_`-prefixed fields set by middleware (e.g.
+    /// `_list_unsubscribe`), moved out of `content` by
+    /// [`RenderedMessage::split_metadata`] so they never reach the recipient.
+    /// Transports may map them to protocol features such as email headers.
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, Value>,
+}
+
+impl RenderedMessage {
+    /// Move `_`-prefixed top-level content fields into `metadata`.
+    pub fn split_metadata(&mut self) {
+        if let Value::Object(content) = &mut self.content {
+            let internal: Vec<String> = content
+                .keys()
+                .filter(|k| k.starts_with('_'))
+                .cloned()
+                .collect();
+            for key in internal {
+                if let Some(value) = content.remove(&key) {
+                    self.metadata.insert(key, value);
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,3 +205,88 @@ pub trait Transport: Send + Sync {
     async fn send(&self, message: &RenderedMessage) -> Result<DeliveryResult, CoreError>;
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> ContentSchema {
+        let field = |name: &str, field_type, required| ContentField {
+            name: name.into(),
+            field_type,
+            required,
+            description: String::new(),
+        };
+        ContentSchema {
+            fields: vec![
+                field("subject", ContentFieldType::Text, true),
+                field("text", ContentFieldType::Text, true),
+                field("html", ContentFieldType::Html, false),
+                field("blocks", ContentFieldType::Json, false),
+            ],
+        }
+    }
+
+    #[test]
+    fn validate_accepts_complete_content() {
+        let body = json!({
+            "subject": "Hi {{ name }}",
+            "text": "Hello",
+            "blocks": {"$json": "{{ blocks | tojson }}"},
+            "extra": 1
+        });
+        assert!(schema().validate(&body).is_empty());
+    }
+
+    #[test]
+    fn validate_reports_field_errors() {
+        let body = json!({
+            "subject": "  ",
+            "html": 5,
+            "blocks": "[]",
+            "footer": "{% if x %}"
+        });
+        let errors = schema().validate(&body);
+        let summary: Vec<(&str, ContentErrorCode)> = errors
+            .iter()
+            .map(|e| (e.field.as_str(), e.code))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("subject", ContentErrorCode::Required),
+                ("text", ContentErrorCode::Required),
+                ("html", ContentErrorCode::InvalidType),
+                ("blocks", ContentErrorCode::InvalidType),
+                ("footer", ContentErrorCode::Syntax),
+            ]
+        );
+        assert_eq!(errors[0].message, "'subject' is required");
+        assert!(errors[4].message.ends_with("(line 1)"));
+
+        let errors = schema().validate(&json!(["not", "an", "object"]));
+        assert_eq!(errors[0].code, ContentErrorCode::InvalidType);
+    }
+
+    #[test]
+    fn split_metadata_moves_internal_fields() {
+        let mut message = RenderedMessage {
+            channel: ChannelId::new("email"),
+            recipient_contact: "user@example.com".into(),
+            content: json!({
+                "subject": "Hi",
+                "_list_unsubscribe": "<https://example.com/u>",
+                "_unsubscribe_url": "https://example.com/u",
+            }),
+            credentials: json!({}),
+            attachments: vec![],
+            metadata: Default::default(),
+        };
+        message.split_metadata();
+
+        assert_eq!(message.content, json!({"subject": "Hi"}));
+        assert_eq!(message.metadata["_list_unsubscribe"], "<https://example.com/u>");
+        assert_eq!(message.metadata.len(), 2);
+    }
+}
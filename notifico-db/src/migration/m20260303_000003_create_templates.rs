@@ -145,7 +145,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum Template {
+pub(crate) enum Template {
     Table,
     Id,
     ProjectId,
@@ -95,7 +95,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum DeliveryLog {
+pub(crate) enum DeliveryLog {
     Table,
     Id,
     ProjectId,
@@ -52,7 +52,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum IdempotencyRecord {
+pub(crate) enum IdempotencyRecord {
     Table,
     Id,
     IdempotencyKey,
@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20260303_000005_create_delivery_log::DeliveryLog;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DeliveryLog::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("provider_message_id"))
+                            .string_len(255)
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_delivery_log_provider_message")
+                    .table(DeliveryLog::Table)
+                    .col(Alias::new("provider_message_id"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_delivery_log_provider_message")
+                    .table(DeliveryLog::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DeliveryLog::Table)
+                    .drop_column(Alias::new("provider_message_id"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
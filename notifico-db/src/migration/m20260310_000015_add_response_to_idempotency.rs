@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20260303_000007_create_idempotency::IdempotencyRecord;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(IdempotencyRecord::Table)
+                    .add_column(ColumnDef::new(Alias::new("response")).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_idempotency_created_at")
+                    .table(IdempotencyRecord::Table)
+                    .col(IdempotencyRecord::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_idempotency_created_at")
+                    .table(IdempotencyRecord::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(IdempotencyRecord::Table)
+                    .drop_column(Alias::new("response"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260311_000017_template_version_publishing"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // NULL published_at marks a draft; versions that are already live
+        // count as published.
+        db.execute_unprepared("ALTER TABLE template_version ADD COLUMN published_at TEXT")
+            .await?;
+        db.execute_unprepared(
+            "UPDATE template_version SET published_at = created_at WHERE is_current = true",
+        )
+        .await?;
+
+        db.execute_unprepared("ALTER TABLE delivery_task ADD COLUMN template_version INTEGER")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_log ADD COLUMN template_version INTEGER")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("ALTER TABLE delivery_log DROP COLUMN template_version")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_task DROP COLUMN template_version")
+            .await?;
+        db.execute_unprepared("ALTER TABLE template_version DROP COLUMN published_at")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use sea_orm::DatabaseBackend;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260312_000019_widen_locale_columns"
+    }
+}
+
+/// Locale columns holding BCP-47 tags such as `zh-Hant-TW` or `sr-Latn-RS`.
+const LOCALE_COLUMNS: [(&str, &str); 3] = [
+    ("template_content", "locale"),
+    ("recipient", "locale"),
+    ("project", "default_locale"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite does not enforce VARCHAR lengths; only Postgres needs the
+        // wider type. 35 characters is the RFC 5646 recommended minimum.
+        if manager.get_database_backend() != DatabaseBackend::Postgres {
+            return Ok(());
+        }
+        for (table, column) in LOCALE_COLUMNS {
+            manager
+                .get_connection()
+                .execute_unprepared(&format!(
+                    "ALTER TABLE {table} ALTER COLUMN {column} TYPE VARCHAR(35)"
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DatabaseBackend::Postgres {
+            return Ok(());
+        }
+        for (table, column) in LOCALE_COLUMNS {
+            manager
+                .get_connection()
+                .execute_unprepared(&format!(
+                    "ALTER TABLE {table} ALTER COLUMN {column} TYPE VARCHAR(10)"
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20260303_000003_create_templates::Template;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Template::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("format"))
+                            .string()
+                            .not_null()
+                            .default("html"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Template::Table)
+                    .drop_column(Alias::new("format"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
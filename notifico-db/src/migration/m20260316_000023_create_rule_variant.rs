@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260316_000023_create_rule_variant"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // A rule with variants splits its recipients between their templates;
+        // `experiment` seeds the split so it stays stable while weights change.
+        db.execute_unprepared("ALTER TABLE pipeline_rule ADD COLUMN experiment TEXT")
+            .await?;
+        db.execute_unprepared(
+            "CREATE TABLE pipeline_rule_variant (
+                id TEXT PRIMARY KEY,
+                rule_id TEXT NOT NULL REFERENCES pipeline_rule(id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                template_id TEXT NOT NULL REFERENCES template(id) ON DELETE CASCADE,
+                weight INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            )",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_pipeline_rule_variant_name ON pipeline_rule_variant(rule_id, name)",
+        )
+        .await?;
+
+        db.execute_unprepared("ALTER TABLE delivery_task ADD COLUMN experiment TEXT")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_task ADD COLUMN variant TEXT")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_log ADD COLUMN experiment TEXT")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_log ADD COLUMN variant TEXT")
+            .await?;
+        // Tracking tokens carry the delivery task ID, so logs record it to
+        // count opens and clicks per variant, and tracking events name it for
+        // what it is
+        db.execute_unprepared("ALTER TABLE delivery_log ADD COLUMN task_id TEXT")
+            .await?;
+        db.execute_unprepared(
+            "ALTER TABLE tracking_event RENAME COLUMN delivery_log_id TO task_id",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE INDEX idx_delivery_log_experiment ON delivery_log(project_id, experiment, variant)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_delivery_log_experiment")
+            .await?;
+        db.execute_unprepared(
+            "ALTER TABLE tracking_event RENAME COLUMN task_id TO delivery_log_id",
+        )
+        .await?;
+        db.execute_unprepared("ALTER TABLE delivery_log DROP COLUMN task_id")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_log DROP COLUMN variant")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_log DROP COLUMN experiment")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_task DROP COLUMN variant")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_task DROP COLUMN experiment")
+            .await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS pipeline_rule_variant")
+            .await?;
+        db.execute_unprepared("ALTER TABLE pipeline_rule DROP COLUMN experiment")
+            .await?;
+
+        Ok(())
+    }
+}
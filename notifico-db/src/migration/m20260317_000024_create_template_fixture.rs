@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260317_000024_create_template_fixture"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Named sample payloads. A fixture belongs to one template, or to an
+        // event and then applies to every template its rules use.
+        db.execute_unprepared(
+            "CREATE TABLE template_fixture (
+                id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL REFERENCES project(id) ON DELETE CASCADE,
+                template_id TEXT REFERENCES template(id) ON DELETE CASCADE,
+                event_id TEXT REFERENCES event(id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                data TEXT NOT NULL DEFAULT '{}',
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                CHECK ((template_id IS NULL) <> (event_id IS NULL))
+            )",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_template_fixture_template ON template_fixture(template_id, name)",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_template_fixture_event ON template_fixture(event_id, name)",
+        )
+        .await?;
+
+        // Accepted render output of a template for one fixture and locale
+        db.execute_unprepared(
+            "CREATE TABLE template_snapshot (
+                id TEXT PRIMARY KEY,
+                template_id TEXT NOT NULL REFERENCES template(id) ON DELETE CASCADE,
+                fixture_id TEXT NOT NULL REFERENCES template_fixture(id) ON DELETE CASCADE,
+                locale TEXT NOT NULL,
+                rendered TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            )",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_template_snapshot_key ON template_snapshot(template_id, fixture_id, locale)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("DROP TABLE IF EXISTS template_snapshot")
+            .await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS template_fixture")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260318_000025_create_rule_credential"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // A rule may name the credential it sends through; rules that don't
+        // fall back to the credential marked as the channel's project default.
+        db.execute_unprepared("ALTER TABLE pipeline_rule ADD COLUMN credential TEXT")
+            .await?;
+        db.execute_unprepared(
+            "ALTER TABLE credential ADD COLUMN is_default BOOLEAN NOT NULL DEFAULT false",
+        )
+        .await?;
+        db.execute_unprepared("ALTER TABLE delivery_task ADD COLUMN credential TEXT")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_log ADD COLUMN credential TEXT")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("ALTER TABLE delivery_log DROP COLUMN credential")
+            .await?;
+        db.execute_unprepared("ALTER TABLE delivery_task DROP COLUMN credential")
+            .await?;
+        db.execute_unprepared("ALTER TABLE credential DROP COLUMN is_default")
+            .await?;
+        db.execute_unprepared("ALTER TABLE pipeline_rule DROP COLUMN credential")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -12,6 +12,19 @@ mod m20260304_000009_create_credentials;
 mod m20260308_000010_create_pipeline_middleware;
 mod m20260308_000011_add_rule_id_to_delivery_task;
 mod m20260308_000012_create_tracking_event;
+mod m20260310_000013_add_provider_message_id_to_delivery_log;
+mod m20260310_000014_create_webhook_subscription;
+mod m20260310_000015_add_response_to_idempotency;
+mod m20260311_000016_create_attachment;
+mod m20260311_000017_template_version_publishing;
+mod m20260312_000018_create_template_partial;
+mod m20260312_000019_widen_locale_columns;
+mod m20260313_000020_create_translation_catalog;
+mod m20260314_000021_add_data_schema_to_event;
+mod m20260315_000022_add_format_to_template;
+mod m20260316_000023_create_rule_variant;
+mod m20260317_000024_create_template_fixture;
+mod m20260318_000025_create_rule_credential;
 
 pub struct Migrator;
 
@@ -31,6 +44,19 @@ impl MigratorTrait for Migrator {
             Box::new(m20260308_000010_create_pipeline_middleware::Migration),
             Box::new(m20260308_000011_add_rule_id_to_delivery_task::Migration),
             Box::new(m20260308_000012_create_tracking_event::Migration),
+            Box::new(m20260310_000013_add_provider_message_id_to_delivery_log::Migration),
+            Box::new(m20260310_000014_create_webhook_subscription::Migration),
+            Box::new(m20260310_000015_add_response_to_idempotency::Migration),
+            Box::new(m20260311_000016_create_attachment::Migration),
+            Box::new(m20260311_000017_template_version_publishing::Migration),
+            Box::new(m20260312_000018_create_template_partial::Migration),
+            Box::new(m20260312_000019_widen_locale_columns::Migration),
+            Box::new(m20260313_000020_create_translation_catalog::Migration),
+            Box::new(m20260314_000021_add_data_schema_to_event::Migration),
+            Box::new(m20260315_000022_add_format_to_template::Migration),
+            Box::new(m20260316_000023_create_rule_variant::Migration),
+            Box::new(m20260317_000024_create_template_fixture::Migration),
+            Box::new(m20260318_000025_create_rule_credential::Migration),
         ]
     }
 }
@@ -91,6 +91,21 @@ pub async fn update_project(
     Ok(())
 }
 
+/// Replace the project's settings JSON.
+pub async fn update_project_settings(
+    db: &DatabaseConnection,
+    id: Uuid,
+    settings: &Value,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "UPDATE project SET settings = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        [settings.to_string().into(), id.to_string().into()],
+    ))
+    .await?;
+    Ok(())
+}
+
 pub async fn delete_project(db: &DatabaseConnection, id: Uuid) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
@@ -110,6 +125,8 @@ pub struct EventRow {
     pub name: String,
     pub category: String,
     pub description: String,
+    /// JSON Schema the event's `data` must match, if one is declared.
+    pub data_schema: Option<Value>,
 }
 
 #[derive(Debug, Clone, FromQueryResult)]
@@ -119,6 +136,7 @@ struct EventRaw {
     name: String,
     category: String,
     description: String,
+    data_schema: Option<String>,
 }
 
 impl EventRaw {
@@ -131,17 +149,22 @@ impl EventRaw {
             name: self.name,
             category: self.category,
             description: self.description,
+            data_schema: self
+                .data_schema
+                .map(|s| serde_json::from_str(&s))
+                .transpose()
+                .map_err(|e| DbErr::Custom(format!("invalid data_schema JSON: {e}")))?,
         })
     }
 }
 
 pub async fn list_events(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     project_id: Uuid,
 ) -> Result<Vec<EventRow>, DbErr> {
     let rows = EventRaw::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT id, project_id, name, category, description FROM event WHERE project_id = ? ORDER BY name",
+        "SELECT id, project_id, name, category, description, data_schema FROM event WHERE project_id = ? ORDER BY name",
         [project_id.to_string().into()],
     ))
     .all(db)
@@ -152,7 +175,7 @@ pub async fn list_events(
 pub async fn get_event(db: &DatabaseConnection, id: Uuid) -> Result<Option<EventRow>, DbErr> {
     let raw = EventRaw::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT id, project_id, name, category, description FROM event WHERE id = ?",
+        "SELECT id, project_id, name, category, description, data_schema FROM event WHERE id = ?",
         [id.to_string().into()],
     ))
     .one(db)
@@ -164,7 +187,7 @@ pub async fn get_event(db: &DatabaseConnection, id: Uuid) -> Result<Option<Event
 }
 
 pub async fn create_event(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     id: Uuid,
     project_id: Uuid,
     name: &str,
@@ -185,7 +208,7 @@ pub async fn create_event(
 }
 
 pub async fn update_event(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     id: Uuid,
     name: &str,
     category: &str,
@@ -205,7 +228,42 @@ pub async fn update_event(
     Ok(())
 }
 
-pub async fn delete_event(db: &DatabaseConnection, id: Uuid) -> Result<(), DbErr> {
+/// Replace the event's data schema; `None` removes it.
+pub async fn update_event_schema(
+    db: &impl ConnectionTrait,
+    id: Uuid,
+    data_schema: Option<&Value>,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "UPDATE event SET data_schema = ? WHERE id = ?",
+        [
+            data_schema.map(|s| s.to_string()).into(),
+            id.to_string().into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Events that have a pipeline rule rendering the given template.
+pub async fn list_events_using_template(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+) -> Result<Vec<EventRow>, DbErr> {
+    let rows = EventRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT DISTINCT e.id, e.project_id, e.name, e.category, e.description, e.data_schema \
+         FROM event e JOIN pipeline_rule r ON r.event_id = e.id \
+         WHERE r.template_id = ? ORDER BY e.name",
+        [template_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter().map(|r| r.into_row()).collect()
+}
+
+pub async fn delete_event(db: &impl ConnectionTrait, id: Uuid) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
         "DELETE FROM event WHERE id = ?",
@@ -225,6 +283,10 @@ pub struct RuleRow {
     pub template_id: Uuid,
     pub enabled: bool,
     pub priority: i32,
+    /// Seeds the variant split while the rule has variants.
+    pub experiment: Option<String>,
+    /// Named credential to send through; `None` uses the channel default.
+    pub credential: Option<String>,
 }
 
 #[derive(Debug, Clone, FromQueryResult)]
@@ -235,6 +297,8 @@ struct RuleRaw {
     template_id: String,
     enabled: bool,
     priority: i32,
+    experiment: Option<String>,
+    credential: Option<String>,
 }
 
 impl RuleRaw {
@@ -249,17 +313,19 @@ impl RuleRaw {
                 .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
             enabled: self.enabled,
             priority: self.priority,
+            experiment: self.experiment,
+            credential: self.credential,
         })
     }
 }
 
 pub async fn list_rules(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     event_id: Uuid,
 ) -> Result<Vec<RuleRow>, DbErr> {
     let rows = RuleRaw::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT id, event_id, channel, template_id, enabled, priority FROM pipeline_rule WHERE event_id = ? ORDER BY priority DESC",
+        "SELECT id, event_id, channel, template_id, enabled, priority, experiment, credential FROM pipeline_rule WHERE event_id = ? ORDER BY priority DESC",
         [event_id.to_string().into()],
     ))
     .all(db)
@@ -267,23 +333,39 @@ pub async fn list_rules(
     rows.into_iter().map(|r| r.into_row()).collect()
 }
 
+pub async fn get_rule(db: &impl ConnectionTrait, id: Uuid) -> Result<Option<RuleRow>, DbErr> {
+    let raw = RuleRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT id, event_id, channel, template_id, enabled, priority, experiment, credential FROM pipeline_rule WHERE id = ?",
+        [id.to_string().into()],
+    ))
+    .one(db)
+    .await?;
+    match raw {
+        Some(r) => Ok(Some(r.into_row()?)),
+        None => Ok(None),
+    }
+}
+
 pub async fn create_rule(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     id: Uuid,
     event_id: Uuid,
     channel: &str,
     template_id: Uuid,
     priority: i32,
+    credential: Option<&str>,
 ) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "INSERT INTO pipeline_rule (id, event_id, channel, template_id, priority) VALUES (?, ?, ?, ?, ?)",
+        "INSERT INTO pipeline_rule (id, event_id, channel, template_id, priority, credential) VALUES (?, ?, ?, ?, ?, ?)",
         [
             id.to_string().into(),
             event_id.to_string().into(),
             channel.into(),
             template_id.to_string().into(),
             priority.into(),
+            credential.map(|s| s.to_string()).into(),
         ],
     ))
     .await?;
@@ -291,21 +373,23 @@ pub async fn create_rule(
 }
 
 pub async fn update_rule(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     id: Uuid,
     channel: &str,
     template_id: Uuid,
     enabled: bool,
     priority: i32,
+    credential: Option<&str>,
 ) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "UPDATE pipeline_rule SET channel = ?, template_id = ?, enabled = ?, priority = ? WHERE id = ?",
+        "UPDATE pipeline_rule SET channel = ?, template_id = ?, enabled = ?, priority = ?, credential = ? WHERE id = ?",
         [
             channel.into(),
             template_id.to_string().into(),
             enabled.into(),
             priority.into(),
+            credential.map(|s| s.to_string()).into(),
             id.to_string().into(),
         ],
     ))
@@ -313,7 +397,7 @@ pub async fn update_rule(
     Ok(())
 }
 
-pub async fn delete_rule(db: &DatabaseConnection, id: Uuid) -> Result<(), DbErr> {
+pub async fn delete_rule(db: &impl ConnectionTrait, id: Uuid) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
         "DELETE FROM pipeline_rule WHERE id = ?",
@@ -331,6 +415,8 @@ pub struct TemplateRow {
     pub project_id: Uuid,
     pub name: String,
     pub channel: String,
+    /// Content authoring format: `html`, `mjml` or `markdown`.
+    pub format: String,
 }
 
 #[derive(Debug, Clone, FromQueryResult)]
@@ -339,6 +425,7 @@ struct TemplateRaw {
     project_id: String,
     name: String,
     channel: String,
+    format: String,
 }
 
 impl TemplateRaw {
@@ -350,17 +437,18 @@ impl TemplateRaw {
                 .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
             name: self.name,
             channel: self.channel,
+            format: self.format,
         })
     }
 }
 
 pub async fn list_templates(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     project_id: Uuid,
 ) -> Result<Vec<TemplateRow>, DbErr> {
     let rows = TemplateRaw::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT id, project_id, name, channel FROM template WHERE project_id = ? ORDER BY name",
+        "SELECT id, project_id, name, channel, format FROM template WHERE project_id = ? ORDER BY name",
         [project_id.to_string().into()],
     ))
     .all(db)
@@ -374,7 +462,7 @@ pub async fn get_template(
 ) -> Result<Option<TemplateRow>, DbErr> {
     let raw = TemplateRaw::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT id, project_id, name, channel FROM template WHERE id = ?",
+        "SELECT id, project_id, name, channel, format FROM template WHERE id = ?",
         [id.to_string().into()],
     ))
     .one(db)
@@ -391,32 +479,64 @@ pub async fn create_template(
     project_id: Uuid,
     name: &str,
     channel: &str,
+    format: &str,
 ) -> Result<(), DbErr> {
+    insert_template(db, id, project_id, name, channel, format).await?;
+
+    // Create initial version (v1, draft until published)
+    let version_id = Uuid::now_v7();
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "INSERT INTO template (id, project_id, name, channel) VALUES (?, ?, ?, ?)",
+        "INSERT INTO template_version (id, template_id, version, is_current) VALUES (?, ?, 1, false)",
+        [version_id.to_string().into(), id.to_string().into()],
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Insert a template without the initial draft, for callers that create
+/// versions themselves.
+pub async fn insert_template(
+    db: &impl ConnectionTrait,
+    id: Uuid,
+    project_id: Uuid,
+    name: &str,
+    channel: &str,
+    format: &str,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO template (id, project_id, name, channel, format) VALUES (?, ?, ?, ?, ?)",
         [
             id.to_string().into(),
             project_id.to_string().into(),
             name.into(),
             channel.into(),
+            format.into(),
         ],
     ))
     .await?;
+    Ok(())
+}
 
-    // Create initial version (v1, current)
-    let version_id = Uuid::now_v7();
+/// Rename a template and change its content format.
+pub async fn update_template(
+    db: &impl ConnectionTrait,
+    id: Uuid,
+    name: &str,
+    format: &str,
+) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "INSERT INTO template_version (id, template_id, version, is_current) VALUES (?, ?, 1, true)",
-        [version_id.to_string().into(), id.to_string().into()],
+        "UPDATE template SET name = ?, format = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        [name.into(), format.into(), id.to_string().into()],
     ))
     .await?;
-
     Ok(())
 }
 
-pub async fn delete_template(db: &DatabaseConnection, id: Uuid) -> Result<(), DbErr> {
+pub async fn delete_template(db: &impl ConnectionTrait, id: Uuid) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
         "DELETE FROM template WHERE id = ?",
@@ -426,26 +546,15 @@ pub async fn delete_template(db: &DatabaseConnection, id: Uuid) -> Result<(), Db
     Ok(())
 }
 
-/// Set template content for a locale on the current version.
+/// Set template content for a locale on the template's draft, opening a new
+/// draft from the current version if none exists. Returns the draft version.
 pub async fn set_template_content(
     db: &DatabaseConnection,
     template_id: Uuid,
     locale: &str,
     body: &Value,
-) -> Result<(), DbErr> {
-    #[derive(Debug, FromQueryResult)]
-    struct VersionId {
-        id: String,
-    }
-
-    let version = VersionId::find_by_statement(Statement::from_sql_and_values(
-        db.get_database_backend(),
-        "SELECT id FROM template_version WHERE template_id = ? AND is_current = true",
-        [template_id.to_string().into()],
-    ))
-    .one(db)
-    .await?
-    .ok_or_else(|| DbErr::Custom("No current version found".into()))?;
+) -> Result<super::template_version::TemplateVersionRow, DbErr> {
+    let version = super::template_version::ensure_draft(db, template_id).await?;
 
     let body_json =
         serde_json::to_string(body).map_err(|e| DbErr::Custom(format!("JSON error: {e}")))?;
@@ -458,7 +567,7 @@ pub async fn set_template_content(
     let existing = ContentExists::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
         "SELECT id FROM template_content WHERE template_version_id = ? AND locale = ?",
-        [version.id.clone().into(), locale.into()],
+        [version.id.to_string().into(), locale.into()],
     ))
     .one(db)
     .await?;
@@ -477,7 +586,7 @@ pub async fn set_template_content(
             "INSERT INTO template_content (id, template_version_id, locale, body) VALUES (?, ?, ?, ?)",
             [
                 content_id.to_string().into(),
-                version.id.into(),
+                version.id.to_string().into(),
                 locale.into(),
                 body_json.into(),
             ],
@@ -485,12 +594,14 @@ pub async fn set_template_content(
         .await?;
     }
 
-    Ok(())
+    Ok(version)
 }
 
+/// Get template content for a locale on a specific version.
 pub async fn get_template_content(
     db: &DatabaseConnection,
     template_id: Uuid,
+    version: i32,
     locale: &str,
 ) -> Result<Option<Value>, DbErr> {
     #[derive(Debug, FromQueryResult)]
@@ -503,8 +614,8 @@ pub async fn get_template_content(
         r#"SELECT tc.body
            FROM template_content tc
            JOIN template_version tv ON tc.template_version_id = tv.id
-           WHERE tv.template_id = ? AND tv.is_current = true AND tc.locale = ?"#,
-        [template_id.to_string().into(), locale.into()],
+           WHERE tv.template_id = ? AND tv.version = ? AND tc.locale = ?"#,
+        [template_id.to_string().into(), version.into(), locale.into()],
     ))
     .one(db)
     .await?;
@@ -785,6 +896,7 @@ pub struct CredentialSummary {
     pub name: String,
     pub channel: String,
     pub enabled: bool,
+    pub is_default: bool,
 }
 
 #[derive(Debug, Clone, FromQueryResult)]
@@ -793,6 +905,7 @@ struct CredentialSummaryRaw {
     name: String,
     channel: String,
     enabled: bool,
+    is_default: bool,
 }
 
 pub async fn list_credentials(
@@ -801,7 +914,7 @@ pub async fn list_credentials(
 ) -> Result<Vec<CredentialSummary>, DbErr> {
     let rows = CredentialSummaryRaw::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT id, name, channel, enabled FROM credential WHERE project_id = ? ORDER BY name",
+        "SELECT id, name, channel, enabled, is_default FROM credential WHERE project_id = ? ORDER BY name",
         [project_id.to_string().into()],
     ))
     .all(db)
@@ -815,6 +928,7 @@ pub async fn list_credentials(
                 name: r.name,
                 channel: r.channel,
                 enabled: r.enabled,
+                is_default: r.is_default,
             })
         })
         .collect()
@@ -892,6 +1006,15 @@ mod tests {
         let updated = get_event(&db, event_id).await.unwrap().unwrap();
         assert_eq!(updated.name, "order.shipped");
         assert_eq!(updated.description, "Shipping notification");
+        assert!(updated.data_schema.is_none());
+
+        let schema = serde_json::json!({"type": "object", "required": ["order_id"]});
+        update_event_schema(&db, event_id, Some(&schema)).await.unwrap();
+        let updated = get_event(&db, event_id).await.unwrap().unwrap();
+        assert_eq!(updated.data_schema, Some(schema));
+        update_event_schema(&db, event_id, None).await.unwrap();
+        let updated = get_event(&db, event_id).await.unwrap().unwrap();
+        assert!(updated.data_schema.is_none());
 
         delete_event(&db, event_id).await.unwrap();
         assert!(get_event(&db, event_id).await.unwrap().is_none());
@@ -909,12 +1032,12 @@ mod tests {
             .unwrap();
 
         let template_id = Uuid::now_v7();
-        create_template(&db, template_id, project_id, "Welcome", "email")
+        create_template(&db, template_id, project_id, "Welcome", "email", "html")
             .await
             .unwrap();
 
         let rule_id = Uuid::now_v7();
-        create_rule(&db, rule_id, event_id, "email", template_id, 10)
+        create_rule(&db, rule_id, event_id, "email", template_id, 10, Some("marketing"))
             .await
             .unwrap();
 
@@ -922,13 +1045,19 @@ mod tests {
         assert_eq!(rules.len(), 1);
         assert_eq!(rules[0].channel, "email");
         assert_eq!(rules[0].priority, 10);
+        assert_eq!(rules[0].credential.as_deref(), Some("marketing"));
 
-        update_rule(&db, rule_id, "sms", template_id, false, 5)
+        let events = list_events_using_template(&db, template_id).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, event_id);
+
+        update_rule(&db, rule_id, "sms", template_id, false, 5, None)
             .await
             .unwrap();
         let updated = list_rules(&db, event_id).await.unwrap();
         assert_eq!(updated[0].channel, "sms");
         assert!(!updated[0].enabled);
+        assert_eq!(updated[0].credential, None);
 
         delete_rule(&db, rule_id).await.unwrap();
         assert!(list_rules(&db, event_id).await.unwrap().is_empty());
@@ -941,34 +1070,73 @@ mod tests {
         create_project(&db, project_id, "P1", "en").await.unwrap();
 
         let template_id = Uuid::now_v7();
-        create_template(&db, template_id, project_id, "Welcome Email", "email")
+        create_template(&db, template_id, project_id, "Welcome Email", "email", "html")
             .await
             .unwrap();
 
         let templates = list_templates(&db, project_id).await.unwrap();
         assert_eq!(templates.len(), 1);
         assert_eq!(templates[0].name, "Welcome Email");
+        assert_eq!(templates[0].format, "html");
+
+        update_template(&db, template_id, "Welcome", "mjml")
+            .await
+            .unwrap();
+        let updated = get_template(&db, template_id).await.unwrap().unwrap();
+        assert_eq!(updated.name, "Welcome");
+        assert_eq!(updated.format, "mjml");
 
         let body = json!({"subject": "Welcome {{ name }}", "text": "Hello {{ name }}"});
-        set_template_content(&db, template_id, "en", &body)
+        let version = set_template_content(&db, template_id, "en", &body)
+            .await
+            .unwrap()
+            .version;
+        assert_eq!(version, 1);
+
+        // Drafts are not live until published
+        assert!(
+            crate::repo::template::resolve_template(
+                &db,
+                template_id,
+                &notifico_core::locale::fallback_chain("en", &[], &Default::default()),
+            )
+                .await
+                .unwrap()
+                .is_none()
+        );
+        crate::repo::template_version::publish_version(&db, template_id, version)
             .await
             .unwrap();
 
         // Verify via existing resolve_template
         let resolved =
-            crate::repo::template::resolve_template(&db, template_id, "en", "en")
+            crate::repo::template::resolve_template(
+                &db,
+                template_id,
+                &notifico_core::locale::fallback_chain("en", &[], &Default::default()),
+            )
                 .await
                 .unwrap()
                 .unwrap();
         assert_eq!(resolved.body["subject"], "Welcome {{ name }}");
+        assert_eq!(resolved.format, "mjml");
 
-        // Update content (idempotent)
+        // Update content (opens draft v2, then publish it)
         let body2 = json!({"subject": "Hi {{ name }}", "text": "Updated"});
-        set_template_content(&db, template_id, "en", &body2)
+        let version = set_template_content(&db, template_id, "en", &body2)
+            .await
+            .unwrap()
+            .version;
+        assert_eq!(version, 2);
+        crate::repo::template_version::publish_version(&db, template_id, version)
             .await
             .unwrap();
         let resolved2 =
-            crate::repo::template::resolve_template(&db, template_id, "en", "en")
+            crate::repo::template::resolve_template(
+                &db,
+                template_id,
+                &notifico_core::locale::fallback_chain("en", &[], &Default::default()),
+            )
                 .await
                 .unwrap()
                 .unwrap();
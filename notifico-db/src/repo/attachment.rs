@@ -0,0 +1,131 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement};
+use uuid::Uuid;
+
+/// Metadata of an uploaded attachment. The content itself lives in the
+/// storage backend under `storage_key`.
+#[derive(Debug, Clone)]
+pub struct AttachmentRow {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    pub storage_key: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct AttachmentRaw {
+    id: String,
+    project_id: String,
+    filename: String,
+    content_type: String,
+    size: i64,
+    storage_key: String,
+    created_at: String,
+}
+
+impl AttachmentRaw {
+    fn into_row(self) -> Result<AttachmentRow, DbErr> {
+        Ok(AttachmentRow {
+            id: Uuid::parse_str(&self.id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            project_id: Uuid::parse_str(&self.project_id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            filename: self.filename,
+            content_type: self.content_type,
+            size: self.size,
+            storage_key: self.storage_key,
+            created_at: self.created_at,
+        })
+    }
+}
+
+/// Record an uploaded attachment.
+pub async fn insert_attachment(
+    db: &DatabaseConnection,
+    id: Uuid,
+    project_id: Uuid,
+    filename: &str,
+    content_type: &str,
+    size: i64,
+    storage_key: &str,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO attachment (id, project_id, filename, content_type, size, storage_key) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+        [
+            id.to_string().into(),
+            project_id.to_string().into(),
+            filename.into(),
+            content_type.into(),
+            size.into(),
+            storage_key.into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Get an attachment by id, scoped to a project.
+pub async fn get_attachment(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<Option<AttachmentRow>, DbErr> {
+    let row = AttachmentRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT id, project_id, filename, content_type, size, storage_key, created_at \
+         FROM attachment WHERE id = ? AND project_id = ?",
+        [id.to_string().into(), project_id.to_string().into()],
+    ))
+    .one(db)
+    .await?;
+    row.map(|r| r.into_row()).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connect, run_migrations};
+
+    #[tokio::test]
+    async fn insert_and_get_scoped_to_project() {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+
+        let project_id = Uuid::now_v7();
+        db.execute_unprepared(&format!(
+            "INSERT INTO project (id, name) VALUES ('{project_id}', 'test')"
+        ))
+        .await
+        .unwrap();
+
+        let id = Uuid::now_v7();
+        insert_attachment(
+            &db,
+            id,
+            project_id,
+            "invoice.pdf",
+            "application/pdf",
+            1024,
+            "attachments/x/y",
+        )
+        .await
+        .unwrap();
+
+        let row = get_attachment(&db, project_id, id).await.unwrap().unwrap();
+        assert_eq!(row.filename, "invoice.pdf");
+        assert_eq!(row.content_type, "application/pdf");
+        assert_eq!(row.size, 1024);
+        assert_eq!(row.storage_key, "attachments/x/y");
+
+        assert!(
+            get_attachment(&db, Uuid::now_v7(), id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
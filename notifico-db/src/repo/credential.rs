@@ -14,6 +14,7 @@ pub struct CredentialRow {
     pub channel: String,
     pub data: Value,
     pub enabled: bool,
+    pub is_default: bool,
 }
 
 #[derive(Debug, Clone, FromQueryResult)]
@@ -24,6 +25,7 @@ struct CredentialRaw {
     channel: String,
     encrypted_data: String,
     enabled: bool,
+    is_default: bool,
 }
 
 impl CredentialRaw {
@@ -40,6 +42,7 @@ impl CredentialRaw {
             channel: self.channel,
             data,
             enabled: self.enabled,
+            is_default: self.is_default,
         })
     }
 }
@@ -113,17 +116,100 @@ pub async fn insert_credential(
     Ok(())
 }
 
-/// Find the first enabled credential for a project + channel, decrypted.
+/// Make a credential the project default for its channel, clearing the flag
+/// on any other credential of that channel. Returns false if it doesn't exist.
+pub async fn set_default(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<bool, DbErr> {
+    let Some(channel) = db
+        .query_one_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT channel FROM credential WHERE id = ? AND project_id = ?",
+            [id.to_string().into(), project_id.to_string().into()],
+        ))
+        .await?
+        .map(|row| row.try_get::<String>("", "channel"))
+        .transpose()?
+    else {
+        return Ok(false);
+    };
+
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "UPDATE credential SET is_default = (id = ?) WHERE project_id = ? AND channel = ?",
+        [
+            id.to_string().into(),
+            project_id.to_string().into(),
+            channel.into(),
+        ],
+    ))
+    .await?;
+    Ok(true)
+}
+
+/// Find the enabled credential for a project + channel, decrypted.
+///
+/// With a `name` only that credential qualifies. Without one, the channel's
+/// project default is preferred, then the oldest enabled credential.
 pub async fn find_credential(
     db: &DatabaseConnection,
     project_id: Uuid,
     channel: &str,
+    name: Option<&str>,
+    key: &[u8; 32],
+) -> Result<Option<CredentialRow>, DbErr> {
+    let stmt = match name {
+        Some(name) => Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT id, project_id, name, channel, encrypted_data, enabled, is_default FROM credential WHERE project_id = ? AND channel = ? AND name = ? AND enabled = true",
+            [project_id.to_string().into(), channel.into(), name.into()],
+        ),
+        None => Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT id, project_id, name, channel, encrypted_data, enabled, is_default FROM credential WHERE project_id = ? AND channel = ? AND enabled = true ORDER BY is_default DESC, created_at, id LIMIT 1",
+            [project_id.to_string().into(), channel.into()],
+        ),
+    };
+    let raw = CredentialRaw::find_by_statement(stmt).one(db).await?;
+
+    match raw {
+        Some(r) => Ok(Some(r.into_row(key)?)),
+        None => Ok(None),
+    }
+}
+
+/// Whether the project has a credential with this name for the channel.
+pub async fn credential_exists(
+    db: &impl ConnectionTrait,
+    project_id: Uuid,
+    channel: &str,
+    name: &str,
+) -> Result<bool, DbErr> {
+    let row = db
+        .query_one_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT 1 AS found FROM credential WHERE project_id = ? AND channel = ? AND name = ?",
+            [project_id.to_string().into(), channel.into(), name.into()],
+        ))
+        .await?;
+    Ok(row.is_some())
+}
+
+/// Find an enabled credential by ID, decrypted.
+///
+/// Used by provider callbacks, which identify the credential (and through
+/// it the project) from the callback URL rather than an API key.
+pub async fn find_credential_by_id(
+    db: &DatabaseConnection,
+    id: Uuid,
     key: &[u8; 32],
 ) -> Result<Option<CredentialRow>, DbErr> {
     let raw = CredentialRaw::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT id, project_id, name, channel, encrypted_data, enabled FROM credential WHERE project_id = ? AND channel = ? AND enabled = true LIMIT 1",
-        [project_id.to_string().into(), channel.into()],
+        "SELECT id, project_id, name, channel, encrypted_data, enabled, is_default FROM credential WHERE id = ? AND enabled = true",
+        [id.to_string().into()],
     ))
     .one(db)
     .await?;
@@ -187,7 +273,7 @@ mod tests {
         .await
         .unwrap();
 
-        let found = find_credential(&db, test_project_id(), "email", &key)
+        let found = find_credential(&db, test_project_id(), "email", None, &key)
             .await
             .unwrap()
             .expect("Should find credential");
@@ -204,7 +290,7 @@ mod tests {
         let db = setup().await;
         let key = test_key();
 
-        let found = find_credential(&db, test_project_id(), "sms", &key)
+        let found = find_credential(&db, test_project_id(), "sms", None, &key)
             .await
             .unwrap();
         assert!(found.is_none());
@@ -230,9 +316,56 @@ mod tests {
         .await
         .unwrap();
 
-        let found = find_credential(&db, test_project_id(), "email", &key)
+        let found = find_credential(&db, test_project_id(), "email", None, &key)
+            .await
+            .unwrap();
+        assert!(found.is_none());
+    }
+
+    #[tokio::test]
+    async fn named_credential_and_default_fallback() {
+        let db = setup().await;
+        let key = test_key();
+        let (first, marketing, transactional) = (Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7());
+
+        for (id, name) in [(first, "legacy"), (marketing, "marketing"), (transactional, "transactional")] {
+            insert_credential(
+                &db, id, test_project_id(), name, "email",
+                &json!({"smtp_host": format!("{name}.example.com")}), &key,
+            )
+            .await
+            .unwrap();
+        }
+
+        // Without a default the oldest credential is used.
+        let find = |name| find_credential(&db, test_project_id(), "email", name, &key);
+        assert_eq!(find(None).await.unwrap().unwrap().id, first);
+        assert!(set_default(&db, test_project_id(), transactional).await.unwrap());
+
+        assert_eq!(find(Some("marketing")).await.unwrap().unwrap().id, marketing);
+        assert_eq!(find(None).await.unwrap().unwrap().id, transactional);
+        assert!(find(Some("missing")).await.unwrap().is_none());
+
+        // Moving the default clears the previous one.
+        assert!(set_default(&db, test_project_id(), marketing).await.unwrap());
+        let found = find(None).await.unwrap().unwrap();
+        assert_eq!(found.id, marketing);
+        assert!(found.is_default);
+
+        // Names are scoped to the channel.
+        let found = find_credential(&db, test_project_id(), "sms", Some("marketing"), &key)
             .await
             .unwrap();
         assert!(found.is_none());
+        assert!(
+            credential_exists(&db, test_project_id(), "email", "marketing")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !credential_exists(&db, test_project_id(), "sms", "marketing")
+                .await
+                .unwrap()
+        );
     }
 }
@@ -13,6 +13,13 @@ pub struct DeliveryLogRow {
     pub attempts: i32,
     pub created_at: String,
     pub delivered_at: Option<String>,
+    pub provider_message_id: Option<String>,
+    pub template_version: Option<i32>,
+    /// A/B experiment and variant the delivery belongs to, if any.
+    pub experiment: Option<String>,
+    pub variant: Option<String>,
+    /// Name of the credential the delivery was sent through.
+    pub credential: Option<String>,
 }
 
 #[derive(Debug, Clone, FromQueryResult)]
@@ -27,6 +34,11 @@ struct DeliveryLogRaw {
     attempts: i32,
     created_at: String,
     delivered_at: Option<String>,
+    provider_message_id: Option<String>,
+    template_version: Option<i32>,
+    experiment: Option<String>,
+    variant: Option<String>,
+    credential: Option<String>,
 }
 
 impl DeliveryLogRaw {
@@ -45,23 +57,38 @@ impl DeliveryLogRaw {
             attempts: self.attempts,
             created_at: self.created_at,
             delivered_at: self.delivered_at,
+            provider_message_id: self.provider_message_id,
+            template_version: self.template_version,
+            experiment: self.experiment,
+            variant: self.variant,
+            credential: self.credential,
         })
     }
 }
 
+/// A delivery attempt to record.
+#[derive(Debug, Clone, Default)]
+pub struct NewDeliveryLog<'a> {
+    pub id: Uuid,
+    /// Delivery task the attempt belongs to.
+    pub task_id: Option<Uuid>,
+    pub project_id: Uuid,
+    pub event_name: &'a str,
+    pub recipient_id: Uuid,
+    pub channel: &'a str,
+    pub status: &'a str,
+    pub error_message: Option<&'a str>,
+    pub attempts: i32,
+    pub provider_message_id: Option<&'a str>,
+    pub template_version: Option<i32>,
+    pub experiment: Option<&'a str>,
+    pub variant: Option<&'a str>,
+    pub credential: Option<&'a str>,
+}
+
 /// Insert a delivery log entry.
-pub async fn insert_log(
-    db: &DatabaseConnection,
-    id: Uuid,
-    project_id: Uuid,
-    event_name: &str,
-    recipient_id: Uuid,
-    channel: &str,
-    status: &str,
-    error_message: Option<&str>,
-    attempts: i32,
-) -> Result<(), DbErr> {
-    let delivered_at = if status == "delivered" {
+pub async fn insert_log(db: &DatabaseConnection, log: &NewDeliveryLog<'_>) -> Result<(), DbErr> {
+    let delivered_at = if log.status == "delivered" {
         "CURRENT_TIMESTAMP"
     } else {
         "NULL"
@@ -71,18 +98,24 @@ pub async fn insert_log(
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
         &format!(
-            "INSERT INTO delivery_log (id, project_id, event_name, recipient_id, channel, status, error_message, attempts, delivered_at) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, {delivered_at})"
+            "INSERT INTO delivery_log (id, task_id, project_id, event_name, recipient_id, channel, status, error_message, attempts, provider_message_id, template_version, experiment, variant, credential, delivered_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, {delivered_at})"
         ),
         [
-            id.to_string().into(),
-            project_id.to_string().into(),
-            event_name.into(),
-            recipient_id.to_string().into(),
-            channel.into(),
-            status.into(),
-            error_message.map(|s| s.to_string()).into(),
-            attempts.into(),
+            log.id.to_string().into(),
+            log.task_id.map(|t| t.to_string()).into(),
+            log.project_id.to_string().into(),
+            log.event_name.into(),
+            log.recipient_id.to_string().into(),
+            log.channel.into(),
+            log.status.into(),
+            log.error_message.map(|s| s.to_string()).into(),
+            log.attempts.into(),
+            log.provider_message_id.map(|s| s.to_string()).into(),
+            log.template_version.into(),
+            log.experiment.map(|s| s.to_string()).into(),
+            log.variant.map(|s| s.to_string()).into(),
+            log.credential.map(|s| s.to_string()).into(),
         ],
     ))
     .await?;
@@ -99,7 +132,7 @@ pub async fn list_logs(
     offset: u64,
 ) -> Result<Vec<DeliveryLogRow>, DbErr> {
     let mut sql = String::from(
-        "SELECT id, project_id, event_name, recipient_id, channel, status, error_message, attempts, created_at, delivered_at \
+        "SELECT id, project_id, event_name, recipient_id, channel, status, error_message, attempts, created_at, delivered_at, provider_message_id, template_version, experiment, variant, credential \
          FROM delivery_log WHERE project_id = ?"
     );
     let mut params: Vec<sea_orm::Value> = vec![project_id.to_string().into()];
@@ -125,6 +158,77 @@ pub async fn list_logs(
     rows.into_iter().map(|r| r.into_row()).collect()
 }
 
+/// Get a delivery log by id.
+pub async fn get_log(db: &DatabaseConnection, id: Uuid) -> Result<Option<DeliveryLogRow>, DbErr> {
+    let row = DeliveryLogRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT id, project_id, event_name, recipient_id, channel, status, error_message, attempts, created_at, delivered_at, provider_message_id, template_version, experiment, variant, credential \
+         FROM delivery_log WHERE id = ?",
+        [id.to_string().into()],
+    ))
+    .one(db)
+    .await?;
+    row.map(|r| r.into_row()).transpose()
+}
+
+/// Update the status of the most recent delivery log matching a provider message ID.
+///
+/// Used by provider status callbacks (delivery receipts, bounces). Returns the
+/// updated row, or `None` if no log in the project carries that ID.
+pub async fn update_status_by_provider_message_id(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    channel: &str,
+    provider_message_id: &str,
+    status: &str,
+    error_message: Option<&str>,
+) -> Result<Option<DeliveryLogRow>, DbErr> {
+    let row = DeliveryLogRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT id, project_id, event_name, recipient_id, channel, status, error_message, attempts, created_at, delivered_at, provider_message_id, template_version, experiment, variant, credential \
+         FROM delivery_log WHERE project_id = ? AND channel = ? AND provider_message_id = ? \
+         ORDER BY created_at DESC LIMIT 1",
+        [
+            project_id.to_string().into(),
+            channel.into(),
+            provider_message_id.into(),
+        ],
+    ))
+    .one(db)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let mut row = row.into_row()?;
+
+    let delivered_at = if status == "delivered" {
+        "CURRENT_TIMESTAMP"
+    } else {
+        "delivered_at"
+    };
+
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "UPDATE delivery_log SET status = ?, error_message = COALESCE(?, error_message), delivered_at = {delivered_at} \
+             WHERE id = ?"
+        ),
+        [
+            status.into(),
+            error_message.map(|s| s.to_string()).into(),
+            row.id.to_string().into(),
+        ],
+    ))
+    .await?;
+
+    row.status = status.to_string();
+    if let Some(msg) = error_message {
+        row.error_message = Some(msg.to_string());
+    }
+    Ok(Some(row))
+}
+
 /// Count delivery logs for a project with optional filters.
 pub async fn count_logs(
     db: &DatabaseConnection,
@@ -185,6 +289,46 @@ pub async fn count_by_event_name(
     Ok(rows.into_iter().map(|r| (r.status, r.cnt)).collect())
 }
 
+/// Outcome counts for one variant of an A/B experiment.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct VariantStatsRow {
+    pub variant: String,
+    /// Deliveries with a final outcome (delivered, failed, undelivered or
+    /// bounced); attempts still queued for retry are not counted.
+    pub sent: i64,
+    pub delivered: i64,
+    /// Sent deliveries whose task has at least one open or click tracking
+    /// event.
+    pub opened: i64,
+    pub clicked: i64,
+}
+
+/// Per-variant delivery, open and click counts of an experiment, ordered by
+/// variant name.
+pub async fn variant_stats(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    experiment: &str,
+) -> Result<Vec<VariantStatsRow>, DbErr> {
+    VariantStatsRow::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT dl.variant AS variant, \
+           COUNT(*) AS sent, \
+           SUM(CASE WHEN dl.status = 'delivered' THEN 1 ELSE 0 END) AS delivered, \
+           SUM(CASE WHEN EXISTS (SELECT 1 FROM tracking_event te \
+             WHERE te.task_id = dl.task_id AND te.event_type = 'open') THEN 1 ELSE 0 END) AS opened, \
+           SUM(CASE WHEN EXISTS (SELECT 1 FROM tracking_event te \
+             WHERE te.task_id = dl.task_id AND te.event_type = 'click') THEN 1 ELSE 0 END) AS clicked \
+         FROM delivery_log dl \
+         WHERE dl.project_id = ? AND dl.experiment = ? AND dl.variant IS NOT NULL \
+           AND dl.status IN ('delivered', 'failed', 'undelivered', 'bounced') \
+         GROUP BY dl.variant ORDER BY dl.variant",
+        [project_id.to_string().into(), experiment.into()],
+    ))
+    .all(db)
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,33 +359,31 @@ mod tests {
     async fn insert_and_list_logs() {
         let (db, project_id, recipient_id) = setup().await;
 
-        insert_log(
-            &db,
-            Uuid::now_v7(),
-            project_id,
-            "order.confirmed",
-            recipient_id,
-            "email",
-            "delivered",
-            None,
-            1,
-        )
-        .await
-        .unwrap();
-
-        insert_log(
-            &db,
-            Uuid::now_v7(),
+        let log = NewDeliveryLog {
+            id: Uuid::now_v7(),
             project_id,
-            "order.confirmed",
+            event_name: "order.confirmed",
             recipient_id,
-            "sms",
-            "failed",
-            Some("SMTP timeout"),
-            3,
-        )
-        .await
-        .unwrap();
+            channel: "email",
+            status: "delivered",
+            attempts: 1,
+            template_version: Some(3),
+            credential: Some("transactional"),
+            ..Default::default()
+        };
+        insert_log(&db, &log).await.unwrap();
+
+        let log = NewDeliveryLog {
+            id: Uuid::now_v7(),
+            channel: "sms",
+            status: "failed",
+            error_message: Some("SMTP timeout"),
+            attempts: 3,
+            template_version: None,
+            credential: None,
+            ..log
+        };
+        insert_log(&db, &log).await.unwrap();
 
         let all = list_logs(&db, project_id, None, None, 50, 0).await.unwrap();
         assert_eq!(all.len(), 2);
@@ -251,6 +393,8 @@ mod tests {
             .unwrap();
         assert_eq!(delivered.len(), 1);
         assert_eq!(delivered[0].channel, "email");
+        assert_eq!(delivered[0].template_version, Some(3));
+        assert_eq!(delivered[0].credential.as_deref(), Some("transactional"));
 
         let count = count_logs(&db, project_id, None, None).await.unwrap();
         assert_eq!(count, 2);
@@ -264,19 +408,17 @@ mod tests {
         let (db, project_id, recipient_id) = setup().await;
 
         for i in 0..5 {
-            insert_log(
-                &db,
-                Uuid::now_v7(),
+            let log = NewDeliveryLog {
+                id: Uuid::now_v7(),
                 project_id,
-                &format!("event.{i}"),
+                event_name: &format!("event.{i}"),
                 recipient_id,
-                "email",
-                "delivered",
-                None,
-                1,
-            )
-            .await
-            .unwrap();
+                channel: "email",
+                status: "delivered",
+                attempts: 1,
+                ..Default::default()
+            };
+            insert_log(&db, &log).await.unwrap();
         }
 
         let page1 = list_logs(&db, project_id, None, None, 2, 0).await.unwrap();
@@ -288,4 +430,133 @@ mod tests {
         let page3 = list_logs(&db, project_id, None, None, 2, 4).await.unwrap();
         assert_eq!(page3.len(), 1);
     }
+
+    #[tokio::test]
+    async fn update_status_by_provider_message_id_updates_latest_log() {
+        let (db, project_id, recipient_id) = setup().await;
+
+        let log = NewDeliveryLog {
+            id: Uuid::now_v7(),
+            project_id,
+            event_name: "otp",
+            recipient_id,
+            channel: "sms",
+            status: "delivered",
+            attempts: 1,
+            provider_message_id: Some("SM123"),
+            ..Default::default()
+        };
+        insert_log(&db, &log).await.unwrap();
+
+        let updated = update_status_by_provider_message_id(
+            &db,
+            project_id,
+            "sms",
+            "SM123",
+            "undelivered",
+            Some("30003: Unreachable destination handset"),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(updated.status, "undelivered");
+        assert_eq!(updated.provider_message_id.as_deref(), Some("SM123"));
+
+        let logs = list_logs(&db, project_id, Some("undelivered"), None, 50, 0)
+            .await
+            .unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            logs[0].error_message.as_deref(),
+            Some("30003: Unreachable destination handset")
+        );
+
+        // Unknown ID or other project: nothing to update
+        let missing =
+            update_status_by_provider_message_id(&db, project_id, "sms", "SM999", "delivered", None)
+                .await
+                .unwrap();
+        assert!(missing.is_none());
+        let other_project = update_status_by_provider_message_id(
+            &db,
+            Uuid::now_v7(),
+            "sms",
+            "SM123",
+            "delivered",
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(other_project.is_none());
+    }
+
+    #[tokio::test]
+    async fn variant_stats_count_outcomes_and_tracking() {
+        let (db, project_id, recipient_id) = setup().await;
+
+        // Tracking events reference the delivery task, which has one log per
+        // attempt
+        let log = |task_id: Uuid, variant: &'static str, status: &'static str| {
+            let db = db.clone();
+            async move {
+                let log = NewDeliveryLog {
+                    id: Uuid::now_v7(),
+                    task_id: Some(task_id),
+                    project_id,
+                    event_name: "promo",
+                    recipient_id,
+                    channel: "email",
+                    status,
+                    attempts: 1,
+                    template_version: Some(1),
+                    experiment: Some("subject-oct"),
+                    variant: Some(variant),
+                    ..Default::default()
+                };
+                insert_log(&db, &log).await.unwrap();
+            }
+        };
+        let [a1, a2, a3, a4, b1] = [1, 2, 3, 4, 5].map(Uuid::from_u128);
+        log(a1, "A", "delivered").await;
+        log(a2, "A", "queued").await;
+        log(a2, "A", "delivered").await;
+        log(a3, "A", "queued").await;
+        log(a4, "A", "failed").await;
+        log(b1, "B", "bounced").await;
+
+        let events = [(a1, "open"), (a1, "open"), (a1, "click"), (a2, "open"), (b1, "click")];
+        for (id, event_type) in events {
+            crate::repo::tracking::insert_tracking_event(
+                &db,
+                Uuid::now_v7(),
+                &id.to_string(),
+                event_type,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let stats = variant_stats(&db, project_id, "subject-oct").await.unwrap();
+        assert_eq!(
+            stats,
+            [
+                VariantStatsRow {
+                    variant: "A".into(),
+                    sent: 3,
+                    delivered: 2,
+                    opened: 2,
+                    clicked: 1,
+                },
+                VariantStatsRow {
+                    variant: "B".into(),
+                    sent: 1,
+                    delivered: 0,
+                    opened: 0,
+                    clicked: 1,
+                },
+            ]
+        );
+        assert!(variant_stats(&db, project_id, "other").await.unwrap().is_empty());
+    }
 }
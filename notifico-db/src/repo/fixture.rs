@@ -0,0 +1,353 @@
+use std::collections::BTreeMap;
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// A named sample payload for previewing and testing templates. It belongs
+/// to either a template or an event; an event's fixtures apply to every
+/// template its rules (and rule variants) use.
+#[derive(Debug, Clone)]
+pub struct FixtureRow {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub template_id: Option<Uuid>,
+    pub event_id: Option<Uuid>,
+    pub name: String,
+    pub data: Value,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct FixtureRaw {
+    id: String,
+    project_id: String,
+    template_id: Option<String>,
+    event_id: Option<String>,
+    name: String,
+    data: String,
+    created_at: String,
+    updated_at: String,
+}
+
+fn parse_uuid(s: &str) -> Result<Uuid, DbErr> {
+    Uuid::parse_str(s).map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))
+}
+
+impl FixtureRaw {
+    fn into_row(self) -> Result<FixtureRow, DbErr> {
+        Ok(FixtureRow {
+            id: parse_uuid(&self.id)?,
+            project_id: parse_uuid(&self.project_id)?,
+            template_id: self.template_id.as_deref().map(parse_uuid).transpose()?,
+            event_id: self.event_id.as_deref().map(parse_uuid).transpose()?,
+            name: self.name,
+            data: serde_json::from_str(&self.data)
+                .map_err(|e| DbErr::Custom(format!("invalid fixture data: {e}")))?,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+const FIXTURE_COLUMNS: &str =
+    "id, project_id, template_id, event_id, name, data, created_at, updated_at";
+
+async fn query(
+    db: &DatabaseConnection,
+    filter: &str,
+    values: Vec<sea_orm::Value>,
+) -> Result<Vec<FixtureRow>, DbErr> {
+    let rows = FixtureRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!("SELECT {FIXTURE_COLUMNS} FROM template_fixture WHERE {filter} ORDER BY name, id"),
+        values,
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter().map(|r| r.into_row()).collect()
+}
+
+/// Fixtures attached directly to a template, ordered by name.
+pub async fn list_by_template(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+) -> Result<Vec<FixtureRow>, DbErr> {
+    query(db, "template_id = ?", vec![template_id.to_string().into()]).await
+}
+
+/// Fixtures attached to an event, ordered by name.
+pub async fn list_by_event(
+    db: &DatabaseConnection,
+    event_id: Uuid,
+) -> Result<Vec<FixtureRow>, DbErr> {
+    query(db, "event_id = ?", vec![event_id.to_string().into()]).await
+}
+
+/// Every fixture a template renders with: its own, plus those of events
+/// whose rules or rule variants use it.
+pub async fn list_for_template(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+) -> Result<Vec<FixtureRow>, DbErr> {
+    let id = template_id.to_string();
+    query(
+        db,
+        "template_id = ? OR event_id IN ( \
+           SELECT event_id FROM pipeline_rule WHERE template_id = ? \
+           UNION SELECT r.event_id FROM pipeline_rule_variant v \
+             JOIN pipeline_rule r ON r.id = v.rule_id WHERE v.template_id = ?)",
+        vec![id.clone().into(), id.clone().into(), id.into()],
+    )
+    .await
+}
+
+/// Get a fixture by id, scoped to a project.
+pub async fn get_fixture(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<Option<FixtureRow>, DbErr> {
+    Ok(query(
+        db,
+        "id = ? AND project_id = ?",
+        vec![id.to_string().into(), project_id.to_string().into()],
+    )
+    .await?
+    .pop())
+}
+
+/// Insert a fixture owned by exactly one of `template_id` or `event_id`.
+pub async fn insert_fixture(
+    db: &DatabaseConnection,
+    id: Uuid,
+    project_id: Uuid,
+    template_id: Option<Uuid>,
+    event_id: Option<Uuid>,
+    name: &str,
+    data: &Value,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO template_fixture (id, project_id, template_id, event_id, name, data) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+        [
+            id.to_string().into(),
+            project_id.to_string().into(),
+            template_id.map(|t| t.to_string()).into(),
+            event_id.map(|e| e.to_string()).into(),
+            name.into(),
+            data.to_string().into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Update a fixture. Returns `false` if it does not exist.
+pub async fn update_fixture(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+    name: &str,
+    data: &Value,
+) -> Result<bool, DbErr> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "UPDATE template_fixture SET name = ?, data = ?, updated_at = datetime('now') \
+             WHERE id = ? AND project_id = ?",
+            [
+                name.into(),
+                data.to_string().into(),
+                id.to_string().into(),
+                project_id.to_string().into(),
+            ],
+        ))
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a fixture and its snapshots. Returns `false` if it does not exist.
+pub async fn delete_fixture(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<bool, DbErr> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "DELETE FROM template_fixture WHERE id = ? AND project_id = ?",
+            [id.to_string().into(), project_id.to_string().into()],
+        ))
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// ── Snapshots ────────────────────────────────────────────────────────
+
+/// Snapshots are keyed by fixture id and locale.
+pub type SnapshotKey = (Uuid, String);
+
+#[derive(Debug, FromQueryResult)]
+struct SnapshotRaw {
+    fixture_id: String,
+    locale: String,
+    rendered: String,
+}
+
+/// The accepted render outputs of a template.
+pub async fn list_snapshots(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+) -> Result<BTreeMap<SnapshotKey, Value>, DbErr> {
+    let rows = SnapshotRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT fixture_id, locale, rendered FROM template_snapshot WHERE template_id = ?",
+        [template_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter()
+        .map(|r| {
+            let rendered = serde_json::from_str(&r.rendered)
+                .map_err(|e| DbErr::Custom(format!("invalid snapshot: {e}")))?;
+            Ok(((parse_uuid(&r.fixture_id)?, r.locale), rendered))
+        })
+        .collect()
+}
+
+/// Replace all snapshots of a template.
+pub async fn replace_snapshots(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+    snapshots: &BTreeMap<SnapshotKey, Value>,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "DELETE FROM template_snapshot WHERE template_id = ?",
+        [template_id.to_string().into()],
+    ))
+    .await?;
+    for ((fixture_id, locale), rendered) in snapshots {
+        db.execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "INSERT INTO template_snapshot (id, template_id, fixture_id, locale, rendered) \
+             VALUES (?, ?, ?, ?, ?)",
+            [
+                Uuid::now_v7().to_string().into(),
+                template_id.to_string().into(),
+                fixture_id.to_string().into(),
+                locale.as_str().into(),
+                rendered.to_string().into(),
+            ],
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::admin;
+    use crate::{connect, run_migrations};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn fixtures_apply_to_templates_of_their_event() {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let project_id = Uuid::now_v7();
+        admin::create_project(&db, project_id, "P1", "en")
+            .await
+            .unwrap();
+        let (a, b) = (Uuid::now_v7(), Uuid::now_v7());
+        admin::create_template(&db, a, project_id, "a", "email", "html")
+            .await
+            .unwrap();
+        admin::create_template(&db, b, project_id, "b", "email", "html")
+            .await
+            .unwrap();
+        let event_id = Uuid::now_v7();
+        admin::create_event(&db, event_id, project_id, "order.paid", "transactional")
+            .await
+            .unwrap();
+        admin::create_rule(&db, Uuid::now_v7(), event_id, "email", a, 0, None)
+            .await
+            .unwrap();
+
+        let own = Uuid::now_v7();
+        insert_fixture(
+            &db,
+            own,
+            project_id,
+            Some(b),
+            None,
+            "vip",
+            &json!({"vip": true}),
+        )
+        .await
+        .unwrap();
+        let shared = Uuid::now_v7();
+        insert_fixture(
+            &db,
+            shared,
+            project_id,
+            None,
+            Some(event_id),
+            "basic",
+            &json!({}),
+        )
+        .await
+        .unwrap();
+        // Exactly one owner, and names are unique per owner
+        assert!(
+            insert_fixture(&db, Uuid::now_v7(), project_id, None, None, "x", &json!({}))
+                .await
+                .is_err()
+        );
+        assert!(
+            insert_fixture(
+                &db,
+                Uuid::now_v7(),
+                project_id,
+                Some(b),
+                None,
+                "vip",
+                &json!({})
+            )
+            .await
+            .is_err()
+        );
+
+        let names = |rows: Vec<FixtureRow>| rows.into_iter().map(|f| f.name).collect::<Vec<_>>();
+        assert_eq!(names(list_for_template(&db, a).await.unwrap()), ["basic"]);
+        assert_eq!(names(list_for_template(&db, b).await.unwrap()), ["vip"]);
+        assert_eq!(
+            names(list_by_event(&db, event_id).await.unwrap()),
+            ["basic"]
+        );
+
+        let snapshots = BTreeMap::from([((own, "en".to_string()), json!({"subject": "Hi"}))]);
+        replace_snapshots(&db, b, &snapshots).await.unwrap();
+        assert_eq!(list_snapshots(&db, b).await.unwrap(), snapshots);
+
+        assert!(
+            update_fixture(&db, project_id, own, "gold", &json!({"vip": 2}))
+                .await
+                .unwrap()
+        );
+        let fixture = get_fixture(&db, project_id, own).await.unwrap().unwrap();
+        assert_eq!(
+            (fixture.name.as_str(), fixture.data["vip"].clone()),
+            ("gold", json!(2))
+        );
+
+        // Deleting a fixture drops its snapshots
+        assert!(delete_fixture(&db, project_id, own).await.unwrap());
+        assert!(!delete_fixture(&db, project_id, own).await.unwrap());
+        assert!(list_snapshots(&db, b).await.unwrap().is_empty());
+    }
+}
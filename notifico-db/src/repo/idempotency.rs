@@ -1,4 +1,5 @@
-use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, Statement};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement};
+use serde_json::Value;
 use uuid::Uuid;
 
 /// Build a compound idempotency key: event_name + recipient_id + channel + optional client key.
@@ -14,12 +15,40 @@ pub fn make_idempotency_key(
     }
 }
 
-/// Check if an idempotency key already exists. If not, insert it and return `false`.
-/// If it already exists, return `true` (duplicate).
+/// Build the key under which a whole ingest request's response is cached.
+pub fn make_request_key(project_id: Uuid, event_name: &str, client_key: &str) -> String {
+    format!("request:{project_id}:{event_name}:{client_key}")
+}
+
+/// Drop the record for `idempotency_key` if it is older than the window, so
+/// the key can be reused.
+async fn expire_key(
+    db: &DatabaseConnection,
+    idempotency_key: &str,
+    ttl_secs: u64,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "DELETE FROM idempotency_record WHERE idempotency_key = ? \
+             AND created_at <= datetime('now', '-{ttl_secs} seconds')"
+        ),
+        [idempotency_key.into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Check if an idempotency key already exists within the last `ttl_secs`.
+/// If not, insert it and return `false`. If it already exists, return `true`
+/// (duplicate).
 pub async fn check_and_insert(
     db: &DatabaseConnection,
     idempotency_key: &str,
+    ttl_secs: u64,
 ) -> Result<bool, DbErr> {
+    expire_key(db, idempotency_key, ttl_secs).await?;
+
     let exists = db
         .query_one_raw(Statement::from_sql_and_values(
             db.get_database_backend(),
@@ -43,6 +72,77 @@ pub async fn check_and_insert(
     Ok(false)
 }
 
+#[derive(Debug, FromQueryResult)]
+struct ResponseRaw {
+    response: Option<String>,
+}
+
+/// Look up the response stored for `idempotency_key` within the last
+/// `ttl_secs`. Returns `None` if there is no live record or it has no
+/// response attached.
+pub async fn find_response(
+    db: &DatabaseConnection,
+    idempotency_key: &str,
+    ttl_secs: u64,
+) -> Result<Option<Value>, DbErr> {
+    let raw = ResponseRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT response FROM idempotency_record WHERE idempotency_key = ? \
+             AND created_at > datetime('now', '-{ttl_secs} seconds')"
+        ),
+        [idempotency_key.into()],
+    ))
+    .one(db)
+    .await?;
+
+    raw.and_then(|r| r.response)
+        .map(|s| {
+            serde_json::from_str(&s).map_err(|e| DbErr::Custom(format!("invalid JSON: {e}")))
+        })
+        .transpose()
+}
+
+/// Store `response` for `idempotency_key`, replacing any expired record.
+pub async fn store_response(
+    db: &DatabaseConnection,
+    idempotency_key: &str,
+    response: &Value,
+    ttl_secs: u64,
+) -> Result<(), DbErr> {
+    expire_key(db, idempotency_key, ttl_secs).await?;
+
+    let id = Uuid::now_v7();
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO idempotency_record (id, idempotency_key, response) VALUES (?, ?, ?) \
+         ON CONFLICT (idempotency_key) DO UPDATE SET response = excluded.response",
+        [
+            id.to_string().into(),
+            idempotency_key.into(),
+            response.to_string().into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Delete all idempotency records older than `ttl_secs`. Returns the number
+/// of rows removed.
+pub async fn purge_expired(db: &DatabaseConnection, ttl_secs: u64) -> Result<u64, DbErr> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            format!(
+                "DELETE FROM idempotency_record \
+                 WHERE created_at <= datetime('now', '-{ttl_secs} seconds')"
+            ),
+            [],
+        ))
+        .await?;
+    Ok(result.rows_affected())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,7 +169,7 @@ mod tests {
         let db = connect("sqlite::memory:").await.unwrap();
         run_migrations(&db).await.unwrap();
 
-        let is_dup = check_and_insert(&db, "test-key-1").await.unwrap();
+        let is_dup = check_and_insert(&db, "test-key-1", 3600).await.unwrap();
         assert!(!is_dup);
     }
 
@@ -78,10 +178,53 @@ mod tests {
         let db = connect("sqlite::memory:").await.unwrap();
         run_migrations(&db).await.unwrap();
 
-        let first = check_and_insert(&db, "test-key-2").await.unwrap();
+        let first = check_and_insert(&db, "test-key-2", 3600).await.unwrap();
         assert!(!first);
 
-        let second = check_and_insert(&db, "test-key-2").await.unwrap();
+        let second = check_and_insert(&db, "test-key-2", 3600).await.unwrap();
         assert!(second);
     }
+
+    #[tokio::test]
+    async fn expired_key_can_be_reused() {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+
+        assert!(!check_and_insert(&db, "test-key-3", 3600).await.unwrap());
+        db.execute_unprepared(
+            "UPDATE idempotency_record SET created_at = datetime('now', '-2 hours') \
+             WHERE idempotency_key = 'test-key-3'",
+        )
+        .await
+        .unwrap();
+
+        // Outside the window the key is treated as new
+        assert!(!check_and_insert(&db, "test-key-3", 3600).await.unwrap());
+        assert!(check_and_insert(&db, "test-key-3", 3600).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn stored_response_replay_and_purge() {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+
+        let key = make_request_key(Uuid::now_v7(), "order.confirmed", "abc");
+        assert!(find_response(&db, &key, 3600).await.unwrap().is_none());
+
+        let response = serde_json::json!({"accepted": 1, "task_ids": []});
+        store_response(&db, &key, &response, 3600).await.unwrap();
+        assert_eq!(find_response(&db, &key, 3600).await.unwrap(), Some(response));
+
+        check_and_insert(&db, "other-key", 3600).await.unwrap();
+        db.execute_unprepared(&format!(
+            "UPDATE idempotency_record SET created_at = datetime('now', '-2 hours') \
+             WHERE idempotency_key = '{key}'"
+        ))
+        .await
+        .unwrap();
+
+        assert!(find_response(&db, &key, 3600).await.unwrap().is_none());
+        assert_eq!(purge_expired(&db, 3600).await.unwrap(), 1);
+        assert!(check_and_insert(&db, "other-key", 3600).await.unwrap());
+    }
 }
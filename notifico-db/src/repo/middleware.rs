@@ -64,7 +64,7 @@ pub async fn list_by_rule(
 
 /// List all middleware for a rule (including disabled), ordered by priority ASC.
 pub async fn list_all_by_rule(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     rule_id: Uuid,
 ) -> Result<Vec<MiddlewareRow>, DbErr> {
     let rows = MiddlewareRaw::find_by_statement(Statement::from_sql_and_values(
@@ -82,7 +82,7 @@ pub async fn list_all_by_rule(
 
 /// Insert a new middleware entry.
 pub async fn insert(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     id: Uuid,
     rule_id: Uuid,
     middleware_name: &str,
@@ -109,7 +109,7 @@ pub async fn insert(
 
 /// Update an existing middleware entry.
 pub async fn update(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     id: Uuid,
     config: &Value,
     priority: i32,
@@ -132,7 +132,7 @@ pub async fn update(
 }
 
 /// Delete a middleware entry by id.
-pub async fn delete(db: &DatabaseConnection, id: Uuid) -> Result<(), DbErr> {
+pub async fn delete(db: &impl ConnectionTrait, id: Uuid) -> Result<(), DbErr> {
     db.execute_raw(
         Statement::from_sql_and_values(
             db.get_database_backend(),
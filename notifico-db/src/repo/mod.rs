@@ -1,11 +1,18 @@
 pub mod admin;
 pub mod api_key;
+pub mod attachment;
 pub mod credential;
 pub mod delivery_log;
+pub mod fixture;
 pub mod idempotency;
 pub mod middleware;
+pub mod partial;
 pub mod preference;
 pub mod queue;
 pub mod recipient;
 pub mod template;
+pub mod template_version;
 pub mod tracking;
+pub mod translation;
+pub mod variant;
+pub mod webhook;
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement};
+use uuid::Uuid;
+
+/// A project-level layout or partial, loadable from templates by name via
+/// `{% extends %}`, `{% include %}` and `{% import %}`, or a stylesheet
+/// that email HTML links by name.
+#[derive(Debug, Clone)]
+pub struct PartialRow {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    /// `layout` or `partial`, both loadable the same way, or `stylesheet`:
+    /// plain CSS resolved from `<link rel="stylesheet" href>` instead.
+    pub kind: String,
+    pub body: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct PartialRaw {
+    id: String,
+    project_id: String,
+    name: String,
+    kind: String,
+    body: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl PartialRaw {
+    fn into_row(self) -> Result<PartialRow, DbErr> {
+        Ok(PartialRow {
+            id: Uuid::parse_str(&self.id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            project_id: Uuid::parse_str(&self.project_id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            name: self.name,
+            kind: self.kind,
+            body: self.body,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+/// The kind of rows holding CSS rather than template source.
+pub const STYLESHEET: &str = "stylesheet";
+
+const PARTIAL_COLUMNS: &str = "id, project_id, name, kind, body, created_at, updated_at";
+
+/// List all layouts and partials of a project, ordered by name.
+pub async fn list_partials(
+    db: &impl ConnectionTrait,
+    project_id: Uuid,
+) -> Result<Vec<PartialRow>, DbErr> {
+    let rows = PartialRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {PARTIAL_COLUMNS} FROM template_partial WHERE project_id = ? ORDER BY name"
+        ),
+        [project_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter().map(|r| r.into_row()).collect()
+}
+
+/// Get a layout or partial by id, scoped to a project.
+pub async fn get_partial(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<Option<PartialRow>, DbErr> {
+    let row = PartialRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!("SELECT {PARTIAL_COLUMNS} FROM template_partial WHERE id = ? AND project_id = ?"),
+        [id.to_string().into(), project_id.to_string().into()],
+    ))
+    .one(db)
+    .await?;
+    row.map(|r| r.into_row()).transpose()
+}
+
+/// All layout and partial sources of a project keyed by name, ready for
+/// the template loader.
+pub async fn load_sources(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+) -> Result<BTreeMap<String, String>, DbErr> {
+    Ok(list_partials(db, project_id)
+        .await?
+        .into_iter()
+        .filter(|p| p.kind != STYLESHEET)
+        .map(|p| (p.name, p.body))
+        .collect())
+}
+
+/// All stylesheets of a project keyed by name.
+pub async fn load_stylesheets(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+) -> Result<BTreeMap<String, String>, DbErr> {
+    Ok(list_partials(db, project_id)
+        .await?
+        .into_iter()
+        .filter(|p| p.kind == STYLESHEET)
+        .map(|p| (p.name, p.body))
+        .collect())
+}
+
+pub async fn insert_partial(
+    db: &impl ConnectionTrait,
+    id: Uuid,
+    project_id: Uuid,
+    name: &str,
+    kind: &str,
+    body: &str,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO template_partial (id, project_id, name, kind, body) VALUES (?, ?, ?, ?, ?)",
+        [
+            id.to_string().into(),
+            project_id.to_string().into(),
+            name.into(),
+            kind.into(),
+            body.into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Update a layout or partial. Returns `false` if it does not exist.
+pub async fn update_partial(
+    db: &impl ConnectionTrait,
+    project_id: Uuid,
+    id: Uuid,
+    name: &str,
+    kind: &str,
+    body: &str,
+) -> Result<bool, DbErr> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "UPDATE template_partial SET name = ?, kind = ?, body = ?, updated_at = datetime('now') \
+             WHERE id = ? AND project_id = ?",
+            [
+                name.into(),
+                kind.into(),
+                body.into(),
+                id.to_string().into(),
+                project_id.to_string().into(),
+            ],
+        ))
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a layout or partial. Returns `false` if it does not exist.
+pub async fn delete_partial(
+    db: &impl ConnectionTrait,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<bool, DbErr> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "DELETE FROM template_partial WHERE id = ? AND project_id = ?",
+            [id.to_string().into(), project_id.to_string().into()],
+        ))
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connect, run_migrations};
+
+    #[tokio::test]
+    async fn crud_and_load_sources() {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+
+        let project_id = Uuid::now_v7();
+        db.execute_unprepared(&format!(
+            "INSERT INTO project (id, name) VALUES ('{project_id}', 'test')"
+        ))
+        .await
+        .unwrap();
+
+        let layout_id = Uuid::now_v7();
+        insert_partial(
+            &db,
+            layout_id,
+            project_id,
+            "base_email",
+            "layout",
+            "<body>{% block content %}{% endblock %}</body>",
+        )
+        .await
+        .unwrap();
+        let footer_id = Uuid::now_v7();
+        insert_partial(&db, footer_id, project_id, "footer", "partial", "Bye")
+            .await
+            .unwrap();
+
+        // Names are unique per project
+        assert!(
+            insert_partial(&db, Uuid::now_v7(), project_id, "footer", "partial", "x")
+                .await
+                .is_err()
+        );
+
+        assert!(
+            update_partial(&db, project_id, footer_id, "footer", "partial", "Cheers")
+                .await
+                .unwrap()
+        );
+        let sources = load_sources(&db, project_id).await.unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources["footer"], "Cheers");
+
+        assert!(
+            get_partial(&db, Uuid::now_v7(), layout_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(delete_partial(&db, project_id, layout_id).await.unwrap());
+        assert!(!delete_partial(&db, project_id, layout_id).await.unwrap());
+        assert_eq!(list_partials(&db, project_id).await.unwrap().len(), 1);
+    }
+}
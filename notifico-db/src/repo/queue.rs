@@ -17,6 +17,15 @@ pub struct TaskRow {
     pub attempt: i32,
     pub max_attempts: i32,
     pub error_message: Option<String>,
+    /// Attachment references (JSON array), resolved by the worker at send time.
+    pub attachments: Value,
+    /// Template version the body was rendered from.
+    pub template_version: Option<i32>,
+    /// A/B experiment and variant the recipient was assigned to, if any.
+    pub experiment: Option<String>,
+    pub variant: Option<String>,
+    /// Named credential requested by the rule, if any.
+    pub credential: Option<String>,
 }
 
 #[derive(Debug, Clone, FromQueryResult)]
@@ -34,6 +43,11 @@ struct TaskRaw {
     attempt: i32,
     max_attempts: i32,
     error_message: Option<String>,
+    attachments: String,
+    template_version: Option<i32>,
+    experiment: Option<String>,
+    variant: Option<String>,
+    credential: Option<String>,
 }
 
 impl TaskRaw {
@@ -46,6 +60,8 @@ impl TaskRaw {
             .map_err(|e| DbErr::Custom(format!("invalid recipient_id UUID: {e}")))?;
         let rendered_body: Value = serde_json::from_str(&self.rendered_body)
             .map_err(|e| DbErr::Custom(format!("invalid rendered_body JSON: {e}")))?;
+        let attachments: Value = serde_json::from_str(&self.attachments)
+            .map_err(|e| DbErr::Custom(format!("invalid attachments JSON: {e}")))?;
         let rule_id = self
             .rule_id
             .as_deref()
@@ -66,43 +82,63 @@ impl TaskRaw {
             attempt: self.attempt,
             max_attempts: self.max_attempts,
             error_message: self.error_message,
+            attachments,
+            template_version: self.template_version,
+            experiment: self.experiment,
+            variant: self.variant,
+            credential: self.credential,
         })
     }
 }
 
+/// A delivery task to enqueue.
+#[derive(Debug, Clone)]
+pub struct NewTask<'a> {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub event_name: &'a str,
+    pub recipient_id: Uuid,
+    pub channel: &'a str,
+    pub contact_value: &'a str,
+    pub rendered_body: &'a Value,
+    pub idempotency_key: Option<&'a str>,
+    pub max_attempts: i32,
+    pub rule_id: Option<Uuid>,
+    pub attachments: &'a Value,
+    pub template_version: Option<i32>,
+    /// A/B experiment and variant the task was rendered for, if any.
+    pub experiment: Option<&'a str>,
+    pub variant: Option<&'a str>,
+    /// Name of the credential to send through; `None` uses the default.
+    pub credential: Option<&'a str>,
+}
+
 /// Insert a new delivery task with status='pending'.
-pub async fn enqueue(
-    db: &DatabaseConnection,
-    id: Uuid,
-    project_id: Uuid,
-    event_name: &str,
-    recipient_id: Uuid,
-    channel: &str,
-    contact_value: &str,
-    rendered_body: &Value,
-    idempotency_key: Option<&str>,
-    max_attempts: i32,
-    rule_id: Option<Uuid>,
-) -> Result<(), DbErr> {
-    let body_json = serde_json::to_string(rendered_body)
+pub async fn enqueue(db: &DatabaseConnection, task: &NewTask<'_>) -> Result<(), DbErr> {
+    let body_json = serde_json::to_string(task.rendered_body)
         .map_err(|e| DbErr::Custom(format!("JSON serialize error: {e}")))?;
-    let idem = idempotency_key.unwrap_or("");
-    let has_idem = idempotency_key.is_some();
+    let idem = task.idempotency_key.unwrap_or("");
+    let has_idem = task.idempotency_key.is_some();
 
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "INSERT INTO delivery_task (id, project_id, event_name, recipient_id, channel, contact_value, rendered_body, idempotency_key, rule_id, status, attempt, max_attempts) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'pending', 0, ?)",
+        "INSERT INTO delivery_task (id, project_id, event_name, recipient_id, channel, contact_value, rendered_body, idempotency_key, rule_id, status, attempt, max_attempts, attachments, template_version, experiment, variant, credential) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'pending', 0, ?, ?, ?, ?, ?, ?)",
         [
-            id.to_string().into(),
-            project_id.to_string().into(),
-            event_name.into(),
-            recipient_id.to_string().into(),
-            channel.into(),
-            contact_value.into(),
+            task.id.to_string().into(),
+            task.project_id.to_string().into(),
+            task.event_name.into(),
+            task.recipient_id.to_string().into(),
+            task.channel.into(),
+            task.contact_value.into(),
             body_json.into(),
             if has_idem { sea_orm::Value::from(idem) } else { sea_orm::Value::from(None::<String>) },
-            rule_id.map(|r| r.to_string()).map(sea_orm::Value::from).unwrap_or(sea_orm::Value::from(None::<String>)),
-            max_attempts.into(),
+            task.rule_id.map(|r| r.to_string()).map(sea_orm::Value::from).unwrap_or(sea_orm::Value::from(None::<String>)),
+            task.max_attempts.into(),
+            task.attachments.to_string().into(),
+            task.template_version.into(),
+            task.experiment.map(|s| s.to_string()).into(),
+            task.variant.map(|s| s.to_string()).into(),
+            task.credential.map(|s| s.to_string()).into(),
         ],
     ))
     .await?;
@@ -119,7 +155,7 @@ pub async fn claim_pending(
     // Step 1: Find pending task IDs ready to process
     let rows = TaskRaw::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT id, project_id, event_name, recipient_id, channel, contact_value, rendered_body, idempotency_key, rule_id, status, attempt, max_attempts, error_message FROM delivery_task WHERE status = 'pending' AND next_retry_at <= CURRENT_TIMESTAMP ORDER BY next_retry_at ASC LIMIT ?",
+        "SELECT id, project_id, event_name, recipient_id, channel, contact_value, rendered_body, idempotency_key, rule_id, status, attempt, max_attempts, error_message, attachments, template_version, experiment, variant, credential FROM delivery_task WHERE status = 'pending' AND next_retry_at <= CURRENT_TIMESTAMP ORDER BY next_retry_at ASC LIMIT ?",
         [limit.into()],
     ))
     .all(db)
@@ -148,6 +184,18 @@ pub async fn claim_pending(
     Ok(result)
 }
 
+/// Get a task by id.
+pub async fn get_task(db: &DatabaseConnection, task_id: Uuid) -> Result<Option<TaskRow>, DbErr> {
+    let row = TaskRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT id, project_id, event_name, recipient_id, channel, contact_value, rendered_body, idempotency_key, rule_id, status, attempt, max_attempts, error_message, attachments, template_version, experiment, variant, credential FROM delivery_task WHERE id = ?",
+        [task_id.to_string().into()],
+    ))
+    .one(db)
+    .await?;
+    row.map(|r| r.into_row()).transpose()
+}
+
 /// Mark task as completed.
 pub async fn mark_completed(db: &DatabaseConnection, task_id: Uuid) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
@@ -248,23 +296,49 @@ mod tests {
         Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap()
     }
 
+    fn new_task<'a>(
+        id: Uuid,
+        event_name: &'a str,
+        body: &'a Value,
+        max_attempts: i32,
+    ) -> NewTask<'a> {
+        static NO_ATTACHMENTS: Value = Value::Array(Vec::new());
+        NewTask {
+            id,
+            project_id: test_project_id(),
+            event_name,
+            recipient_id: test_recipient_id(),
+            channel: "email",
+            contact_value: "a@b.com",
+            rendered_body: body,
+            idempotency_key: None,
+            max_attempts,
+            rule_id: None,
+            attachments: &NO_ATTACHMENTS,
+            template_version: None,
+            experiment: None,
+            variant: None,
+            credential: None,
+        }
+    }
+
     #[tokio::test]
     async fn enqueue_and_claim() {
         let db = setup().await;
         let task_id = Uuid::now_v7();
 
-        enqueue(
-            &db, task_id, test_project_id(), "order.confirmed",
-            test_recipient_id(), "email", "test@example.com",
-            &json!({"subject": "Hi", "text": "Hello"}),
-            None, 5, None,
-        )
-        .await
-        .unwrap();
+        let body = json!({"subject": "Hi", "text": "Hello"});
+        let task = NewTask {
+            contact_value: "test@example.com",
+            credential: Some("marketing"),
+            ..new_task(task_id, "order.confirmed", &body, 5)
+        };
+        enqueue(&db, &task).await.unwrap();
 
         let claimed = claim_pending(&db, 10).await.unwrap();
         assert_eq!(claimed.len(), 1);
         assert_eq!(claimed[0].id, task_id);
+        assert_eq!(claimed[0].credential.as_deref(), Some("marketing"));
         assert_eq!(claimed[0].status, "processing");
         assert_eq!(claimed[0].attempt, 1);
         assert_eq!(claimed[0].channel, "email");
@@ -276,13 +350,9 @@ mod tests {
         let db = setup().await;
         let task_id = Uuid::now_v7();
 
-        enqueue(
-            &db, task_id, test_project_id(), "test",
-            test_recipient_id(), "email", "a@b.com",
-            &json!({}), None, 5, None,
-        )
-        .await
-        .unwrap();
+        enqueue(&db, &new_task(task_id, "test", &json!({}), 5))
+            .await
+            .unwrap();
 
         // Set next_retry_at far in the future
         db.execute_unprepared(&format!(
@@ -300,13 +370,9 @@ mod tests {
         let db = setup().await;
         let task_id = Uuid::now_v7();
 
-        enqueue(
-            &db, task_id, test_project_id(), "test",
-            test_recipient_id(), "email", "a@b.com",
-            &json!({}), None, 5, None,
-        )
-        .await
-        .unwrap();
+        enqueue(&db, &new_task(task_id, "test", &json!({}), 5))
+            .await
+            .unwrap();
 
         let claimed = claim_pending(&db, 10).await.unwrap();
         assert_eq!(claimed.len(), 1);
@@ -327,13 +393,9 @@ mod tests {
         let db = setup().await;
         let task_id = Uuid::now_v7();
 
-        enqueue(
-            &db, task_id, test_project_id(), "test",
-            test_recipient_id(), "email", "a@b.com",
-            &json!({}), None, 5, None,
-        )
-        .await
-        .unwrap();
+        enqueue(&db, &new_task(task_id, "test", &json!({}), 5))
+            .await
+            .unwrap();
 
         let claimed = claim_pending(&db, 10).await.unwrap();
         assert_eq!(claimed.len(), 1);
@@ -350,13 +412,9 @@ mod tests {
         let db = setup().await;
         let task_id = Uuid::now_v7();
 
-        enqueue(
-            &db, task_id, test_project_id(), "test",
-            test_recipient_id(), "email", "a@b.com",
-            &json!({}), None, 2, None,
-        )
-        .await
-        .unwrap();
+        enqueue(&db, &new_task(task_id, "test", &json!({}), 2))
+            .await
+            .unwrap();
 
         claim_pending(&db, 10).await.unwrap();
 
@@ -1,3 +1,4 @@
+use notifico_core::locale::LocaleChain;
 use sea_orm::{DatabaseConnection, DbErr, FromQueryResult, Statement};
 use serde_json::Value;
 use uuid::Uuid;
@@ -8,6 +9,10 @@ pub struct ResolvedTemplate {
     pub template_id: Uuid,
     pub template_name: String,
     pub channel: String,
+    /// Content authoring format (see [`notifico_core::content_format`]).
+    pub format: String,
+    /// Id of the `template_version` row the content belongs to.
+    pub version_id: Uuid,
     pub version: i32,
     pub locale: String,
     pub body: Value,
@@ -19,6 +24,8 @@ struct ResolvedTemplateRaw {
     template_id: String,
     template_name: String,
     channel: String,
+    format: String,
+    version_id: String,
     version: i32,
     locale: String,
     body: Value,
@@ -28,10 +35,14 @@ impl ResolvedTemplateRaw {
     fn into_resolved(self) -> Result<ResolvedTemplate, DbErr> {
         let template_id = Uuid::parse_str(&self.template_id)
             .map_err(|e| DbErr::Custom(format!("invalid template_id UUID: {e}")))?;
+        let version_id = Uuid::parse_str(&self.version_id)
+            .map_err(|e| DbErr::Custom(format!("invalid version_id UUID: {e}")))?;
         Ok(ResolvedTemplate {
             template_id,
             template_name: self.template_name,
             channel: self.channel,
+            format: self.format,
+            version_id,
             version: self.version,
             locale: self.locale,
             body: self.body,
@@ -39,63 +50,85 @@ impl ResolvedTemplateRaw {
     }
 }
 
-/// Resolve a template to its current version content for the given locale,
-/// falling back to the default locale if necessary.
+/// Resolve a template to its current version content, picking the locale
+/// with the locale fallback chain (see [`notifico_core::locale`]).
 ///
 /// Returns `None` if the template does not exist, has no current version,
-/// or has no content in either the requested or default locale.
+/// or has no content matching the chain.
 pub async fn resolve_template(
     db: &DatabaseConnection,
     template_id: Uuid,
-    locale: &str,
-    default_locale: &str,
+    chain: &LocaleChain,
 ) -> Result<Option<ResolvedTemplate>, DbErr> {
+    resolve(db, template_id, None, chain).await
+}
+
+/// Like [`resolve_template`], but for a specific version number (e.g. a
+/// draft being previewed) instead of the current one.
+pub async fn resolve_template_version(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+    version: i32,
+    chain: &LocaleChain,
+) -> Result<Option<ResolvedTemplate>, DbErr> {
+    resolve(db, template_id, Some(version), chain).await
+}
+
+async fn resolve(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+    version: Option<i32>,
+    chain: &LocaleChain,
+) -> Result<Option<ResolvedTemplate>, DbErr> {
+    #[derive(Debug, FromQueryResult)]
+    struct LocaleRaw {
+        locale: String,
+    }
+
     let backend = db.get_database_backend();
 
-    let sql = r#"
-        SELECT
-            t.id AS template_id,
-            t.name AS template_name,
-            t.channel,
-            tv.version,
-            tc.locale,
-            tc.body
-        FROM template t
-        JOIN template_version tv ON tv.template_id = t.id AND tv.is_current = true
-        JOIN template_content tc ON tc.template_version_id = tv.id
-        WHERE t.id = ? AND tc.locale = ?
-        LIMIT 1
-    "#;
+    let version_filter = match version {
+        Some(_) => "tv.version = ?",
+        None => "tv.is_current = true",
+    };
+    let from = format!(
+        "FROM template t \
+         JOIN template_version tv ON tv.template_id = t.id AND {version_filter} \
+         JOIN template_content tc ON tc.template_version_id = tv.id \
+         WHERE t.id = ?"
+    );
+    let mut values: Vec<sea_orm::Value> = version
+        .map(Into::into)
+        .into_iter()
+        .chain([template_id.to_string().into()])
+        .collect();
+
+    // Pick the locale among those the version has content for.
+    let available = LocaleRaw::find_by_statement(Statement::from_sql_and_values(
+        backend,
+        format!("SELECT tc.locale {from}"),
+        values.clone(),
+    ))
+    .all(db)
+    .await?;
+    let Some(locale) = chain.select(available.iter().map(|r| r.locale.as_str())) else {
+        return Ok(None);
+    };
 
-    // Try exact locale match first.
+    values.push(locale.into());
     let raw = ResolvedTemplateRaw::find_by_statement(Statement::from_sql_and_values(
         backend,
-        sql,
-        [template_id.to_string().into(), locale.into()],
+        format!(
+            "SELECT t.id AS template_id, t.name AS template_name, t.channel, t.format, \
+             tv.id AS version_id, tv.version, \
+             tc.locale, tc.body {from} AND tc.locale = ? LIMIT 1"
+        ),
+        values,
     ))
     .one(db)
     .await?;
 
-    if let Some(row) = raw {
-        return Ok(Some(row.into_resolved()?));
-    }
-
-    // Fall back to default locale if it differs from the requested one.
-    if locale != default_locale {
-        let raw = ResolvedTemplateRaw::find_by_statement(Statement::from_sql_and_values(
-            backend,
-            sql,
-            [template_id.to_string().into(), default_locale.into()],
-        ))
-        .one(db)
-        .await?;
-
-        if let Some(row) = raw {
-            return Ok(Some(row.into_resolved()?));
-        }
-    }
-
-    Ok(None)
+    raw.map(|r| r.into_resolved()).transpose()
 }
 
 /// A row from the `pipeline_rule` table.
@@ -107,6 +140,10 @@ pub struct PipelineRuleRow {
     pub enabled: bool,
     pub conditions: Option<Value>,
     pub priority: i32,
+    /// Set while the rule splits recipients between template variants.
+    pub experiment: Option<String>,
+    /// Named credential to send through; `None` uses the channel default.
+    pub credential: Option<String>,
 }
 
 /// Internal raw row for pipeline_rule queries.
@@ -118,6 +155,8 @@ struct PipelineRuleRaw {
     enabled: bool,
     conditions: Option<Value>,
     priority: i32,
+    experiment: Option<String>,
+    credential: Option<String>,
 }
 
 impl PipelineRuleRaw {
@@ -133,6 +172,8 @@ impl PipelineRuleRaw {
             enabled: self.enabled,
             conditions: self.conditions,
             priority: self.priority,
+            experiment: self.experiment,
+            credential: self.credential,
         })
     }
 }
@@ -146,7 +187,7 @@ pub async fn get_pipeline_rules(
     let backend = db.get_database_backend();
 
     let sql = r#"
-        SELECT id, channel, template_id, enabled, conditions, priority
+        SELECT id, channel, template_id, enabled, conditions, priority, experiment, credential
         FROM pipeline_rule
         WHERE event_id = ? AND enabled = true
         ORDER BY priority DESC
@@ -170,6 +211,8 @@ pub struct EventRow {
     pub project_id: Uuid,
     pub name: String,
     pub category: String,
+    /// JSON Schema the event's `data` must match, if one is declared.
+    pub data_schema: Option<Value>,
 }
 
 /// Internal raw row for event queries.
@@ -179,6 +222,7 @@ struct EventRaw {
     project_id: String,
     name: String,
     category: String,
+    data_schema: Option<String>,
 }
 
 impl EventRaw {
@@ -187,11 +231,17 @@ impl EventRaw {
             .map_err(|e| DbErr::Custom(format!("invalid event id UUID: {e}")))?;
         let project_id = Uuid::parse_str(&self.project_id)
             .map_err(|e| DbErr::Custom(format!("invalid project_id UUID: {e}")))?;
+        let data_schema = self
+            .data_schema
+            .map(|s| serde_json::from_str(&s))
+            .transpose()
+            .map_err(|e| DbErr::Custom(format!("invalid data_schema JSON: {e}")))?;
         Ok(EventRow {
             id,
             project_id,
             name: self.name,
             category: self.category,
+            data_schema,
         })
     }
 }
@@ -205,7 +255,7 @@ pub async fn find_event_by_name(
     let backend = db.get_database_backend();
 
     let sql = r#"
-        SELECT id, project_id, name, category
+        SELECT id, project_id, name, category, data_schema
         FROM event
         WHERE project_id = ? AND name = ?
         LIMIT 1
@@ -229,8 +279,13 @@ pub async fn find_event_by_name(
 mod tests {
     use super::*;
     use crate::{connect, run_migrations};
+    use notifico_core::locale::{LocaleFallbackConfig, fallback_chain};
     use sea_orm::ConnectionTrait;
 
+    fn chain(locale: &str, default_locale: &str) -> LocaleChain {
+        fallback_chain(locale, &[default_locale], &LocaleFallbackConfig::default())
+    }
+
     async fn setup_db() -> DatabaseConnection {
         let db = connect("sqlite::memory:").await.unwrap();
         run_migrations(&db).await.unwrap();
@@ -283,7 +338,7 @@ mod tests {
     #[tokio::test]
     async fn resolve_template_exact_locale() {
         let db = setup_db().await;
-        let (_project_id, template_id, _version_id) = seed_template_with_content(
+        let (_project_id, template_id, version_id) = seed_template_with_content(
             &db,
             &[
                 ("en", r#"{"subject":"Hello"}"#),
@@ -292,7 +347,7 @@ mod tests {
         )
         .await;
 
-        let result = resolve_template(&db, template_id, "ru", "en")
+        let result = resolve_template(&db, template_id, &chain("ru", "en"))
             .await
             .unwrap();
         let resolved = result.expect("should resolve template");
@@ -300,6 +355,7 @@ mod tests {
         assert_eq!(resolved.template_id, template_id);
         assert_eq!(resolved.template_name, "welcome");
         assert_eq!(resolved.channel, "email");
+        assert_eq!(resolved.version_id, version_id);
         assert_eq!(resolved.version, 1);
         assert_eq!(resolved.locale, "ru");
         assert_eq!(resolved.body["subject"], "Привет");
@@ -318,7 +374,7 @@ mod tests {
         .await;
 
         // Request "de" which does not exist; should fall back to default "en".
-        let result = resolve_template(&db, template_id, "de", "en")
+        let result = resolve_template(&db, template_id, &chain("de", "en"))
             .await
             .unwrap();
         let resolved = result.expect("should fall back to default locale");
@@ -327,12 +383,76 @@ mod tests {
         assert_eq!(resolved.body["subject"], "Hello");
     }
 
+    #[tokio::test]
+    async fn resolve_template_bcp47_fallback_chain() {
+        let db = setup_db().await;
+        let (_project_id, template_id, _version_id) = seed_template_with_content(
+            &db,
+            &[
+                ("en", r#"{"subject":"Hello"}"#),
+                ("pt", r#"{"subject":"Olá"}"#),
+                ("zh-Hant", r#"{"subject":"你好"}"#),
+                ("sr_Latn", r#"{"subject":"Zdravo"}"#),
+            ],
+        )
+        .await;
+
+        let resolve = |locale: &'static str, config: LocaleFallbackConfig| {
+            let db = db.clone();
+            async move {
+                let chain = fallback_chain(locale, &["en"], &config);
+                resolve_template(&db, template_id, &chain)
+                    .await
+                    .unwrap()
+                    .map(|t| t.locale)
+            }
+        };
+
+        // Region falls back to the language
+        assert_eq!(resolve("pt-BR", Default::default()).await.as_deref(), Some("pt"));
+        // Script subtag is kept before dropping to the language
+        assert_eq!(resolve("zh-hant-TW", Default::default()).await.as_deref(), Some("zh-Hant"));
+        assert_eq!(resolve("sr-Latn-RS", Default::default()).await.as_deref(), Some("sr_Latn"));
+        // Simplified Chinese does not match the Traditional script; falls to the default
+        assert_eq!(resolve("zh-Hans-CN", Default::default()).await.as_deref(), Some("en"));
+        // Per-project override is tried before the parent
+        let config = LocaleFallbackConfig {
+            overrides: [("zh-Hans".to_string(), vec!["zh-Hant".to_string()])].into(),
+            any: true,
+        };
+        assert_eq!(resolve("zh-Hans-CN", config).await.as_deref(), Some("zh-Hant"));
+    }
+
+    #[tokio::test]
+    async fn resolve_template_any_locale_as_last_resort() {
+        let db = setup_db().await;
+        let (_project_id, template_id, _version_id) =
+            seed_template_with_content(&db, &[("fr", r#"{"subject":"Bonjour"}"#)]).await;
+
+        let resolved = resolve_template(&db, template_id, &chain("de", "en"))
+            .await
+            .unwrap()
+            .expect("should fall back to any available locale");
+        assert_eq!(resolved.locale, "fr");
+
+        let strict = LocaleChain {
+            any: false,
+            ..chain("de", "en")
+        };
+        assert!(
+            resolve_template(&db, template_id, &strict)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[tokio::test]
     async fn resolve_template_not_found() {
         let db = setup_db().await;
 
         let random_id = Uuid::now_v7();
-        let result = resolve_template(&db, random_id, "en", "en")
+        let result = resolve_template(&db, random_id, &chain("en", "en"))
             .await
             .unwrap();
 
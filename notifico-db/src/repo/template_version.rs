@@ -0,0 +1,408 @@
+use std::collections::BTreeMap;
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// A template version. A version is a draft until it is published; exactly
+/// one published version per template is current (live).
+#[derive(Debug, Clone)]
+pub struct TemplateVersionRow {
+    pub id: Uuid,
+    pub template_id: Uuid,
+    pub version: i32,
+    pub is_current: bool,
+    pub published_at: Option<String>,
+    pub created_at: String,
+}
+
+impl TemplateVersionRow {
+    /// `draft`, `current`, or `archived` (published before, no longer live).
+    pub fn status(&self) -> &'static str {
+        if self.is_current {
+            "current"
+        } else if self.published_at.is_none() {
+            "draft"
+        } else {
+            "archived"
+        }
+    }
+
+    pub fn is_draft(&self) -> bool {
+        !self.is_current && self.published_at.is_none()
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct TemplateVersionRaw {
+    id: String,
+    template_id: String,
+    version: i32,
+    is_current: bool,
+    published_at: Option<String>,
+    created_at: String,
+}
+
+impl TemplateVersionRaw {
+    fn into_row(self) -> Result<TemplateVersionRow, DbErr> {
+        Ok(TemplateVersionRow {
+            id: Uuid::parse_str(&self.id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            template_id: Uuid::parse_str(&self.template_id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            version: self.version,
+            is_current: self.is_current,
+            published_at: self.published_at,
+            created_at: self.created_at,
+        })
+    }
+}
+
+const VERSION_COLUMNS: &str =
+    "id, template_id, version, is_current, published_at, CAST(created_at AS TEXT) AS created_at";
+
+/// List all versions of a template, newest first.
+pub async fn list_versions(
+    db: &impl ConnectionTrait,
+    template_id: Uuid,
+) -> Result<Vec<TemplateVersionRow>, DbErr> {
+    let rows = TemplateVersionRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {VERSION_COLUMNS} FROM template_version \
+             WHERE template_id = ? ORDER BY version DESC"
+        ),
+        [template_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter().map(|r| r.into_row()).collect()
+}
+
+/// Get a version by number.
+pub async fn get_version(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+    version: i32,
+) -> Result<Option<TemplateVersionRow>, DbErr> {
+    let row = TemplateVersionRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {VERSION_COLUMNS} FROM template_version \
+             WHERE template_id = ? AND version = ?"
+        ),
+        [template_id.to_string().into(), version.into()],
+    ))
+    .one(db)
+    .await?;
+    row.map(|r| r.into_row()).transpose()
+}
+
+/// The version an editor works on: the open draft if there is one,
+/// otherwise the current version.
+pub async fn working_version(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+) -> Result<Option<TemplateVersionRow>, DbErr> {
+    let versions = list_versions(db, template_id).await?;
+    Ok(versions
+        .iter()
+        .find(|v| v.is_draft())
+        .or_else(|| versions.iter().find(|v| v.is_current))
+        .cloned())
+}
+
+/// Return the open draft of a template, creating one if needed. A new draft
+/// gets the next version number and starts as a copy of the current
+/// version's content.
+pub async fn ensure_draft(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+) -> Result<TemplateVersionRow, DbErr> {
+    let versions = list_versions(db, template_id).await?;
+    if let Some(draft) = versions.iter().find(|v| v.is_draft()) {
+        return Ok(draft.clone());
+    }
+
+    let next = versions.iter().map(|v| v.version).max().unwrap_or(0) + 1;
+    let id = Uuid::now_v7();
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO template_version (id, template_id, version, is_current) VALUES (?, ?, ?, false)",
+        [id.to_string().into(), template_id.to_string().into(), next.into()],
+    ))
+    .await?;
+
+    if let Some(current) = versions.iter().find(|v| v.is_current) {
+        for (locale, body) in get_contents(db, current.id).await? {
+            db.execute_raw(Statement::from_sql_and_values(
+                db.get_database_backend(),
+                "INSERT INTO template_content (id, template_version_id, locale, body) VALUES (?, ?, ?, ?)",
+                [
+                    Uuid::now_v7().to_string().into(),
+                    id.to_string().into(),
+                    locale.into(),
+                    body.to_string().into(),
+                ],
+            ))
+            .await?;
+        }
+    }
+
+    get_version(db, template_id, next)
+        .await?
+        .ok_or_else(|| DbErr::Custom("Draft version not found after insert".into()))
+}
+
+/// Make `version` the current version of its template, stamping
+/// `published_at` the first time. Used both to publish a draft and to roll
+/// back to an earlier version. Returns `false` if the version does not exist.
+pub async fn publish_version(
+    db: &DatabaseConnection,
+    template_id: Uuid,
+    version: i32,
+) -> Result<bool, DbErr> {
+    if get_version(db, template_id, version).await?.is_none() {
+        return Ok(false);
+    }
+
+    // Single statement so there is never a moment without a current version.
+    // The outgoing current version is stamped too, in case it predates
+    // `published_at`, so it reads as archived rather than as a draft.
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "UPDATE template_version SET \
+           is_current = (version = ?), \
+           published_at = CASE WHEN version = ? OR is_current \
+             THEN COALESCE(published_at, CURRENT_TIMESTAMP) ELSE published_at END \
+         WHERE template_id = ?",
+        [
+            version.into(),
+            version.into(),
+            template_id.to_string().into(),
+        ],
+    ))
+    .await?;
+    Ok(true)
+}
+
+/// Insert an empty draft with a given version number. Used by directory
+/// sync, which mirrors version numbers instead of allocating the next one.
+pub async fn insert_version(
+    db: &impl ConnectionTrait,
+    template_id: Uuid,
+    version: i32,
+) -> Result<Uuid, DbErr> {
+    let id = Uuid::now_v7();
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO template_version (id, template_id, version, is_current) VALUES (?, ?, ?, false)",
+        [id.to_string().into(), template_id.to_string().into(), version.into()],
+    ))
+    .await?;
+    Ok(id)
+}
+
+/// Delete a version and its contents.
+pub async fn delete_version(db: &impl ConnectionTrait, version_id: Uuid) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "DELETE FROM template_version WHERE id = ?",
+        [version_id.to_string().into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Set the status of every version of a template at once: `current` becomes
+/// live, `drafts` lose their publish stamp, and all others are archived.
+pub async fn set_statuses(
+    db: &impl ConnectionTrait,
+    template_id: Uuid,
+    current: Option<i32>,
+    drafts: &[i32],
+) -> Result<(), DbErr> {
+    let drafts_sql = if drafts.is_empty() {
+        "false".to_string()
+    } else {
+        format!("version IN ({})", vec!["?"; drafts.len()].join(", "))
+    };
+    let mut values: Vec<sea_orm::Value> = vec![current.unwrap_or(0).into()];
+    values.extend(drafts.iter().map(|&v| v.into()));
+    values.push(template_id.to_string().into());
+
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "UPDATE template_version SET \
+               is_current = (version = ?), \
+               published_at = CASE WHEN {drafts_sql} THEN NULL \
+                 ELSE COALESCE(published_at, CURRENT_TIMESTAMP) END \
+             WHERE template_id = ?"
+        ),
+        values,
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Replace all locale contents of a version.
+pub async fn replace_contents(
+    db: &impl ConnectionTrait,
+    version_id: Uuid,
+    contents: &BTreeMap<String, Value>,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "DELETE FROM template_content WHERE template_version_id = ?",
+        [version_id.to_string().into()],
+    ))
+    .await?;
+    for (locale, body) in contents {
+        db.execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "INSERT INTO template_content (id, template_version_id, locale, body) VALUES (?, ?, ?, ?)",
+            [
+                Uuid::now_v7().to_string().into(),
+                version_id.to_string().into(),
+                locale.as_str().into(),
+                body.to_string().into(),
+            ],
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// All locale contents of a version, keyed by locale.
+pub async fn get_contents(
+    db: &impl ConnectionTrait,
+    version_id: Uuid,
+) -> Result<BTreeMap<String, Value>, DbErr> {
+    #[derive(Debug, FromQueryResult)]
+    struct ContentRaw {
+        locale: String,
+        body: String,
+    }
+
+    let rows = ContentRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT locale, body FROM template_content WHERE template_version_id = ? ORDER BY locale",
+        [version_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            let body =
+                serde_json::from_str(&r.body).map_err(|e| DbErr::Custom(format!("JSON: {e}")))?;
+            Ok((r.locale, body))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::admin;
+    use crate::{connect, run_migrations};
+    use serde_json::json;
+
+    async fn setup() -> (DatabaseConnection, Uuid) {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let project_id = Uuid::now_v7();
+        admin::create_project(&db, project_id, "P1", "en")
+            .await
+            .unwrap();
+        let template_id = Uuid::now_v7();
+        admin::create_template(&db, template_id, project_id, "welcome", "email", "html")
+            .await
+            .unwrap();
+        (db, template_id)
+    }
+
+    #[tokio::test]
+    async fn new_template_starts_with_a_draft() {
+        let (db, template_id) = setup().await;
+
+        let versions = list_versions(&db, template_id).await.unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, 1);
+        assert_eq!(versions[0].status(), "draft");
+
+        // Reuses the open draft instead of creating another
+        assert_eq!(ensure_draft(&db, template_id).await.unwrap().version, 1);
+    }
+
+    #[tokio::test]
+    async fn draft_publish_and_rollback() {
+        let (db, template_id) = setup().await;
+
+        admin::set_template_content(&db, template_id, "en", &json!({"subject": "v1"}))
+            .await
+            .unwrap();
+        assert!(publish_version(&db, template_id, 1).await.unwrap());
+
+        // Editing after publish opens draft v2 seeded from v1
+        let draft = admin::set_template_content(&db, template_id, "de", &json!({"subject": "de"}))
+            .await
+            .unwrap();
+        assert_eq!(draft.version, 2);
+        let v2 = get_version(&db, template_id, 2).await.unwrap().unwrap();
+        let contents = get_contents(&db, v2.id).await.unwrap();
+        assert_eq!(contents["en"]["subject"], "v1");
+        assert_eq!(contents["de"]["subject"], "de");
+
+        // v1 stays live until v2 is published
+        let live = crate::repo::template::resolve_template(
+            &db,
+            template_id,
+            &notifico_core::locale::fallback_chain("de", &["en"], &Default::default()),
+        )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(live.version, 1);
+        assert_eq!(live.locale, "en");
+
+        assert!(publish_version(&db, template_id, 2).await.unwrap());
+        let versions = list_versions(&db, template_id).await.unwrap();
+        assert_eq!(versions[0].status(), "current");
+        assert_eq!(versions[1].status(), "archived");
+
+        // Roll back to v1
+        assert!(publish_version(&db, template_id, 1).await.unwrap());
+        let current = working_version(&db, template_id).await.unwrap().unwrap();
+        assert_eq!(current.version, 1);
+        assert!(current.is_current);
+
+        assert!(!publish_version(&db, template_id, 9).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn explicit_versions_and_statuses() {
+        let (db, template_id) = setup().await;
+
+        let v3 = insert_version(&db, template_id, 3).await.unwrap();
+        let contents = BTreeMap::from([("en".to_string(), json!({"subject": "v3"}))]);
+        replace_contents(&db, v3, &contents).await.unwrap();
+        assert_eq!(get_contents(&db, v3).await.unwrap(), contents);
+        replace_contents(&db, v3, &BTreeMap::new()).await.unwrap();
+        assert!(get_contents(&db, v3).await.unwrap().is_empty());
+
+        set_statuses(&db, template_id, Some(1), &[3]).await.unwrap();
+        let versions = list_versions(&db, template_id).await.unwrap();
+        assert_eq!(versions[0].status(), "draft");
+        assert_eq!(versions[1].status(), "current");
+
+        set_statuses(&db, template_id, Some(3), &[]).await.unwrap();
+        let versions = list_versions(&db, template_id).await.unwrap();
+        assert_eq!(versions[0].status(), "current");
+        assert_eq!(versions[1].status(), "archived");
+
+        delete_version(&db, v3).await.unwrap();
+        assert_eq!(list_versions(&db, template_id).await.unwrap().len(), 1);
+    }
+}
@@ -4,7 +4,8 @@ use uuid::Uuid;
 #[derive(Debug, Clone, FromQueryResult)]
 pub struct TrackingEventRow {
     pub id: String,
-    pub delivery_log_id: Option<String>,
+    /// Delivery task the tracked message was rendered for.
+    pub task_id: Option<String>,
     pub event_type: String,
     pub url: Option<String>,
     pub created_at: String,
@@ -13,16 +14,16 @@ pub struct TrackingEventRow {
 pub async fn insert_tracking_event(
     db: &DatabaseConnection,
     id: Uuid,
-    delivery_log_id: &str,
+    task_id: &str,
     event_type: &str,
     url: Option<&str>,
 ) -> Result<(), DbErr> {
     db.execute_raw(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "INSERT INTO tracking_event (id, delivery_log_id, event_type, url) VALUES (?, ?, ?, ?)",
+        "INSERT INTO tracking_event (id, task_id, event_type, url) VALUES (?, ?, ?, ?)",
         [
             id.to_string().into(),
-            delivery_log_id.into(),
+            task_id.into(),
             event_type.into(),
             url.unwrap_or("").into(),
         ],
@@ -31,9 +32,9 @@ pub async fn insert_tracking_event(
     Ok(())
 }
 
-pub async fn count_by_delivery(
+pub async fn count_by_task(
     db: &DatabaseConnection,
-    delivery_log_id: &str,
+    task_id: &str,
 ) -> Result<Vec<(String, i64)>, DbErr> {
     #[derive(FromQueryResult)]
     struct TypeCount {
@@ -43,8 +44,8 @@ pub async fn count_by_delivery(
 
     let rows = TypeCount::find_by_statement(Statement::from_sql_and_values(
         db.get_database_backend(),
-        "SELECT event_type, COUNT(*) as cnt FROM tracking_event WHERE delivery_log_id = ? GROUP BY event_type",
-        [delivery_log_id.into()],
+        "SELECT event_type, COUNT(*) as cnt FROM tracking_event WHERE task_id = ? GROUP BY event_type",
+        [task_id.into()],
     ))
     .all(db)
     .await?;
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// A project's translation catalog for one locale, used by `t()` in templates.
+#[derive(Debug, Clone)]
+pub struct CatalogRow {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub locale: String,
+    /// Message keys to ICU messages; nested objects form dotted keys.
+    pub messages: Value,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CatalogRaw {
+    id: String,
+    project_id: String,
+    locale: String,
+    messages: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl CatalogRaw {
+    fn into_row(self) -> Result<CatalogRow, DbErr> {
+        Ok(CatalogRow {
+            id: Uuid::parse_str(&self.id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            project_id: Uuid::parse_str(&self.project_id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            locale: self.locale,
+            messages: serde_json::from_str(&self.messages).unwrap_or_default(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+const CATALOG_COLUMNS: &str = "id, project_id, locale, messages, created_at, updated_at";
+
+/// List all catalogs of a project, ordered by locale.
+pub async fn list_catalogs(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+) -> Result<Vec<CatalogRow>, DbErr> {
+    let rows = CatalogRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {CATALOG_COLUMNS} FROM translation_catalog WHERE project_id = ? ORDER BY locale"
+        ),
+        [project_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter().map(|r| r.into_row()).collect()
+}
+
+pub async fn get_catalog(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    locale: &str,
+) -> Result<Option<CatalogRow>, DbErr> {
+    let row = CatalogRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {CATALOG_COLUMNS} FROM translation_catalog WHERE project_id = ? AND locale = ?"
+        ),
+        [project_id.to_string().into(), locale.into()],
+    ))
+    .one(db)
+    .await?;
+    row.map(|r| r.into_row()).transpose()
+}
+
+/// All catalogs of a project keyed by locale, ready for rendering.
+pub async fn load_catalogs(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+) -> Result<BTreeMap<String, Value>, DbErr> {
+    Ok(list_catalogs(db, project_id)
+        .await?
+        .into_iter()
+        .map(|c| (c.locale, c.messages))
+        .collect())
+}
+
+/// Create or replace the catalog for a locale.
+pub async fn upsert_catalog(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    locale: &str,
+    messages: &Value,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO translation_catalog (id, project_id, locale, messages) VALUES (?, ?, ?, ?) \
+         ON CONFLICT (project_id, locale) DO UPDATE \
+         SET messages = excluded.messages, updated_at = datetime('now')",
+        [
+            Uuid::now_v7().to_string().into(),
+            project_id.to_string().into(),
+            locale.into(),
+            messages.to_string().into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Delete the catalog for a locale. Returns `false` if it does not exist.
+pub async fn delete_catalog(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    locale: &str,
+) -> Result<bool, DbErr> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "DELETE FROM translation_catalog WHERE project_id = ? AND locale = ?",
+            [project_id.to_string().into(), locale.into()],
+        ))
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connect, run_migrations};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn upsert_load_and_delete() {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+
+        let project_id = Uuid::now_v7();
+        db.execute_unprepared(&format!(
+            "INSERT INTO project (id, name) VALUES ('{project_id}', 'test')"
+        ))
+        .await
+        .unwrap();
+
+        upsert_catalog(&db, project_id, "en", &json!({"hi": "Hello"}))
+            .await
+            .unwrap();
+        upsert_catalog(&db, project_id, "de", &json!({"hi": "Hallo"}))
+            .await
+            .unwrap();
+        // Upserting the same locale replaces its messages
+        upsert_catalog(&db, project_id, "en", &json!({"hi": "Hi", "bye": "Bye"}))
+            .await
+            .unwrap();
+
+        let catalogs = load_catalogs(&db, project_id).await.unwrap();
+        assert_eq!(catalogs.len(), 2);
+        assert_eq!(catalogs["en"], json!({"hi": "Hi", "bye": "Bye"}));
+
+        assert!(
+            get_catalog(&db, Uuid::now_v7(), "en")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(delete_catalog(&db, project_id, "de").await.unwrap());
+        assert!(!delete_catalog(&db, project_id, "de").await.unwrap());
+        assert_eq!(list_catalogs(&db, project_id).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,157 @@
+use sea_orm::{ConnectionTrait, DbErr, FromQueryResult, Statement};
+use uuid::Uuid;
+
+/// One template variant of a pipeline rule running an A/B experiment.
+#[derive(Debug, Clone)]
+pub struct VariantRow {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub name: String,
+    pub template_id: Uuid,
+    /// Relative share of recipients; a variant with weight 0 gets none.
+    pub weight: i32,
+}
+
+#[derive(Debug, Clone, FromQueryResult)]
+struct VariantRaw {
+    id: String,
+    rule_id: String,
+    name: String,
+    template_id: String,
+    weight: i32,
+}
+
+impl VariantRaw {
+    fn into_row(self) -> Result<VariantRow, DbErr> {
+        Ok(VariantRow {
+            id: Uuid::parse_str(&self.id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            rule_id: Uuid::parse_str(&self.rule_id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            name: self.name,
+            template_id: Uuid::parse_str(&self.template_id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            weight: self.weight,
+        })
+    }
+}
+
+/// List the variants of a rule, ordered by name. Empty when the rule runs no
+/// experiment.
+pub async fn list_by_rule(
+    db: &impl ConnectionTrait,
+    rule_id: Uuid,
+) -> Result<Vec<VariantRow>, DbErr> {
+    let rows = VariantRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT id, rule_id, name, template_id, weight \
+         FROM pipeline_rule_variant WHERE rule_id = ? ORDER BY name",
+        [rule_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter().map(|r| r.into_row()).collect()
+}
+
+/// Replace a rule's variants, given as `(name, template_id, weight)`, and
+/// set its experiment id. An empty list ends the experiment: the rule goes
+/// back to its own template.
+pub async fn replace(
+    db: &impl ConnectionTrait,
+    rule_id: Uuid,
+    experiment: Option<&str>,
+    variants: &[(&str, Uuid, i32)],
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "DELETE FROM pipeline_rule_variant WHERE rule_id = ?",
+        [rule_id.to_string().into()],
+    ))
+    .await?;
+    for &(name, template_id, weight) in variants {
+        db.execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "INSERT INTO pipeline_rule_variant (id, rule_id, name, template_id, weight) \
+             VALUES (?, ?, ?, ?, ?)",
+            [
+                Uuid::now_v7().to_string().into(),
+                rule_id.to_string().into(),
+                name.into(),
+                template_id.to_string().into(),
+                weight.into(),
+            ],
+        ))
+        .await?;
+    }
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "UPDATE pipeline_rule SET experiment = ? WHERE id = ?",
+        [
+            experiment
+                .filter(|_| !variants.is_empty())
+                .map(str::to_string)
+                .into(),
+            rule_id.to_string().into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::admin;
+    use crate::{connect, run_migrations};
+
+    #[tokio::test]
+    async fn replace_and_clear_variants() {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let project_id = Uuid::now_v7();
+        admin::create_project(&db, project_id, "P1", "en")
+            .await
+            .unwrap();
+        let (a, b) = (Uuid::now_v7(), Uuid::now_v7());
+        admin::create_template(&db, a, project_id, "subject-a", "email", "html")
+            .await
+            .unwrap();
+        admin::create_template(&db, b, project_id, "subject-b", "email", "html")
+            .await
+            .unwrap();
+        let event_id = Uuid::now_v7();
+        admin::create_event(&db, event_id, project_id, "user.signup", "transactional")
+            .await
+            .unwrap();
+        let rule_id = Uuid::now_v7();
+        admin::create_rule(&db, rule_id, event_id, "email", a, 0, None)
+            .await
+            .unwrap();
+
+        replace(
+            &db,
+            rule_id,
+            Some("subject-oct"),
+            &[("B", b, 30), ("A", a, 70)],
+        )
+        .await
+        .unwrap();
+        let variants = list_by_rule(&db, rule_id).await.unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!((variants[0].name.as_str(), variants[0].weight), ("A", 70));
+        assert_eq!(variants[1].template_id, b);
+        let rules = admin::list_rules(&db, event_id).await.unwrap();
+        assert_eq!(rules[0].experiment.as_deref(), Some("subject-oct"));
+
+        // Deleting a template drops its variant
+        admin::delete_template(&db, b).await.unwrap();
+        assert_eq!(list_by_rule(&db, rule_id).await.unwrap().len(), 1);
+
+        replace(&db, rule_id, Some("subject-oct"), &[])
+            .await
+            .unwrap();
+        assert!(list_by_rule(&db, rule_id).await.unwrap().is_empty());
+        let rules = admin::list_rules(&db, event_id).await.unwrap();
+        assert_eq!(rules[0].experiment, None);
+    }
+}
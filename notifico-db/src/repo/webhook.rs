@@ -0,0 +1,555 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::credential::{decrypt_credential, encrypt_credential};
+
+// ── Subscriptions ───────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct WebhookSubscriptionRow {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+impl WebhookSubscriptionRow {
+    /// Whether this subscription wants the given event type.
+    /// An empty list subscribes to every event type.
+    pub fn matches(&self, event_type: &str) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type)
+    }
+}
+
+#[derive(Debug, Clone, FromQueryResult)]
+struct WebhookSubscriptionRaw {
+    id: String,
+    project_id: String,
+    url: String,
+    event_types: String,
+    enabled: i32,
+    created_at: String,
+}
+
+impl WebhookSubscriptionRaw {
+    fn into_row(self) -> Result<WebhookSubscriptionRow, DbErr> {
+        Ok(WebhookSubscriptionRow {
+            id: Uuid::parse_str(&self.id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            project_id: Uuid::parse_str(&self.project_id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            url: self.url,
+            event_types: serde_json::from_str(&self.event_types)
+                .map_err(|e| DbErr::Custom(format!("invalid event_types JSON: {e}")))?,
+            enabled: self.enabled != 0,
+            created_at: self.created_at,
+        })
+    }
+}
+
+const SUBSCRIPTION_COLUMNS: &str = "id, project_id, url, event_types, enabled, created_at";
+
+/// List all webhook subscriptions for a project.
+pub async fn list_subscriptions(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+) -> Result<Vec<WebhookSubscriptionRow>, DbErr> {
+    let rows = WebhookSubscriptionRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {SUBSCRIPTION_COLUMNS} FROM webhook_subscription \
+             WHERE project_id = ? ORDER BY created_at"
+        ),
+        [project_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter().map(|r| r.into_row()).collect()
+}
+
+/// Get a webhook subscription by id, scoped to a project.
+pub async fn get_subscription(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<Option<WebhookSubscriptionRow>, DbErr> {
+    let row = WebhookSubscriptionRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {SUBSCRIPTION_COLUMNS} FROM webhook_subscription \
+             WHERE id = ? AND project_id = ?"
+        ),
+        [id.to_string().into(), project_id.to_string().into()],
+    ))
+    .one(db)
+    .await?;
+    row.map(|r| r.into_row()).transpose()
+}
+
+/// List enabled subscriptions of a project that want the given event type.
+pub async fn list_active_for_event(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    event_type: &str,
+) -> Result<Vec<WebhookSubscriptionRow>, DbErr> {
+    let rows = WebhookSubscriptionRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {SUBSCRIPTION_COLUMNS} FROM webhook_subscription \
+             WHERE project_id = ? AND enabled = 1"
+        ),
+        [project_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+
+    let mut result = Vec::new();
+    for raw in rows {
+        let row = raw.into_row()?;
+        if row.matches(event_type) {
+            result.push(row);
+        }
+    }
+    Ok(result)
+}
+
+/// Insert a new webhook subscription. The signing secret is stored
+/// encrypted, like credential data.
+pub async fn insert_subscription(
+    db: &DatabaseConnection,
+    id: Uuid,
+    project_id: Uuid,
+    url: &str,
+    event_types: &[String],
+    secret: &str,
+    key: &[u8; 32],
+) -> Result<(), DbErr> {
+    let encrypted_secret = encrypt_credential(&Value::from(secret), key)?;
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO webhook_subscription (id, project_id, url, event_types, encrypted_secret) \
+         VALUES (?, ?, ?, ?, ?)",
+        [
+            id.to_string().into(),
+            project_id.to_string().into(),
+            url.into(),
+            Value::from(event_types).to_string().into(),
+            encrypted_secret.into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Update a webhook subscription. Returns false if it does not exist.
+pub async fn update_subscription(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+    url: &str,
+    event_types: &[String],
+    enabled: bool,
+) -> Result<bool, DbErr> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "UPDATE webhook_subscription SET url = ?, event_types = ?, enabled = ? \
+             WHERE id = ? AND project_id = ?",
+            [
+                url.into(),
+                Value::from(event_types).to_string().into(),
+                (enabled as i32).into(),
+                id.to_string().into(),
+                project_id.to_string().into(),
+            ],
+        ))
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a webhook subscription (and its delivery history).
+pub async fn delete_subscription(
+    db: &DatabaseConnection,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<bool, DbErr> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "DELETE FROM webhook_subscription WHERE id = ? AND project_id = ?",
+            [id.to_string().into(), project_id.to_string().into()],
+        ))
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// ── Deliveries ──────────────────────────────────────────────────────
+
+/// A queued or attempted webhook call, joined with its subscription's
+/// target so the dispatcher can send it without another lookup.
+#[derive(Debug, Clone)]
+pub struct WebhookDeliveryRow {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub url: String,
+    /// The subscription's signing secret; see [`decrypt_secret`].
+    pub encrypted_secret: String,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempt: i32,
+    pub max_attempts: i32,
+    pub response_status: Option<i32>,
+    pub error_message: Option<String>,
+    pub created_at: String,
+    pub delivered_at: Option<String>,
+}
+
+#[derive(Debug, Clone, FromQueryResult)]
+struct WebhookDeliveryRaw {
+    id: String,
+    subscription_id: String,
+    url: String,
+    encrypted_secret: String,
+    event_type: String,
+    payload: String,
+    status: String,
+    attempt: i32,
+    max_attempts: i32,
+    response_status: Option<i32>,
+    error_message: Option<String>,
+    created_at: String,
+    delivered_at: Option<String>,
+}
+
+impl WebhookDeliveryRaw {
+    fn into_row(self) -> Result<WebhookDeliveryRow, DbErr> {
+        Ok(WebhookDeliveryRow {
+            id: Uuid::parse_str(&self.id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            subscription_id: Uuid::parse_str(&self.subscription_id)
+                .map_err(|e| DbErr::Custom(format!("invalid UUID: {e}")))?,
+            url: self.url,
+            encrypted_secret: self.encrypted_secret,
+            event_type: self.event_type,
+            payload: serde_json::from_str(&self.payload)
+                .map_err(|e| DbErr::Custom(format!("invalid payload JSON: {e}")))?,
+            status: self.status,
+            attempt: self.attempt,
+            max_attempts: self.max_attempts,
+            response_status: self.response_status,
+            error_message: self.error_message,
+            created_at: self.created_at,
+            delivered_at: self.delivered_at,
+        })
+    }
+}
+
+const DELIVERY_COLUMNS: &str = "d.id, d.subscription_id, s.url, s.encrypted_secret, d.event_type, d.payload, \
+     d.status, d.attempt, d.max_attempts, d.response_status, d.error_message, d.created_at, d.delivered_at";
+
+/// Decrypt a subscription's signing secret.
+pub fn decrypt_secret(encrypted: &str, key: &[u8; 32]) -> Result<String, DbErr> {
+    match decrypt_credential(encrypted, key)? {
+        Value::String(secret) => Ok(secret),
+        _ => Err(DbErr::Custom("Webhook secret is not a string".into())),
+    }
+}
+
+/// Queue a webhook delivery with status='pending'.
+pub async fn enqueue_delivery(
+    db: &DatabaseConnection,
+    id: Uuid,
+    subscription_id: Uuid,
+    event_type: &str,
+    payload: &Value,
+    max_attempts: i32,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO webhook_delivery (id, subscription_id, event_type, payload, max_attempts) \
+         VALUES (?, ?, ?, ?, ?)",
+        [
+            id.to_string().into(),
+            subscription_id.to_string().into(),
+            event_type.into(),
+            payload.to_string().into(),
+            max_attempts.into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Claim pending deliveries that are due, marking them 'processing' and
+/// incrementing their attempt counter.
+pub async fn claim_pending_deliveries(
+    db: &DatabaseConnection,
+    limit: u32,
+) -> Result<Vec<WebhookDeliveryRow>, DbErr> {
+    let rows = WebhookDeliveryRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {DELIVERY_COLUMNS} FROM webhook_delivery d \
+             JOIN webhook_subscription s ON s.id = d.subscription_id \
+             WHERE d.status = 'pending' AND d.next_retry_at <= CURRENT_TIMESTAMP \
+             ORDER BY d.next_retry_at ASC LIMIT ?"
+        ),
+        [limit.into()],
+    ))
+    .all(db)
+    .await?;
+
+    if rows.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let ids: Vec<String> = rows.iter().map(|r| format!("'{}'", r.id)).collect();
+    let id_list = ids.join(", ");
+    db.execute_unprepared(&format!(
+        "UPDATE webhook_delivery SET status = 'processing', attempt = attempt + 1 WHERE id IN ({id_list})"
+    ))
+    .await?;
+
+    let mut result = Vec::with_capacity(rows.len());
+    for raw in rows {
+        let mut row = raw.into_row()?;
+        row.attempt += 1;
+        row.status = "processing".into();
+        result.push(row);
+    }
+    Ok(result)
+}
+
+/// Mark a delivery as successfully delivered.
+pub async fn mark_delivery_succeeded(
+    db: &DatabaseConnection,
+    id: Uuid,
+    response_status: i32,
+) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "UPDATE webhook_delivery SET status = 'delivered', response_status = ?, error_message = NULL, \
+         delivered_at = CURRENT_TIMESTAMP WHERE id = ?",
+        [response_status.into(), id.to_string().into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Mark a delivery attempt as failed. Requeues with backoff while attempts
+/// remain, otherwise marks it 'failed'.
+pub async fn mark_delivery_failed(
+    db: &DatabaseConnection,
+    id: Uuid,
+    response_status: Option<i32>,
+    error: &str,
+    attempt: i32,
+    max_attempts: i32,
+) -> Result<(), DbErr> {
+    if attempt < max_attempts {
+        // Exponential backoff: 30s * 4^(attempt-1) → 30s, 2m, 8m, 32m
+        let backoff_secs = 30i64 * 4i64.pow((attempt - 1).max(0) as u32);
+        let sql = format!(
+            "UPDATE webhook_delivery SET status = 'pending', response_status = ?, error_message = ?, \
+             next_retry_at = datetime('now', '+{backoff_secs} seconds') WHERE id = ?"
+        );
+        db.execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            sql,
+            [response_status.into(), error.into(), id.to_string().into()],
+        ))
+        .await?;
+    } else {
+        db.execute_raw(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "UPDATE webhook_delivery SET status = 'failed', response_status = ?, error_message = ? WHERE id = ?",
+            [response_status.into(), error.into(), id.to_string().into()],
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// List delivery history for a subscription, newest first.
+pub async fn list_deliveries(
+    db: &DatabaseConnection,
+    subscription_id: Uuid,
+    limit: u64,
+    offset: u64,
+) -> Result<Vec<WebhookDeliveryRow>, DbErr> {
+    let rows = WebhookDeliveryRaw::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        format!(
+            "SELECT {DELIVERY_COLUMNS} FROM webhook_delivery d \
+             JOIN webhook_subscription s ON s.id = d.subscription_id \
+             WHERE d.subscription_id = ? \
+             ORDER BY d.created_at DESC, d.id DESC LIMIT {limit} OFFSET {offset}"
+        ),
+        [subscription_id.to_string().into()],
+    ))
+    .all(db)
+    .await?;
+    rows.into_iter().map(|r| r.into_row()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connect, run_migrations};
+
+    const KEY: [u8; 32] = [0x11; 32];
+
+    async fn setup() -> (DatabaseConnection, Uuid) {
+        let db = connect("sqlite::memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+
+        let project_id = Uuid::now_v7();
+        db.execute_unprepared(&format!(
+            "INSERT INTO project (id, name) VALUES ('{project_id}', 'test')"
+        ))
+        .await
+        .unwrap();
+
+        (db, project_id)
+    }
+
+    #[tokio::test]
+    async fn subscription_crud_and_event_matching() {
+        let (db, project_id) = setup().await;
+        let all_id = Uuid::now_v7();
+        let opens_id = Uuid::now_v7();
+
+        insert_subscription(
+            &db,
+            all_id,
+            project_id,
+            "https://a.example/hook",
+            &[],
+            "s1",
+            &KEY,
+        )
+        .await
+        .unwrap();
+        insert_subscription(
+            &db,
+            opens_id,
+            project_id,
+            "https://b.example/hook",
+            &["tracking.open".to_string()],
+            "s2",
+            &KEY,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(list_subscriptions(&db, project_id).await.unwrap().len(), 2);
+
+        let delivered = list_active_for_event(&db, project_id, "delivery.delivered")
+            .await
+            .unwrap();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].id, all_id);
+
+        let opens = list_active_for_event(&db, project_id, "tracking.open")
+            .await
+            .unwrap();
+        assert_eq!(opens.len(), 2);
+
+        // Disable the catch-all subscription
+        assert!(
+            update_subscription(
+                &db,
+                project_id,
+                all_id,
+                "https://a.example/hook",
+                &[],
+                false
+            )
+            .await
+            .unwrap()
+        );
+        let opens = list_active_for_event(&db, project_id, "tracking.open")
+            .await
+            .unwrap();
+        assert_eq!(opens.len(), 1);
+
+        // Other projects cannot see or delete it
+        assert!(
+            get_subscription(&db, Uuid::now_v7(), all_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            !delete_subscription(&db, Uuid::now_v7(), all_id)
+                .await
+                .unwrap()
+        );
+        assert!(delete_subscription(&db, project_id, all_id).await.unwrap());
+        assert_eq!(list_subscriptions(&db, project_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn delivery_claim_retry_and_history() {
+        let (db, project_id) = setup().await;
+        let sub_id = Uuid::now_v7();
+        insert_subscription(
+            &db,
+            sub_id,
+            project_id,
+            "https://a.example/hook",
+            &[],
+            "secret",
+            &KEY,
+        )
+        .await
+        .unwrap();
+
+        let delivery_id = Uuid::now_v7();
+        let payload = serde_json::json!({"type": "delivery.delivered"});
+        enqueue_delivery(&db, delivery_id, sub_id, "delivery.delivered", &payload, 2)
+            .await
+            .unwrap();
+
+        let claimed = claim_pending_deliveries(&db, 10).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].attempt, 1);
+        assert_eq!(claimed[0].url, "https://a.example/hook");
+        assert_ne!(claimed[0].encrypted_secret, "secret");
+        assert_eq!(
+            decrypt_secret(&claimed[0].encrypted_secret, &KEY).unwrap(),
+            "secret"
+        );
+        assert_eq!(claimed[0].payload, payload);
+
+        // Nothing left to claim while processing
+        assert!(claim_pending_deliveries(&db, 10).await.unwrap().is_empty());
+
+        // First failure is requeued with backoff (not immediately claimable)
+        mark_delivery_failed(&db, delivery_id, Some(500), "HTTP 500", 1, 2)
+            .await
+            .unwrap();
+        let history = list_deliveries(&db, sub_id, 50, 0).await.unwrap();
+        assert_eq!(history[0].status, "pending");
+        assert_eq!(history[0].response_status, Some(500));
+        assert!(claim_pending_deliveries(&db, 10).await.unwrap().is_empty());
+
+        // Final failure is terminal
+        mark_delivery_failed(&db, delivery_id, None, "connection refused", 2, 2)
+            .await
+            .unwrap();
+        let history = list_deliveries(&db, sub_id, 50, 0).await.unwrap();
+        assert_eq!(history[0].status, "failed");
+
+        mark_delivery_succeeded(&db, delivery_id, 204)
+            .await
+            .unwrap();
+        let history = list_deliveries(&db, sub_id, 50, 0).await.unwrap();
+        assert_eq!(history[0].status, "delivered");
+        assert!(history[0].delivered_at.is_some());
+    }
+}
@@ -12,6 +12,14 @@ export interface Event {
   name: string;
   category: string;
   description?: string;
+  data_schema?: Record<string, unknown> | null;
+}
+
+export interface SchemaWarning {
+  event: string;
+  template_id: string;
+  locale: string;
+  variable: string;
 }
 
 export interface PipelineRule {
@@ -23,11 +31,27 @@ export interface PipelineRule {
   priority: number;
 }
 
+export type ContentFormat = 'html' | 'mjml' | 'markdown';
+
 export interface Template {
   id: string;
   project_id: string;
   name: string;
   channel: string;
+  format: ContentFormat;
+}
+
+export interface TemplateVersion {
+  version: number;
+  status: 'draft' | 'current' | 'archived';
+  published_at: string | null;
+  created_at: string;
+}
+
+export interface ContentError {
+  field: string;
+  code: 'required' | 'invalid_type' | 'syntax';
+  message: string;
 }
 
 export interface Recipient {
@@ -17,6 +17,33 @@ pub struct DeliveryTask {
     pub rule_id: Option<Uuid>,
     pub attempt: u32,
     pub max_attempts: u32,
+    #[serde(default)]
+    pub attachments: Vec<AttachmentRef>,
+    /// Template version the body was rendered from, for the delivery log.
+    #[serde(default)]
+    pub template_version: Option<i32>,
+    /// Experiment and variant the recipient was assigned to, if the rule
+    /// runs an A/B test.
+    #[serde(default)]
+    pub experiment: Option<String>,
+    #[serde(default)]
+    pub variant: Option<String>,
+    /// Named credential the rule sends through; `None` uses the channel's
+    /// project default.
+    #[serde(default)]
+    pub credential: Option<String>,
+}
+
+/// Reference to a stored attachment carried on a delivery task. The worker
+/// loads the content from the storage backend right before sending.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentRef {
+    pub id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    /// Content-ID for inline attachments (referenced from HTML as `cid:...`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_id: Option<String>,
 }
 
 #[cfg(test)]
@@ -37,11 +64,22 @@ mod tests {
             rule_id: None,
             attempt: 0,
             max_attempts: 5,
+            attachments: vec![AttachmentRef {
+                id: Uuid::now_v7(),
+                filename: "logo.png".into(),
+                content_type: "image/png".into(),
+                content_id: Some("logo".into()),
+            }],
+            template_version: Some(3),
+            experiment: None,
+            variant: None,
+            credential: None,
         };
 
         let json = serde_json::to_string(&task).unwrap();
         let deserialized: DeliveryTask = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.event_name, "order.confirmed");
+        assert_eq!(deserialized.attachments, task.attachments);
         assert_eq!(deserialized.channel, "email");
     }
 
@@ -59,6 +97,11 @@ mod tests {
             rule_id: None,
             attempt: 0,
             max_attempts: 3,
+            attachments: vec![],
+            template_version: None,
+            experiment: None,
+            variant: None,
+            credential: None,
         };
 
         let json = serde_json::to_string(&task).unwrap();
@@ -80,6 +123,11 @@ mod tests {
             rule_id: None,
             attempt: 0,
             max_attempts: 5,
+            attachments: vec![],
+            template_version: None,
+            experiment: None,
+            variant: None,
+            credential: None,
         };
 
         task.attempt += 1;
@@ -13,10 +13,20 @@ use uuid::Uuid;
 
 use axum::extract::Query;
 
-use notifico_db::repo::{admin, api_key, credential, delivery_log, middleware};
+use notifico_db::repo::{
+    admin, api_key, credential, delivery_log, fixture, middleware, partial, template_version,
+    translation, variant, webhook,
+};
+
+use notifico_core::content_format::ContentFormat;
+use notifico_core::data_schema::DataSchema;
+use notifico_core::locale::{LocaleFallbackConfig, normalize};
+use notifico_core::registry::TransportRegistry;
+use notifico_template::RenderOptions;
 
 use crate::AppState;
 use crate::auth::AuthContext;
+use crate::sync;
 
 type ApiResult = Result<Response, Response>;
 
@@ -28,23 +38,65 @@ pub fn admin_router() -> Router<Arc<AppState>> {
             get(get_project).put(update_project).delete(delete_project),
         )
         .route("/events", get(list_events).post(create_event))
-        .route("/events/{id}", put(update_event).delete(delete_event))
+        .route(
+            "/events/{id}",
+            get(get_event).put(update_event).delete(delete_event),
+        )
+        .route("/events/{id}/schema", put(set_event_schema))
         .route(
             "/events/{event_id}/rules",
             get(list_rules).post(create_rule),
         )
         .route("/rules/{id}", put(update_rule).delete(delete_rule))
         .route("/templates", get(list_templates).post(create_template))
-        .route("/templates/{id}", get(get_template).delete(delete_template))
+        .route(
+            "/templates/{id}",
+            get(get_template).put(update_template).delete(delete_template),
+        )
         .route(
             "/templates/{template_id}/content/{locale}",
             get(get_template_content).put(set_template_content),
         )
+        .route(
+            "/templates/{id}/versions",
+            get(list_template_versions).post(create_template_draft),
+        )
+        .route(
+            "/templates/{id}/versions/{version}",
+            get(get_template_version),
+        )
+        .route(
+            "/templates/{id}/versions/{version}/publish",
+            axum::routing::post(publish_template_version),
+        )
+        .route(
+            "/templates/{id}/versions/{version}/rollback",
+            axum::routing::post(rollback_template_version),
+        )
+        .route("/templates/{id}/diff", get(diff_template_versions))
+        // Layouts and partials
+        .route("/partials", get(list_partials).post(create_partial))
+        .route(
+            "/partials/{id}",
+            get(get_partial).put(update_partial).delete(delete_partial),
+        )
+        // Translation catalogs
+        .route("/translations", get(list_translations))
+        .route(
+            "/translations/{locale}",
+            get(get_translation)
+                .put(put_translation)
+                .delete(delete_translation),
+        )
         .route(
             "/credentials",
             get(list_credentials).post(create_credential),
         )
         .route("/credentials/{id}", delete(delete_credential))
+        .route(
+            "/credentials/{id}/default",
+            axum::routing::post(set_default_credential),
+        )
         // Recipients
         .route(
             "/recipients",
@@ -66,6 +118,28 @@ pub fn admin_router() -> Router<Arc<AppState>> {
         .route("/api-keys/{id}", delete(delete_api_key))
         // Template preview
         .route("/templates/{id}/preview", axum::routing::post(preview_template))
+        // Preview fixtures and snapshot tests
+        .route(
+            "/templates/{id}/fixtures",
+            get(list_template_fixtures).post(create_template_fixture),
+        )
+        .route(
+            "/events/{id}/fixtures",
+            get(list_event_fixtures).post(create_event_fixture),
+        )
+        .route(
+            "/fixtures/{id}",
+            put(update_fixture).delete(delete_fixture),
+        )
+        .route(
+            "/templates/{id}/fixtures/render",
+            axum::routing::post(render_template_fixtures),
+        )
+        .route(
+            "/templates/{id}/snapshots",
+            get(list_template_snapshots).put(record_template_snapshots),
+        )
+        .route("/templates/{id}/test", axum::routing::post(test_template))
         // Event stats
         .route("/events/{id}/stats", get(event_stats))
         // Middleware
@@ -77,8 +151,23 @@ pub fn admin_router() -> Router<Arc<AppState>> {
             "/middleware/{id}",
             put(update_middleware).delete(delete_middleware),
         )
+        // A/B template variants
+        .route(
+            "/rules/{rule_id}/variants",
+            get(list_variants).put(set_variants),
+        )
+        .route("/rules/{rule_id}/variants/stats", get(variant_stats))
         // Channels
         .route("/channels", get(list_channels))
+        // Outbound status webhooks
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route(
+            "/webhooks/{id}",
+            get(get_webhook).put(update_webhook).delete(delete_webhook),
+        )
+        .route("/webhooks/{id}/deliveries", get(list_webhook_deliveries))
+        // Directory export and sync
+        .route("/sync", get(export_project_files).post(sync_project_files))
 }
 
 fn require_admin(auth: &AuthContext) -> Result<(), Response> {
@@ -102,6 +191,20 @@ struct ProjectResponse {
     id: Uuid,
     name: String,
     default_locale: String,
+    locale_fallback: LocaleFallbackConfig,
+    render: RenderOptions,
+}
+
+impl From<admin::ProjectRow> for ProjectResponse {
+    fn from(p: admin::ProjectRow) -> Self {
+        Self {
+            locale_fallback: LocaleFallbackConfig::from_settings(&p.settings),
+            render: RenderOptions::from_settings(&p.settings),
+            id: p.id,
+            name: p.name,
+            default_locale: p.default_locale,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -119,6 +222,12 @@ fn default_locale() -> String {
 struct UpdateProjectRequest {
     name: String,
     default_locale: String,
+    /// Replaces the project's locale fallback settings when present.
+    #[serde(default)]
+    locale_fallback: Option<LocaleFallbackConfig>,
+    /// Replaces the project's undefined-variable mode and render limits when present.
+    #[serde(default)]
+    render: Option<RenderOptions>,
 }
 
 async fn list_projects(
@@ -130,11 +239,7 @@ async fn list_projects(
     Ok(Json(
         projects
             .into_iter()
-            .map(|p| ProjectResponse {
-                id: p.id,
-                name: p.name,
-                default_locale: p.default_locale,
-            })
+            .map(ProjectResponse::from)
             .collect::<Vec<_>>(),
     )
     .into_response())
@@ -150,12 +255,7 @@ async fn get_project(
         .await
         .map_err(db_err)?
         .ok_or_else(|| not_found("Project not found"))?;
-    Ok(Json(ProjectResponse {
-        id: project.id,
-        name: project.name,
-        default_locale: project.default_locale,
-    })
-    .into_response())
+    Ok(Json(ProjectResponse::from(project)).into_response())
 }
 
 async fn create_project(
@@ -174,6 +274,8 @@ async fn create_project(
             id,
             name: body.name,
             default_locale: body.default_locale,
+            locale_fallback: LocaleFallbackConfig::default(),
+            render: RenderOptions::default(),
         }),
     )
         .into_response())
@@ -186,15 +288,49 @@ async fn update_project(
     Json(body): Json<UpdateProjectRequest>,
 ) -> ApiResult {
     require_admin(&auth)?;
+    if let Some(render) = &body.render {
+        render.validate().map_err(|message| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({"error": "Invalid render settings", "message": message})),
+            )
+                .into_response()
+        })?;
+    }
     admin::update_project(&state.db, id, &body.name, &body.default_locale)
         .await
         .map_err(db_err)?;
-    Ok(Json(ProjectResponse {
-        id,
-        name: body.name,
-        default_locale: body.default_locale,
-    })
-    .into_response())
+    let project = admin::get_project(&state.db, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Project not found"))?;
+
+    let mut updates = serde_json::Map::new();
+    if let Some(fallback) = &body.locale_fallback {
+        updates.insert("locale_fallback".into(), serde_json::json!(fallback));
+    }
+    if let Some(render) = &body.render {
+        updates.insert("render".into(), serde_json::json!(render));
+    }
+    let project = if updates.is_empty() {
+        project
+    } else {
+        let mut settings = project.settings.clone();
+        if !settings.is_object() {
+            settings = Value::Object(Default::default());
+        }
+        for (key, value) in updates {
+            settings[key] = value;
+        }
+        admin::update_project_settings(&state.db, id, &settings)
+            .await
+            .map_err(db_err)?;
+        admin::ProjectRow {
+            settings,
+            ..project
+        }
+    };
+    Ok(Json(ProjectResponse::from(project)).into_response())
 }
 
 async fn delete_project(
@@ -218,12 +354,29 @@ struct EventResponse {
     name: String,
     category: String,
     description: String,
+    data_schema: Option<Value>,
+}
+
+impl From<admin::EventRow> for EventResponse {
+    fn from(e: admin::EventRow) -> Self {
+        Self {
+            id: e.id,
+            project_id: e.project_id,
+            name: e.name,
+            category: e.category,
+            description: e.description,
+            data_schema: e.data_schema,
+        }
+    }
 }
 
 #[derive(Deserialize)]
 struct CreateEventRequest {
     name: String,
     category: String,
+    /// JSON Schema the event's `data` must match at ingest.
+    #[serde(default)]
+    data_schema: Option<Value>,
 }
 
 #[derive(Deserialize)]
@@ -234,6 +387,53 @@ struct UpdateEventRequest {
     description: String,
 }
 
+#[derive(Deserialize)]
+struct SetEventSchemaRequest {
+    /// New schema; `null` removes it.
+    data_schema: Option<Value>,
+}
+
+/// A variable a template reads that the event's data schema does not declare.
+#[derive(Debug, Serialize)]
+struct SchemaWarning {
+    event: String,
+    template_id: Uuid,
+    locale: String,
+    variable: String,
+}
+
+fn invalid_data_schema(message: String) -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(serde_json::json!({"error": "Invalid data schema", "message": message})),
+    )
+        .into_response()
+}
+
+/// Warn about variables in template `contents` (locale → body) that the
+/// event's schema does not declare.
+fn schema_warnings(
+    event: &str,
+    schema: &DataSchema,
+    template_id: Uuid,
+    contents: &std::collections::BTreeMap<String, Value>,
+) -> Vec<SchemaWarning> {
+    contents
+        .iter()
+        .flat_map(|(locale, body)| {
+            notifico_template::referenced_variables(body)
+                .into_iter()
+                .filter(|variable| !schema.defines(variable))
+                .map(|variable| SchemaWarning {
+                    event: event.to_string(),
+                    template_id,
+                    locale: locale.clone(),
+                    variable,
+                })
+        })
+        .collect()
+}
+
 async fn list_events(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
@@ -245,28 +445,43 @@ async fn list_events(
     Ok(Json(
         events
             .into_iter()
-            .map(|e| EventResponse {
-                id: e.id,
-                project_id: e.project_id,
-                name: e.name,
-                category: e.category,
-                description: e.description,
-            })
+            .map(EventResponse::from)
             .collect::<Vec<_>>(),
     )
     .into_response())
 }
 
+async fn get_event(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let event = admin::get_event(&state.db, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Event not found"))?;
+    Ok(Json(EventResponse::from(event)).into_response())
+}
+
 async fn create_event(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
     Json(body): Json<CreateEventRequest>,
 ) -> ApiResult {
     require_admin(&auth)?;
+    if let Some(schema) = &body.data_schema {
+        DataSchema::compile(schema).map_err(invalid_data_schema)?;
+    }
     let id = Uuid::now_v7();
     admin::create_event(&state.db, id, auth.project_id, &body.name, &body.category)
         .await
         .map_err(db_err)?;
+    if body.data_schema.is_some() {
+        admin::update_event_schema(&state.db, id, body.data_schema.as_ref())
+            .await
+            .map_err(db_err)?;
+    }
     Ok((
         StatusCode::CREATED,
         Json(EventResponse {
@@ -275,6 +490,7 @@ async fn create_event(
             name: body.name,
             category: body.category,
             description: String::new(),
+            data_schema: body.data_schema,
         }),
     )
         .into_response())
@@ -290,13 +506,68 @@ async fn update_event(
     admin::update_event(&state.db, id, &body.name, &body.category, &body.description)
         .await
         .map_err(db_err)?;
-    Ok(Json(EventResponse {
-        id,
-        project_id: auth.project_id,
-        name: body.name,
-        category: body.category,
-        description: body.description,
-    })
+    let event = admin::get_event(&state.db, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Event not found"))?;
+    Ok(Json(EventResponse::from(event)).into_response())
+}
+
+/// Replace the event's data schema and report template variables used by
+/// the event's rules that the new schema does not declare.
+async fn set_event_schema(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(body): Json<SetEventSchemaRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let event = admin::get_event(&state.db, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Event not found"))?;
+    let schema = body
+        .data_schema
+        .as_ref()
+        .map(DataSchema::compile)
+        .transpose()
+        .map_err(invalid_data_schema)?;
+
+    admin::update_event_schema(&state.db, id, body.data_schema.as_ref())
+        .await
+        .map_err(db_err)?;
+
+    let mut warnings = Vec::new();
+    if let Some(schema) = &schema {
+        let mut template_ids: Vec<Uuid> = admin::list_rules(&state.db, id)
+            .await
+            .map_err(db_err)?
+            .into_iter()
+            .map(|r| r.template_id)
+            .collect();
+        template_ids.sort();
+        template_ids.dedup();
+        for template_id in template_ids {
+            let Some(version) = template_version::working_version(&state.db, template_id)
+                .await
+                .map_err(db_err)?
+            else {
+                continue;
+            };
+            let contents = template_version::get_contents(&state.db, version.id)
+                .await
+                .map_err(db_err)?;
+            warnings.extend(schema_warnings(&event.name, schema, template_id, &contents));
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "event": EventResponse {
+            data_schema: body.data_schema,
+            ..EventResponse::from(event)
+        },
+        "warnings": warnings,
+    }))
     .into_response())
 }
 
@@ -320,6 +591,8 @@ struct RuleResponse {
     template_id: Uuid,
     enabled: bool,
     priority: i32,
+    experiment: Option<String>,
+    credential: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -328,6 +601,10 @@ struct CreateRuleRequest {
     template_id: Uuid,
     #[serde(default)]
     priority: i32,
+    /// Name of the credential to send through; omitted uses the channel's
+    /// project default.
+    #[serde(default)]
+    credential: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -338,6 +615,8 @@ struct UpdateRuleRequest {
     enabled: bool,
     #[serde(default)]
     priority: i32,
+    #[serde(default)]
+    credential: Option<String>,
 }
 
 fn default_true() -> bool {
@@ -363,6 +642,8 @@ async fn list_rules(
                 template_id: r.template_id,
                 enabled: r.enabled,
                 priority: r.priority,
+                experiment: r.experiment,
+                credential: r.credential,
             })
             .collect::<Vec<_>>(),
     )
@@ -376,6 +657,13 @@ async fn create_rule(
     Json(body): Json<CreateRuleRequest>,
 ) -> ApiResult {
     require_admin(&auth)?;
+    check_rule_credential(
+        &state,
+        auth.project_id,
+        &body.channel,
+        body.credential.as_deref(),
+    )
+    .await?;
     let id = Uuid::now_v7();
     admin::create_rule(
         &state.db,
@@ -384,6 +672,7 @@ async fn create_rule(
         &body.channel,
         body.template_id,
         body.priority,
+        body.credential.as_deref(),
     )
     .await
     .map_err(db_err)?;
@@ -396,6 +685,8 @@ async fn create_rule(
             template_id: body.template_id,
             enabled: true,
             priority: body.priority,
+            experiment: None,
+            credential: body.credential,
         }),
     )
         .into_response())
@@ -408,6 +699,13 @@ async fn update_rule(
     Json(body): Json<UpdateRuleRequest>,
 ) -> ApiResult {
     require_admin(&auth)?;
+    check_rule_credential(
+        &state,
+        auth.project_id,
+        &body.channel,
+        body.credential.as_deref(),
+    )
+    .await?;
     admin::update_rule(
         &state.db,
         id,
@@ -415,12 +713,40 @@ async fn update_rule(
         body.template_id,
         body.enabled,
         body.priority,
+        body.credential.as_deref(),
     )
     .await
     .map_err(db_err)?;
     Ok(Json(serde_json::json!({"id": id, "updated": true})).into_response())
 }
 
+/// Reject a rule naming a credential the project doesn't have for the
+/// rule's channel; deliveries would otherwise fail at send time.
+async fn check_rule_credential(
+    state: &AppState,
+    project_id: Uuid,
+    channel: &str,
+    name: Option<&str>,
+) -> Result<(), Response> {
+    let Some(name) = name else {
+        return Ok(());
+    };
+    let exists = credential::credential_exists(&state.db, project_id, channel, name)
+        .await
+        .map_err(db_err)?;
+    if exists {
+        return Ok(());
+    }
+    Err((
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(serde_json::json!({
+            "error": "Unknown credential",
+            "message": format!("No credential named '{name}' for channel '{channel}'"),
+        })),
+    )
+        .into_response())
+}
+
 async fn delete_rule(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
@@ -439,12 +765,47 @@ struct TemplateResponse {
     project_id: Uuid,
     name: String,
     channel: String,
+    format: String,
 }
 
 #[derive(Deserialize)]
 struct CreateTemplateRequest {
     name: String,
     channel: String,
+    /// Content authoring format: `html` (default), `mjml` or `markdown`.
+    #[serde(default = "default_format")]
+    format: String,
+}
+
+fn default_format() -> String {
+    ContentFormat::Html.to_string()
+}
+
+#[derive(Deserialize)]
+struct UpdateTemplateRequest {
+    name: String,
+    format: String,
+}
+
+/// Parse a content format and check the channel can take its output.
+/// Channels without a registered transport accept any format.
+fn check_format(
+    state: &AppState,
+    channel: &str,
+    format: &str,
+) -> Result<ContentFormat, (StatusCode, String)> {
+    let format: ContentFormat = format.parse().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let supported = state
+        .registry
+        .get(&notifico_core::channel::ChannelId::new(channel))
+        .is_none_or(|t| format.supports(&t.content_schema()));
+    if !supported {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Channel '{channel}' does not support the {format} format"),
+        ));
+    }
+    Ok(format)
 }
 
 #[derive(Deserialize)]
@@ -468,6 +829,7 @@ async fn list_templates(
                 project_id: t.project_id,
                 name: t.name,
                 channel: t.channel,
+                format: t.format,
             })
             .collect::<Vec<_>>(),
     )
@@ -480,10 +842,19 @@ async fn create_template(
     Json(body): Json<CreateTemplateRequest>,
 ) -> ApiResult {
     require_admin(&auth)?;
+    let format =
+        check_format(&state, &body.channel, &body.format).map_err(IntoResponse::into_response)?;
     let id = Uuid::now_v7();
-    admin::create_template(&state.db, id, auth.project_id, &body.name, &body.channel)
-        .await
-        .map_err(db_err)?;
+    admin::create_template(
+        &state.db,
+        id,
+        auth.project_id,
+        &body.name,
+        &body.channel,
+        format.as_str(),
+    )
+    .await
+    .map_err(db_err)?;
     Ok((
         StatusCode::CREATED,
         Json(TemplateResponse {
@@ -491,6 +862,7 @@ async fn create_template(
             project_id: auth.project_id,
             name: body.name,
             channel: body.channel,
+            format: format.to_string(),
         }),
     )
         .into_response())
@@ -511,6 +883,33 @@ async fn get_template(
         project_id: template.project_id,
         name: template.name,
         channel: template.channel,
+        format: template.format,
+    })
+    .into_response())
+}
+
+async fn update_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateTemplateRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let template = admin::get_template(&state.db, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Template not found"))?;
+    let format = check_format(&state, &template.channel, &body.format)
+        .map_err(IntoResponse::into_response)?;
+    admin::update_template(&state.db, id, &body.name, format.as_str())
+        .await
+        .map_err(db_err)?;
+    Ok(Json(TemplateResponse {
+        id,
+        project_id: template.project_id,
+        name: body.name,
+        channel: template.channel,
+        format: format.to_string(),
     })
     .into_response())
 }
@@ -527,19 +926,63 @@ async fn delete_template(
     Ok(StatusCode::NO_CONTENT.into_response())
 }
 
+#[derive(Deserialize)]
+struct ContentQuery {
+    version: Option<i32>,
+}
+
+/// Resolve an explicit version number, or fall back to the working copy
+/// (open draft, else current version).
+async fn select_version(
+    state: &AppState,
+    template_id: Uuid,
+    version: Option<i32>,
+) -> Result<template_version::TemplateVersionRow, Response> {
+    let row = match version {
+        Some(v) => template_version::get_version(&state.db, template_id, v).await,
+        None => template_version::working_version(&state.db, template_id).await,
+    };
+    row.map_err(db_err)?
+        .ok_or_else(|| not_found("Template version not found"))
+}
+
 async fn get_template_content(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
     Path((template_id, locale)): Path<(Uuid, String)>,
+    Query(q): Query<ContentQuery>,
 ) -> ApiResult {
     require_admin(&auth)?;
-    let content = admin::get_template_content(&state.db, template_id, &locale)
+    let version = select_version(&state, template_id, q.version).await?;
+    let content = admin::get_template_content(&state.db, template_id, version.version, &locale)
         .await
         .map_err(db_err)?
         .ok_or_else(|| {
             (StatusCode::NOT_FOUND, "Content not found for locale".to_string()).into_response()
         })?;
-    Ok(Json(serde_json::json!({ "body": content })).into_response())
+    Ok(Json(serde_json::json!({
+        "body": content,
+        "version": version.version,
+        "status": version.status(),
+    }))
+    .into_response())
+}
+
+/// Validate a template body against the channel's content schema, as
+/// adjusted for the template's authoring format, and check that every
+/// template string compiles. Channels without a registered transport only
+/// get the syntax check.
+pub(crate) fn validate_content(
+    registry: &TransportRegistry,
+    channel: &str,
+    format: ContentFormat,
+    body: &Value,
+) -> Vec<notifico_core::transport::ContentError> {
+    let schema = registry
+        .get(&notifico_core::channel::ChannelId::new(channel))
+        .map(|t| format.content_schema(t.content_schema()))
+        .unwrap_or(notifico_core::transport::ContentSchema { fields: vec![] });
+    schema.validate(body)
 }
 
 async fn set_template_content(
@@ -549,118 +992,767 @@ async fn set_template_content(
     Json(body): Json<SetContentRequest>,
 ) -> ApiResult {
     require_admin(&auth)?;
-    admin::set_template_content(&state.db, template_id, &locale, &body.body)
+    let template = admin::get_template(&state.db, template_id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Template not found"))?;
+    let errors = validate_content(
+        &state.registry,
+        &template.channel,
+        template.format.parse().unwrap_or_default(),
+        &body.body,
+    );
+    if !errors.is_empty() {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({"error": "Invalid template content", "errors": errors})),
+        )
+            .into_response());
+    }
+    let draft = admin::set_template_content(&state.db, template_id, &locale, &body.body)
         .await
         .map_err(db_err)?;
-    Ok(
-        Json(serde_json::json!({"template_id": template_id, "locale": locale, "updated": true}))
-            .into_response(),
-    )
+    state
+        .template_cache
+        .invalidate_version(&draft.id.to_string());
+
+    // Variables the schemas of events rendering this template don't declare
+    let contents = std::collections::BTreeMap::from([(locale.clone(), body.body)]);
+    let mut warnings = Vec::new();
+    for event in admin::list_events_using_template(&state.db, template_id)
+        .await
+        .map_err(db_err)?
+    {
+        let Some(schema) = event.data_schema.as_ref() else {
+            continue;
+        };
+        // Stored schemas were validated on save; skip one that no longer compiles
+        let Ok(schema) = DataSchema::compile(schema) else {
+            continue;
+        };
+        warnings.extend(schema_warnings(&event.name, &schema, template_id, &contents));
+    }
+
+    Ok(Json(serde_json::json!({
+        "template_id": template_id,
+        "locale": locale,
+        "version": draft.version,
+        "status": "draft",
+        "updated": true,
+        "warnings": warnings,
+    }))
+    .into_response())
 }
 
-// ── Credentials ──────────────────────────────────────────────────────
+// ── Template versions ────────────────────────────────────────────────
 
 #[derive(Serialize)]
-struct CredentialResponse {
-    id: Uuid,
-    name: String,
-    channel: String,
-    enabled: bool,
+struct TemplateVersionResponse {
+    version: i32,
+    status: &'static str,
+    published_at: Option<String>,
+    created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contents: Option<std::collections::BTreeMap<String, Value>>,
+}
+
+impl From<template_version::TemplateVersionRow> for TemplateVersionResponse {
+    fn from(v: template_version::TemplateVersionRow) -> Self {
+        Self {
+            version: v.version,
+            status: v.status(),
+            published_at: v.published_at,
+            created_at: v.created_at,
+            contents: None,
+        }
+    }
 }
 
 #[derive(Deserialize)]
-struct CreateCredentialRequest {
-    name: String,
-    channel: String,
-    data: Value,
+struct DiffQuery {
+    from: i32,
+    to: i32,
+    locale: Option<String>,
 }
 
-async fn list_credentials(
+#[derive(Serialize)]
+struct FieldDiff {
+    field: String,
+    diff: String,
+}
+
+#[derive(Serialize)]
+struct LocaleDiff {
+    locale: String,
+    /// `added`, `removed`, or `changed`
+    change: &'static str,
+    fields: Vec<FieldDiff>,
+}
+
+async fn list_template_versions(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
+    Path(id): Path<Uuid>,
 ) -> ApiResult {
     require_admin(&auth)?;
-    let creds = admin::list_credentials(&state.db, auth.project_id)
+    let versions = template_version::list_versions(&state.db, id)
         .await
         .map_err(db_err)?;
     Ok(Json(
-        creds
+        versions
             .into_iter()
-            .map(|c| CredentialResponse {
-                id: c.id,
-                name: c.name,
-                channel: c.channel,
-                enabled: c.enabled,
-            })
+            .map(TemplateVersionResponse::from)
             .collect::<Vec<_>>(),
     )
     .into_response())
 }
 
-async fn create_credential(
+async fn create_template_draft(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
-    Json(body): Json<CreateCredentialRequest>,
+    Path(id): Path<Uuid>,
 ) -> ApiResult {
     require_admin(&auth)?;
-    let key = state.encryption_key.as_ref().ok_or_else(|| {
-        (
-            StatusCode::BAD_REQUEST,
-            "Encryption key not configured. Set NOTIFICO_AUTH_ENCRYPTION_KEY.".to_string(),
-        )
-            .into_response()
-    })?;
-    let id = Uuid::now_v7();
-    credential::insert_credential(
-        &state.db,
-        id,
-        auth.project_id,
-        &body.name,
-        &body.channel,
-        &body.data,
-        key,
-    )
-    .await
-    .map_err(db_err)?;
-    Ok((
-        StatusCode::CREATED,
-        Json(CredentialResponse {
-            id,
-            name: body.name,
-            channel: body.channel,
-            enabled: true,
-        }),
-    )
-        .into_response())
+    admin::get_template(&state.db, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Template not found"))?;
+    let draft = template_version::ensure_draft(&state.db, id)
+        .await
+        .map_err(db_err)?;
+    Ok((StatusCode::CREATED, Json(TemplateVersionResponse::from(draft))).into_response())
 }
 
-async fn delete_credential(
+async fn get_template_version(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
-    Path(id): Path<Uuid>,
+    Path((id, version)): Path<(Uuid, i32)>,
 ) -> ApiResult {
     require_admin(&auth)?;
-    admin::delete_credential(&state.db, id)
+    let row = select_version(&state, id, Some(version)).await?;
+    let contents = template_version::get_contents(&state.db, row.id)
         .await
         .map_err(db_err)?;
-    Ok(StatusCode::NO_CONTENT.into_response())
+    let mut response = TemplateVersionResponse::from(row);
+    response.contents = Some(contents);
+    Ok(Json(response).into_response())
 }
 
-// ── Recipients ──────────────────────────────────────────────────────
+async fn publish_template_version(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path((id, version)): Path<(Uuid, i32)>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let row = select_version(&state, id, Some(version)).await?;
+    if !row.is_draft() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Version {version} is {}, only drafts can be published", row.status()),
+        )
+            .into_response());
+    }
+    switch_current_version(&state, id, version).await
+}
 
-#[derive(Serialize)]
-struct RecipientResponse {
-    id: Uuid,
-    external_id: String,
-    locale: String,
-    timezone: String,
-    metadata: Value,
+async fn rollback_template_version(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path((id, version)): Path<(Uuid, i32)>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let row = select_version(&state, id, Some(version)).await?;
+    if row.status() != "archived" {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "Version {version} is {}, only previously published versions can be restored",
+                row.status()
+            ),
+        )
+            .into_response());
+    }
+    switch_current_version(&state, id, version).await
 }
 
-#[derive(Deserialize)]
-struct CreateRecipientRequest {
-    external_id: String,
-    #[serde(default = "default_locale")]
+async fn switch_current_version(state: &AppState, id: Uuid, version: i32) -> ApiResult {
+    template_version::publish_version(&state.db, id, version)
+        .await
+        .map_err(db_err)?;
+    let row = select_version(state, id, Some(version)).await?;
+    tracing::info!(template_id = %id, version, "Template version is now current");
+    Ok(Json(TemplateVersionResponse::from(row)).into_response())
+}
+
+async fn diff_template_versions(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(q): Query<DiffQuery>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let from = select_version(&state, id, Some(q.from)).await?;
+    let to = select_version(&state, id, Some(q.to)).await?;
+    let from_contents = template_version::get_contents(&state.db, from.id)
+        .await
+        .map_err(db_err)?;
+    let to_contents = template_version::get_contents(&state.db, to.id)
+        .await
+        .map_err(db_err)?;
+
+    let locales = diff_contents(
+        &from_contents,
+        &to_contents,
+        q.locale.as_deref(),
+        (q.from, q.to),
+    );
+    Ok(Json(serde_json::json!({
+        "from": q.from,
+        "to": q.to,
+        "locales": locales,
+    }))
+    .into_response())
+}
+
+/// Per-locale, per-field unified diff between two versions' contents.
+/// Unchanged locales and fields are omitted.
+fn diff_contents(
+    from: &std::collections::BTreeMap<String, Value>,
+    to: &std::collections::BTreeMap<String, Value>,
+    only_locale: Option<&str>,
+    (from_version, to_version): (i32, i32),
+) -> Vec<LocaleDiff> {
+    let empty = Value::Object(Default::default());
+    let mut locales: Vec<&String> = from.keys().chain(to.keys()).collect();
+    locales.sort();
+    locales.dedup();
+
+    let mut result = Vec::new();
+    for locale in locales {
+        if only_locale.is_some_and(|l| l != locale) {
+            continue;
+        }
+        let old = from.get(locale).unwrap_or(&empty);
+        let new = to.get(locale).unwrap_or(&empty);
+        if old == new {
+            continue;
+        }
+
+        let field_diffs = diff_fields(
+            old,
+            new,
+            (
+                &format!("v{from_version}/{locale}"),
+                &format!("v{to_version}/{locale}"),
+            ),
+        );
+
+        result.push(LocaleDiff {
+            locale: locale.clone(),
+            change: match (from.contains_key(locale), to.contains_key(locale)) {
+                (false, _) => "added",
+                (_, false) => "removed",
+                _ => "changed",
+            },
+            fields: field_diffs,
+        });
+    }
+    result
+}
+
+/// Unified diff of each top-level field that differs between two content
+/// objects. Headers are `<label>/<field>`.
+fn diff_fields(old: &Value, new: &Value, (old_label, new_label): (&str, &str)) -> Vec<FieldDiff> {
+    let mut fields: Vec<&String> = old
+        .as_object()
+        .into_iter()
+        .chain(new.as_object())
+        .flat_map(|o| o.keys())
+        .collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|f| old.get(f.as_str()) != new.get(f.as_str()))
+        .map(|field| {
+            let old_text = field_text(old.get(field.as_str()));
+            let new_text = field_text(new.get(field.as_str()));
+            let diff = similar::TextDiff::from_lines(&old_text, &new_text)
+                .unified_diff()
+                .header(
+                    &format!("{old_label}/{field}"),
+                    &format!("{new_label}/{field}"),
+                )
+                .to_string();
+            FieldDiff {
+                field: field.clone(),
+                diff,
+            }
+        })
+        .collect()
+}
+
+fn field_text(value: Option<&Value>) -> String {
+    let mut text = match value {
+        None => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+// ── Layouts, partials & stylesheets ──────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct PartialResponse {
+    id: Uuid,
+    name: String,
+    kind: String,
+    body: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<partial::PartialRow> for PartialResponse {
+    fn from(p: partial::PartialRow) -> Self {
+        Self {
+            id: p.id,
+            name: p.name,
+            kind: p.kind,
+            body: p.body,
+            created_at: p.created_at,
+            updated_at: p.updated_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PartialRequest {
+    name: String,
+    #[serde(default = "default_partial_kind")]
+    kind: String,
+    body: String,
+}
+
+fn default_partial_kind() -> String {
+    "partial".into()
+}
+
+/// Check a layout/partial/stylesheet before saving: valid name and kind,
+/// unique name, and no include/extends cycle with the project's other
+/// template sources.
+async fn validate_partial(
+    state: &AppState,
+    project_id: Uuid,
+    id: Option<Uuid>,
+    req: &PartialRequest,
+) -> Result<(), Response> {
+    if req.name.is_empty()
+        || !req
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "name must be non-empty and contain only letters, digits, '_', '-', '.' or '/'",
+        )
+            .into_response());
+    }
+    if !matches!(
+        req.kind.as_str(),
+        "layout" | "partial" | partial::STYLESHEET
+    ) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "kind must be 'layout', 'partial' or 'stylesheet'",
+        )
+            .into_response());
+    }
+
+    let existing = partial::list_partials(&state.db, project_id)
+        .await
+        .map_err(db_err)?;
+    if existing
+        .iter()
+        .any(|p| p.name == req.name && Some(p.id) != id)
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "A layout, partial or stylesheet named '{}' already exists",
+                req.name
+            ),
+        )
+            .into_response());
+    }
+
+    let mut sources: std::collections::BTreeMap<String, String> = existing
+        .into_iter()
+        .filter(|p| Some(p.id) != id && p.kind != partial::STYLESHEET)
+        .map(|p| (p.name, p.body))
+        .collect();
+    if req.kind != partial::STYLESHEET {
+        sources.insert(req.name.clone(), req.body.clone());
+    }
+    notifico_template::Partials::new(sources)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response())?;
+    Ok(())
+}
+
+async fn list_partials(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let partials = partial::list_partials(&state.db, auth.project_id)
+        .await
+        .map_err(db_err)?;
+    Ok(Json(
+        partials
+            .into_iter()
+            .map(PartialResponse::from)
+            .collect::<Vec<_>>(),
+    )
+    .into_response())
+}
+
+async fn create_partial(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(body): Json<PartialRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    validate_partial(&state, auth.project_id, None, &body).await?;
+    let id = Uuid::now_v7();
+    partial::insert_partial(
+        &state.db,
+        id,
+        auth.project_id,
+        &body.name,
+        &body.kind,
+        &body.body,
+    )
+    .await
+    .map_err(db_err)?;
+    let row = partial::get_partial(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Partial not found"))?;
+    Ok((StatusCode::CREATED, Json(PartialResponse::from(row))).into_response())
+}
+
+async fn get_partial(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let row = partial::get_partial(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Partial not found"))?;
+    Ok(Json(PartialResponse::from(row)).into_response())
+}
+
+async fn update_partial(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(body): Json<PartialRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    validate_partial(&state, auth.project_id, Some(id), &body).await?;
+    let updated = partial::update_partial(
+        &state.db,
+        auth.project_id,
+        id,
+        &body.name,
+        &body.kind,
+        &body.body,
+    )
+    .await
+    .map_err(db_err)?;
+    if !updated {
+        return Err(not_found("Partial not found"));
+    }
+    Ok(Json(serde_json::json!({"id": id, "updated": true})).into_response())
+}
+
+async fn delete_partial(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let deleted = partial::delete_partial(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?;
+    if !deleted {
+        return Err(not_found("Partial not found"));
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+// ── Translation catalogs ────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct TranslationSummary {
+    locale: String,
+    keys: usize,
+    updated_at: String,
+}
+
+#[derive(Serialize)]
+struct TranslationResponse {
+    locale: String,
+    messages: Value,
+    updated_at: String,
+}
+
+/// Catalog locales are stored in canonical BCP-47 casing.
+fn catalog_locale(locale: &str) -> Option<String> {
+    let locale = normalize(locale);
+    let valid = !locale.is_empty()
+        && locale.len() <= 35
+        && locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then_some(locale)
+}
+
+fn invalid_locale() -> Response {
+    (StatusCode::BAD_REQUEST, "Invalid locale").into_response()
+}
+
+async fn list_translations(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let catalogs = translation::list_catalogs(&state.db, auth.project_id)
+        .await
+        .map_err(db_err)?;
+    Ok(Json(
+        catalogs
+            .into_iter()
+            .map(|c| TranslationSummary {
+                keys: notifico_template::flatten_catalog(&c.messages).len(),
+                locale: c.locale,
+                updated_at: c.updated_at,
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_response())
+}
+
+async fn get_translation(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(locale): Path<String>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let locale = catalog_locale(&locale).ok_or_else(invalid_locale)?;
+    let row = translation::get_catalog(&state.db, auth.project_id, &locale)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Translation catalog not found"))?;
+    Ok(Json(TranslationResponse {
+        locale: row.locale,
+        messages: row.messages,
+        updated_at: row.updated_at,
+    })
+    .into_response())
+}
+
+/// Replace the catalog for a locale. The body is the message object; nested
+/// objects form dotted keys. Messages with ICU syntax errors are rejected.
+async fn put_translation(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(locale): Path<String>,
+    Json(messages): Json<Value>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let locale = catalog_locale(&locale).ok_or_else(invalid_locale)?;
+    if !messages.is_object() {
+        return Err((StatusCode::BAD_REQUEST, "Catalog must be a JSON object").into_response());
+    }
+    let errors = notifico_template::check_catalog(&messages);
+    if !errors.is_empty() {
+        let errors: Vec<Value> = errors
+            .into_iter()
+            .map(|(key, error)| serde_json::json!({"key": key, "error": error}))
+            .collect();
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({"error": "Invalid messages", "errors": errors})),
+        )
+            .into_response());
+    }
+
+    translation::upsert_catalog(&state.db, auth.project_id, &locale, &messages)
+        .await
+        .map_err(db_err)?;
+    let row = translation::get_catalog(&state.db, auth.project_id, &locale)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Translation catalog not found"))?;
+    Ok(Json(TranslationResponse {
+        locale: row.locale,
+        messages: row.messages,
+        updated_at: row.updated_at,
+    })
+    .into_response())
+}
+
+async fn delete_translation(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(locale): Path<String>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let locale = catalog_locale(&locale).ok_or_else(invalid_locale)?;
+    let deleted = translation::delete_catalog(&state.db, auth.project_id, &locale)
+        .await
+        .map_err(db_err)?;
+    if !deleted {
+        return Err(not_found("Translation catalog not found"));
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+// ── Credentials ──────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct CredentialResponse {
+    id: Uuid,
+    name: String,
+    channel: String,
+    enabled: bool,
+    is_default: bool,
+}
+
+#[derive(Deserialize)]
+struct CreateCredentialRequest {
+    name: String,
+    channel: String,
+    data: Value,
+    /// Use this credential for rules that don't name one.
+    #[serde(default)]
+    is_default: bool,
+}
+
+async fn list_credentials(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let creds = admin::list_credentials(&state.db, auth.project_id)
+        .await
+        .map_err(db_err)?;
+    Ok(Json(
+        creds
+            .into_iter()
+            .map(|c| CredentialResponse {
+                id: c.id,
+                name: c.name,
+                channel: c.channel,
+                enabled: c.enabled,
+                is_default: c.is_default,
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_response())
+}
+
+async fn create_credential(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(body): Json<CreateCredentialRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let key = state.encryption_key.as_ref().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Encryption key not configured. Set NOTIFICO_AUTH_ENCRYPTION_KEY.".to_string(),
+        )
+            .into_response()
+    })?;
+    let id = Uuid::now_v7();
+    credential::insert_credential(
+        &state.db,
+        id,
+        auth.project_id,
+        &body.name,
+        &body.channel,
+        &body.data,
+        key,
+    )
+    .await
+    .map_err(db_err)?;
+    if body.is_default {
+        credential::set_default(&state.db, auth.project_id, id)
+            .await
+            .map_err(db_err)?;
+    }
+    Ok((
+        StatusCode::CREATED,
+        Json(CredentialResponse {
+            id,
+            name: body.name,
+            channel: body.channel,
+            enabled: true,
+            is_default: body.is_default,
+        }),
+    )
+        .into_response())
+}
+
+async fn set_default_credential(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let found = credential::set_default(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?;
+    if !found {
+        return Err(not_found("Credential not found"));
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn delete_credential(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    admin::delete_credential(&state.db, id)
+        .await
+        .map_err(db_err)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+// ── Recipients ──────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct RecipientResponse {
+    id: Uuid,
+    external_id: String,
+    locale: String,
+    timezone: String,
+    metadata: Value,
+}
+
+#[derive(Deserialize)]
+struct CreateRecipientRequest {
+    external_id: String,
+    #[serde(default = "default_locale")]
     locale: String,
     #[serde(default = "default_timezone")]
     timezone: String,
@@ -879,6 +1971,11 @@ struct DeliveryLogResponse {
     attempts: i32,
     created_at: String,
     delivered_at: Option<String>,
+    provider_message_id: Option<String>,
+    template_version: Option<i32>,
+    experiment: Option<String>,
+    variant: Option<String>,
+    credential: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -928,6 +2025,11 @@ async fn query_delivery_log(
                 attempts: l.attempts,
                 created_at: l.created_at,
                 delivered_at: l.delivered_at,
+                provider_message_id: l.provider_message_id,
+                template_version: l.template_version,
+                experiment: l.experiment,
+                variant: l.variant,
+                credential: l.credential,
             })
             .collect(),
         total,
@@ -1067,47 +2169,569 @@ async fn list_channels(
 struct PreviewRequest {
     locale: Option<String>,
     data: Value,
+    /// Version to render; defaults to the working copy (draft, else current).
+    version: Option<i32>,
+    /// Time zone for date filters; defaults to UTC.
+    timezone: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PreviewResponse {
+    rendered: Value,
+    /// Locale of the content that was rendered, after fallback.
+    locale: String,
+}
+
+async fn preview_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(template_id): Path<Uuid>,
+    Json(req): Json<PreviewRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+
+    let context = PreviewContext::load(&state, auth.project_id).await?;
+    let locale = req
+        .locale
+        .as_deref()
+        .unwrap_or(&context.locales.default_locale);
+
+    let version = select_version(&state, template_id, req.version).await?;
+    let template = notifico_db::repo::template::resolve_template_version(
+        &state.db,
+        template_id,
+        version.version,
+        &context.locales.chain(locale),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Template not found: {template_id} locale {locale}"),
+        )
+            .into_response()
+    })?;
+
+    let rendered = context
+        .render(
+            &template,
+            locale,
+            req.timezone.as_deref().unwrap_or("UTC"),
+            &req.data,
+        )
+        .map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+
+    Ok(Json(PreviewResponse {
+        rendered: Value::Object(rendered),
+        locale: template.locale,
+    })
+    .into_response())
+}
+
+/// Project-wide inputs for rendering templates outside the delivery
+/// pipeline, loaded once per request.
+struct PreviewContext {
+    locales: crate::ingest::ProjectLocales,
+    partials: notifico_template::Partials,
+    translations: notifico_template::Translations,
+    render_options: RenderOptions,
+}
+
+impl PreviewContext {
+    async fn load(state: &AppState, project_id: Uuid) -> Result<Self, Response> {
+        let internal = |e: String| (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+        Ok(Self {
+            locales: crate::ingest::load_project_locales(state, project_id)
+                .await
+                .map_err(internal)?,
+            partials: crate::ingest::load_partials(state, project_id)
+                .await
+                .map_err(internal)?,
+            translations: crate::ingest::load_translations(state, project_id)
+                .await
+                .map_err(internal)?,
+            render_options: crate::ingest::load_render_options(state, project_id)
+                .await
+                .map_err(internal)?,
+        })
+    }
+
+    /// Render and compile resolved content for `locale`, as the pipeline
+    /// would for a recipient in that locale.
+    fn render(
+        &self,
+        template: &notifico_db::repo::template::ResolvedTemplate,
+        locale: &str,
+        timezone: &str,
+        data: &Value,
+    ) -> Result<serde_json::Map<String, Value>, String> {
+        let settings = notifico_template::RenderSettings {
+            partials: self.partials.clone(),
+            localization: notifico_template::Localization::new(locale)
+                .with_timezone(timezone)
+                .with_translations(
+                    self.translations
+                        .clone()
+                        .with_fallback(self.locales.chain(locale).locales),
+                ),
+            options: self.render_options,
+        };
+        let mut rendered = notifico_template::render_body(&template.body, data, &settings)
+            .map_err(|e| format!("Render error: {e}"))?;
+        template
+            .format
+            .parse::<ContentFormat>()
+            .unwrap_or_default()
+            .compile(&mut rendered)
+            .map_err(|e| format!("Compile error: {e}"))?;
+        Ok(rendered)
+    }
+}
+
+// --- Preview fixtures and snapshot tests ---
+
+#[derive(Serialize)]
+struct FixtureResponse {
+    id: Uuid,
+    template_id: Option<Uuid>,
+    event_id: Option<Uuid>,
+    name: String,
+    data: Value,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<fixture::FixtureRow> for FixtureResponse {
+    fn from(f: fixture::FixtureRow) -> Self {
+        Self {
+            id: f.id,
+            template_id: f.template_id,
+            event_id: f.event_id,
+            name: f.name,
+            data: f.data,
+            created_at: f.created_at,
+            updated_at: f.updated_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FixtureRequest {
+    name: String,
+    /// Event data the template is rendered with, as in an ingest request.
+    #[serde(default = "empty_object")]
+    data: Value,
+}
+
+fn empty_object() -> Value {
+    Value::Object(Default::default())
+}
+
+/// One fixture rendered in one locale.
+#[derive(Serialize)]
+struct FixtureRender {
+    fixture_id: Uuid,
+    fixture: String,
+    locale: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rendered: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct FixtureRenderResponse {
+    version: i32,
+    results: Vec<FixtureRender>,
+}
+
+#[derive(Serialize)]
+struct SnapshotResponse {
+    fixture_id: Uuid,
+    locale: String,
+    rendered: Value,
+}
+
+/// Outcome of comparing one fixture render against its snapshot.
+#[derive(Serialize)]
+struct SnapshotCheck {
+    fixture_id: Uuid,
+    fixture: String,
+    locale: String,
+    /// `unchanged`, `changed`, `error`, `new` (no snapshot yet) or `removed`
+    /// (snapshot exists but the fixture or locale no longer renders).
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<FieldDiff>,
+}
+
+#[derive(Serialize)]
+struct TemplateTestResponse {
+    passed: bool,
+    version: i32,
+    results: Vec<SnapshotCheck>,
+}
+
+fn validate_fixture(req: &FixtureRequest) -> Result<(), (StatusCode, &'static str)> {
+    if req.name.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name must not be empty"));
+    }
+    if !req.data.is_object() {
+        return Err((StatusCode::BAD_REQUEST, "data must be a JSON object"));
+    }
+    Ok(())
+}
+
+/// Map a duplicate fixture name to 409; names are unique per owner.
+fn fixture_db_err(e: sea_orm::DbErr) -> Response {
+    if matches!(e.sql_err(), Some(sea_orm::SqlErr::UniqueConstraintViolation(_))) {
+        return (StatusCode::CONFLICT, "A fixture with this name already exists").into_response();
+    }
+    db_err(e)
+}
+
+async fn project_template(
+    state: &AppState,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<admin::TemplateRow, Response> {
+    admin::get_template(&state.db, id)
+        .await
+        .map_err(db_err)?
+        .filter(|t| t.project_id == project_id)
+        .ok_or_else(|| not_found("Template not found"))
+}
+
+async fn project_event(
+    state: &AppState,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<admin::EventRow, Response> {
+    admin::get_event(&state.db, id)
+        .await
+        .map_err(db_err)?
+        .filter(|e| e.project_id == project_id)
+        .ok_or_else(|| not_found("Event not found"))
+}
+
+fn fixtures_response(rows: Vec<fixture::FixtureRow>) -> Response {
+    Json(
+        rows.into_iter()
+            .map(FixtureResponse::from)
+            .collect::<Vec<_>>(),
+    )
+    .into_response()
+}
+
+async fn created_fixture(state: &AppState, project_id: Uuid, id: Uuid) -> ApiResult {
+    let row = fixture::get_fixture(&state.db, project_id, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Fixture not found"))?;
+    Ok((StatusCode::CREATED, Json(FixtureResponse::from(row))).into_response())
+}
+
+async fn list_template_fixtures(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    project_template(&state, auth.project_id, id).await?;
+    let rows = fixture::list_by_template(&state.db, id)
+        .await
+        .map_err(db_err)?;
+    Ok(fixtures_response(rows))
+}
+
+async fn create_template_fixture(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(template_id): Path<Uuid>,
+    Json(body): Json<FixtureRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    validate_fixture(&body).map_err(IntoResponse::into_response)?;
+    project_template(&state, auth.project_id, template_id).await?;
+    let id = Uuid::now_v7();
+    fixture::insert_fixture(
+        &state.db,
+        id,
+        auth.project_id,
+        Some(template_id),
+        None,
+        &body.name,
+        &body.data,
+    )
+    .await
+    .map_err(fixture_db_err)?;
+    created_fixture(&state, auth.project_id, id).await
+}
+
+async fn list_event_fixtures(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    project_event(&state, auth.project_id, id).await?;
+    let rows = fixture::list_by_event(&state.db, id)
+        .await
+        .map_err(db_err)?;
+    Ok(fixtures_response(rows))
+}
+
+async fn create_event_fixture(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(event_id): Path<Uuid>,
+    Json(body): Json<FixtureRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    validate_fixture(&body).map_err(IntoResponse::into_response)?;
+    project_event(&state, auth.project_id, event_id).await?;
+    let id = Uuid::now_v7();
+    fixture::insert_fixture(
+        &state.db,
+        id,
+        auth.project_id,
+        None,
+        Some(event_id),
+        &body.name,
+        &body.data,
+    )
+    .await
+    .map_err(fixture_db_err)?;
+    created_fixture(&state, auth.project_id, id).await
+}
+
+async fn update_fixture(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(body): Json<FixtureRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    validate_fixture(&body).map_err(IntoResponse::into_response)?;
+    let updated = fixture::update_fixture(&state.db, auth.project_id, id, &body.name, &body.data)
+        .await
+        .map_err(fixture_db_err)?;
+    if !updated {
+        return Err(not_found("Fixture not found"));
+    }
+    let row = fixture::get_fixture(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Fixture not found"))?;
+    Ok(Json(FixtureResponse::from(row)).into_response())
+}
+
+async fn delete_fixture(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    if !fixture::delete_fixture(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?
+    {
+        return Err(not_found("Fixture not found"));
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Render every fixture that applies to a template in every locale the
+/// version has content for. Dates render in UTC so outputs are stable.
+async fn render_fixtures(
+    state: &AppState,
+    project_id: Uuid,
+    template_id: Uuid,
+    version: Option<i32>,
+) -> Result<FixtureRenderResponse, Response> {
+    project_template(state, project_id, template_id).await?;
+    let version = select_version(state, template_id, version).await?;
+    let locales: Vec<String> = template_version::get_contents(&state.db, version.id)
+        .await
+        .map_err(db_err)?
+        .into_keys()
+        .collect();
+    let fixtures = fixture::list_for_template(&state.db, template_id)
+        .await
+        .map_err(db_err)?;
+    let context = PreviewContext::load(state, project_id).await?;
+
+    let mut results = Vec::new();
+    for locale in &locales {
+        let template = notifico_db::repo::template::resolve_template_version(
+            &state.db,
+            template_id,
+            version.version,
+            &context.locales.chain(locale),
+        )
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Template version not found"))?;
+        for f in &fixtures {
+            let outcome = context.render(&template, locale, "UTC", &f.data);
+            results.push(FixtureRender {
+                fixture_id: f.id,
+                fixture: f.name.clone(),
+                locale: locale.clone(),
+                rendered: outcome.as_ref().ok().cloned().map(Value::Object),
+                error: outcome.err(),
+            });
+        }
+    }
+    results.sort_by(|a, b| (&a.fixture, &a.locale).cmp(&(&b.fixture, &b.locale)));
+    Ok(FixtureRenderResponse {
+        version: version.version,
+        results,
+    })
+}
+
+async fn render_template_fixtures(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(q): Query<ContentQuery>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let response = render_fixtures(&state, auth.project_id, id, q.version).await?;
+    Ok(Json(response).into_response())
+}
+
+async fn list_template_snapshots(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    project_template(&state, auth.project_id, id).await?;
+    let snapshots = fixture::list_snapshots(&state.db, id)
+        .await
+        .map_err(db_err)?;
+    Ok(Json(
+        snapshots
+            .into_iter()
+            .map(|((fixture_id, locale), rendered)| SnapshotResponse {
+                fixture_id,
+                locale,
+                rendered,
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_response())
 }
 
-#[derive(Serialize)]
-struct PreviewResponse {
-    rendered: Value,
+/// Accept the current renders as the template's snapshots, replacing the
+/// previous ones. Refused while any fixture fails to render.
+async fn record_template_snapshots(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(q): Query<ContentQuery>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let response = render_fixtures(&state, auth.project_id, id, q.version).await?;
+    if response.results.iter().any(|r| r.error.is_some()) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(response)).into_response());
+    }
+    let snapshots = response
+        .results
+        .iter()
+        .filter_map(|r| Some(((r.fixture_id, r.locale.clone()), r.rendered.clone()?)))
+        .collect();
+    fixture::replace_snapshots(&state.db, id, &snapshots)
+        .await
+        .map_err(db_err)?;
+    Ok(Json(response).into_response())
 }
 
-async fn preview_template(
+/// Render all fixtures and compare against the recorded snapshots. Responds
+/// 422 when any render changed, failed, or went missing; new fixtures or
+/// locales without a snapshot are reported but do not fail the test.
+async fn test_template(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
-    Path(template_id): Path<Uuid>,
-    Json(req): Json<PreviewRequest>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<ContentQuery>,
 ) -> ApiResult {
     require_admin(&auth)?;
+    let rendered = render_fixtures(&state, auth.project_id, id, q.version).await?;
+    let mut snapshots = fixture::list_snapshots(&state.db, id)
+        .await
+        .map_err(db_err)?;
 
-    let locale = req.locale.as_deref().unwrap_or("en");
-    let default_locale = &state.config.project.default_locale;
-
-    let template = notifico_db::repo::template::resolve_template(
-        &state.db,
-        template_id,
-        locale,
-        default_locale,
+    let mut results = Vec::new();
+    for r in rendered.results {
+        let snapshot = snapshots.remove(&(r.fixture_id, r.locale.clone()));
+        let (status, fields) = match (&r.rendered, &snapshot) {
+            (None, _) => ("error", vec![]),
+            (Some(_), None) => ("new", vec![]),
+            (Some(new), Some(old)) if new == old => ("unchanged", vec![]),
+            (Some(new), Some(old)) => (
+                "changed",
+                diff_fields(
+                    old,
+                    new,
+                    (
+                        &format!("snapshot/{}/{}", r.fixture, r.locale),
+                        &format!("v{}/{}/{}", rendered.version, r.fixture, r.locale),
+                    ),
+                ),
+            ),
+        };
+        results.push(SnapshotCheck {
+            fixture_id: r.fixture_id,
+            fixture: r.fixture,
+            locale: r.locale,
+            status,
+            error: r.error,
+            fields,
+        });
+    }
+    // Snapshots left over belong to fixture/locale pairs that no longer render
+    if !snapshots.is_empty() {
+        let names: std::collections::HashMap<Uuid, String> =
+            fixture::list_for_template(&state.db, id)
+                .await
+                .map_err(db_err)?
+                .into_iter()
+                .map(|f| (f.id, f.name))
+                .collect();
+        for ((fixture_id, locale), _) in snapshots {
+            results.push(SnapshotCheck {
+                fixture_id,
+                fixture: names.get(&fixture_id).cloned().unwrap_or_default(),
+                locale,
+                status: "removed",
+                error: None,
+                fields: vec![],
+            });
+        }
+    }
+
+    let passed = results
+        .iter()
+        .all(|r| matches!(r.status, "unchanged" | "new"));
+    let status = if passed {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+    Ok((
+        status,
+        Json(TemplateTestResponse {
+            passed,
+            version: rendered.version,
+            results,
+        }),
     )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
-    .ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            format!("Template not found: {template_id} locale {locale}"),
-        )
-            .into_response()
-    })?;
-
-    let rendered = notifico_template::render_body(&template.body, &req.data)
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Render error: {e}")).into_response())?;
-
-    Ok(Json(PreviewResponse {
-        rendered: Value::Object(rendered),
-    })
-    .into_response())
+        .into_response())
 }
 
 // --- Event Stats ---
@@ -1253,3 +2877,483 @@ async fn delete_middleware(
     middleware::delete(&state.db, id).await.map_err(db_err)?;
     Ok(StatusCode::NO_CONTENT.into_response())
 }
+
+// ── Template variants ───────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct VariantsResponse {
+    rule_id: Uuid,
+    experiment: Option<String>,
+    variants: Vec<VariantResponse>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VariantResponse {
+    name: String,
+    template_id: Uuid,
+    weight: i32,
+}
+
+#[derive(Deserialize)]
+struct SetVariantsRequest {
+    /// Seeds the assignment; defaults to the rule id. Changing it reshuffles
+    /// recipients between variants.
+    experiment: Option<String>,
+    variants: Vec<VariantResponse>,
+}
+
+#[derive(Serialize)]
+struct VariantStatsResponse {
+    rule_id: Uuid,
+    experiment: Option<String>,
+    variants: Vec<VariantStats>,
+}
+
+#[derive(Serialize)]
+struct VariantStats {
+    name: String,
+    sent: i64,
+    delivered: i64,
+    opened: i64,
+    clicked: i64,
+    /// `delivered / sent`; open and click rates are relative to `delivered`.
+    delivery_rate: f64,
+    open_rate: f64,
+    click_rate: f64,
+}
+
+fn rate(count: i64, total: i64) -> f64 {
+    if total == 0 { 0.0 } else { count as f64 / total as f64 }
+}
+
+async fn load_rule(state: &AppState, rule_id: Uuid) -> Result<admin::RuleRow, Response> {
+    admin::get_rule(&state.db, rule_id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Rule not found"))
+}
+
+async fn variants_response(state: &AppState, rule: admin::RuleRow) -> ApiResult {
+    let variants = variant::list_by_rule(&state.db, rule.id)
+        .await
+        .map_err(db_err)?;
+    Ok(Json(VariantsResponse {
+        rule_id: rule.id,
+        experiment: rule.experiment,
+        variants: variants
+            .into_iter()
+            .map(|v| VariantResponse {
+                name: v.name,
+                template_id: v.template_id,
+                weight: v.weight,
+            })
+            .collect(),
+    })
+    .into_response())
+}
+
+async fn list_variants(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(rule_id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let rule = load_rule(&state, rule_id).await?;
+    variants_response(&state, rule).await
+}
+
+/// Replace the rule's variants. An empty list ends the experiment and the
+/// rule goes back to its own template.
+async fn set_variants(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(rule_id): Path<Uuid>,
+    Json(body): Json<SetVariantsRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    load_rule(&state, rule_id).await?;
+    let invalid = |message: String| (StatusCode::UNPROCESSABLE_ENTITY, message).into_response();
+
+    let mut names = std::collections::HashSet::new();
+    for v in &body.variants {
+        if v.name.trim().is_empty() {
+            return Err(invalid("Variant names must not be empty".into()));
+        }
+        if !names.insert(v.name.as_str()) {
+            return Err(invalid(format!("Duplicate variant '{}'", v.name)));
+        }
+        if v.weight < 0 {
+            return Err(invalid(format!("Variant '{}' has a negative weight", v.name)));
+        }
+        let template = admin::get_template(&state.db, v.template_id)
+            .await
+            .map_err(db_err)?;
+        if template.is_none_or(|t| t.project_id != auth.project_id) {
+            return Err(invalid(format!(
+                "Variant '{}' references an unknown template",
+                v.name
+            )));
+        }
+    }
+    if !body.variants.is_empty() && body.variants.iter().all(|v| v.weight == 0) {
+        return Err(invalid("At least one variant needs a positive weight".into()));
+    }
+    let experiment = body.experiment.unwrap_or_else(|| rule_id.to_string());
+    if experiment.trim().is_empty() {
+        return Err(invalid("Experiment id must not be empty".into()));
+    }
+
+    let variants: Vec<_> = body
+        .variants
+        .iter()
+        .map(|v| (v.name.as_str(), v.template_id, v.weight))
+        .collect();
+    variant::replace(&state.db, rule_id, Some(&experiment), &variants)
+        .await
+        .map_err(db_err)?;
+    let rule = load_rule(&state, rule_id).await?;
+    variants_response(&state, rule).await
+}
+
+/// Per-variant outcomes of the rule's current experiment, from the delivery
+/// log and open/click tracking events.
+async fn variant_stats(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(rule_id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let rule = load_rule(&state, rule_id).await?;
+    let variants = variant::list_by_rule(&state.db, rule_id)
+        .await
+        .map_err(db_err)?;
+    let counts = match &rule.experiment {
+        Some(experiment) => delivery_log::variant_stats(&state.db, auth.project_id, experiment)
+            .await
+            .map_err(db_err)?,
+        None => vec![],
+    };
+
+    // Current variants first, so ones without deliveries yet show up with
+    // zeros, then any removed variants that still have logged deliveries.
+    let mut names: Vec<String> = variants.into_iter().map(|v| v.name).collect();
+    for row in &counts {
+        if !names.contains(&row.variant) {
+            names.push(row.variant.clone());
+        }
+    }
+    let stats = names
+        .into_iter()
+        .map(|name| {
+            let row = counts.iter().find(|r| r.variant == name);
+            let [sent, delivered, opened, clicked] =
+                row.map_or([0; 4], |r| [r.sent, r.delivered, r.opened, r.clicked]);
+            VariantStats {
+                name,
+                sent,
+                delivered,
+                opened,
+                clicked,
+                delivery_rate: rate(delivered, sent),
+                open_rate: rate(opened, delivered),
+                click_rate: rate(clicked, delivered),
+            }
+        })
+        .collect();
+
+    Ok(Json(VariantStatsResponse {
+        rule_id,
+        experiment: rule.experiment,
+        variants: stats,
+    })
+    .into_response())
+}
+
+// ── Directory sync ──────────────────────────────────────────────────
+
+/// The project as files keyed by relative path; see [`crate::sync`].
+#[derive(Serialize)]
+struct ProjectFilesResponse {
+    files: std::collections::BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct SyncRequest {
+    files: std::collections::BTreeMap<String, String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct SyncResponse {
+    changes: Vec<sync::Change>,
+    dry_run: bool,
+}
+
+async fn export_project_files(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let bundle = sync::export(&state.db, auth.project_id)
+        .await
+        .map_err(db_err)?;
+    let files = bundle
+        .to_files()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e).into_response())?;
+    Ok(Json(ProjectFilesResponse { files }).into_response())
+}
+
+async fn sync_project_files(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(body): Json<SyncRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let bundle = sync::Bundle::from_files(&body.files)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+    let changes = sync::sync(
+        &state.db,
+        &state.registry,
+        auth.project_id,
+        &bundle,
+        body.dry_run,
+    )
+    .await
+    .map_err(|e| match e {
+        sync::SyncError::Invalid(message) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
+        }
+        sync::SyncError::Db(e) => db_err(e),
+    })?;
+    Ok(Json(SyncResponse {
+        changes,
+        dry_run: body.dry_run,
+    })
+    .into_response())
+}
+
+// ── Webhooks ────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct WebhookResponse {
+    id: Uuid,
+    url: String,
+    event_types: Vec<String>,
+    enabled: bool,
+    created_at: String,
+}
+
+impl From<webhook::WebhookSubscriptionRow> for WebhookResponse {
+    fn from(w: webhook::WebhookSubscriptionRow) -> Self {
+        Self {
+            id: w.id,
+            url: w.url,
+            event_types: w.event_types,
+            enabled: w.enabled,
+            created_at: w.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookCreatedResponse {
+    id: Uuid,
+    url: String,
+    event_types: Vec<String>,
+    secret: String,
+}
+
+#[derive(Deserialize)]
+struct CreateWebhookRequest {
+    url: String,
+    /// Event types to receive; empty means all.
+    #[serde(default)]
+    event_types: Vec<String>,
+    /// Signing secret; generated if omitted.
+    #[serde(default)]
+    secret: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateWebhookRequest {
+    url: String,
+    #[serde(default)]
+    event_types: Vec<String>,
+    enabled: bool,
+}
+
+#[derive(Serialize)]
+struct WebhookDeliveryResponse {
+    id: Uuid,
+    event_type: String,
+    payload: Value,
+    status: String,
+    attempt: i32,
+    response_status: Option<i32>,
+    error_message: Option<String>,
+    created_at: String,
+    delivered_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WebhookDeliveryQuery {
+    #[serde(default = "default_limit")]
+    limit: u64,
+    #[serde(default)]
+    offset: u64,
+}
+
+fn validate_webhook(url: &str, event_types: &[String]) -> Result<(), String> {
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err("Webhook URL must be http(s)".into());
+    }
+    if let Some(unknown) = event_types
+        .iter()
+        .find(|t| !crate::webhooks::EVENT_TYPES.contains(&t.as_str()))
+    {
+        return Err(format!(
+            "Unknown event type '{unknown}', expected one of: {}",
+            crate::webhooks::EVENT_TYPES.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let rows = webhook::list_subscriptions(&state.db, auth.project_id)
+        .await
+        .map_err(db_err)?;
+    Ok(Json(rows.into_iter().map(WebhookResponse::from).collect::<Vec<_>>()).into_response())
+}
+
+async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(body): Json<CreateWebhookRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    validate_webhook(&body.url, &body.event_types)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+    let key = state.encryption_key.as_ref().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Encryption key not configured. Set NOTIFICO_AUTH_ENCRYPTION_KEY.".to_string(),
+        )
+            .into_response()
+    })?;
+    let id = Uuid::now_v7();
+    let secret = body
+        .secret
+        .unwrap_or_else(|| format!("whsec_{}", hex::encode(rand::random::<[u8; 24]>())));
+    webhook::insert_subscription(
+        &state.db,
+        id,
+        auth.project_id,
+        &body.url,
+        &body.event_types,
+        &secret,
+        key,
+    )
+    .await
+    .map_err(db_err)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(WebhookCreatedResponse {
+            id,
+            url: body.url,
+            event_types: body.event_types,
+            secret,
+        }),
+    )
+        .into_response())
+}
+
+async fn get_webhook(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let row = webhook::get_subscription(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Webhook not found"))?;
+    Ok(Json(WebhookResponse::from(row)).into_response())
+}
+
+async fn update_webhook(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateWebhookRequest>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    validate_webhook(&body.url, &body.event_types)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+    let updated = webhook::update_subscription(
+        &state.db,
+        auth.project_id,
+        id,
+        &body.url,
+        &body.event_types,
+        body.enabled,
+    )
+    .await
+    .map_err(db_err)?;
+    if !updated {
+        return Err(not_found("Webhook not found"));
+    }
+    Ok(Json(serde_json::json!({"id": id, "updated": true})).into_response())
+}
+
+async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    let deleted = webhook::delete_subscription(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?;
+    if !deleted {
+        return Err(not_found("Webhook not found"));
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn list_webhook_deliveries(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(q): Query<WebhookDeliveryQuery>,
+) -> ApiResult {
+    require_admin(&auth)?;
+    webhook::get_subscription(&state.db, auth.project_id, id)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| not_found("Webhook not found"))?;
+    let rows = webhook::list_deliveries(&state.db, id, q.limit.min(200), q.offset)
+        .await
+        .map_err(db_err)?;
+    Ok(Json(
+        rows.into_iter()
+            .map(|d| WebhookDeliveryResponse {
+                id: d.id,
+                event_type: d.event_type,
+                payload: d.payload,
+                status: d.status,
+                attempt: d.attempt,
+                response_status: d.response_status,
+                error_message: d.error_message,
+                created_at: d.created_at,
+                delivered_at: d.delivered_at,
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_response())
+}
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use base64::engine::{Engine, general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use notifico_core::event::EventAttachment;
+use notifico_core::storage::Storage;
+use notifico_core::transport::{Attachment, AttachmentDisposition};
+use notifico_db::repo;
+use notifico_queue::AttachmentRef;
+use sea_orm::DatabaseConnection;
+
+use crate::AppState;
+use crate::auth::AuthContext;
+
+/// Upper bound for a single uploaded attachment.
+pub const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
+/// Body limit for ingest requests: room for an attachment at the upload
+/// limit sent inline as base64, plus the rest of the event.
+pub const MAX_INGEST_BODY_BYTES: usize = MAX_ATTACHMENT_BYTES / 3 * 4 + 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct UploadParams {
+    filename: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttachmentResponse {
+    pub id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub size: usize,
+}
+
+fn storage_key(project_id: Uuid, id: Uuid) -> String {
+    format!("attachments/{project_id}/{id}")
+}
+
+fn guess_content_type(filename: &str) -> String {
+    mime_guess::from_path(filename)
+        .first_or_octet_stream()
+        .essence_str()
+        .to_string()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/attachments",
+    tag = "events",
+    params(("filename" = String, Query, description = "File name shown to recipients")),
+    request_body(content = Vec<u8>, description = "Raw file content; Content-Type is stored as the MIME type"),
+    responses(
+        (status = 201, description = "Attachment stored", body = AttachmentResponse),
+        (status = 400, description = "Empty body or missing filename"),
+        (status = 401, description = "Unauthorized"),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn handle_upload(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Query(params): Query<UploadParams>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    auth.require_scope("ingest")
+        .map_err(|e| (StatusCode::FORBIDDEN, format!("{e:?}")))?;
+
+    if params.filename.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "filename is required".into()));
+    }
+    if body.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Attachment body is empty".into()));
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|ct| *ct != "application/octet-stream")
+        .map(str::to_string)
+        .unwrap_or_else(|| guess_content_type(&params.filename));
+
+    let id = store_attachment(
+        &state,
+        auth.project_id,
+        &params.filename,
+        &content_type,
+        &body,
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(AttachmentResponse {
+            id,
+            filename: params.filename,
+            content_type,
+            size: body.len(),
+        }),
+    ))
+}
+
+/// Write attachment content to storage and record its metadata.
+async fn store_attachment(
+    state: &AppState,
+    project_id: Uuid,
+    filename: &str,
+    content_type: &str,
+    data: &[u8],
+) -> Result<Uuid, String> {
+    let id = Uuid::now_v7();
+    let key = storage_key(project_id, id);
+
+    state
+        .storage
+        .put(&key, data)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    repo::attachment::insert_attachment(
+        &state.db,
+        id,
+        project_id,
+        filename,
+        content_type,
+        data.len() as i64,
+        &key,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Turn the attachments of an ingest event into references for the
+/// delivery tasks, storing inline (base64) content on the way.
+pub(crate) async fn resolve_event_attachments(
+    state: &AppState,
+    project_id: Uuid,
+    attachments: &[EventAttachment],
+) -> Result<Vec<AttachmentRef>, (StatusCode, String)> {
+    let mut refs = Vec::with_capacity(attachments.len());
+
+    for (i, attachment) in attachments.iter().enumerate() {
+        let reference = match (&attachment.id, &attachment.content) {
+            (Some(id), None) => {
+                let row = repo::attachment::get_attachment(&state.db, project_id, *id)
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                    .ok_or_else(|| {
+                        (
+                            StatusCode::BAD_REQUEST,
+                            format!("attachments[{i}]: attachment {id} not found"),
+                        )
+                    })?;
+                AttachmentRef {
+                    id: row.id,
+                    filename: attachment.filename.clone().unwrap_or(row.filename),
+                    content_type: attachment.content_type.clone().unwrap_or(row.content_type),
+                    content_id: attachment.content_id.clone(),
+                }
+            }
+            (None, Some(content)) => {
+                let filename = attachment.filename.clone().ok_or_else(|| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("attachments[{i}]: filename is required for inline content"),
+                    )
+                })?;
+                let data = STANDARD.decode(content).map_err(|e| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("attachments[{i}]: invalid base64 content: {e}"),
+                    )
+                })?;
+                if data.len() > MAX_ATTACHMENT_BYTES {
+                    return Err((
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        format!(
+                            "attachments[{i}]: content exceeds {} MB",
+                            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+                        ),
+                    ));
+                }
+                let content_type = attachment
+                    .content_type
+                    .clone()
+                    .unwrap_or_else(|| guess_content_type(&filename));
+                let id = store_attachment(state, project_id, &filename, &content_type, &data)
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+                AttachmentRef {
+                    id,
+                    filename,
+                    content_type,
+                    content_id: attachment.content_id.clone(),
+                }
+            }
+            _ => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("attachments[{i}]: exactly one of 'id' or 'content' is required"),
+                ));
+            }
+        };
+        refs.push(reference);
+    }
+
+    Ok(refs)
+}
+
+/// Load the content of a task's attachments from storage.
+pub(crate) async fn load_attachments(
+    db: &DatabaseConnection,
+    storage: &dyn Storage,
+    project_id: Uuid,
+    refs: &[AttachmentRef],
+) -> Result<Vec<Attachment>, String> {
+    let mut attachments = Vec::with_capacity(refs.len());
+
+    for reference in refs {
+        let row = repo::attachment::get_attachment(db, project_id, reference.id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Attachment {} not found", reference.id))?;
+        let data = storage
+            .get(&row.storage_key)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        attachments.push(Attachment {
+            filename: reference.filename.clone(),
+            content_type: reference.content_type.clone(),
+            data,
+            disposition: if reference.content_id.is_some() {
+                AttachmentDisposition::Inline
+            } else {
+                AttachmentDisposition::Attachment
+            },
+            content_id: reference.content_id.clone(),
+        });
+    }
+
+    Ok(attachments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_type_guessed_from_filename() {
+        assert_eq!(guess_content_type("invoice.pdf"), "application/pdf");
+        assert_eq!(guess_content_type("logo.png"), "image/png");
+        assert_eq!(guess_content_type("blob"), "application/octet-stream");
+    }
+
+    #[test]
+    fn storage_keys_are_scoped_by_project() {
+        let project_id = Uuid::now_v7();
+        let id = Uuid::now_v7();
+        assert_eq!(
+            storage_key(project_id, id),
+            format!("attachments/{project_id}/{id}")
+        );
+    }
+}
@@ -10,6 +10,7 @@ use notifico_db::repo;
 
 use crate::AppState;
 use crate::auth::AuthContext;
+use crate::ingest::{IngestError, pick_variant, validate_event_data};
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct BroadcastRequest {
@@ -41,6 +42,7 @@ pub struct BroadcastResponse {
         (status = 200, description = "Broadcast enqueued", body = BroadcastResponse),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Event not found"),
+        (status = 422, description = "Event data does not match the event's data schema"),
         (status = 429, description = "Rate limited"),
     ),
     security(("bearer" = []))
@@ -49,7 +51,7 @@ pub async fn handle_broadcast(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
     Json(req): Json<BroadcastRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, IngestError> {
     auth.require_scope("ingest")
         .map_err(|e| (StatusCode::FORBIDDEN, format!("{e:?}")))?;
 
@@ -57,11 +59,11 @@ pub async fn handle_broadcast(
         return Err((
             StatusCode::TOO_MANY_REQUESTS,
             format!("Rate limit exceeded. Retry after {retry_after}s"),
-        ));
+        )
+            .into());
     }
 
     let project_id = auth.project_id;
-    let default_locale = &state.config.project.default_locale;
     let broadcast_id = Uuid::now_v7();
 
     // Resolve event
@@ -70,6 +72,8 @@ pub async fn handle_broadcast(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Event not found: {}", req.event)))?;
 
+    validate_event_data(&state, &event_row, &req.data)?;
+
     // Get pipeline rules
     let rules = repo::template::get_pipeline_rules(&state.db, event_row.id)
         .await
@@ -84,6 +88,25 @@ pub async fn handle_broadcast(
         }));
     }
 
+    let partials = crate::ingest::load_partials(&state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let stylesheets = crate::ingest::load_stylesheets(&state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let locales = crate::ingest::load_project_locales(&state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let translations = crate::ingest::load_translations(&state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let render_options = crate::ingest::load_render_options(&state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let variants = crate::ingest::load_rule_variants(&state, &rules)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
     // Resolve recipients
     let all_recipients = repo::admin::list_recipients(&state.db, project_id)
         .await
@@ -105,7 +128,7 @@ pub async fn handle_broadcast(
     for recipient in &recipients {
         let recipient_id = recipient.id;
         let recipient_locale = if recipient.locale.is_empty() {
-            default_locale.as_str()
+            locales.default_locale.as_str()
         } else {
             &recipient.locale
         };
@@ -147,12 +170,12 @@ pub async fn handle_broadcast(
                 }
             }
 
-            // Resolve template
+            // Resolve template, or the recipient's variant of it
+            let variant = pick_variant(rule, &variants, recipient_id);
             let template = match repo::template::resolve_template(
                 &state.db,
-                rule.template_id,
-                recipient_locale,
-                default_locale,
+                variant.map_or(rule.template_id, |v| v.template_id),
+                &locales.chain(recipient_locale),
             )
             .await
             {
@@ -176,10 +199,23 @@ pub async fn handle_broadcast(
                 event_name: req.event.clone(),
                 recipient_id,
                 recipient_locale: recipient_locale.to_string(),
+                recipient_timezone: recipient.timezone.clone(),
                 channel: rule.channel.clone(),
                 contact_value,
                 template_body: template.body,
                 context_data: req.data.clone(),
+                partials: partials.clone(),
+                stylesheets: stylesheets.clone(),
+                translations: translations
+                    .clone()
+                    .with_fallback(locales.chain(recipient_locale).locales),
+                render_options,
+                template_cache: state.template_cache.clone(),
+                template_key: Some(notifico_template::TemplateKey::new(
+                    template.version_id.to_string(),
+                    template.locale.clone(),
+                )),
+                content_format: template.format.parse().unwrap_or_default(),
                 idempotency_key: None,
                 max_attempts: 5,
             };
@@ -224,21 +260,24 @@ pub async fn handle_broadcast(
                         }
                     }
 
-                    if let Err(e) = repo::queue::enqueue(
-                        &state.db,
-                        output.id,
-                        output.project_id,
-                        &output.event_name,
-                        output.recipient_id,
-                        &output.channel,
-                        &output.contact_value,
-                        &output.rendered_body,
-                        output.idempotency_key.as_deref(),
-                        output.max_attempts as i32,
-                        Some(rule_id),
-                    )
-                    .await
-                    {
+                    let task = repo::queue::NewTask {
+                        id: output.id,
+                        project_id: output.project_id,
+                        event_name: &output.event_name,
+                        recipient_id: output.recipient_id,
+                        channel: &output.channel,
+                        contact_value: &output.contact_value,
+                        rendered_body: &output.rendered_body,
+                        idempotency_key: output.idempotency_key.as_deref(),
+                        max_attempts: output.max_attempts as i32,
+                        rule_id: Some(rule_id),
+                        attachments: &serde_json::json!([]),
+                        template_version: Some(template.version),
+                        experiment: variant.and(rule.experiment.as_deref()),
+                        variant: variant.map(|v| v.name.as_str()),
+                        credential: rule.credential.as_deref(),
+                    };
+                    if let Err(e) = repo::queue::enqueue(&state.db, &task).await {
                         errors.push(format!("Enqueue error: {}", e));
                         continue;
                     }
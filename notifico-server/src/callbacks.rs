@@ -0,0 +1,470 @@
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, OriginalUri, Path, Request, State},
+    http::{HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use base64::engine::{Engine, general_purpose::STANDARD};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha1::Sha1;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use notifico_db::repo::credential::{self, CredentialRow};
+use notifico_db::repo::delivery_log;
+
+use crate::AppState;
+use crate::webhooks;
+
+type ApiResult = Result<Response, Response>;
+
+/// Inbound provider status callbacks (delivery receipts and bounces).
+///
+/// Each callback is correlated with a delivery log via the
+/// `provider_message_id` returned by the transport at send time.
+///
+/// Callbacks don't carry an API key. The URL names the credential the
+/// message was sent with, and the request must be signed with that
+/// credential's secret; the credential also scopes the lookup to its project
+/// and channel.
+pub fn callbacks_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/twilio/{credential_id}", post(twilio_status))
+        .route("/dsn/{credential_id}", post(dsn_status))
+}
+
+fn db_err(e: sea_orm::DbErr) -> Response {
+    tracing::error!(error = %e, "Database error");
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+}
+
+fn bad_request(msg: &str) -> Response {
+    (StatusCode::BAD_REQUEST, msg.to_string()).into_response()
+}
+
+fn unauthorized(msg: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, msg.to_string()).into_response()
+}
+
+/// A callback body whose signature was verified against a stored credential.
+struct Verified<T> {
+    credential: CredentialRow,
+    body: T,
+}
+
+/// Provider-specific request signature scheme.
+trait CallbackSignature {
+    /// Channel the credential must belong to.
+    const CHANNEL: &'static str;
+    /// Whether the signature covers the request URL, which then has to be
+    /// rebuilt from `server.public_url`.
+    const SIGNS_URL: bool;
+
+    /// Check the request signature with the credential's secret. The error
+    /// is the reason reported with the 401 response.
+    fn verify(
+        credential: &CredentialRow,
+        url: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<(), &'static str>;
+
+    /// Decode the verified body.
+    fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, String>;
+}
+
+/// Load the credential named in the URL, verify the signature with `S`, then
+/// decode the body.
+async fn verify_callback<S: CallbackSignature, T: DeserializeOwned>(
+    req: Request,
+    state: &Arc<AppState>,
+) -> Result<Verified<T>, Response> {
+    let (mut parts, body) = req.into_parts();
+    let Path(credential_id) = Path::<Uuid>::from_request_parts(&mut parts, state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    let OriginalUri(uri) = OriginalUri::from_request_parts(&mut parts, state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    let headers = parts.headers.clone();
+    let body = Bytes::from_request(Request::from_parts(parts, body), state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let key = state.encryption_key.as_ref().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Encryption key not configured. Set NOTIFICO_AUTH_ENCRYPTION_KEY.",
+        )
+            .into_response()
+    })?;
+    let credential = credential::find_credential_by_id(&state.db, credential_id, key)
+        .await
+        .map_err(db_err)?
+        .filter(|c| c.channel == S::CHANNEL)
+        .ok_or_else(|| unauthorized("Unknown credential"))?;
+
+    let url = match state.config.server.public_url.as_deref() {
+        Some(base) => public_url(base, &uri),
+        None if S::SIGNS_URL => {
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Public URL not configured. Set server.public_url.",
+            )
+                .into_response());
+        }
+        None => String::new(),
+    };
+    S::verify(&credential, &url, &headers, &body).map_err(unauthorized)?;
+
+    Ok(Verified {
+        credential,
+        body: S::parse(&body).map_err(|e| bad_request(&e))?,
+    })
+}
+
+/// Reconstruct the URL the provider called, which its signature covers.
+///
+/// The host comes from the configured public URL, never from request
+/// headers, which the caller controls.
+fn public_url(base: &str, uri: &Uri) -> String {
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    format!("{}{path}", base.trim_end_matches('/'))
+}
+
+/// Apply a provider status to the matching delivery log.
+async fn apply_status(
+    state: &AppState,
+    credential: &CredentialRow,
+    provider_message_id: &str,
+    status: &str,
+    error_message: Option<&str>,
+) -> Result<bool, Response> {
+    let updated = delivery_log::update_status_by_provider_message_id(
+        &state.db,
+        credential.project_id,
+        &credential.channel,
+        provider_message_id,
+        status,
+        error_message,
+    )
+    .await
+    .map_err(db_err)?;
+
+    match updated {
+        Some(log) => {
+            webhooks::emit_delivery_log(&state.db, &log).await;
+            Ok(true)
+        }
+        None => {
+            tracing::debug!(
+                channel = %credential.channel,
+                provider_message_id = %provider_message_id,
+                "Status callback for unknown message"
+            );
+            Ok(false)
+        }
+    }
+}
+
+// ── Twilio ──────────────────────────────────────────────────────────
+
+/// Twilio status callback (form-encoded), see
+/// https://www.twilio.com/docs/messaging/guides/track-outbound-message-status
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TwilioStatusCallback {
+    message_sid: String,
+    message_status: String,
+    #[serde(default)]
+    error_code: Option<String>,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+/// Twilio request validation, see
+/// https://www.twilio.com/docs/usage/webhooks/webhooks-security
+///
+/// `X-Twilio-Signature` is base64(HMAC-SHA1(auth_token, url + sorted params)),
+/// where each POST parameter contributes its name followed by its value.
+struct Twilio;
+
+impl CallbackSignature for Twilio {
+    const CHANNEL: &'static str = "sms";
+    const SIGNS_URL: bool = true;
+
+    fn verify(
+        credential: &CredentialRow,
+        url: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<(), &'static str> {
+        let auth_token = credential
+            .data
+            .get("auth_token")
+            .and_then(|v| v.as_str())
+            .ok_or("Credential has no auth_token")?;
+        let signature = headers
+            .get("x-twilio-signature")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| STANDARD.decode(v).ok())
+            .ok_or("Missing or malformed X-Twilio-Signature")?;
+        let params: Vec<(String, String)> = Self::parse(body).map_err(|_| "Malformed form body")?;
+
+        let mac = twilio_signature_mac(auth_token, url, params);
+        mac.verify_slice(&signature)
+            .map_err(|_| "Invalid X-Twilio-Signature")
+    }
+
+    fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, String> {
+        serde_urlencoded::from_bytes(body).map_err(|e| e.to_string())
+    }
+}
+
+fn twilio_signature_mac(
+    auth_token: &str,
+    url: &str,
+    mut params: Vec<(String, String)>,
+) -> Hmac<Sha1> {
+    params.sort();
+    let mut mac = Hmac::<Sha1>::new_from_slice(auth_token.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(url.as_bytes());
+    for (name, value) in &params {
+        mac.update(name.as_bytes());
+        mac.update(value.as_bytes());
+    }
+    mac
+}
+
+/// Map a Twilio `MessageStatus` onto a delivery log status.
+///
+/// Intermediate states (queued, sending, sent, ...) return `None`.
+fn twilio_log_status(message_status: &str) -> Option<&'static str> {
+    match message_status {
+        "delivered" | "read" => Some("delivered"),
+        "undelivered" => Some("undelivered"),
+        "failed" => Some("failed"),
+        _ => None,
+    }
+}
+
+async fn twilio_status(State(state): State<Arc<AppState>>, req: Request) -> ApiResult {
+    let Verified { credential, body } =
+        verify_callback::<Twilio, TwilioStatusCallback>(req, &state).await?;
+
+    let Some(status) = twilio_log_status(&body.message_status) else {
+        return Ok(Json(serde_json::json!({"updated": false})).into_response());
+    };
+
+    let error = match (&body.error_code, &body.error_message) {
+        (Some(code), Some(msg)) => Some(format!("{code}: {msg}")),
+        (Some(code), None) => Some(format!("Twilio error {code}")),
+        (None, Some(msg)) => Some(msg.clone()),
+        (None, None) => None,
+    };
+
+    let updated = apply_status(
+        &state,
+        &credential,
+        &body.message_sid,
+        status,
+        error.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({"updated": updated})).into_response())
+}
+
+// ── Generic DSN / bounce webhook ────────────────────────────────────
+
+/// DSN callback signing: `X-Notifico-Signature: sha256=<hex>` over the raw
+/// body, keyed with the email credential's `callback_secret`. This is the
+/// same scheme the webhook transport signs with.
+struct Dsn;
+
+impl CallbackSignature for Dsn {
+    const CHANNEL: &'static str = "email";
+    const SIGNS_URL: bool = false;
+
+    fn verify(
+        credential: &CredentialRow,
+        _url: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<(), &'static str> {
+        let secret = credential
+            .data
+            .get("callback_secret")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .ok_or("Credential has no callback_secret")?;
+        let signature = headers
+            .get("x-notifico-signature")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("sha256="))
+            .and_then(|v| hex::decode(v).ok())
+            .ok_or("Missing or malformed X-Notifico-Signature")?;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(body);
+        mac.verify_slice(&signature)
+            .map_err(|_| "Invalid X-Notifico-Signature")
+    }
+
+    fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, String> {
+        serde_json::from_slice(body).map_err(|e| e.to_string())
+    }
+}
+
+/// Generic delivery status notification, e.g. forwarded from an SMTP
+/// relay's DSN/bounce processing. Accepts a single object or an array.
+#[derive(Deserialize)]
+struct DsnNotification {
+    /// Provider message ID (for email: the Message-ID, without angle brackets)
+    provider_message_id: String,
+    /// delivered, bounced, undelivered, failed (or common aliases); transient
+    /// states such as delayed are accepted and ignored
+    status: String,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DsnPayload {
+    One(DsnNotification),
+    Many(Vec<DsnNotification>),
+}
+
+/// Map a DSN status (or RFC 3464 action) onto a final delivery log status.
+/// Transient states (`delayed`, `deferred`) and unknown ones yield `None`:
+/// the MTA is still trying, or there is nothing to record.
+fn dsn_log_status(status: &str) -> Option<&'static str> {
+    match status.to_ascii_lowercase().as_str() {
+        "delivered" | "delivery" | "relayed" | "expanded" => Some("delivered"),
+        "bounced" | "bounce" | "hard_bounce" | "soft_bounce" | "complaint" => Some("bounced"),
+        "undelivered" => Some("undelivered"),
+        "failed" => Some("failed"),
+        _ => None,
+    }
+}
+
+async fn dsn_status(State(state): State<Arc<AppState>>, req: Request) -> ApiResult {
+    let Verified { credential, body } = verify_callback::<Dsn, DsnPayload>(req, &state).await?;
+
+    let notifications = match body {
+        DsnPayload::One(n) => vec![n],
+        DsnPayload::Many(n) => n,
+    };
+
+    let (mut updated, mut skipped) = (0, 0);
+    for notification in &notifications {
+        let Some(status) = dsn_log_status(&notification.status) else {
+            tracing::debug!(status = %notification.status, "Skipping non-final DSN status");
+            skipped += 1;
+            continue;
+        };
+        let message_id = notification
+            .provider_message_id
+            .trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>');
+
+        if apply_status(
+            &state,
+            &credential,
+            message_id,
+            status,
+            notification.reason.as_deref(),
+        )
+        .await?
+        {
+            updated += 1;
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "received": notifications.len(),
+        "updated": updated,
+        "skipped": skipped,
+    }))
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn twilio_status_mapping() {
+        assert_eq!(twilio_log_status("delivered"), Some("delivered"));
+        assert_eq!(twilio_log_status("undelivered"), Some("undelivered"));
+        assert_eq!(twilio_log_status("failed"), Some("failed"));
+        assert_eq!(twilio_log_status("sent"), None);
+        assert_eq!(twilio_log_status("queued"), None);
+    }
+
+    #[test]
+    fn dsn_status_mapping() {
+        assert_eq!(dsn_log_status("Delivered"), Some("delivered"));
+        assert_eq!(dsn_log_status("hard_bounce"), Some("bounced"));
+        assert_eq!(dsn_log_status("undelivered"), Some("undelivered"));
+        assert_eq!(dsn_log_status("delayed"), None);
+        assert_eq!(dsn_log_status("Deferred"), None);
+        assert_eq!(dsn_log_status("failed"), Some("failed"));
+        assert_eq!(dsn_log_status("opened"), None);
+    }
+
+    #[test]
+    fn dsn_payload_single_or_batch() {
+        let one: DsnPayload =
+            serde_json::from_str(r#"{"provider_message_id":"a@b","status":"bounced"}"#).unwrap();
+        assert!(matches!(one, DsnPayload::One(ref n) if n.status == "bounced"));
+
+        let many: DsnPayload = serde_json::from_str(
+            r#"[{"provider_message_id":"a","status":"delivered"},{"provider_message_id":"b","status":"bounced"}]"#,
+        )
+        .unwrap();
+        assert!(matches!(many, DsnPayload::Many(ref v) if v.len() == 2));
+    }
+
+    #[test]
+    fn twilio_signature_matches_documented_example() {
+        // Example from Twilio's webhook security documentation.
+        let params = [
+            ("CallSid", "CA1234567890ABCDE"),
+            ("Caller", "+14158675310"),
+            ("Digits", "1234"),
+            ("From", "+14158675310"),
+            ("To", "+18005551212"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .to_vec();
+        let mac = twilio_signature_mac(
+            "12345",
+            "https://mycompany.com/myapp.php?foo=1&bar=2",
+            params,
+        );
+        assert_eq!(
+            STANDARD.encode(mac.finalize().into_bytes()),
+            "GvWf1cFY/Q7PnoempGyD5oXAezc="
+        );
+    }
+
+    #[test]
+    fn public_url_uses_configured_base() {
+        let uri: Uri = "/api/v1/callbacks/twilio/abc?x=1".parse().unwrap();
+        assert_eq!(
+            public_url("https://notifico.example.com/", &uri),
+            "https://notifico.example.com/api/v1/callbacks/twilio/abc?x=1"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Subcommands of the `notifico` binary. Without arguments it starts the
+//! server; with one of these it runs against the configured database and
+//! exits.
+
+use std::path::PathBuf;
+
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use notifico_db::repo::admin;
+
+use crate::config::Config;
+use crate::sync::{self, Bundle};
+
+pub(crate) const USAGE: &str = "\
+Usage:
+  notifico                                       Start the server
+  notifico export <dir> --project <project>      Write the project's events, rules, templates
+                                                 and layouts to <dir>
+  notifico sync <dir> --project <project> [--dry-run]
+                                                 Create, update and delete so the project
+                                                 matches <dir>; --dry-run only lists changes
+
+<project> is a project id or name. The database comes from the usual
+configuration (notifico.toml and NOTIFICO_* variables).";
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Command {
+    Help,
+    Export {
+        dir: PathBuf,
+        project: String,
+    },
+    Sync {
+        dir: PathBuf,
+        project: String,
+        dry_run: bool,
+    },
+}
+
+impl Command {
+    pub(crate) fn parse(args: &[String]) -> Result<Self, String> {
+        let Some((command, rest)) = args.split_first() else {
+            return Ok(Self::Help);
+        };
+        if matches!(command.as_str(), "help" | "-h" | "--help") {
+            return Ok(Self::Help);
+        }
+        if !matches!(command.as_str(), "export" | "sync") {
+            return Err(format!("Unknown command '{command}'"));
+        }
+
+        let mut dir = None;
+        let mut project = None;
+        let mut dry_run = false;
+        let mut rest = rest.iter();
+        while let Some(arg) = rest.next() {
+            match arg.as_str() {
+                "--project" => {
+                    project = Some(rest.next().ok_or("--project needs a value")?.clone());
+                }
+                "--dry-run" if command == "sync" => dry_run = true,
+                flag if flag.starts_with("--") => return Err(format!("Unknown option '{flag}'")),
+                _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+                _ => return Err(format!("Unexpected argument '{arg}'")),
+            }
+        }
+        let dir = dir.ok_or("Missing <dir>")?;
+        let project = project.ok_or("Missing --project")?;
+
+        Ok(if command == "export" {
+            Self::Export { dir, project }
+        } else {
+            Self::Sync {
+                dir,
+                project,
+                dry_run,
+            }
+        })
+    }
+}
+
+pub(crate) async fn run(command: Command) -> Result<(), String> {
+    let (Command::Export { project, .. } | Command::Sync { project, .. }) = &command else {
+        println!("{USAGE}");
+        return Ok(());
+    };
+
+    let config = Config::load(None).map_err(|e| format!("Failed to load configuration: {e}"))?;
+    let db = notifico_db::connect(&config.database.url)
+        .await
+        .map_err(|e| format!("Failed to connect to database: {e}"))?;
+    notifico_db::run_migrations(&db)
+        .await
+        .map_err(|e| format!("Failed to run database migrations: {e}"))?;
+    let project_id = resolve_project(&db, project).await?;
+
+    match command {
+        Command::Export { dir, .. } => {
+            let bundle = sync::export(&db, project_id)
+                .await
+                .map_err(|e| e.to_string())?;
+            let files = bundle.to_files()?;
+            sync::write_dir(&dir, &files).map_err(|e| format!("{}: {e}", dir.display()))?;
+            println!("Exported {} files to {}", files.len(), dir.display());
+        }
+        Command::Sync { dir, dry_run, .. } => {
+            let files = sync::read_dir(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+            let bundle = Bundle::from_files(&files)?;
+            let registry = crate::transport_registry();
+            let changes = sync::sync(&db, &registry, project_id, &bundle, dry_run)
+                .await
+                .map_err(|e| e.to_string())?;
+            for change in &changes {
+                println!("{change}");
+            }
+            match (changes.len(), dry_run) {
+                (0, _) => println!("Up to date"),
+                (n, true) => println!("{n} changes (dry run, nothing written)"),
+                (n, false) => println!("{n} changes applied"),
+            }
+        }
+        Command::Help => {}
+    }
+    Ok(())
+}
+
+/// Accept a project id or a unique project name.
+async fn resolve_project(db: &DatabaseConnection, project: &str) -> Result<Uuid, String> {
+    let projects = admin::list_projects(db).await.map_err(|e| e.to_string())?;
+    let matches: Vec<_> = projects
+        .iter()
+        .filter(|p| p.id.to_string() == project || p.name == project)
+        .collect();
+    match matches.as_slice() {
+        [p] => Ok(p.id),
+        [] => Err(format!("No project '{project}'")),
+        _ => Err(format!(
+            "Several projects are named '{project}'; use the id"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        Command::parse(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        assert_eq!(
+            parse(&["sync", "notifications", "--project", "Shop", "--dry-run"]).unwrap(),
+            Command::Sync {
+                dir: "notifications".into(),
+                project: "Shop".into(),
+                dry_run: true,
+            }
+        );
+        assert_eq!(
+            parse(&["export", "--project", "Shop", "out"]).unwrap(),
+            Command::Export {
+                dir: "out".into(),
+                project: "Shop".into(),
+            }
+        );
+        assert_eq!(parse(&["--help"]).unwrap(), Command::Help);
+
+        assert!(parse(&["export", "out", "--dry-run", "--project", "Shop"]).is_err());
+        assert!(parse(&["sync", "out"]).unwrap_err().contains("--project"));
+        assert!(parse(&["serve"]).is_err());
+    }
+}
@@ -0,0 +1,419 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use base64::engine::{Engine, general_purpose::STANDARD};
+use serde_json::{Map, Value};
+
+use notifico_core::event::{EventRecipient, IngestEvent};
+
+use crate::AppState;
+use crate::auth::AuthContext;
+use crate::config::CloudEventsConfig;
+use crate::ingest::{IngestError, IngestResponse, process_event_idempotent};
+
+const SPEC_VERSION: &str = "1.0";
+const STRUCTURED_CONTENT_TYPE: &str = "application/cloudevents+json";
+
+/// Context attributes that are not extensions (CloudEvents 1.0 core spec).
+const CONTEXT_ATTRIBUTES: &[&str] = &[
+    "specversion",
+    "id",
+    "source",
+    "type",
+    "datacontenttype",
+    "dataschema",
+    "subject",
+    "time",
+    "data",
+    "data_base64",
+];
+
+/// A CloudEvent decoded from either HTTP content mode.
+#[derive(Debug)]
+struct CloudEvent {
+    id: String,
+    event_type: String,
+    data: Value,
+    extensions: Map<String, Value>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/cloudevents",
+    tag = "events",
+    request_body(
+        content = serde_json::Value,
+        description = "CloudEvents 1.0 event, structured (application/cloudevents+json) or binary (ce-* headers) mode"
+    ),
+    responses(
+        (status = 200, description = "Event accepted", body = IngestResponse),
+        (status = 400, description = "Malformed CloudEvent"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Event not found"),
+        (status = 422, description = "Event data does not match the event's data schema"),
+        (status = 429, description = "Rate limited"),
+    ),
+    security(("bearer" = []))
+)]
+#[tracing::instrument(
+    name = "ingest_cloudevent",
+    skip(state, auth, headers, body),
+    fields(project_id = %auth.project_id)
+)]
+pub async fn handle_cloudevent(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, IngestError> {
+    auth.require_scope("ingest")
+        .map_err(|e| (StatusCode::FORBIDDEN, format!("{e:?}")))?;
+
+    if let Err(retry_after) = state.rate_limiter.check(auth.api_key_id) {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Rate limit exceeded. Retry after {retry_after}s"),
+        )
+            .into());
+    }
+
+    let cloud_event = decode(&headers, &body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let event = into_ingest_event(cloud_event, &state.config.cloudevents)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    process_event_idempotent(&state, auth.project_id, &event).await
+}
+
+/// Decode a CloudEvent from an HTTP request, detecting the content mode.
+fn decode(headers: &HeaderMap, body: &[u8]) -> Result<CloudEvent, String> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    if content_type.starts_with(STRUCTURED_CONTENT_TYPE) {
+        decode_structured(body)
+    } else if headers.contains_key("ce-specversion") {
+        decode_binary(headers, content_type, body)
+    } else {
+        Err(format!(
+            "Expected {STRUCTURED_CONTENT_TYPE} body or ce-* headers (binary mode)"
+        ))
+    }
+}
+
+/// Structured mode: the whole event, attributes and data, is the JSON body.
+fn decode_structured(body: &[u8]) -> Result<CloudEvent, String> {
+    let mut attrs: Map<String, Value> =
+        serde_json::from_slice(body).map_err(|e| format!("Invalid CloudEvent JSON: {e}"))?;
+
+    let attr = |attrs: &Map<String, Value>, name: &str| -> Result<String, String> {
+        attrs
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| format!("Missing required attribute '{name}'"))
+    };
+
+    check_spec_version(&attr(&attrs, "specversion")?)?;
+    let id = attr(&attrs, "id")?;
+    attr(&attrs, "source")?;
+    let event_type = attr(&attrs, "type")?;
+
+    let data = match (attrs.remove("data"), attrs.remove("data_base64")) {
+        (Some(data), _) => data,
+        (None, Some(Value::String(encoded))) => {
+            let bytes = STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("Invalid data_base64: {e}"))?;
+            serde_json::from_slice(&bytes)
+                .map_err(|e| format!("data_base64 must contain JSON: {e}"))?
+        }
+        (None, Some(_)) => return Err("data_base64 must be a string".into()),
+        (None, None) => Value::Object(Map::new()),
+    };
+
+    let extensions = attrs
+        .into_iter()
+        .filter(|(k, _)| !CONTEXT_ATTRIBUTES.contains(&k.as_str()))
+        .collect();
+
+    Ok(CloudEvent {
+        id,
+        event_type,
+        data,
+        extensions,
+    })
+}
+
+/// Binary mode: attributes are `ce-*` headers, the body is the event data.
+fn decode_binary(
+    headers: &HeaderMap,
+    content_type: &str,
+    body: &[u8],
+) -> Result<CloudEvent, String> {
+    let mut attrs = Map::new();
+    for (name, value) in headers {
+        if let Some(attr) = name.as_str().strip_prefix("ce-") {
+            let value = value
+                .to_str()
+                .map_err(|_| format!("Header '{name}' is not valid UTF-8"))?;
+            attrs.insert(attr.to_string(), Value::String(value.to_string()));
+        }
+    }
+
+    let attr = |name: &str| -> Result<String, String> {
+        attrs
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| format!("Missing required header 'ce-{name}'"))
+    };
+
+    check_spec_version(&attr("specversion")?)?;
+    let id = attr("id")?;
+    attr("source")?;
+    let event_type = attr("type")?;
+
+    let data = if body.is_empty() {
+        Value::Object(Map::new())
+    } else if content_type.is_empty() || is_json_content_type(content_type) {
+        serde_json::from_slice(body).map_err(|e| format!("Invalid JSON data: {e}"))?
+    } else {
+        return Err(format!("Unsupported data content type: {content_type}"));
+    };
+
+    let extensions = attrs
+        .into_iter()
+        .filter(|(k, _)| !CONTEXT_ATTRIBUTES.contains(&k.as_str()))
+        .collect();
+
+    Ok(CloudEvent {
+        id,
+        event_type,
+        data,
+        extensions,
+    })
+}
+
+fn check_spec_version(version: &str) -> Result<(), String> {
+    if version == SPEC_VERSION {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unsupported specversion '{version}', expected {SPEC_VERSION}"
+        ))
+    }
+}
+
+fn is_json_content_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+/// Map a CloudEvent onto a Notifico ingest event.
+///
+/// `type` becomes the event name, `data` the template data and `id` the
+/// idempotency key. Recipients come from the configured JSON pointer into
+/// `data` if set, otherwise from the configured extension attribute.
+fn into_ingest_event(
+    cloud_event: CloudEvent,
+    config: &CloudEventsConfig,
+) -> Result<IngestEvent, String> {
+    let recipients_value = match &config.recipients_pointer {
+        Some(pointer) => cloud_event
+            .data
+            .pointer(pointer)
+            .ok_or_else(|| format!("No recipients found at data pointer '{pointer}'"))?,
+        None => cloud_event
+            .extensions
+            .get(&config.recipients_extension)
+            .ok_or_else(|| {
+                format!(
+                    "Missing recipients extension '{}'",
+                    config.recipients_extension
+                )
+            })?,
+    };
+
+    let recipients = parse_recipients(recipients_value)?;
+    if recipients.is_empty() {
+        return Err("CloudEvent has no recipients".into());
+    }
+
+    Ok(IngestEvent {
+        event: cloud_event.event_type,
+        recipients,
+        data: cloud_event.data,
+        idempotency_key: Some(cloud_event.id),
+        attachments: vec![],
+    })
+}
+
+/// Parse recipients from an extension or data value.
+///
+/// Accepts a recipient ID, a recipient object (`{"id", "contacts"}`), or an
+/// array of either. Extension attributes are strings on the wire, so a string
+/// holding JSON is decoded first.
+fn parse_recipients(value: &Value) -> Result<Vec<EventRecipient>, String> {
+    match value {
+        Value::Array(items) => items.iter().map(parse_recipient).collect(),
+        Value::String(s) => match serde_json::from_str::<Value>(s) {
+            Ok(decoded @ (Value::Array(_) | Value::Object(_))) => parse_recipients(&decoded),
+            _ => Ok(s
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| EventRecipient {
+                    id: id.to_string(),
+                    contacts: Default::default(),
+                })
+                .collect()),
+        },
+        Value::Object(_) => Ok(vec![parse_recipient(value)?]),
+        _ => Err("Recipients must be a string, object or array".into()),
+    }
+}
+
+fn parse_recipient(value: &Value) -> Result<EventRecipient, String> {
+    match value {
+        Value::String(id) => Ok(EventRecipient {
+            id: id.clone(),
+            contacts: Default::default(),
+        }),
+        _ => serde_json::from_value(value.clone()).map_err(|e| format!("Invalid recipient: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    fn config() -> CloudEventsConfig {
+        CloudEventsConfig {
+            recipients_extension: "recipients".into(),
+            recipients_pointer: None,
+        }
+    }
+
+    fn structured_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/cloudevents+json; charset=utf-8"),
+        );
+        headers
+    }
+
+    #[test]
+    fn structured_mode_maps_to_ingest_event() {
+        let body = json!({
+            "specversion": "1.0",
+            "id": "evt-1",
+            "source": "/orders",
+            "type": "order.confirmed",
+            "datacontenttype": "application/json",
+            "recipients": [{"id": "user-1", "contacts": {"email": "a@b.com"}}, "user-2"],
+            "data": {"order_id": 42}
+        });
+        let ce = decode(&structured_headers(), body.to_string().as_bytes()).unwrap();
+        let event = into_ingest_event(ce, &config()).unwrap();
+
+        assert_eq!(event.event, "order.confirmed");
+        assert_eq!(event.idempotency_key.as_deref(), Some("evt-1"));
+        assert_eq!(event.data["order_id"], 42);
+        assert_eq!(event.recipients.len(), 2);
+        assert_eq!(event.recipients[0].contacts["email"], "a@b.com");
+        assert_eq!(event.recipients[1].id, "user-2");
+    }
+
+    #[test]
+    fn structured_mode_decodes_data_base64() {
+        let body = json!({
+            "specversion": "1.0",
+            "id": "evt-2",
+            "source": "/orders",
+            "type": "order.shipped",
+            "recipients": "user-1",
+            "data_base64": STANDARD.encode(br#"{"tracking":"XYZ"}"#)
+        });
+        let ce = decode(&structured_headers(), body.to_string().as_bytes()).unwrap();
+        assert_eq!(ce.data["tracking"], "XYZ");
+    }
+
+    #[test]
+    fn binary_mode_reads_ce_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        headers.insert("ce-specversion", HeaderValue::from_static("1.0"));
+        headers.insert("ce-id", HeaderValue::from_static("evt-3"));
+        headers.insert("ce-source", HeaderValue::from_static("/billing"));
+        headers.insert("ce-type", HeaderValue::from_static("invoice.paid"));
+        headers.insert("ce-recipients", HeaderValue::from_static("user-1, user-2"));
+
+        let ce = decode(&headers, br#"{"amount": 10}"#).unwrap();
+        let event = into_ingest_event(ce, &config()).unwrap();
+
+        assert_eq!(event.event, "invoice.paid");
+        assert_eq!(event.idempotency_key.as_deref(), Some("evt-3"));
+        assert_eq!(event.data["amount"], 10);
+        let ids: Vec<_> = event.recipients.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["user-1", "user-2"]);
+    }
+
+    #[test]
+    fn recipients_from_data_pointer() {
+        let ce = CloudEvent {
+            id: "evt-4".into(),
+            event_type: "alert".into(),
+            data: json!({"notify": {"users": [{"id": "ops-1"}]}}),
+            extensions: Map::new(),
+        };
+        let config = CloudEventsConfig {
+            recipients_pointer: Some("/notify/users".into()),
+            ..config()
+        };
+        let event = into_ingest_event(ce, &config).unwrap();
+        assert_eq!(event.recipients[0].id, "ops-1");
+    }
+
+    #[test]
+    fn missing_recipients_is_an_error() {
+        let ce = CloudEvent {
+            id: "evt-5".into(),
+            event_type: "alert".into(),
+            data: json!({}),
+            extensions: Map::new(),
+        };
+        assert!(into_ingest_event(ce, &config()).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_spec_version_and_missing_type() {
+        let body = json!({"specversion": "0.3", "id": "x", "source": "/", "type": "t"});
+        assert!(decode(&structured_headers(), body.to_string().as_bytes()).is_err());
+
+        let body = json!({"specversion": "1.0", "id": "x", "source": "/"});
+        assert!(decode(&structured_headers(), body.to_string().as_bytes()).is_err());
+    }
+
+    #[test]
+    fn rejects_request_without_cloudevent_markers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        assert!(decode(&headers, b"{}").is_err());
+    }
+}
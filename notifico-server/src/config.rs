@@ -20,6 +20,10 @@ pub struct Config {
     pub project: ProjectConfig,
     #[serde(default)]
     pub otel: OtelConfig,
+    #[serde(default)]
+    pub cloudevents: CloudEventsConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -34,6 +38,11 @@ pub struct ServerConfig {
     pub admin_port: u16,
     #[serde(default)]
     pub log_format: LogFormat,
+    /// Externally visible base URL (e.g. `https://notifico.example.com`).
+    /// Twilio signs the full callback URL, so its status callbacks are only
+    /// accepted once this is set.
+    #[serde(default)]
+    pub public_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
@@ -80,6 +89,12 @@ pub struct StorageConfig {
     pub s3_bucket: Option<String>,
     #[serde(default)]
     pub s3_endpoint: Option<String>,
+    #[serde(default = "default_s3_region")]
+    pub s3_region: String,
+    #[serde(default)]
+    pub s3_access_key_id: Option<String>,
+    #[serde(default)]
+    pub s3_secret_access_key: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -114,6 +129,41 @@ pub struct OtelConfig {
     pub service_name: String,
 }
 
+/// How the CloudEvents ingest adapter finds recipients in an incoming event.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloudEventsConfig {
+    /// Extension attribute carrying the recipients (`ce-<name>` in binary mode).
+    #[serde(default = "default_cloudevents_recipients_extension")]
+    pub recipients_extension: String,
+    /// JSON pointer into `data` used instead of the extension when set,
+    /// e.g. `/recipients`.
+    #[serde(default)]
+    pub recipients_pointer: Option<String>,
+}
+
+/// Idempotency window for ingest requests carrying an `idempotency_key`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdempotencyConfig {
+    /// How long a key (and its cached response) is remembered, in seconds.
+    #[serde(default = "default_idempotency_ttl_secs")]
+    pub ttl_secs: u64,
+    /// How often expired records are purged, in seconds.
+    #[serde(default = "default_idempotency_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+fn default_idempotency_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_idempotency_purge_interval_secs() -> u64 {
+    60 * 60
+}
+
+fn default_cloudevents_recipients_extension() -> String {
+    "recipients".into()
+}
+
 fn default_otel_service_name() -> String {
     "notifico".into()
 }
@@ -145,6 +195,9 @@ fn default_storage_backend() -> String {
 fn default_storage_path() -> String {
     "./data/assets".into()
 }
+fn default_s3_region() -> String {
+    "us-east-1".into()
+}
 fn default_locale() -> String {
     "en".into()
 }
@@ -157,6 +210,7 @@ impl Default for ServerConfig {
             port: default_port(),
             admin_port: default_admin_port(),
             log_format: LogFormat::default(),
+            public_url: None,
         }
     }
 }
@@ -187,6 +241,9 @@ impl Default for StorageConfig {
             path: default_storage_path(),
             s3_bucket: None,
             s3_endpoint: None,
+            s3_region: default_s3_region(),
+            s3_access_key_id: None,
+            s3_secret_access_key: None,
         }
     }
 }
@@ -218,6 +275,24 @@ impl Default for OtelConfig {
     }
 }
 
+impl Default for CloudEventsConfig {
+    fn default() -> Self {
+        Self {
+            recipients_extension: default_cloudevents_recipients_extension(),
+            recipients_pointer: None,
+        }
+    }
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_idempotency_ttl_secs(),
+            purge_interval_secs: default_idempotency_purge_interval_secs(),
+        }
+    }
+}
+
 impl Config {
     /// Load config from notifico.toml (optional) + NOTIFICO_ env vars.
     pub fn load(config_path: Option<&str>) -> Result<Self, figment::Error> {
@@ -294,4 +369,39 @@ mod tests {
 
         assert_eq!(config.server.log_format, LogFormat::Json);
     }
+
+    #[test]
+    fn config_cloudevents_recipients_pointer() {
+        let toml_str = r#"
+            [cloudevents]
+            recipients_pointer = "/to"
+        "#;
+
+        let config: Config = Figment::new()
+            .merge(Toml::string(toml_str))
+            .extract()
+            .unwrap();
+
+        assert_eq!(config.cloudevents.recipients_extension, "recipients"); // default
+        assert_eq!(config.cloudevents.recipients_pointer.as_deref(), Some("/to"));
+    }
+
+    #[test]
+    fn config_idempotency_window() {
+        let config = Config::load(None).unwrap();
+        assert_eq!(config.idempotency.ttl_secs, 86400);
+
+        let toml_str = r#"
+            [idempotency]
+            ttl_secs = 600
+        "#;
+
+        let config: Config = Figment::new()
+            .merge(Toml::string(toml_str))
+            .extract()
+            .unwrap();
+
+        assert_eq!(config.idempotency.ttl_secs, 600);
+        assert_eq!(config.idempotency.purge_interval_secs, 3600); // default
+    }
 }
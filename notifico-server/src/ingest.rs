@@ -1,17 +1,31 @@
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 use serde::Serialize;
+use serde_json::Value;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use notifico_core::data_schema::DataError;
 use notifico_core::event::IngestEvent;
+use notifico_core::locale::{LocaleChain, LocaleFallbackConfig, fallback_chain};
 use notifico_core::pipeline::{PipelineInput, execute_pipeline};
 use notifico_db::repo;
 
 use crate::AppState;
 use crate::auth::AuthContext;
 
+/// Response header set when a duplicate request is answered from the
+/// idempotency cache instead of being processed again.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct IngestResponse {
     pub accepted: usize,
@@ -20,15 +34,74 @@ pub struct IngestResponse {
     pub errors: Vec<String>,
 }
 
+/// Why an event was not processed.
+#[derive(Debug)]
+pub enum IngestError {
+    /// Plain status and message.
+    Status(StatusCode, String),
+    /// The event data does not match the event's declared schema.
+    InvalidData(Vec<DataError>),
+}
+
+impl From<(StatusCode, String)> for IngestError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        Self::Status(status, message)
+    }
+}
+
+impl IntoResponse for IngestError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Status(status, message) => (status, message).into_response(),
+            Self::InvalidData(errors) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({
+                    "error": "Event data does not match the event schema",
+                    "errors": errors,
+                })),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Check event data against the event's declared schema, if it has one.
+pub(crate) fn validate_event_data(
+    state: &AppState,
+    event: &repo::template::EventRow,
+    data: &Value,
+) -> Result<(), IngestError> {
+    let Some(schema) = &event.data_schema else {
+        return Ok(());
+    };
+    let schema = state
+        .data_schema_cache
+        .get_or_compile(event.id, schema)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Invalid data schema for event: {e}"),
+            )
+        })?;
+    let errors = schema.validate(data);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(IngestError::InvalidData(errors))
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/api/v1/events",
     tag = "events",
     request_body(content = serde_json::Value, description = "Ingest event payload"),
     responses(
-        (status = 200, description = "Event accepted", body = IngestResponse),
+        (status = 200, description = "Event accepted (or replayed, see `Idempotent-Replayed` header)", body = IngestResponse),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Event not found"),
+        (status = 413, description = "An inline attachment exceeds 25 MB decoded"),
+        (status = 422, description = "Event data does not match the event's data schema"),
         (status = 429, description = "Rate limited"),
     ),
     security(("bearer" = []))
@@ -42,7 +115,7 @@ pub async fn handle_ingest(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
     Json(event): Json<IngestEvent>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, IngestError> {
     auth.require_scope("ingest")
         .map_err(|e| (StatusCode::FORBIDDEN, format!("{e:?}")))?;
 
@@ -50,12 +123,195 @@ pub async fn handle_ingest(
         return Err((
             StatusCode::TOO_MANY_REQUESTS,
             format!("Rate limit exceeded. Retry after {retry_after}s"),
-        ));
+        )
+            .into());
     }
 
-    let project_id = auth.project_id;
-    let default_locale = &state.config.project.default_locale;
+    process_event_idempotent(&state, auth.project_id, &event).await
+}
+
+/// Process an event, honouring its `idempotency_key` at the request level.
+///
+/// The first request with a given key is processed and its response cached
+/// for the configured window; repeats within the window get the cached
+/// response back with `Idempotent-Replayed: true` and enqueue nothing.
+pub(crate) async fn process_event_idempotent(
+    state: &AppState,
+    project_id: Uuid,
+    event: &IngestEvent,
+) -> Result<Response, IngestError> {
+    let Some(client_key) = event.idempotency_key.as_deref() else {
+        let response = process_event(state, project_id, event).await?;
+        return Ok(Json(response).into_response());
+    };
+
+    let ttl_secs = state.config.idempotency.ttl_secs;
+    let request_key = repo::idempotency::make_request_key(project_id, &event.event, client_key);
+
+    match repo::idempotency::find_response(&state.db, &request_key, ttl_secs).await {
+        Ok(Some(cached)) => {
+            tracing::debug!(key = %request_key, "Replaying cached ingest response");
+            return Ok(([(IDEMPOTENT_REPLAYED_HEADER, "true")], Json(cached)).into_response());
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!(error = %e, "Idempotency lookup failed");
+        }
+    }
 
+    let response = process_event(state, project_id, event).await?;
+
+    let body = serde_json::to_value(&response)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if let Err(e) =
+        repo::idempotency::store_response(&state.db, &request_key, &body, ttl_secs).await
+    {
+        tracing::error!(error = %e, "Failed to store idempotent response");
+    }
+
+    Ok(Json(body).into_response())
+}
+
+/// Periodically delete idempotency records older than the configured window.
+pub async fn run_idempotency_purge(state: Arc<AppState>) {
+    let config = &state.config.idempotency;
+    let mut interval = tokio::time::interval(Duration::from_secs(config.purge_interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+        match repo::idempotency::purge_expired(&state.db, config.ttl_secs).await {
+            Ok(0) => {}
+            Ok(purged) => tracing::info!(purged, "Purged expired idempotency records"),
+            Err(e) => tracing::error!(error = %e, "Failed to purge idempotency records"),
+        }
+    }
+}
+
+/// Locale settings of a project, used to pick template content per recipient.
+pub(crate) struct ProjectLocales {
+    /// The project's default locale (used for recipients without one).
+    pub default_locale: String,
+    server_default: String,
+    fallback: LocaleFallbackConfig,
+}
+
+impl ProjectLocales {
+    /// Fallback chain for a recipient locale: the locale and its BCP-47
+    /// parents, then the project default, then the server default.
+    pub fn chain(&self, locale: &str) -> LocaleChain {
+        fallback_chain(
+            locale,
+            &[&self.default_locale, &self.server_default],
+            &self.fallback,
+        )
+    }
+}
+
+pub(crate) async fn load_project_locales(
+    state: &AppState,
+    project_id: Uuid,
+) -> Result<ProjectLocales, String> {
+    let server_default = state.config.project.default_locale.clone();
+    let project = repo::admin::get_project(&state.db, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(match project {
+        Some(p) => ProjectLocales {
+            fallback: LocaleFallbackConfig::from_settings(&p.settings),
+            default_locale: p.default_locale,
+            server_default,
+        },
+        None => ProjectLocales {
+            default_locale: server_default.clone(),
+            server_default,
+            fallback: LocaleFallbackConfig::default(),
+        },
+    })
+}
+
+/// Load the project's translation catalogs for `t()` in templates.
+pub(crate) async fn load_translations(
+    state: &AppState,
+    project_id: Uuid,
+) -> Result<notifico_template::Translations, String> {
+    let catalogs = repo::translation::load_catalogs(&state.db, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(notifico_template::Translations::new(catalogs))
+}
+
+/// Load the project's undefined-variable mode and render limits.
+pub(crate) async fn load_render_options(
+    state: &AppState,
+    project_id: Uuid,
+) -> Result<notifico_template::RenderOptions, String> {
+    let project = repo::admin::get_project(&state.db, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(project
+        .map(|p| notifico_template::RenderOptions::from_settings(&p.settings))
+        .unwrap_or_default())
+}
+
+/// Load the project's layouts and partials for template rendering.
+pub(crate) async fn load_partials(
+    state: &AppState,
+    project_id: Uuid,
+) -> Result<notifico_template::Partials, String> {
+    let sources = repo::partial::load_sources(&state.db, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    notifico_template::Partials::new(sources).map_err(|e| e.to_string())
+}
+
+/// Load the project's stylesheets for linked-stylesheet inlining.
+pub(crate) async fn load_stylesheets(
+    state: &AppState,
+    project_id: Uuid,
+) -> Result<Arc<BTreeMap<String, String>>, String> {
+    repo::partial::load_stylesheets(&state.db, project_id)
+        .await
+        .map(Arc::new)
+        .map_err(|e| e.to_string())
+}
+
+/// Load the A/B variants of each rule, keyed by rule id.
+pub(crate) async fn load_rule_variants(
+    state: &AppState,
+    rules: &[repo::template::PipelineRuleRow],
+) -> Result<HashMap<Uuid, Vec<repo::variant::VariantRow>>, String> {
+    let mut variants = HashMap::new();
+    for rule in rules.iter().filter(|r| r.experiment.is_some()) {
+        let rows = repo::variant::list_by_rule(&state.db, rule.id)
+            .await
+            .map_err(|e| e.to_string())?;
+        variants.insert(rule.id, rows);
+    }
+    Ok(variants)
+}
+
+/// The variant a recipient gets while the rule runs an experiment; `None`
+/// means the rule's own template.
+pub(crate) fn pick_variant<'a>(
+    rule: &repo::template::PipelineRuleRow,
+    variants: &'a HashMap<Uuid, Vec<repo::variant::VariantRow>>,
+    recipient_id: Uuid,
+) -> Option<&'a repo::variant::VariantRow> {
+    let experiment = rule.experiment.as_deref()?;
+    let variants = variants.get(&rule.id)?;
+    let weights: Vec<u32> = variants.iter().map(|v| v.weight.max(0) as u32).collect();
+    notifico_core::experiment::assign(experiment, recipient_id, &weights).map(|i| &variants[i])
+}
+
+/// Run an ingest event through the pipeline: resolve rules, recipients and
+/// templates, render, and enqueue one delivery task per recipient/channel.
+///
+/// Shared by the native ingest endpoint and protocol adapters (CloudEvents).
+pub(crate) async fn process_event(
+    state: &AppState,
+    project_id: Uuid,
+    event: &IngestEvent,
+) -> Result<IngestResponse, IngestError> {
     // Resolve event by name
     let event_row = repo::template::find_event_by_name(&state.db, project_id, &event.event)
         .await
@@ -67,22 +323,50 @@ pub async fn handle_ingest(
             )
         })?;
 
+    validate_event_data(state, &event_row, &event.data)?;
+
     // Get pipeline rules for this event
     let rules = repo::template::get_pipeline_rules(&state.db, event_row.id)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     if rules.is_empty() {
-        return Ok(Json(IngestResponse {
+        return Ok(IngestResponse {
             accepted: 0,
             task_ids: vec![],
             errors: vec![format!(
                 "No pipeline rules configured for event: {}",
                 event.event
             )],
-        }));
+        });
     }
 
+    // Store inline attachments once; every task carries references only
+    let attachments =
+        crate::attachments::resolve_event_attachments(state, project_id, &event.attachments)
+            .await?;
+    let attachments = serde_json::to_value(&attachments)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let partials = load_partials(state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let stylesheets = load_stylesheets(state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let locales = load_project_locales(state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let translations = load_translations(state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let render_options = load_render_options(state, project_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let variants = load_rule_variants(state, &rules)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
     let mut task_ids = Vec::new();
     let mut errors = Vec::new();
 
@@ -129,7 +413,12 @@ pub async fn handle_ingest(
         let recipient_locale = recipient_row
             .as_ref()
             .map(|r| r.locale.as_str())
-            .unwrap_or(default_locale);
+            .filter(|l| !l.is_empty())
+            .unwrap_or(&locales.default_locale);
+        let recipient_timezone = recipient_row
+            .as_ref()
+            .map(|r| r.timezone.as_str())
+            .unwrap_or("UTC");
 
         // Get contacts from DB
         let db_contacts = repo::recipient::get_contacts(&state.db, recipient_id)
@@ -194,7 +483,12 @@ pub async fn handle_ingest(
                     &rule.channel,
                     Some(client_key),
                 );
-                match repo::idempotency::check_and_insert(&state.db, &idem_key).await {
+                match repo::idempotency::check_and_insert(
+                    &state.db,
+                    &idem_key,
+                    state.config.idempotency.ttl_secs,
+                )
+                .await {
                     Ok(true) => {
                         tracing::debug!(key = %idem_key, "Duplicate delivery skipped");
                         continue;
@@ -207,12 +501,13 @@ pub async fn handle_ingest(
                 }
             }
 
-            // Resolve template
+            // Resolve template, or the recipient's variant of it
+            let variant = pick_variant(rule, &variants, recipient_id);
+            let template_id = variant.map_or(rule.template_id, |v| v.template_id);
             let template = match repo::template::resolve_template(
                 &state.db,
-                rule.template_id,
-                recipient_locale,
-                default_locale,
+                template_id,
+                &locales.chain(recipient_locale),
             )
             .await
             {
@@ -220,7 +515,7 @@ pub async fn handle_ingest(
                 Ok(None) => {
                     errors.push(format!(
                         "Template not found for rule {} (template_id: {}, locale: {})",
-                        rule.id, rule.template_id, recipient_locale
+                        rule.id, template_id, recipient_locale
                     ));
                     continue;
                 }
@@ -236,10 +531,23 @@ pub async fn handle_ingest(
                 event_name: event.event.clone(),
                 recipient_id,
                 recipient_locale: recipient_locale.to_string(),
+                recipient_timezone: recipient_timezone.to_string(),
                 channel: rule.channel.clone(),
                 contact_value,
                 template_body: template.body,
                 context_data: event.data.clone(),
+                partials: partials.clone(),
+                stylesheets: stylesheets.clone(),
+                translations: translations
+                    .clone()
+                    .with_fallback(locales.chain(recipient_locale).locales),
+                render_options,
+                template_cache: state.template_cache.clone(),
+                template_key: Some(notifico_template::TemplateKey::new(
+                    template.version_id.to_string(),
+                    template.locale.clone(),
+                )),
+                content_format: template.format.parse().unwrap_or_default(),
                 idempotency_key: event.idempotency_key.clone(),
                 max_attempts: 5,
             };
@@ -284,21 +592,24 @@ pub async fn handle_ingest(
                         }
                     }
 
-                    if let Err(e) = repo::queue::enqueue(
-                        &state.db,
-                        output.id,
-                        output.project_id,
-                        &output.event_name,
-                        output.recipient_id,
-                        &output.channel,
-                        &output.contact_value,
-                        &output.rendered_body,
-                        output.idempotency_key.as_deref(),
-                        output.max_attempts as i32,
-                        Some(rule_id),
-                    )
-                    .await
-                    {
+                    let task = repo::queue::NewTask {
+                        id: output.id,
+                        project_id: output.project_id,
+                        event_name: &output.event_name,
+                        recipient_id: output.recipient_id,
+                        channel: &output.channel,
+                        contact_value: &output.contact_value,
+                        rendered_body: &output.rendered_body,
+                        idempotency_key: output.idempotency_key.as_deref(),
+                        max_attempts: output.max_attempts as i32,
+                        rule_id: Some(rule_id),
+                        attachments: &attachments,
+                        template_version: Some(template.version),
+                        experiment: variant.and(rule.experiment.as_deref()),
+                        variant: variant.map(|v| v.name.as_str()),
+                        credential: rule.credential.as_deref(),
+                    };
+                    if let Err(e) = repo::queue::enqueue(&state.db, &task).await {
                         errors.push(format!(
                             "Failed to enqueue task for recipient {} channel {}: {}",
                             recipient_input.id, rule.channel, e
@@ -325,11 +636,11 @@ pub async fn handle_ingest(
     }
 
     let accepted = task_ids.len();
-    Ok(Json(IngestResponse {
+    Ok(IngestResponse {
         accepted,
         task_ids,
         errors,
-    }))
+    })
 }
 
 #[cfg(test)]
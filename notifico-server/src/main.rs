@@ -1,6 +1,10 @@
 mod admin;
+mod attachments;
 mod auth;
 mod broadcast;
+mod callbacks;
+mod cli;
+mod cloudevents;
 mod config;
 mod frontend;
 mod ingest;
@@ -8,12 +12,15 @@ mod metrics;
 mod openapi;
 mod public;
 mod rate_limit;
+mod storage;
+mod sync;
 mod tracking;
+mod webhooks;
 mod worker;
 
 use std::sync::Arc;
 
-use axum::{Router, extract::State, middleware, routing::{get, post}};
+use axum::{Router, extract::{DefaultBodyLimit, State}, middleware, routing::{get, post}};
 use sea_orm::DatabaseConnection;
 use tower_http::trace::TraceLayer;
 
@@ -23,6 +30,7 @@ use tracing_subscriber::util::SubscriberInitExt;
 use config::{Config, ServerMode};
 use notifico_core::middleware::MiddlewareRegistry;
 use notifico_core::middleware::click_tracking::ClickTrackingMiddleware;
+use notifico_core::middleware::css_inline::CssInlineMiddleware;
 use notifico_core::middleware::open_tracking::OpenTrackingMiddleware;
 use notifico_core::middleware::plaintext_fallback::PlaintextFallbackMiddleware;
 use notifico_core::middleware::unsubscribe_link::UnsubscribeLinkMiddleware;
@@ -47,10 +55,26 @@ pub(crate) struct AppState {
     pub(crate) encryption_key: Option<[u8; 32]>,
     pub(crate) metrics_handle: Option<metrics_exporter_prometheus::PrometheusHandle>,
     pub(crate) rate_limiter: rate_limit::RateLimiter,
+    pub(crate) storage: Arc<dyn notifico_core::storage::Storage>,
+    pub(crate) template_cache: notifico_template::TemplateCache,
+    pub(crate) data_schema_cache: notifico_core::data_schema::DataSchemaCache,
 }
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let result = match cli::Command::parse(&args) {
+            Ok(command) => cli::run(command).await,
+            Err(e) => Err(format!("{e}\n\n{}", cli::USAGE)),
+        };
+        if let Err(e) = result {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let config = Config::load(None).expect("Failed to load configuration");
 
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
@@ -124,17 +148,7 @@ async fn main() {
 
     tracing::info!("Database migrations complete");
 
-    let mut registry = TransportRegistry::new();
-    registry.register(Arc::new(ConsoleTransport));
-    registry.register(Arc::new(EmailTransport));
-    registry.register(Arc::new(SlackTransport::new()));
-    registry.register(Arc::new(DiscordTransport::new()));
-    registry.register(Arc::new(TwilioSmsTransport::new()));
-    registry.register(Arc::new(TelegramTransport::new()));
-    registry.register(Arc::new(WebhookTransport::new()));
-    registry.register(Arc::new(FcmTransport::new()));
-    registry.register(Arc::new(ApnsTransport::new()));
-    registry.register(Arc::new(WebPushTransport::new()));
+    let registry = transport_registry();
 
     // Parse encryption key from config (hex-encoded 32-byte key)
     let encryption_key = config.auth.encryption_key.as_ref().map(|hex_key| {
@@ -149,10 +163,13 @@ async fn main() {
 
     let mut middleware_registry = MiddlewareRegistry::new();
     middleware_registry.register(Arc::new(UnsubscribeLinkMiddleware));
-    middleware_registry.register(Arc::new(ClickTrackingMiddleware));
-    middleware_registry.register(Arc::new(OpenTrackingMiddleware));
+    middleware_registry.register(Arc::new(ClickTrackingMiddleware::new(encryption_key)));
+    middleware_registry.register(Arc::new(OpenTrackingMiddleware::new(encryption_key)));
     middleware_registry.register(Arc::new(UtmParamsMiddleware));
     middleware_registry.register(Arc::new(PlaintextFallbackMiddleware));
+    middleware_registry.register(Arc::new(CssInlineMiddleware));
+
+    let storage = storage::from_config(&config.storage).expect("Invalid storage configuration");
 
     let state = Arc::new(AppState {
         db,
@@ -162,6 +179,9 @@ async fn main() {
         encryption_key,
         metrics_handle: Some(metrics_handle),
         rate_limiter: rate_limit::RateLimiter::new(100, 60),
+        storage,
+        template_cache: Default::default(),
+        data_schema_cache: Default::default(),
     });
 
     match config.server.mode {
@@ -170,6 +190,8 @@ async fn main() {
             tokio::spawn(async move {
                 worker::run_worker_loop(worker_state).await;
             });
+            tokio::spawn(webhooks::run_webhook_dispatcher(state.clone()));
+            tokio::spawn(ingest::run_idempotency_purge(state.clone()));
             start_api_server(state).await;
         }
         ServerMode::Api => {
@@ -177,6 +199,8 @@ async fn main() {
         }
         ServerMode::Worker => {
             tracing::info!("Worker mode — HTTP server not started");
+            tokio::spawn(webhooks::run_webhook_dispatcher(state.clone()));
+            tokio::spawn(ingest::run_idempotency_purge(state.clone()));
             worker::run_worker_loop(state).await;
         }
     }
@@ -187,11 +211,22 @@ pub(crate) fn build_router(state: Arc<AppState>) -> Router {
         .route("/health", get(health))
         .route("/ready", get(health))
         .route("/metrics", get(metrics::metrics_handler))
-        .route("/api/v1/events", post(ingest::handle_ingest))
+        .route(
+            "/api/v1/events",
+            post(ingest::handle_ingest)
+                .layer(DefaultBodyLimit::max(attachments::MAX_INGEST_BODY_BYTES)),
+        )
         .route("/api/v1/broadcasts", post(broadcast::handle_broadcast))
+        .route("/api/v1/cloudevents", post(cloudevents::handle_cloudevent))
+        .route(
+            "/api/v1/attachments",
+            post(attachments::handle_upload)
+                .layer(DefaultBodyLimit::max(attachments::MAX_ATTACHMENT_BYTES)),
+        )
         .merge(openapi::swagger_ui_router())
         .nest("/admin/api/v1", admin::admin_router())
         .nest("/api/v1/public", public::public_router())
+        .nest("/api/v1/callbacks", callbacks::callbacks_router())
         .route("/t/open/{token}", get(tracking::handle_open))
         .route("/t/click/{token}", get(tracking::handle_click))
         .fallback(frontend::serve_frontend)
@@ -200,6 +235,22 @@ pub(crate) fn build_router(state: Arc<AppState>) -> Router {
         .with_state(state)
 }
 
+/// Every built-in transport, keyed by channel.
+pub(crate) fn transport_registry() -> TransportRegistry {
+    let mut registry = TransportRegistry::new();
+    registry.register(Arc::new(ConsoleTransport));
+    registry.register(Arc::new(EmailTransport::new()));
+    registry.register(Arc::new(SlackTransport::new()));
+    registry.register(Arc::new(DiscordTransport::new()));
+    registry.register(Arc::new(TwilioSmsTransport::new()));
+    registry.register(Arc::new(TelegramTransport::new()));
+    registry.register(Arc::new(WebhookTransport::new()));
+    registry.register(Arc::new(FcmTransport::new()));
+    registry.register(Arc::new(ApnsTransport::new()));
+    registry.register(Arc::new(WebPushTransport::new()));
+    registry
+}
+
 async fn start_api_server(state: Arc<AppState>) {
     let app = build_router(state.clone());
 
@@ -249,7 +300,17 @@ mod integration_tests {
     use tower::ServiceExt;
     use uuid::Uuid;
 
+    fn test_storage() -> Arc<dyn notifico_core::storage::Storage> {
+        let root = std::env::temp_dir().join(format!("notifico-test-{}", Uuid::now_v7()));
+        Arc::new(storage::FilesystemStorage::new(root))
+    }
+
     async fn setup_app() -> (Router, String) {
+        let (state, api_key) = setup_app_state().await;
+        (build_router(state), api_key)
+    }
+
+    async fn setup_app_state() -> (Arc<AppState>, String) {
         let db = notifico_db::connect("sqlite::memory:").await.unwrap();
         notifico_db::run_migrations(&db).await.unwrap();
 
@@ -328,9 +389,12 @@ mod integration_tests {
             encryption_key: None,
             metrics_handle: None,
             rate_limiter: rate_limit::RateLimiter::new(1000, 60),
+            storage: test_storage(),
+            template_cache: Default::default(),
+            data_schema_cache: Default::default(),
         });
 
-        (build_router(state), raw_key.to_string())
+        (state, raw_key.to_string())
     }
 
     #[tokio::test]
@@ -363,7 +427,225 @@ mod integration_tests {
         assert_eq!(json["task_ids"].as_array().unwrap().len(), 1);
     }
 
+    #[tokio::test]
+    async fn ingest_with_uploaded_and_inline_attachments() {
+        use base64::engine::{Engine, general_purpose::STANDARD};
+
+        let (state, api_key) = setup_app_state().await;
+        let app = build_router(state.clone());
+
+        // Upload first
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/attachments?filename=invoice.pdf")
+            .header("content-type", "application/pdf")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(&b"%PDF-1.4 invoice"[..]))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let uploaded = json_body(resp).await;
+        assert_eq!(uploaded["content_type"], "application/pdf");
+        assert_eq!(uploaded["size"], 16);
+
+        // Reference it and add an inline image by content
+        let body = serde_json::json!({
+            "event": "order.confirmed",
+            "recipients": [{"id": "user-123", "contacts": {"email": "test@example.com"}}],
+            "data": {"order_id": 42, "name": "Alice"},
+            "attachments": [
+                {"id": uploaded["id"]},
+                {"filename": "logo.png", "content": STANDARD.encode(b"PNG"), "content_id": "logo"}
+            ]
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/events")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["accepted"], 1);
+
+        // The task carries references, which the worker resolves from storage
+        let tasks = notifico_db::repo::queue::claim_pending(&state.db, 10)
+            .await
+            .unwrap();
+        let task = worker::task_row_to_delivery_task(&tasks[0]);
+        assert_eq!(task.attachments.len(), 2);
+        assert_eq!(task.attachments[0].filename, "invoice.pdf");
+        assert_eq!(task.attachments[1].content_type, "image/png");
+        assert_eq!(task.attachments[1].content_id.as_deref(), Some("logo"));
+
+        let loaded = attachments::load_attachments(
+            &state.db,
+            state.storage.as_ref(),
+            task.project_id,
+            &task.attachments,
+        )
+        .await
+        .unwrap();
+        assert_eq!(loaded[0].data, b"%PDF-1.4 invoice");
+        assert_eq!(loaded[1].data, b"PNG");
+        assert!(matches!(
+            loaded[1].disposition,
+            notifico_core::transport::AttachmentDisposition::Inline
+        ));
+
+        // Inline content past axum's default 2 MB body limit is accepted
+        let body = serde_json::json!({
+            "event": "order.confirmed",
+            "recipients": [{"id": "user-123", "contacts": {"email": "test@example.com"}}],
+            "data": {"order_id": 43, "name": "Alice"},
+            "attachments": [{"filename": "scan.bin", "content": STANDARD.encode(vec![7u8; 3 << 20])}]
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/events")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Neither id nor content
+        let body = serde_json::json!({
+            "event": "order.confirmed",
+            "recipients": [{"id": "user-123"}],
+            "data": {},
+            "attachments": [{"filename": "empty.txt"}]
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/events")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn cloudevent_structured_mode_end_to_end() {
+        let (app, api_key) = setup_app().await;
+
+        let body = serde_json::json!({
+            "specversion": "1.0",
+            "id": "ce-123",
+            "source": "/orders",
+            "type": "order.confirmed",
+            "recipients": [{"id": "user-123", "contacts": {"email": "test@example.com"}}],
+            "data": {"order_id": 42, "name": "Alice"}
+        });
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/cloudevents")
+            .header("content-type", "application/cloudevents+json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json = json_body(resp).await;
+        assert_eq!(json["accepted"], 1);
+
+        let task_ids = json["task_ids"].clone();
+
+        // Same CloudEvent id is deduplicated via the idempotency key
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/cloudevents")
+            .header("content-type", "application/cloudevents+json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.headers()["idempotent-replayed"], "true");
+        let json = json_body(resp).await;
+        assert_eq!(json["task_ids"], task_ids);
+    }
+
+    #[tokio::test]
+    async fn ingest_duplicate_replays_original_response() {
+        let (app, api_key) = setup_app().await;
+
+        let body = serde_json::json!({
+            "event": "order.confirmed",
+            "recipients": [{"id": "user-123", "contacts": {"email": "test@example.com"}}],
+            "data": {"order_id": 42, "name": "Alice"},
+            "idempotency_key": "order-42"
+        });
+        let request = || {
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/events")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {api_key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get("idempotent-replayed").is_none());
+        let first = json_body(resp).await;
+        assert_eq!(first["accepted"], 1);
+
+        let resp = app.oneshot(request()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["idempotent-replayed"], "true");
+        assert_eq!(json_body(resp).await, first);
+    }
+
+    #[tokio::test]
+    async fn cloudevent_binary_mode_end_to_end() {
+        let (app, api_key) = setup_app().await;
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/cloudevents")
+            .header("content-type", "application/json")
+            .header("ce-specversion", "1.0")
+            .header("ce-id", "ce-456")
+            .header("ce-source", "/orders")
+            .header("ce-type", "order.confirmed")
+            .header(
+                "ce-recipients",
+                r#"[{"id":"user-9","contacts":{"email":"nine@example.com"}}]"#,
+            )
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(r#"{"order_id": 7, "name": "Bob"}"#))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json = json_body(resp).await;
+        assert_eq!(json["accepted"], 1);
+
+        // Missing ce-type is rejected
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/cloudevents")
+            .header("content-type", "application/json")
+            .header("ce-specversion", "1.0")
+            .header("ce-id", "ce-789")
+            .header("ce-source", "/orders")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from("{}"))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     async fn setup_admin_app() -> (Router, String) {
+        setup_admin_app_with(TransportRegistry::new()).await
+    }
+
+    async fn setup_admin_app_with(registry: TransportRegistry) -> (Router, String) {
         let db = notifico_db::connect("sqlite::memory:").await.unwrap();
         notifico_db::run_migrations(&db).await.unwrap();
 
@@ -390,16 +672,18 @@ mod integration_tests {
         .unwrap();
 
         let config = Config::load(None).unwrap();
-        let registry = TransportRegistry::new();
 
         let state = Arc::new(AppState {
             db,
             config,
             registry,
             middleware_registry: MiddlewareRegistry::new(),
-            encryption_key: None,
+            encryption_key: Some([0x42; 32]),
             metrics_handle: None,
             rate_limiter: rate_limit::RateLimiter::new(1000, 60),
+            storage: test_storage(),
+            template_cache: Default::default(),
+            data_schema_cache: Default::default(),
         });
 
         (build_router(state), raw_key.to_string())
@@ -466,6 +750,23 @@ mod integration_tests {
         let body = json_body(resp).await;
         assert_eq!(body["name"], "Updated");
         assert_eq!(body["default_locale"], "fr");
+        assert_eq!(body["locale_fallback"]["any"], true);
+
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/admin/api/v1/projects/{project_id}"))
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(
+                r#"{"name":"Updated","default_locale":"pt-BR","locale_fallback":{"overrides":{"es":["pt"]},"any":false}}"#,
+            ))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        assert_eq!(body["default_locale"], "pt-BR");
+        assert_eq!(body["locale_fallback"]["overrides"]["es"][0], "pt");
+        assert_eq!(body["locale_fallback"]["any"], false);
 
         // Delete project
         let req = Request::builder()
@@ -780,6 +1081,28 @@ mod integration_tests {
         let body = json_body(resp).await;
         assert_eq!(body["unsubscribed"], false);
 
+        // RFC 8058 one-click POST: token in the query, form body
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/public/unsubscribe?token=invalid_token")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from("List-Unsubscribe=One-Click"))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        assert_eq!(body["unsubscribed"], false);
+
+        // One-click POST without a token
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/public/unsubscribe")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from("List-Unsubscribe=One-Click"))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
         // Unsubscribe with an invalid token (GET)
         let req = Request::builder()
             .uri("/api/v1/public/unsubscribe?token=invalid_token")
@@ -828,6 +1151,113 @@ mod integration_tests {
         assert_eq!(body["db"], "connected");
     }
 
+    #[tokio::test]
+    async fn locale_fallback_chain_in_ingest_and_broadcast() {
+        let (state, api_key) = setup_app_state().await;
+        let db = state.db.clone();
+        let app = build_router(state);
+
+        // Portuguese content next to the seeded English one, plus recipients
+        // with regional locales.
+        db.execute_unprepared(
+            r#"INSERT INTO template_content (id, template_version_id, locale, body)
+               SELECT 'pt-content', id, 'pt', '{"subject": "Pedido #{{ order_id }}", "text": "Olá {{ name }}"}'
+               FROM template_version"#,
+        )
+        .await
+        .unwrap();
+        db.execute_unprepared(
+            "UPDATE project SET settings = '{\"locale_fallback\": {\"overrides\": {\"es\": [\"pt\"]}}}'",
+        )
+        .await
+        .unwrap();
+        for (user, locale) in [("user-br", "pt-BR"), ("user-mx", "es-MX"), ("user-jp", "ja-JP")] {
+            db.execute_unprepared(&format!(
+                "INSERT INTO recipient (id, project_id, external_id, locale) \
+                 SELECT '{}', id, '{user}', '{locale}' FROM project",
+                Uuid::now_v7()
+            ))
+            .await
+            .unwrap();
+        }
+
+        let subjects = || {
+            let db = db.clone();
+            async move {
+                let rows = db
+                    .query_all_raw(sea_orm::Statement::from_string(
+                        db.get_database_backend(),
+                        "SELECT r.external_id, t.rendered_body FROM delivery_task t \
+                         JOIN recipient r ON r.id = t.recipient_id ORDER BY t.created_at",
+                    ))
+                    .await
+                    .unwrap();
+                rows.into_iter()
+                    .map(|row| {
+                        let user: String = row.try_get("", "external_id").unwrap();
+                        let body: String = row.try_get("", "rendered_body").unwrap();
+                        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+                        (user, body["subject"].as_str().unwrap().to_string())
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let body = serde_json::json!({
+            "event": "order.confirmed",
+            "recipients": [
+                {"id": "user-br", "contacts": {"email": "br@example.com"}},
+                {"id": "user-mx", "contacts": {"email": "mx@example.com"}},
+                {"id": "user-jp", "contacts": {"email": "jp@example.com"}}
+            ],
+            "data": {"order_id": 42, "name": "Ana"}
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/events")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(json_body(resp).await["accepted"], 3);
+
+        // pt-BR -> pt; es-MX -> es -> (override) pt; ja-JP -> project default en
+        assert_eq!(
+            subjects().await,
+            vec![
+                ("user-br".to_string(), "Pedido #42".to_string()),
+                ("user-mx".to_string(), "Pedido #42".to_string()),
+                ("user-jp".to_string(), "Order #42".to_string()),
+            ]
+        );
+
+        db.execute_unprepared("DELETE FROM delivery_task").await.unwrap();
+        let body = serde_json::json!({
+            "event": "order.confirmed",
+            "data": {"order_id": 7},
+            "recipients": ["user-br", "user-jp"]
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/broadcasts")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(json_body(resp).await["task_count"], 2);
+        let mut broadcast = subjects().await;
+        broadcast.sort();
+        assert_eq!(
+            broadcast,
+            vec![
+                ("user-br".to_string(), "Pedido #7".to_string()),
+                ("user-jp".to_string(), "Order #7".to_string()),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn broadcast_sends_to_all_recipients() {
         let (app, api_key) = setup_app().await;
@@ -873,6 +1303,71 @@ mod integration_tests {
         assert!(body["task_count"].as_u64().unwrap() >= 2);
     }
 
+    #[tokio::test]
+    async fn ingest_and_broadcast_enforce_event_data_schema() {
+        let (state, api_key) = setup_app_state().await;
+        state
+            .db
+            .execute_unprepared(
+                r#"UPDATE event SET data_schema = '{"type": "object", "required": ["order_id"], "properties": {"order_id": {"type": "integer"}}}'"#,
+            )
+            .await
+            .unwrap();
+        let app = build_router(state);
+        let request = |uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {api_key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "/api/v1/events",
+                serde_json::json!({
+                    "event": "order.confirmed",
+                    "recipients": [{"id": "u-1", "contacts": {"email": "u1@example.com"}}],
+                    "data": {"orderId": 1}
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = json_body(resp).await;
+        assert_eq!(body["error"], "Event data does not match the event schema");
+        assert_eq!(body["errors"][0]["pointer"], "");
+        assert!(body["errors"][0]["message"].as_str().unwrap().contains("order_id"));
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "/api/v1/broadcasts",
+                serde_json::json!({"event": "order.confirmed", "data": {"order_id": "42"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(resp).await["errors"][0]["pointer"], "/order_id");
+
+        let resp = app
+            .oneshot(request(
+                "/api/v1/events",
+                serde_json::json!({
+                    "event": "order.confirmed",
+                    "recipients": [{"id": "u-1", "contacts": {"email": "u1@example.com"}}],
+                    "data": {"order_id": 1}
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["accepted"], 1);
+    }
+
     #[tokio::test]
     async fn openapi_spec_is_valid() {
         let (app, _) = setup_app().await;
@@ -940,39 +1435,1725 @@ mod integration_tests {
         let body = json_body(resp).await;
         assert_eq!(body["rendered"]["subject"], "Hello Alice");
         assert_eq!(body["rendered"]["text"], "Order #42 confirmed");
-    }
+        assert_eq!(body["locale"], "en");
 
-    #[tokio::test]
-    async fn event_stats_returns_delivery_counts() {
-        let (app, key) = setup_admin_app().await;
-
-        // Create event
+        // Regional locale falls back through the chain
         let req = Request::builder()
             .method("POST")
-            .uri("/admin/api/v1/events")
+            .uri(format!("/admin/api/v1/templates/{template_id}/preview"))
             .header("content-type", "application/json")
             .header("authorization", format!("Bearer {key}"))
-            .body(Body::from(
-                r#"{"name":"stats.test","category":"transactional"}"#,
-            ))
+            .body(Body::from(r#"{"locale":"en-GB","data":{"name":"Bob","order_id":1}}"#))
             .unwrap();
         let resp = app.clone().oneshot(req).await.unwrap();
-        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.status(), StatusCode::OK);
         let body = json_body(resp).await;
-        let event_id = body["id"].as_str().unwrap().to_string();
+        assert_eq!(body["locale"], "en");
+        assert_eq!(body["rendered"]["subject"], "Hello Bob");
+    }
 
-        // Get stats (should be empty initially)
-        let req = Request::builder()
+    #[tokio::test]
+    async fn strict_render_mode_rejects_undefined_variables_in_preview() {
+        let (app, key) = setup_admin_app().await;
+
+        let req = Request::builder()
+            .uri("/admin/api/v1/projects")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let body = json_body(resp).await;
+        let project_id = body[0]["id"].as_str().unwrap().to_string();
+        assert_eq!(body[0]["render"]["strict_undefined"], false);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/admin/api/v1/templates")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(r#"{"name":"strict_tpl","channel":"email"}"#))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let template_id = json_body(resp).await["id"].as_str().unwrap().to_string();
+
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/admin/api/v1/templates/{template_id}/content/en"))
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(
+                r#"{"body":{"subject":"Hi {{ name }}","text":"Order {{ order_id }}"}}"#,
+            ))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let preview = || {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/api/v1/templates/{template_id}/preview"))
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(Body::from(r#"{"locale":"en","data":{"order_id":42}}"#))
+                .unwrap()
+        };
+
+        // Lenient by default: missing values render as empty
+        let resp = app.clone().oneshot(preview()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["rendered"]["subject"], "Hi ");
+
+        // Bad limits are rejected when saved rather than ignored at render time
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/admin/api/v1/projects/{project_id}"))
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(
+                r#"{"name":"test","default_locale":"en","render":{"strict_undefined":true,"fuel":0}}"#,
+            ))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(resp).await["message"], "fuel must be greater than 0");
+
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/admin/api/v1/projects/{project_id}"))
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(
+                r#"{"name":"test","default_locale":"en","render":{"strict_undefined":true,"fuel":5000}}"#,
+            ))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        assert_eq!(body["render"]["strict_undefined"], true);
+        assert_eq!(body["render"]["fuel"], 5000);
+        assert_eq!(body["render"]["max_recursion"], 100);
+        assert_eq!(body["locale_fallback"]["any"], true);
+
+        let resp = app.clone().oneshot(preview()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let message = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(message.contains("Undefined variable: name"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn fixtures_render_all_locales_and_snapshot_tests() {
+        let (app, key) = setup_admin_app().await;
+        let request = |method: &str, uri: String, body: Option<serde_json::Value>| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(body.map(|b| Body::from(b.to_string())).unwrap_or_default())
+                .unwrap()
+        };
+        let send = |method: &str, uri: String, body: Option<serde_json::Value>| {
+            let app = app.clone();
+            let req = request(method, uri, body);
+            async move {
+                let resp = app.oneshot(req).await.unwrap();
+                let status = resp.status();
+                let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+                (status, serde_json::from_slice::<serde_json::Value>(&bytes).unwrap_or_default())
+            }
+        };
+
+        let (_, template) = send(
+            "POST",
+            "/admin/api/v1/templates".into(),
+            Some(serde_json::json!({"name": "receipt", "channel": "email"})),
+        )
+        .await;
+        let template_id = template["id"].as_str().unwrap().to_string();
+        let uri = |path: &str| format!("/admin/api/v1/templates/{template_id}/{path}");
+        let content =
+            |subject: &str| serde_json::json!({"body": {"subject": subject, "text": "Hi {{ name }}"}});
+        let subjects = [("en", "Order {{ order_id }}"), ("de", "Bestellung {{ order_id }}")];
+        for (locale, subject) in subjects {
+            let resp = app
+                .clone()
+                .oneshot(request("PUT", uri(&format!("content/{locale}")), Some(content(subject))))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let (_, event) = send(
+            "POST",
+            "/admin/api/v1/events".into(),
+            Some(serde_json::json!({"name": "order.paid", "category": "transactional"})),
+        )
+        .await;
+        let event_id = event["id"].as_str().unwrap().to_string();
+        send(
+            "POST",
+            format!("/admin/api/v1/events/{event_id}/rules"),
+            Some(serde_json::json!({"channel": "email", "template_id": template_id})),
+        )
+        .await;
+
+        // Fixtures come from the template itself and from events using it
+        let (status, _) = send(
+            "POST",
+            uri("fixtures"),
+            Some(serde_json::json!({"name": "vip", "data": {"order_id": 7, "name": "Ana"}})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, basic) = send(
+            "POST",
+            format!("/admin/api/v1/events/{event_id}/fixtures"),
+            Some(serde_json::json!({"name": "basic", "data": {"order_id": 1, "name": "Bo"}})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = send(
+            "POST",
+            uri("fixtures"),
+            Some(serde_json::json!({"name": "vip"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, body) = send(
+            "POST",
+            uri("fixtures/render"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let results = body["results"].as_array().unwrap();
+        let pairs: Vec<_> = results
+            .iter()
+            .map(|r| (r["fixture"].as_str().unwrap(), r["locale"].as_str().unwrap()))
+            .collect();
+        assert_eq!(pairs, [("basic", "de"), ("basic", "en"), ("vip", "de"), ("vip", "en")]);
+        assert_eq!(results[0]["rendered"]["subject"], "Bestellung 1");
+
+        // Nothing recorded yet: everything is new, which does not fail
+        let (status, body) = send("POST", uri("test"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["results"].as_array().unwrap().iter().all(|r| r["status"] == "new"));
+
+        let (status, _) = send("PUT", uri("snapshots"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, snapshots) = send("GET", uri("snapshots"), None).await;
+        assert_eq!(snapshots.as_array().unwrap().len(), 4);
+        let (status, body) = send("POST", uri("test"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["passed"], true);
+
+        // A content edit that alters output fails the test with a diff
+        app.clone()
+            .oneshot(request(
+                "PUT",
+                uri("content/en"),
+                Some(content("Your order {{ order_id }}")),
+            ))
+            .await
+            .unwrap();
+        let (status, body) = send("POST", uri("test"), None).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["passed"], false);
+        let changed: Vec<_> = body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|r| r["status"] == "changed")
+            .collect();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed[0]["locale"], "en");
+        assert_eq!(changed[0]["fields"][0]["field"], "subject");
+        assert!(changed[0]["fields"][0]["diff"].as_str().unwrap().contains("+Your order 1"));
+
+        // Deleting a fixture drops its snapshots along with it
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "DELETE",
+                format!("/admin/api/v1/fixtures/{}", basic["id"].as_str().unwrap()),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let (_, snapshots) = send("GET", uri("snapshots"), None).await;
+        assert_eq!(snapshots.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn template_versions_draft_publish_rollback_and_diff() {
+        let (state, api_key) = setup_app_state().await;
+        let db = state.db.clone();
+        let app = build_router(state);
+
+        let row = db
+            .query_one_raw(sea_orm::Statement::from_string(
+                db.get_database_backend(),
+                "SELECT t.id AS template_id, t.project_id FROM template t",
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        let template_id: String = row.try_get("", "template_id").unwrap();
+        let project_id: String = row.try_get("", "project_id").unwrap();
+        let admin_key = "nk_live_versions_admin_key_1234";
+        notifico_db::repo::api_key::insert_api_key(
+            &db,
+            Uuid::now_v7(),
+            Uuid::parse_str(&project_id).unwrap(),
+            "Admin Key",
+            admin_key,
+            "admin",
+        )
+        .await
+        .unwrap();
+
+        let admin = |method: &str, uri: String, body: Option<serde_json::Value>| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {admin_key}"))
+                .body(body.map(|b| Body::from(b.to_string())).unwrap_or_default())
+                .unwrap()
+        };
+        let ingest = |key: &str| {
+            let body = serde_json::json!({
+                "event": "order.confirmed",
+                "recipients": [{"id": "user-1", "contacts": {"email": "a@example.com"}}],
+                "data": {"order_id": 42, "name": "Alice"},
+                "idempotency_key": key,
+            });
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/events")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {api_key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let task_version = |body: serde_json::Value| {
+            let db = db.clone();
+            async move {
+                let task_id = Uuid::parse_str(body["task_ids"][0].as_str().unwrap()).unwrap();
+                let task = notifico_db::repo::queue::get_task(&db, task_id)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                (task.template_version, task.rendered_body)
+            }
+        };
+
+        // Editing opens draft v2; v1 stays live
+        let resp = app
+            .clone()
+            .oneshot(admin(
+                "PUT",
+                format!("/admin/api/v1/templates/{template_id}/content/en"),
+                Some(serde_json::json!({"body": {"subject": "Your order {{ order_id }}", "text": "Hello {{ name }}"}})),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["version"], 2);
+
+        let resp = app.clone().oneshot(ingest("v-1")).await.unwrap();
+        let (version, rendered) = task_version(json_body(resp).await).await;
+        assert_eq!(version, Some(1));
+        assert_eq!(rendered["subject"], "Order #42");
+
+        // Publish v2; publishing it again conflicts
+        let publish = format!("/admin/api/v1/templates/{template_id}/versions/2/publish");
+        let resp = app
+            .clone()
+            .oneshot(admin("POST", publish.clone(), None))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["status"], "current");
+        let resp = app.clone().oneshot(admin("POST", publish, None)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let resp = app.clone().oneshot(ingest("v-2")).await.unwrap();
+        let (version, rendered) = task_version(json_body(resp).await).await;
+        assert_eq!(version, Some(2));
+        assert_eq!(rendered["subject"], "Your order 42");
+
+        // Roll back to v1; the current version cannot be rolled back to
+        let resp = app
+            .clone()
+            .oneshot(admin(
+                "POST",
+                format!("/admin/api/v1/templates/{template_id}/versions/1/rollback"),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .clone()
+            .oneshot(admin(
+                "POST",
+                format!("/admin/api/v1/templates/{template_id}/versions/1/rollback"),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let resp = app
+            .clone()
+            .oneshot(admin(
+                "GET",
+                format!("/admin/api/v1/templates/{template_id}/versions"),
+                None,
+            ))
+            .await
+            .unwrap();
+        let versions = json_body(resp).await;
+        assert_eq!(versions[0]["version"], 2);
+        assert_eq!(versions[0]["status"], "archived");
+        assert_eq!(versions[1]["status"], "current");
+
+        // Diff only reports the changed field
+        let resp = app
+            .oneshot(admin(
+                "GET",
+                format!("/admin/api/v1/templates/{template_id}/diff?from=1&to=2"),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let diff = json_body(resp).await;
+        let locale = &diff["locales"][0];
+        assert_eq!(locale["locale"], "en");
+        assert_eq!(locale["change"], "changed");
+        assert_eq!(locale["fields"].as_array().unwrap().len(), 1);
+        assert_eq!(locale["fields"][0]["field"], "subject");
+        let text = locale["fields"][0]["diff"].as_str().unwrap();
+        assert!(text.contains("-Order #{{ order_id }}"));
+        assert!(text.contains("+Your order {{ order_id }}"));
+    }
+
+    #[tokio::test]
+    async fn partials_crud_cycle_check_and_preview() {
+        let (app, key) = setup_admin_app().await;
+        let request = |method: &str, uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/partials".into(),
+                serde_json::json!({
+                    "name": "base_email",
+                    "kind": "layout",
+                    "body": "<html>{% block content %}{% endblock %}{% include \"footer\" %}</html>"
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/partials".into(),
+                serde_json::json!({"name": "footer", "body": "<p>Thanks, {{ name }}</p>"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let footer = json_body(resp).await;
+        assert_eq!(footer["kind"], "partial");
+        let footer_id = footer["id"].as_str().unwrap().to_string();
+
+        // Duplicate names conflict
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/partials".into(),
+                serde_json::json!({"name": "footer", "body": "x"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        // footer -> base_email -> footer is a cycle
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/partials/{footer_id}"),
+                serde_json::json!({"name": "footer", "body": "{% extends \"base_email\" %}"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("base_email -> footer -> base_email"));
+
+        // A template extending the layout renders with it in preview
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/templates".into(),
+                serde_json::json!({"name": "welcome", "channel": "email"}),
+            ))
+            .await
+            .unwrap();
+        let template_id = json_body(resp).await["id"].as_str().unwrap().to_string();
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{template_id}/content/en"),
+                serde_json::json!({"body": {
+                    "subject": "Welcome",
+                    "html": "{% extends \"base_email\" %}{% block content %}<h1>Hi {{ name }}</h1>{% endblock %}"
+                }}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                format!("/admin/api/v1/templates/{template_id}/preview"),
+                serde_json::json!({"locale": "en", "data": {"name": "Ann"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp).await["rendered"]["html"],
+            "<html><h1>Hi Ann</h1><p>Thanks, Ann</p></html>"
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/api/v1/partials".into(), serde_json::json!(null)))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp).await.as_array().unwrap().len(), 2);
+
+        let resp = app
+            .oneshot(request(
+                "DELETE",
+                format!("/admin/api/v1/partials/{footer_id}"),
+                serde_json::json!(null),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn translation_catalogs_and_localized_preview() {
+        let (app, key) = setup_admin_app().await;
+        let request = |method: &str, uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        // ICU syntax errors are reported per key
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/admin/api/v1/translations/de".into(),
+                serde_json::json!({"cart": {"items": "{n, plural, one {# Artikel}"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(resp).await["errors"][0]["key"], "cart.items");
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/admin/api/v1/translations/de".into(),
+                serde_json::json!({
+                    "greeting": "Hallo {name}!",
+                    "cart": {"items": "{n, plural, one {# Artikel} other {# Artikel}}"}
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/admin/api/v1/translations/en_us".into(),
+                serde_json::json!({"greeting": "Hi {name}!"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp).await["locale"], "en-US");
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/api/v1/translations".into(), serde_json::json!(null)))
+            .await
+            .unwrap();
+        let list = json_body(resp).await;
+        assert_eq!(list[0]["locale"], "de");
+        assert_eq!(list[0]["keys"], 2);
+        assert_eq!(list[1]["locale"], "en-US");
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/templates".into(),
+                serde_json::json!({"name": "receipt", "channel": "email"}),
+            ))
+            .await
+            .unwrap();
+        let template_id = json_body(resp).await["id"].as_str().unwrap().to_string();
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{template_id}/content/en"),
+                serde_json::json!({"body": {
+                    "subject": "{{ t('greeting', name=name) }}",
+                    "text": "{{ t('cart.items', n=count) }}: {{ total | currency('EUR') }}, {{ paid_at | datetime('short') }}"
+                }}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                format!("/admin/api/v1/templates/{template_id}/preview"),
+                serde_json::json!({
+                    "locale": "de-AT",
+                    "timezone": "+01:00",
+                    "data": {"name": "Jan", "count": 3, "total": 1234.5, "paid_at": "2026-01-05T14:04:05Z"}
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let rendered = json_body(resp).await["rendered"].clone();
+        assert_eq!(rendered["subject"], "Hallo Jan!");
+        assert_eq!(
+            rendered["text"],
+            "3 Artikel: €\u{a0}1\u{a0}234,50, 05.01.26, 15:04"
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "DELETE",
+                "/admin/api/v1/translations/de".into(),
+                serde_json::json!(null),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = app
+            .oneshot(request("GET", "/admin/api/v1/translations/de".into(), serde_json::json!(null)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn ingest_renders_in_recipient_locale_and_timezone() {
+        let (state, api_key) = setup_app_state().await;
+        let db = state.db.clone();
+        let app = build_router(state);
+
+        db.execute_unprepared(
+            r#"UPDATE template_content SET body = '{"subject": "{{ t(''subject'') }}", "text": "{{ at | datetime }}"}'"#,
+        )
+        .await
+        .unwrap();
+        let project_id: String = db
+            .query_one_raw(sea_orm::Statement::from_string(
+                db.get_database_backend(),
+                "SELECT id FROM project",
+            ))
+            .await
+            .unwrap()
+            .unwrap()
+            .try_get("", "id")
+            .unwrap();
+        let project_id = Uuid::parse_str(&project_id).unwrap();
+        notifico_db::repo::translation::upsert_catalog(
+            &db,
+            project_id,
+            "fr",
+            &serde_json::json!({"subject": "Commande confirmée"}),
+        )
+        .await
+        .unwrap();
+        db.execute_unprepared(&format!(
+            "INSERT INTO recipient (id, project_id, external_id, locale, timezone) \
+             VALUES ('{}', '{project_id}', 'user-fr', 'fr-CA', '-05:00')",
+            Uuid::now_v7()
+        ))
+        .await
+        .unwrap();
+
+        let body = serde_json::json!({
+            "event": "order.confirmed",
+            "recipients": [{"id": "user-fr", "contacts": {"email": "fr@example.com"}}],
+            "data": {"at": "2026-01-05T14:04:05Z"}
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/events")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let task_id = json_body(resp).await["task_ids"][0]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let task = notifico_db::repo::queue::get_task(&db, Uuid::parse_str(&task_id).unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.rendered_body["subject"], "Commande confirmée");
+        // Canadian French times use "h", "min" and "s"
+        assert_eq!(task.rendered_body["text"], "5 janv. 2026, 09 h 04 min 05 s");
+    }
+
+    #[tokio::test]
+    async fn template_content_is_validated_against_channel_schema() {
+        let mut registry = TransportRegistry::new();
+        registry.register(Arc::new(EmailTransport::new()));
+        let (app, key) = setup_admin_app_with(registry).await;
+        let request = |method: &str, uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let mut template_ids = Vec::new();
+        for channel in ["email", "carrier-pigeon"] {
+            let resp = app
+                .clone()
+                .oneshot(request(
+                    "POST",
+                    "/admin/api/v1/templates".into(),
+                    serde_json::json!({"name": channel, "channel": channel}),
+                ))
+                .await
+                .unwrap();
+            template_ids.push(json_body(resp).await["id"].as_str().unwrap().to_string());
+        }
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{}/content/en", template_ids[0]),
+                serde_json::json!({"body": {"subject": "Hi {{ name }", "html": ["x"]}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = json_body(resp).await;
+        assert_eq!(body["error"], "Invalid template content");
+        let errors: Vec<(String, String)> = body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                (
+                    e["field"].as_str().unwrap().to_string(),
+                    e["code"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("text".to_string(), "required".to_string()),
+                ("html".to_string(), "invalid_type".to_string()),
+                ("subject".to_string(), "syntax".to_string()),
+            ]
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{}/content/en", template_ids[0]),
+                serde_json::json!({"body": {"subject": "Hi {{ name }}", "text": "Hello"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Unknown channels still get the syntax check
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{}/content/en", template_ids[1]),
+                serde_json::json!({"body": {"anything": "{% if %}"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let resp = app
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{}/content/en", template_ids[1]),
+                serde_json::json!({"body": {"anything": "goes"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn mjml_and_markdown_templates_compile_in_preview() {
+        let mut registry = TransportRegistry::new();
+        registry.register(Arc::new(EmailTransport::new()));
+        registry.register(Arc::new(TwilioSmsTransport::new()));
+        let (app, key) = setup_admin_app_with(registry).await;
+        let request = |method: &str, uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let text = |resp: axum::response::Response| async {
+            let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+
+        // Compiled formats need a channel that takes HTML
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/templates".into(),
+                serde_json::json!({"name": "sms", "channel": "sms", "format": "markdown"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/templates".into(),
+                serde_json::json!({"name": "x", "channel": "email", "format": "docx"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/templates".into(),
+                serde_json::json!({"name": "welcome", "channel": "email", "format": "markdown"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = json_body(resp).await;
+        assert_eq!(body["format"], "markdown");
+        let template_id = body["id"].as_str().unwrap().to_string();
+
+        // The source field is required instead of text
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{template_id}/content/en"),
+                serde_json::json!({"body": {"subject": "Hi"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(resp).await["errors"][0]["field"], "markdown");
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{template_id}/content/en"),
+                serde_json::json!({"body": {
+                    "subject": "Hi {{ name }}",
+                    "markdown": "# Welcome, {{ name }}\n\n[Start](https://example.com)",
+                    "mjml": "<mjml><mj-body><mj-section><mj-column>\n<mj-text>Hi {{ name }}</mj-text>\n</mj-column></mj-section></mj-body></mjml>"
+                }}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let preview = || {
+            request(
+                "POST",
+                format!("/admin/api/v1/templates/{template_id}/preview"),
+                serde_json::json!({"locale": "en", "data": {"name": "Ann"}}),
+            )
+        };
+        let resp = app.clone().oneshot(preview()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let rendered = json_body(resp).await["rendered"].clone();
+        assert!(rendered.get("markdown").is_none());
+        assert!(rendered["html"].as_str().unwrap().contains("<h1>Welcome, Ann</h1>"));
+        assert!(rendered["text"].as_str().unwrap().contains("Welcome, Ann"));
+
+        // Switch the same content to MJML
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{template_id}"),
+                serde_json::json!({"name": "welcome", "format": "mjml"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["format"], "mjml");
+        let resp = app.clone().oneshot(preview()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let rendered = json_body(resp).await["rendered"].clone();
+        let html = rendered["html"].as_str().unwrap();
+        assert!(html.contains("mj-column-per-100"));
+        assert!(html.contains(">Hi Ann</div>"));
+        assert_eq!(rendered["text"], "Hi Ann");
+
+        // Compile errors name the format and line
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{template_id}/content/en"),
+                serde_json::json!({"body": {
+                    "subject": "Hi",
+                    "mjml": "<mjml><mj-body>\n<mj-section>\n<mj-txt>loose</mj-txt>\n</mj-section></mj-body></mjml>"
+                }}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app.clone().oneshot(preview()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            text(resp).await,
+            "Compile error: mjml line 3: Unknown element <mj-txt>"
+        );
+    }
+
+    #[tokio::test]
+    async fn event_data_schema_warns_about_undeclared_template_variables() {
+        let (app, key) = setup_admin_app().await;
+        let request = |method: &str, uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/events".into(),
+                serde_json::json!({
+                    "name": "order.created",
+                    "category": "transactional",
+                    "data_schema": {"type": "nope"}
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {"order_id": {"type": "integer"}, "customer": {"type": "object"}}
+        });
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/events".into(),
+                serde_json::json!({
+                    "name": "order.created",
+                    "category": "transactional",
+                    "data_schema": schema
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let event_id = json_body(resp).await["id"].as_str().unwrap().to_string();
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/templates".into(),
+                serde_json::json!({"name": "order", "channel": "email"}),
+            ))
+            .await
+            .unwrap();
+        let template_id = json_body(resp).await["id"].as_str().unwrap().to_string();
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                format!("/admin/api/v1/events/{event_id}/rules"),
+                serde_json::json!({"channel": "email", "template_id": template_id}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/templates/{template_id}/content/en"),
+                serde_json::json!({"body": {
+                    "subject": "Order #{{ orderId }} for {{ customer.name }}",
+                    "text": "{{ order_id }}"
+                }}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        let warnings = body["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0]["event"], "order.created");
+        assert_eq!(warnings[0]["locale"], "en");
+        assert_eq!(warnings[0]["variable"], "orderId");
+
+        // Replacing the schema re-checks the event's templates
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/events/{event_id}/schema"),
+                serde_json::json!({"data_schema": {
+                    "type": "object",
+                    "properties": {"orderId": {}, "order_id": {}, "customer": {"properties": {}}}
+                }}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        assert_eq!(body["warnings"].as_array().unwrap().len(), 1);
+        assert_eq!(body["warnings"][0]["variable"], "customer.name");
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                format!("/admin/api/v1/events/{event_id}/schema"),
+                serde_json::json!({"data_schema": null}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(json_body(resp).await["warnings"].as_array().unwrap().is_empty());
+
+        let resp = app
+            .oneshot(request(
+                "GET",
+                format!("/admin/api/v1/events/{event_id}"),
+                serde_json::Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(json_body(resp).await["data_schema"].is_null());
+    }
+
+    #[tokio::test]
+    async fn event_stats_returns_delivery_counts() {
+        let (app, key) = setup_admin_app().await;
+
+        // Create event
+        let req = Request::builder()
+            .method("POST")
+            .uri("/admin/api/v1/events")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(
+                r#"{"name":"stats.test","category":"transactional"}"#,
+            ))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = json_body(resp).await;
+        let event_id = body["id"].as_str().unwrap().to_string();
+
+        // Get stats (should be empty initially)
+        let req = Request::builder()
             .uri(format!("/admin/api/v1/events/{event_id}/stats"))
             .header("authorization", format!("Bearer {key}"))
             .body(Body::empty())
             .unwrap();
-        let resp = app.clone().oneshot(req).await.unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = json_body(resp).await;
+        assert_eq!(body["event_id"], event_id);
+        assert!(body["stats"].as_array().unwrap().is_empty());
+    }
+
+    /// Delivers every message except those of variant B, which fail.
+    struct VariantOutcomeTransport;
+
+    #[async_trait::async_trait]
+    impl notifico_core::transport::Transport for VariantOutcomeTransport {
+        fn channel_id(&self) -> notifico_core::channel::ChannelId {
+            notifico_core::channel::ChannelId::new("email")
+        }
+
+        fn display_name(&self) -> &str {
+            "Variant outcome"
+        }
+
+        fn content_schema(&self) -> notifico_core::transport::ContentSchema {
+            notifico_core::transport::ContentSchema { fields: vec![] }
+        }
+
+        fn credential_schema(&self) -> notifico_core::transport::CredentialSchema {
+            notifico_core::transport::CredentialSchema { fields: vec![] }
+        }
+
+        async fn send(
+            &self,
+            message: &notifico_core::transport::RenderedMessage,
+        ) -> Result<notifico_core::transport::DeliveryResult, notifico_core::error::CoreError>
+        {
+            if message.content["subject"] == "B" {
+                return Ok(notifico_core::transport::DeliveryResult::Failed {
+                    error: "mailbox full".into(),
+                    retryable: false,
+                });
+            }
+            Ok(notifico_core::transport::DeliveryResult::Delivered {
+                provider_message_id: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn rule_variants_split_recipients_and_report_stats() {
+        use notifico_db::repo::{admin, middleware, queue};
+
+        let (base, api_key) = setup_app_state().await;
+        let db = base.db.clone();
+        let key = [0x5A; 32];
+        let mut registry = TransportRegistry::new();
+        registry.register(Arc::new(VariantOutcomeTransport));
+        let mut middleware_registry = MiddlewareRegistry::new();
+        middleware_registry.register(Arc::new(OpenTrackingMiddleware::new(Some(key))));
+        middleware_registry.register(Arc::new(ClickTrackingMiddleware::new(Some(key))));
+        let state = Arc::new(AppState {
+            db: db.clone(),
+            config: base.config.clone(),
+            registry,
+            middleware_registry,
+            encryption_key: Some(key),
+            metrics_handle: None,
+            rate_limiter: rate_limit::RateLimiter::new(1000, 60),
+            storage: test_storage(),
+            template_cache: Default::default(),
+            data_schema_cache: Default::default(),
+        });
+
+        let project_id = admin::list_projects(&db).await.unwrap()[0].id;
+        let event = &admin::list_events(&db, project_id).await.unwrap()[0];
+        let rule = &admin::list_rules(&db, event.id).await.unwrap()[0];
+        let (template_a, template_b) = (rule.template_id, Uuid::now_v7());
+        let version_b = Uuid::now_v7();
+        let html = r#"<p><a href=\"https://example.com/offer\">Offer</a></p>"#;
+        db.execute_unprepared(&format!(
+            r#"UPDATE template_content SET body = '{{"subject": "A", "text": "Hi", "html": "{html}"}}'
+               WHERE template_version_id IN (SELECT id FROM template_version WHERE template_id = '{template_a}')"#
+        ))
+        .await
+        .unwrap();
+        for (name, priority) in [("open_tracking", 1), ("click_tracking", 2)] {
+            middleware::insert(
+                &db,
+                Uuid::now_v7(),
+                rule.id,
+                name,
+                &serde_json::json!({"base_url": "https://notifico.example.com"}),
+                priority,
+            )
+            .await
+            .unwrap();
+        }
+        db.execute_unprepared(&format!(
+            "INSERT INTO template (id, project_id, name, channel) \
+             VALUES ('{template_b}', '{project_id}', 'order_email_b', 'email')"
+        ))
+        .await
+        .unwrap();
+        db.execute_unprepared(&format!(
+            "INSERT INTO template_version (id, template_id, version, is_current) \
+             VALUES ('{version_b}', '{template_b}', 1, true)"
+        ))
+        .await
+        .unwrap();
+        db.execute_unprepared(&format!(
+            r#"INSERT INTO template_content (id, template_version_id, locale, body) VALUES ('{}', '{version_b}', 'en', '{{"subject": "B", "text": "Hi", "html": "{html}"}}')"#,
+            Uuid::now_v7()
+        ))
+        .await
+        .unwrap();
+        let admin_key = "nk_live_variants_admin_key_1234";
+        notifico_db::repo::api_key::insert_api_key(
+            &db,
+            Uuid::now_v7(),
+            project_id,
+            "Admin Key",
+            admin_key,
+            "admin",
+        )
+        .await
+        .unwrap();
+        let app = build_router(state.clone());
+        let request = |method: &str, uri: &str, key: &str, body: Option<serde_json::Value>| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+                .unwrap()
+        };
+        let variants_uri = format!("/admin/api/v1/rules/{}/variants", rule.id);
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                &variants_uri,
+                admin_key,
+                Some(serde_json::json!({"variants": [
+                    {"name": "A", "template_id": template_a, "weight": 1},
+                    {"name": "A", "template_id": template_b, "weight": 1}
+                ]})),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                &variants_uri,
+                admin_key,
+                Some(serde_json::json!({"experiment": "subject-test", "variants": [
+                    {"name": "A", "template_id": template_a, "weight": 1},
+                    {"name": "B", "template_id": template_b, "weight": 1}
+                ]})),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        assert_eq!(body["experiment"], "subject-test");
+        assert_eq!(body["variants"].as_array().unwrap().len(), 2);
+
+        let recipients: Vec<_> = (0..20)
+            .map(|i| {
+                serde_json::json!({
+                    "id": format!("ab-{i}"),
+                    "contacts": {"email": format!("ab-{i}@example.com")}
+                })
+            })
+            .collect();
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/v1/events",
+                &api_key,
+                Some(serde_json::json!({
+                    "event": "order.confirmed",
+                    "recipients": recipients,
+                    "data": {"order_id": 7, "name": "Ana"}
+                })),
+            ))
+            .await
+            .unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
 
+        // Each recipient gets the template of the variant its id hashes to
+        let tasks = queue::claim_pending(&db, 100).await.unwrap();
+        assert_eq!(tasks.len(), 20);
+        let mut per_variant = std::collections::HashMap::new();
+        for task in &tasks {
+            let variant = task.variant.as_deref().unwrap();
+            let expected =
+                notifico_core::experiment::assign("subject-test", task.recipient_id, &[1, 1]);
+            assert_eq!(Some(variant), expected.map(|i| ["A", "B"][i]));
+            assert_eq!(task.experiment.as_deref(), Some("subject-test"));
+            let subject = task.rendered_body["subject"].as_str().unwrap();
+            assert_eq!(subject == "B", variant == "B");
+            *per_variant.entry(variant.to_string()).or_insert(0) += 1;
+        }
+        assert!(per_variant["A"] > 0 && per_variant["B"] > 0);
+
+        // Deliver through the worker: A succeeds and every A recipient opens
+        // the message twice and clicks its link; B deliveries all fail
+        let tracking_path = |html: &str, kind: &str| {
+            let start = html.find(&format!("https://notifico.example.com/t/{kind}/")).unwrap();
+            let end = start + html[start..].find('"').unwrap();
+            html[start + "https://notifico.example.com".len()..end].to_string()
+        };
+        for task in &tasks {
+            let task = worker::task_row_to_delivery_task(task);
+            worker::process_delivery(
+                &task,
+                &state.registry,
+                &state.middleware_registry,
+                &db,
+                state.storage.as_ref(),
+                Some(&key),
+            )
+            .await
+            .unwrap();
+
+            let html = task.rendered_body["html"].as_str().unwrap();
+            let open = tracking_path(html, "open");
+            let click = tracking_path(html, "click");
+            if task.variant.as_deref() == Some("A") {
+                for path in [&open, &open, &click] {
+                    let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+                    let resp = app.clone().oneshot(req).await.unwrap();
+                    assert!(resp.status().is_success() || resp.status().is_redirection());
+                }
+            }
+        }
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", &format!("{variants_uri}/stats"), admin_key, None))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
         let body = json_body(resp).await;
-        assert_eq!(body["event_id"], event_id);
-        assert!(body["stats"].as_array().unwrap().is_empty());
+        assert_eq!(body["experiment"], "subject-test");
+        let a = &body["variants"][0];
+        assert_eq!(a["name"], "A");
+        assert_eq!(a["sent"], per_variant["A"]);
+        assert_eq!(a["opened"], per_variant["A"]);
+        assert_eq!(a["delivery_rate"], 1.0);
+        assert_eq!(a["open_rate"], 1.0);
+        assert_eq!(a["click_rate"], 1.0);
+        let b = &body["variants"][1];
+        assert_eq!(b["sent"], per_variant["B"]);
+        assert_eq!(b["delivered"], 0);
+        assert_eq!(b["delivery_rate"], 0.0);
+        assert_eq!(b["open_rate"], 0.0);
+
+        // Clearing the variants returns the rule to its own template
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                &variants_uri,
+                admin_key,
+                Some(serde_json::json!({"variants": []})),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        assert!(body["experiment"].is_null());
+        assert!(body["variants"].as_array().unwrap().is_empty());
+    }
+
+    /// Echoes the `account` field of whichever credential the worker resolved
+    /// back as the provider message id.
+    struct CredentialEchoTransport;
+
+    #[async_trait::async_trait]
+    impl notifico_core::transport::Transport for CredentialEchoTransport {
+        fn channel_id(&self) -> notifico_core::channel::ChannelId {
+            notifico_core::channel::ChannelId::new("email")
+        }
+
+        fn display_name(&self) -> &str {
+            "Credential echo"
+        }
+
+        fn content_schema(&self) -> notifico_core::transport::ContentSchema {
+            notifico_core::transport::ContentSchema { fields: vec![] }
+        }
+
+        fn credential_schema(&self) -> notifico_core::transport::CredentialSchema {
+            notifico_core::transport::CredentialSchema { fields: vec![] }
+        }
+
+        async fn send(
+            &self,
+            message: &notifico_core::transport::RenderedMessage,
+        ) -> Result<notifico_core::transport::DeliveryResult, notifico_core::error::CoreError>
+        {
+            Ok(notifico_core::transport::DeliveryResult::Delivered {
+                provider_message_id: message.credentials["account"].as_str().map(String::from),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn rules_send_through_named_or_default_credential() {
+        use notifico_db::repo::{admin, credential, delivery_log, queue};
+
+        let (state, api_key) = setup_app_state().await;
+        let db = state.db.clone();
+        let project_id = admin::list_projects(&db).await.unwrap()[0].id;
+        let event = &admin::list_events(&db, project_id).await.unwrap()[0];
+        let rule = &admin::list_rules(&db, event.id).await.unwrap()[0];
+        let key: [u8; 32] = [0xAB; 32];
+        let mut credential_ids = Vec::new();
+        for name in ["transactional", "marketing"] {
+            let id = Uuid::now_v7();
+            credential::insert_credential(
+                &db,
+                id,
+                project_id,
+                name,
+                "email",
+                &serde_json::json!({"account": name}),
+                &key,
+            )
+            .await
+            .unwrap();
+            credential_ids.push(id);
+        }
+        credential::set_default(&db, project_id, credential_ids[0])
+            .await
+            .unwrap();
+        let admin_key = "nk_live_credentials_admin_key_1234";
+        notifico_db::repo::api_key::insert_api_key(
+            &db,
+            Uuid::now_v7(),
+            project_id,
+            "Admin Key",
+            admin_key,
+            "admin",
+        )
+        .await
+        .unwrap();
+        let app = build_router(state.clone());
+        let request = |method: &str, uri: &str, key: &str, body: Option<serde_json::Value>| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/api/v1/credentials", admin_key, None))
+            .await
+            .unwrap();
+        let body = json_body(resp).await;
+        assert_eq!(body[0]["name"], "marketing");
+        assert_eq!(body[0]["is_default"], false);
+        assert_eq!(body[1]["is_default"], true);
+
+        // Rules may only name a credential that exists for their channel
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                &format!("/admin/api/v1/events/{}/rules", event.id),
+                admin_key,
+                Some(serde_json::json!({
+                    "channel": "sms",
+                    "template_id": rule.template_id,
+                    "credential": "marketing"
+                })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            json_body(resp).await["message"],
+            "No credential named 'marketing' for channel 'sms'"
+        );
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                &format!("/admin/api/v1/rules/{}", rule.id),
+                admin_key,
+                Some(serde_json::json!({
+                    "channel": "email",
+                    "template_id": rule.template_id,
+                    "credential": "support"
+                })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Point the rule at the marketing account
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                &format!("/admin/api/v1/rules/{}", rule.id),
+                admin_key,
+                Some(serde_json::json!({
+                    "channel": "email",
+                    "template_id": rule.template_id,
+                    "priority": rule.priority,
+                    "credential": "marketing"
+                })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "GET",
+                &format!("/admin/api/v1/events/{}/rules", event.id),
+                admin_key,
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp).await[0]["credential"], "marketing");
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/v1/events",
+                &api_key,
+                Some(serde_json::json!({
+                    "event": "order.confirmed",
+                    "recipients": [{"id": "user-1", "contacts": {"email": "a@example.com"}}],
+                    "data": {"order_id": 1, "name": "Ana"}
+                })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let tasks = queue::claim_pending(&db, 10).await.unwrap();
+        let mut task = worker::task_row_to_delivery_task(&tasks[0]);
+        assert_eq!(task.credential.as_deref(), Some("marketing"));
+
+        let mut registry = TransportRegistry::new();
+        registry.register(Arc::new(CredentialEchoTransport));
+        let middleware = MiddlewareRegistry::new();
+        let deliver = async |task: &notifico_queue::DeliveryTask| {
+            worker::process_delivery(
+                task,
+                &registry,
+                &middleware,
+                &db,
+                state.storage.as_ref(),
+                Some(&key),
+            )
+            .await
+        };
+        let last_log = async || {
+            let logs = delivery_log::list_logs(&db, project_id, None, None, 10, 0)
+                .await
+                .unwrap();
+            let log = logs.into_iter().max_by_key(|l| l.id).unwrap();
+            (log.credential, log.provider_message_id)
+        };
+
+        deliver(&task).await.unwrap();
+        let used = Some("marketing".to_string());
+        assert_eq!(last_log().await, (used.clone(), used));
+
+        // Without a name the project default is used, and the default can move
+        task.credential = None;
+        deliver(&task).await.unwrap();
+        let used = Some("transactional".to_string());
+        assert_eq!(last_log().await, (used.clone(), used));
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                &format!("/admin/api/v1/credentials/{}/default", credential_ids[1]),
+                admin_key,
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        deliver(&task).await.unwrap();
+        let used = Some("marketing".to_string());
+        assert_eq!(last_log().await, (used.clone(), used));
+
+        // A missing named credential fails rather than falling back
+        task.credential = Some("support".into());
+        let err = deliver(&task).await.unwrap_err();
+        assert!(err.contains("'support'"), "{err}");
+
+        // So does one that cannot be read, leaving the task to be retried
+        task.credential = Some("marketing".into());
+        let err = worker::process_delivery(
+            &task,
+            &registry,
+            &middleware,
+            &db,
+            state.storage.as_ref(),
+            Some(&[0xCD; 32]),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.starts_with("Failed to resolve credential"), "{err}");
+    }
+
+    /// Email transport whose every send errors out.
+    struct ErroringTransport;
+
+    #[async_trait::async_trait]
+    impl notifico_core::transport::Transport for ErroringTransport {
+        fn channel_id(&self) -> notifico_core::channel::ChannelId {
+            notifico_core::channel::ChannelId::new("email")
+        }
+
+        fn display_name(&self) -> &str {
+            "Erroring"
+        }
+
+        fn content_schema(&self) -> notifico_core::transport::ContentSchema {
+            notifico_core::transport::ContentSchema { fields: vec![] }
+        }
+
+        fn credential_schema(&self) -> notifico_core::transport::CredentialSchema {
+            notifico_core::transport::CredentialSchema { fields: vec![] }
+        }
+
+        async fn send(
+            &self,
+            _message: &notifico_core::transport::RenderedMessage,
+        ) -> Result<notifico_core::transport::DeliveryResult, notifico_core::error::CoreError>
+        {
+            Err(notifico_core::error::CoreError::Transport(
+                "connection reset".into(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn transport_errors_are_final_only_on_last_attempt() {
+        use notifico_db::repo::{admin, delivery_log, queue};
+
+        let (state, api_key) = setup_app_state().await;
+        let db = state.db.clone();
+        let project_id = admin::list_projects(&db).await.unwrap()[0].id;
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/v1/events")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {api_key}"))
+            .body(Body::from(
+                serde_json::json!({
+                    "event": "order.confirmed",
+                    "recipients": [{"id": "user-1", "contacts": {"email": "a@example.com"}}],
+                    "data": {"order_id": 1, "name": "Ana"}
+                })
+                .to_string(),
+            ))
+            .unwrap();
+        let resp = build_router(state.clone()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let tasks = queue::claim_pending(&db, 10).await.unwrap();
+        let mut task = worker::task_row_to_delivery_task(&tasks[0]);
+
+        let mut registry = TransportRegistry::new();
+        registry.register(Arc::new(ErroringTransport));
+        let middleware = MiddlewareRegistry::new();
+        let deliver = async |task: &notifico_queue::DeliveryTask| {
+            worker::process_delivery(
+                task,
+                &registry,
+                &middleware,
+                &db,
+                state.storage.as_ref(),
+                None,
+            )
+            .await
+        };
+        let last_status = async || {
+            let logs = delivery_log::list_logs(&db, project_id, None, None, 10, 0)
+                .await
+                .unwrap();
+            logs.into_iter().max_by_key(|l| l.id).unwrap().status
+        };
+
+        task.attempt = 1;
+        task.max_attempts = 3;
+        assert!(deliver(&task).await.is_err());
+        assert_eq!(last_status().await, "queued");
+
+        task.attempt = 3;
+        assert!(deliver(&task).await.is_err());
+        assert_eq!(last_status().await, "failed");
     }
 
     #[tokio::test]
@@ -1106,6 +3287,9 @@ mod integration_tests {
             encryption_key: Some(key),
             metrics_handle: None,
             rate_limiter: rate_limit::RateLimiter::new(1000, 60),
+            storage: test_storage(),
+            template_cache: Default::default(),
+            data_schema_cache: Default::default(),
         });
 
         (build_router(state), key)
@@ -1115,7 +3299,7 @@ mod integration_tests {
     async fn tracking_open_returns_gif() {
         let (app, key) = setup_tracking_app().await;
 
-        let token = tracking::create_tracking_token("dlv-001", None, &key);
+        let token = notifico_core::tracking::create_tracking_token("dlv-001", None, &key);
         let req = Request::builder()
             .uri(format!("/t/open/{token}"))
             .body(Body::empty())
@@ -1151,7 +3335,7 @@ mod integration_tests {
         let (app, key) = setup_tracking_app().await;
 
         let url = "https://example.com/landing";
-        let token = tracking::create_tracking_token("dlv-002", Some(url), &key);
+        let token = notifico_core::tracking::create_tracking_token("dlv-002", Some(url), &key);
         let req = Request::builder()
             .uri(format!("/t/click/{token}"))
             .body(Body::empty())
@@ -1175,4 +3359,367 @@ mod integration_tests {
         let resp = app.oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[tokio::test]
+    async fn admin_webhook_crud_and_history() {
+        let (app, key) = setup_admin_app().await;
+
+        // Unknown event types are rejected
+        let req = Request::builder()
+            .method("POST")
+            .uri("/admin/api/v1/webhooks")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(
+                r#"{"url":"https://hooks.example.com/n","event_types":["delivery.exploded"]}"#,
+            ))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Create returns the generated secret once
+        let req = Request::builder()
+            .method("POST")
+            .uri("/admin/api/v1/webhooks")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(
+                r#"{"url":"https://hooks.example.com/n","event_types":["delivery.delivered","tracking.open"]}"#,
+            ))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body = json_body(resp).await;
+        let id = body["id"].as_str().unwrap().to_string();
+        assert!(body["secret"].as_str().unwrap().starts_with("whsec_"));
+
+        // Get does not expose the secret
+        let req = Request::builder()
+            .uri(format!("/admin/api/v1/webhooks/{id}"))
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        assert_eq!(body["event_types"].as_array().unwrap().len(), 2);
+        assert!(body.get("secret").is_none());
+
+        // Update
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/admin/api/v1/webhooks/{id}"))
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::from(
+                r#"{"url":"https://hooks.example.com/v2","event_types":[],"enabled":false}"#,
+            ))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Delivery history (empty)
+        let req = Request::builder()
+            .uri(format!("/admin/api/v1/webhooks/{id}/deliveries"))
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(json_body(resp).await.as_array().unwrap().is_empty());
+
+        // Delete
+        let req = Request::builder()
+            .method("DELETE")
+            .uri(format!("/admin/api/v1/webhooks/{id}"))
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        let req = Request::builder()
+            .uri(format!("/admin/api/v1/webhooks/{id}"))
+            .header("authorization", format!("Bearer {key}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Callbacks app with one Twilio (`sms`) and one `email` credential.
+    /// Returns the router and the two credential IDs.
+    async fn setup_callbacks_app() -> (Router, Uuid, Uuid) {
+        let db = notifico_db::connect("sqlite::memory:").await.unwrap();
+        notifico_db::run_migrations(&db).await.unwrap();
+
+        let project_id = Uuid::now_v7();
+        let recipient_id = Uuid::now_v7();
+
+        db.execute_unprepared(&format!(
+            "INSERT INTO project (id, name) VALUES ('{project_id}', 'test')"
+        ))
+        .await
+        .unwrap();
+        db.execute_unprepared(&format!(
+            "INSERT INTO recipient (id, project_id, external_id) VALUES ('{recipient_id}', '{project_id}', 'user-1')"
+        ))
+        .await
+        .unwrap();
+
+        for (channel, provider_id) in [("sms", "SM0001"), ("email", "abc@mail.example.com")] {
+            let log = notifico_db::repo::delivery_log::NewDeliveryLog {
+                id: Uuid::now_v7(),
+                project_id,
+                event_name: "order.confirmed",
+                recipient_id,
+                channel,
+                status: "delivered",
+                attempts: 1,
+                provider_message_id: Some(provider_id),
+                ..Default::default()
+            };
+            notifico_db::repo::delivery_log::insert_log(&db, &log)
+                .await
+                .unwrap();
+        }
+
+        let key: [u8; 32] = [0xAB; 32];
+        let sms_credential = Uuid::now_v7();
+        let email_credential = Uuid::now_v7();
+        for (id, channel, data) in [
+            (
+                sms_credential,
+                "sms",
+                serde_json::json!({"account_sid": "AC1", "auth_token": "twilio-token", "from_number": "+15550000"}),
+            ),
+            (
+                email_credential,
+                "email",
+                serde_json::json!({"callback_secret": "dsn-secret"}),
+            ),
+        ] {
+            notifico_db::repo::credential::insert_credential(
+                &db, id, project_id, channel, channel, &data, &key,
+            )
+            .await
+            .unwrap();
+        }
+
+        let mut config = Config::load(None).unwrap();
+        config.server.public_url = Some("https://notifico.example.com".into());
+        let registry = TransportRegistry::new();
+
+        let state = Arc::new(AppState {
+            db,
+            config,
+            registry,
+            middleware_registry: MiddlewareRegistry::new(),
+            encryption_key: Some(key),
+            metrics_handle: None,
+            rate_limiter: rate_limit::RateLimiter::new(1000, 60),
+            storage: test_storage(),
+            template_cache: Default::default(),
+            data_schema_cache: Default::default(),
+        });
+
+        (build_router(state), sms_credential, email_credential)
+    }
+
+    #[tokio::test]
+    async fn twilio_status_callback_requires_valid_signature() {
+        use base64::engine::{Engine, general_purpose::STANDARD};
+        use hmac::{Hmac, Mac};
+
+        let (app, sms_credential, email_credential) = setup_callbacks_app().await;
+        let path = format!("/api/v1/callbacks/twilio/{sms_credential}");
+        let body = "MessageSid=SM0001&MessageStatus=undelivered&ErrorCode=30003";
+
+        let mut mac = Hmac::<sha1::Sha1>::new_from_slice(b"twilio-token").unwrap();
+        mac.update(format!("https://notifico.example.com{path}").as_bytes());
+        for part in ["ErrorCode30003", "MessageSidSM0001", "MessageStatusundelivered"] {
+            mac.update(part.as_bytes());
+        }
+        let signature = STANDARD.encode(mac.finalize().into_bytes());
+
+        let request = |uri: &str, signature: Option<&str>| {
+            let mut builder = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/x-www-form-urlencoded");
+            if let Some(signature) = signature {
+                builder = builder.header("x-twilio-signature", signature);
+            }
+            builder.body(Body::from(body)).unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(request(&path, Some(&signature)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["updated"], true);
+
+        // Unsigned, wrongly signed, or addressed to another channel's credential
+        let resp = app.clone().oneshot(request(&path, None)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = app
+            .clone()
+            .oneshot(request(&path, Some("AAAAAAAAAAAAAAAAAAAAAAAAAAA=")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = app
+            .oneshot(request(
+                &format!("/api/v1/callbacks/twilio/{email_credential}"),
+                Some(&signature),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn dsn_bounce_callback_updates_email_log() {
+        use hmac::{Hmac, Mac};
+
+        let (app, _, email_credential) = setup_callbacks_app().await;
+        // Transient and unknown statuses are skipped without aborting the batch
+        let body = r#"[{"provider_message_id":"abc@mail.example.com","status":"delayed"},
+                    {"provider_message_id":"abc@mail.example.com","status":"opened"},
+                    {"provider_message_id":"<abc@mail.example.com>","status":"bounced","reason":"5.1.1 User unknown"},
+                    {"provider_message_id":"unknown@mail.example.com","status":"delivered"}]"#;
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"dsn-secret").unwrap();
+        mac.update(body.as_bytes());
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        let request = |signature: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/callbacks/dsn/{email_credential}"))
+                .header("content-type", "application/json")
+                .header("x-notifico-signature", signature)
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = app.clone().oneshot(request(&signature)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json = json_body(resp).await;
+        assert_eq!(json["received"], 4);
+        assert_eq!(json["updated"], 1);
+        assert_eq!(json["skipped"], 2);
+
+        let resp = app.oneshot(request("sha256=00")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn export_and_sync_project_files() {
+        let (app, key) = setup_admin_app().await;
+        let request = |method: &str, uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {key}"))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/templates",
+                serde_json::json!({"name": "welcome", "channel": "email"}),
+            ))
+            .await
+            .unwrap();
+        let template_id = json_body(resp).await["id"].as_str().unwrap().to_string();
+        app.clone()
+            .oneshot(request(
+                "PUT",
+                &format!("/admin/api/v1/templates/{template_id}/content/en"),
+                serde_json::json!({"body": {"subject": "Hi {{ name }}", "body": "<p>Welcome</p>"}}),
+            ))
+            .await
+            .unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(request("GET", "/admin/api/v1/sync", serde_json::json!(null)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let mut files = json_body(resp).await["files"].clone();
+        assert_eq!(
+            files["templates/welcome/template.toml"],
+            "channel = \"email\"\nformat = \"html\"\ndrafts = [1]\n"
+        );
+        assert_eq!(files["templates/welcome/v1/en/subject.j2"], "Hi {{ name }}");
+
+        // Syncing an unchanged export is a no-op
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/sync",
+                serde_json::json!({"files": files, "dry_run": true}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp).await["changes"], serde_json::json!([]));
+
+        files["templates/welcome/v1/en/subject.j2"] = "Hello {{ name }}".into();
+        files["events/user.signup.toml"] =
+            "category = \"transactional\"\n\n[[rules]]\nchannel = \"email\"\ntemplate = \"welcome\"\n"
+                .into();
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/sync",
+                serde_json::json!({"files": files}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp).await;
+        assert_eq!(body["dry_run"], false);
+        assert_eq!(
+            body["changes"],
+            serde_json::json!([
+                {"action": "update", "kind": "version", "name": "welcome v1"},
+                {"action": "create", "kind": "event", "name": "user.signup"},
+                {"action": "create", "kind": "rule", "name": "user.signup → email:welcome"},
+            ])
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/sync",
+                serde_json::json!({"files": files}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp).await["changes"], serde_json::json!([]));
+
+        // Rules must point at a template in the same tree
+        files["events/user.signup.toml"] =
+            "category = \"transactional\"\n\n[[rules]]\nchannel = \"email\"\ntemplate = \"missing\"\n"
+                .into();
+        let resp = app
+            .oneshot(request(
+                "POST",
+                "/admin/api/v1/sync",
+                serde_json::json!({"files": files}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
 }
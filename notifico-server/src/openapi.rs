@@ -2,6 +2,7 @@ use axum::Router;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+use crate::attachments::AttachmentResponse;
 use crate::broadcast::{BroadcastRequest, BroadcastResponse};
 use crate::ingest::IngestResponse;
 
@@ -16,10 +17,13 @@ use crate::ingest::IngestResponse;
     ),
     paths(
         crate::ingest::handle_ingest,
+        crate::cloudevents::handle_cloudevent,
+        crate::attachments::handle_upload,
         crate::broadcast::handle_broadcast,
     ),
     components(schemas(
         IngestResponse,
+        AttachmentResponse,
         BroadcastRequest,
         BroadcastResponse,
     )),
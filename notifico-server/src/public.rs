@@ -2,8 +2,9 @@ use std::sync::Arc;
 
 use axum::{
     Json, Router,
+    body::Bytes,
     extract::{Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode, header},
     response::{IntoResponse, Response},
     routing::get,
 };
@@ -118,6 +119,11 @@ struct UnsubscribeRequest {
     token: String,
 }
 
+#[derive(Deserialize)]
+struct OneClickQuery {
+    token: Option<String>,
+}
+
 /// GET /unsubscribe?token=... — one-click unsubscribe for email links (RFC 8058).
 async fn unsubscribe_get(
     State(state): State<Arc<AppState>>,
@@ -134,12 +140,33 @@ async fn unsubscribe_get(
     }
 }
 
-/// POST /unsubscribe — programmatic unsubscribe.
+/// POST /unsubscribe — programmatic unsubscribe with a JSON `{"token"}`
+/// body, or an RFC 8058 one-click POST as sent by mailbox providers for the
+/// `List-Unsubscribe` URL: `?token=...` in the query and a form-encoded
+/// (`application/x-www-form-urlencoded` or `multipart/form-data`)
+/// `List-Unsubscribe=One-Click` body.
 async fn unsubscribe_post(
     State(state): State<Arc<AppState>>,
-    Json(body): Json<UnsubscribeRequest>,
+    Query(q): Query<OneClickQuery>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> ApiResult {
-    let applied = preference::apply_unsubscribe(&state.db, &body.token)
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    let token = if is_json {
+        serde_json::from_slice::<UnsubscribeRequest>(&body)
+            .map_err(|e| bad_request(&format!("Invalid request body: {e}")))?
+            .token
+    } else {
+        if !String::from_utf8_lossy(&body).contains("List-Unsubscribe=One-Click") {
+            return Err(bad_request("Expected a List-Unsubscribe=One-Click body"));
+        }
+        q.token.ok_or_else(|| bad_request("Missing token"))?
+    };
+
+    let applied = preference::apply_unsubscribe(&state.db, &token)
         .await
         .map_err(db_err)?;
 
@@ -0,0 +1,371 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use notifico_core::error::CoreError;
+use notifico_core::sigv4;
+use notifico_core::storage::Storage;
+
+use crate::config::StorageConfig;
+
+/// Build the storage backend selected by `[storage] backend`.
+pub fn from_config(config: &StorageConfig) -> Result<Arc<dyn Storage>, String> {
+    match config.backend.as_str() {
+        "filesystem" => Ok(Arc::new(FilesystemStorage::new(&config.path))),
+        "s3" => {
+            let bucket = config
+                .s3_bucket
+                .clone()
+                .ok_or("storage.s3_bucket is required for the s3 backend")?;
+            let endpoint = config
+                .s3_endpoint
+                .clone()
+                .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", config.s3_region));
+            let access_key_id = config
+                .s3_access_key_id
+                .clone()
+                .ok_or("storage.s3_access_key_id is required for the s3 backend")?;
+            let secret_access_key = config
+                .s3_secret_access_key
+                .clone()
+                .ok_or("storage.s3_secret_access_key is required for the s3 backend")?;
+            Ok(Arc::new(S3Storage::new(
+                &endpoint,
+                bucket,
+                config.s3_region.clone(),
+                access_key_id,
+                secret_access_key,
+            )?))
+        }
+        other => Err(format!("Unknown storage backend: {other}")),
+    }
+}
+
+/// Reject keys that could escape the storage root.
+fn validate_key(key: &str) -> Result<(), CoreError> {
+    let valid = !key.is_empty()
+        && Path::new(key)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if valid {
+        Ok(())
+    } else {
+        Err(CoreError::Storage(format!("Invalid storage key: {key}")))
+    }
+}
+
+// ── Filesystem ──────────────────────────────────────────────────────
+
+/// Stores objects as files below a root directory.
+pub struct FilesystemStorage {
+    root: PathBuf,
+}
+
+impl FilesystemStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf, CoreError> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for FilesystemStorage {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), CoreError> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| CoreError::Storage(format!("{}: {e}", parent.display())))?;
+        }
+        tokio::fs::write(&path, data)
+            .await
+            .map_err(|e| CoreError::Storage(format!("{}: {e}", path.display())))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, CoreError> {
+        let path = self.path_for(key)?;
+        tokio::fs::read(&path)
+            .await
+            .map_err(|e| CoreError::Storage(format!("{}: {e}", path.display())))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CoreError> {
+        let path = self.path_for(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(CoreError::Storage(format!("{}: {e}", path.display())))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// ── S3-compatible ───────────────────────────────────────────────────
+
+/// Stores objects in an S3-compatible bucket (AWS S3, MinIO, R2, ...) using
+/// path-style addressing and Signature Version 4.
+pub struct S3Storage {
+    client: Client,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl S3Storage {
+    pub fn new(
+        endpoint: &str,
+        bucket: String,
+        region: String,
+        access_key_id: String,
+        secret_access_key: String,
+    ) -> Result<Self, String> {
+        let endpoint =
+            Url::parse(endpoint).map_err(|e| format!("Invalid S3 endpoint '{endpoint}': {e}"))?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {e}"))?;
+        Ok(Self {
+            client,
+            endpoint,
+            bucket,
+            region,
+            access_key_id,
+            secret_access_key,
+        })
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, CoreError> {
+        validate_key(key)?;
+
+        let canonical_uri = format!(
+            "{}/{}/{}",
+            self.endpoint.path().trim_end_matches('/'),
+            uri_encode(&self.bucket),
+            key.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+        );
+        let mut url = self.endpoint.clone();
+        url.set_path(&canonical_uri);
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        let authorization = sigv4::authorization(
+            method.as_str(),
+            &canonical_uri,
+            &[
+                ("host", &host),
+                ("x-amz-content-sha256", &payload_hash),
+                ("x-amz-date", &amz_date),
+            ],
+            &payload_hash,
+            &sigv4::SigningKey {
+                access_key_id: &self.access_key_id,
+                secret_access_key: &self.secret_access_key,
+                region: &self.region,
+                service: "s3",
+            },
+            &amz_date,
+        );
+
+        self.client
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| CoreError::Storage(format!("S3 request failed: {e}")))
+    }
+}
+
+async fn s3_error(key: &str, resp: reqwest::Response) -> CoreError {
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    CoreError::Storage(format!("S3 returned {status} for '{key}': {body}"))
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), CoreError> {
+        let resp = self
+            .request(reqwest::Method::PUT, key, data.to_vec())
+            .await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(s3_error(key, resp).await)
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, CoreError> {
+        let resp = self.request(reqwest::Method::GET, key, vec![]).await?;
+        if !resp.status().is_success() {
+            return Err(s3_error(key, resp).await);
+        }
+        resp.bytes()
+            .await
+            .map(|b| b.to_vec())
+            .map_err(|e| CoreError::Storage(format!("S3 read failed: {e}")))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CoreError> {
+        let resp = self.request(reqwest::Method::DELETE, key, vec![]).await?;
+        if resp.status().is_success() || resp.status() == StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            Err(s3_error(key, resp).await)
+        }
+    }
+}
+
+/// Percent-encode a path segment as required by SigV4 (RFC 3986 unreserved
+/// characters are kept as-is).
+fn uri_encode(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_keys_escaping_root() {
+        assert!(validate_key("attachments/p/a").is_ok());
+        assert!(validate_key("../etc/passwd").is_err());
+        assert!(validate_key("/etc/passwd").is_err());
+        assert!(validate_key("a/./b").is_ok());
+        assert!(validate_key("").is_err());
+    }
+
+    #[tokio::test]
+    async fn filesystem_roundtrip() {
+        let root = std::env::temp_dir().join(format!("notifico-storage-{}", uuid::Uuid::now_v7()));
+        let storage = FilesystemStorage::new(&root);
+
+        storage.put("attachments/p/a", b"hello").await.unwrap();
+        assert_eq!(storage.get("attachments/p/a").await.unwrap(), b"hello");
+
+        storage.delete("attachments/p/a").await.unwrap();
+        assert!(storage.get("attachments/p/a").await.is_err());
+        // Deleting a missing object is fine
+        storage.delete("attachments/p/a").await.unwrap();
+
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn s3_roundtrip_against_mock_server() {
+        use axum::{
+            Router,
+            body::Bytes,
+            extract::{Path as AxumPath, State},
+            http::{HeaderMap, StatusCode},
+            routing::put,
+        };
+        use std::collections::HashMap;
+        use std::sync::Mutex;
+
+        type Objects = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+        async fn put_object(
+            State(objects): State<Objects>,
+            AxumPath((bucket, key)): AxumPath<(String, String)>,
+            headers: HeaderMap,
+            body: Bytes,
+        ) -> StatusCode {
+            let auth = headers["authorization"].to_str().unwrap();
+            assert!(auth.starts_with("AWS4-HMAC-SHA256 Credential=AKID/"));
+            assert!(auth.contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date"));
+            assert_eq!(
+                headers["x-amz-content-sha256"].to_str().unwrap(),
+                hex::encode(Sha256::digest(&body))
+            );
+            objects
+                .lock()
+                .unwrap()
+                .insert(format!("{bucket}/{key}"), body.to_vec());
+            StatusCode::OK
+        }
+
+        async fn get_object(
+            State(objects): State<Objects>,
+            AxumPath((bucket, key)): AxumPath<(String, String)>,
+        ) -> Result<Vec<u8>, StatusCode> {
+            objects
+                .lock()
+                .unwrap()
+                .get(&format!("{bucket}/{key}"))
+                .cloned()
+                .ok_or(StatusCode::NOT_FOUND)
+        }
+
+        async fn delete_object(
+            State(objects): State<Objects>,
+            AxumPath((bucket, key)): AxumPath<(String, String)>,
+        ) -> StatusCode {
+            objects.lock().unwrap().remove(&format!("{bucket}/{key}"));
+            StatusCode::NO_CONTENT
+        }
+
+        let objects: Objects = Arc::default();
+        let app = Router::new()
+            .route(
+                "/{bucket}/{*key}",
+                put(put_object).get(get_object).delete(delete_object),
+            )
+            .with_state(objects.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let storage = S3Storage::new(
+            &format!("http://{addr}"),
+            "assets".into(),
+            "us-east-1".into(),
+            "AKID".into(),
+            "secret".into(),
+        )
+        .unwrap();
+
+        storage.put("attachments/p/a", b"hello s3").await.unwrap();
+        assert!(
+            objects
+                .lock()
+                .unwrap()
+                .contains_key("assets/attachments/p/a")
+        );
+        assert_eq!(storage.get("attachments/p/a").await.unwrap(), b"hello s3");
+
+        storage.delete("attachments/p/a").await.unwrap();
+        assert!(storage.get("attachments/p/a").await.is_err());
+    }
+}
@@ -0,0 +1,1485 @@
+//! Export a project's configuration as a directory of files, and sync such a
+//! directory back into a project, so templates can live in a repository and
+//! be reviewed as code.
+//!
+//! Layout, relative to the directory root:
+//!
+//! ```text
+//! events/<event>.toml                           category, description, rules with their middleware
+//!                                               and A/B variants
+//! events/<event>.schema.json                    the event's data schema, if it declares one
+//! templates/<name>/template.toml                channel, format and version statuses
+//! templates/<name>/v<N>/<locale>/<field>.j2     string content fields
+//! templates/<name>/v<N>/<locale>/content.json   all other content fields
+//! layouts/<name>.j2                             layouts
+//! partials/<name>.j2                            partials
+//! stylesheets/<name>                            stylesheets, named as linked (`brand.css`)
+//! ```
+//!
+//! A sync diffs the directory against the database and then creates, updates
+//! and deletes to match, so syncing the same directory twice changes nothing
+//! the second time. Rules are matched by channel and template, middleware by
+//! name; everything else by name.
+//!
+//! Published versions (current or archived) are immutable, as in the admin
+//! API: a bundle that edits their content or turns them back into drafts is
+//! rejected, and the edit belongs in a new draft version instead.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use notifico_core::content_format::ContentFormat;
+use notifico_core::data_schema::DataSchema;
+use notifico_core::registry::TransportRegistry;
+use notifico_db::repo::{admin, credential, middleware, partial, template_version, variant};
+
+use crate::admin::validate_content;
+
+/// Top-level directories owned by the sync. Export replaces them wholesale.
+const MANAGED_DIRS: [&str; 5] = ["events", "templates", "layouts", "partials", "stylesheets"];
+
+/// Partial kind, the directory it lives in, and its file extension.
+const PARTIAL_DIRS: [(&str, &str, &str); 3] = [
+    ("layout", "layouts", ".j2"),
+    ("partial", "partials", ".j2"),
+    (partial::STYLESHEET, "stylesheets", ""),
+];
+
+/// The kind as a static label for [`Change`]; unknown kinds read as partials.
+fn partial_kind(kind: &str) -> &'static str {
+    PARTIAL_DIRS
+        .iter()
+        .find(|(k, _, _)| *k == kind)
+        .map_or("partial", |(k, _, _)| k)
+}
+
+// ── Model ────────────────────────────────────────────────────────────
+
+/// Everything a sync manages for one project, keyed by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Bundle {
+    events: BTreeMap<String, EventSpec>,
+    templates: BTreeMap<String, TemplateSpec>,
+    /// Layouts, partials and stylesheets share one namespace.
+    partials: BTreeMap<String, PartialSpec>,
+}
+
+/// `events/<event>.toml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EventSpec {
+    category: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    description: String,
+    /// Lives in its own `.schema.json` file, since JSON Schema can hold
+    /// `null`, which TOML cannot.
+    #[serde(skip)]
+    data_schema: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rules: Vec<RuleSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    channel: String,
+    template: String,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    middleware: Vec<MiddlewareSpec>,
+    /// Required when `variants` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    experiment: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    variants: Vec<VariantSpec>,
+    /// Named credential to send through; credentials themselves are not synced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    credential: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VariantSpec {
+    name: String,
+    template: String,
+    #[serde(default = "default_weight")]
+    weight: i32,
+}
+
+fn default_weight() -> i32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MiddlewareSpec {
+    name: String,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    config: Value,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TemplateSpec {
+    channel: String,
+    format: String,
+    versions: BTreeMap<i32, VersionSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct VersionSpec {
+    /// `current`, `draft` or `archived`, as in `TemplateVersionRow::status`.
+    status: &'static str,
+    /// Content per locale.
+    contents: BTreeMap<String, Value>,
+}
+
+/// `templates/<name>/template.toml`. Every version is listed under exactly
+/// one status, so a version without any content still round-trips.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplateFile {
+    channel: String,
+    #[serde(default = "default_format")]
+    format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    current: Option<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    drafts: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    archived: Vec<i32>,
+}
+
+fn default_format() -> String {
+    ContentFormat::Html.to_string()
+}
+
+/// The files of one locale of one template version.
+#[derive(Default)]
+struct LocaleFiles {
+    /// `content.json`
+    json: Option<Value>,
+    /// `<field>.j2`, keyed by field
+    fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PartialSpec {
+    /// `layout`, `partial` or `stylesheet`, from the directory it lives in.
+    kind: String,
+    body: String,
+}
+
+// ── Files ────────────────────────────────────────────────────────────
+
+impl Bundle {
+    /// Serialize into file contents keyed by `/`-separated relative path.
+    pub(crate) fn to_files(&self) -> Result<BTreeMap<String, String>, String> {
+        let mut files = BTreeMap::new();
+
+        for (name, event) in &self.events {
+            check_segment("event", name)?;
+            let mut event = event.clone();
+            for m in event.rules.iter_mut().flat_map(|r| r.middleware.iter_mut()) {
+                strip_nulls(&mut m.config);
+            }
+            let toml = toml::to_string(&event).map_err(|e| format!("event '{name}': {e}"))?;
+            files.insert(format!("events/{name}.toml"), toml);
+            if let Some(schema) = &event.data_schema {
+                files.insert(format!("events/{name}.schema.json"), pretty_json(schema));
+            }
+        }
+
+        for (name, template) in &self.templates {
+            check_segment("template", name)?;
+            let status_list = |status| {
+                template
+                    .versions
+                    .iter()
+                    .filter(|(_, v)| v.status == status)
+                    .map(|(&n, _)| n)
+                    .collect::<Vec<_>>()
+            };
+            let file = TemplateFile {
+                channel: template.channel.clone(),
+                format: template.format.clone(),
+                current: status_list("current").first().copied(),
+                drafts: status_list("draft"),
+                archived: status_list("archived"),
+            };
+            let toml = toml::to_string(&file).map_err(|e| format!("template '{name}': {e}"))?;
+            files.insert(format!("templates/{name}/template.toml"), toml);
+
+            for (number, version) in &template.versions {
+                for (locale, body) in &version.contents {
+                    check_segment("locale", locale)?;
+                    let dir = format!("templates/{name}/v{number}/{locale}");
+                    let mut rest = body.clone();
+                    let mut split = false;
+                    if let Value::Object(fields) = &mut rest {
+                        fields.retain(|field, value| match value {
+                            Value::String(text) if is_field_file_name(field) => {
+                                files.insert(format!("{dir}/{field}.j2"), text.clone());
+                                split = true;
+                                false
+                            }
+                            _ => true,
+                        });
+                    }
+                    if !split || rest.as_object().is_some_and(|f| !f.is_empty()) {
+                        files.insert(format!("{dir}/content.json"), pretty_json(&rest));
+                    }
+                }
+            }
+        }
+
+        for (name, partial) in &self.partials {
+            check_path(name)?;
+            let (_, dir, ext) = PARTIAL_DIRS
+                .iter()
+                .find(|(k, _, _)| *k == partial_kind(&partial.kind))
+                .expect("partial_kind returns a listed kind");
+            files.insert(format!("{dir}/{name}{ext}"), partial.body.clone());
+        }
+
+        Ok(files)
+    }
+
+    /// Parse file contents keyed by `/`-separated relative path, as produced
+    /// by [`Bundle::to_files`] or [`read_dir`].
+    pub(crate) fn from_files(files: &BTreeMap<String, String>) -> Result<Self, String> {
+        let mut bundle = Bundle::default();
+        let mut schemas = BTreeMap::new();
+        let mut template_files = BTreeMap::new();
+        let mut contents: BTreeMap<(String, i32, String), LocaleFiles> = BTreeMap::new();
+
+        for (path, text) in files {
+            let segments: Vec<&str> = path.split('/').collect();
+            match segments.as_slice() {
+                ["events", file] if file.ends_with(".schema.json") => {
+                    let name = file.trim_end_matches(".schema.json");
+                    check_segment("event", name)?;
+                    let schema: Value =
+                        serde_json::from_str(text).map_err(|e| format!("{path}: {e}"))?;
+                    schemas.insert(name.to_string(), schema);
+                }
+                ["events", file] if file.ends_with(".toml") => {
+                    let name = file.trim_end_matches(".toml");
+                    check_segment("event", name)?;
+                    let event: EventSpec =
+                        toml::from_str(text).map_err(|e| format!("{path}: {e}"))?;
+                    bundle.events.insert(name.to_string(), event);
+                }
+                ["templates", name, "template.toml"] => {
+                    check_segment("template", name)?;
+                    let file: TemplateFile =
+                        toml::from_str(text).map_err(|e| format!("{path}: {e}"))?;
+                    template_files.insert(name.to_string(), file);
+                }
+                ["templates", name, version, locale, file] => {
+                    let number = version
+                        .strip_prefix('v')
+                        .and_then(|n| n.parse::<i32>().ok())
+                        .ok_or_else(|| format!("{path}: version directory must look like 'v1'"))?;
+                    let entry = contents
+                        .entry((name.to_string(), number, locale.to_string()))
+                        .or_default();
+                    if *file == "content.json" {
+                        let value =
+                            serde_json::from_str(text).map_err(|e| format!("{path}: {e}"))?;
+                        entry.json = Some(value);
+                    } else if let Some(field) = file.strip_suffix(".j2") {
+                        entry.fields.insert(field.to_string(), text.clone());
+                    } else {
+                        return Err(format!("Unexpected file: {path}"));
+                    }
+                }
+                [dir, ..]
+                    if let Some((kind, _, ext)) = PARTIAL_DIRS
+                        .iter()
+                        .find(|(_, d, ext)| d == dir && path.ends_with(ext)) =>
+                {
+                    let name = &path[dir.len() + 1..path.len() - ext.len()];
+                    check_path(name)?;
+                    let previous = bundle.partials.insert(
+                        name.to_string(),
+                        PartialSpec {
+                            kind: kind.to_string(),
+                            body: text.clone(),
+                        },
+                    );
+                    if let Some(previous) = previous {
+                        return Err(format!(
+                            "'{name}' is both a {} and a {kind}",
+                            partial_kind(&previous.kind)
+                        ));
+                    }
+                }
+                _ => return Err(format!("Unexpected file: {path}")),
+            }
+        }
+
+        for (name, schema) in schemas {
+            let event = bundle
+                .events
+                .get_mut(&name)
+                .ok_or_else(|| format!("events/{name}.schema.json has no events/{name}.toml"))?;
+            event.data_schema = Some(schema);
+        }
+
+        for (name, file) in template_files {
+            let mut versions = BTreeMap::new();
+            let statuses = file
+                .current
+                .iter()
+                .map(|&n| (n, "current"))
+                .chain(file.drafts.iter().map(|&n| (n, "draft")))
+                .chain(file.archived.iter().map(|&n| (n, "archived")));
+            for (number, status) in statuses {
+                let version = VersionSpec {
+                    status,
+                    contents: BTreeMap::new(),
+                };
+                if versions.insert(number, version).is_some() {
+                    return Err(format!("template '{name}': v{number} is listed twice"));
+                }
+            }
+            bundle.templates.insert(
+                name,
+                TemplateSpec {
+                    channel: file.channel,
+                    format: file.format,
+                    versions,
+                },
+            );
+        }
+
+        for ((name, number, locale), LocaleFiles { json, fields }) in contents {
+            let version = bundle
+                .templates
+                .get_mut(&name)
+                .ok_or_else(|| format!("templates/{name}/template.toml is missing"))?
+                .versions
+                .get_mut(&number)
+                .ok_or_else(|| {
+                    format!("template '{name}': v{number} is not listed in template.toml")
+                })?;
+            let mut body = json.unwrap_or_else(|| Value::Object(Default::default()));
+            if !fields.is_empty() {
+                let Value::Object(object) = &mut body else {
+                    return Err(format!(
+                        "templates/{name}/v{number}/{locale}/content.json must be an object \
+                         when the locale also has .j2 fields"
+                    ));
+                };
+                for (field, text) in fields {
+                    object.insert(field, Value::String(text));
+                }
+            }
+            version.contents.insert(locale, body);
+        }
+
+        Ok(bundle)
+    }
+
+    /// Check the bundle is consistent on its own, before touching the
+    /// database. Template content gets the same checks as in the admin API.
+    fn validate(&self, registry: &TransportRegistry) -> Result<(), String> {
+        for (name, event) in &self.events {
+            if let Some(schema) = &event.data_schema {
+                DataSchema::compile(schema)
+                    .map_err(|e| format!("event '{name}': invalid data schema: {e}"))?;
+            }
+            for rule in &event.rules {
+                if !self.templates.contains_key(&rule.template) {
+                    return Err(format!(
+                        "event '{name}': rule uses unknown template '{}'",
+                        rule.template
+                    ));
+                }
+                if !rule.variants.is_empty() && rule.experiment.is_none() {
+                    return Err(format!(
+                        "event '{name}': rule with variants needs an experiment id"
+                    ));
+                }
+                for v in &rule.variants {
+                    if !self.templates.contains_key(&v.template) {
+                        return Err(format!(
+                            "event '{name}': variant '{}' uses unknown template '{}'",
+                            v.name, v.template
+                        ));
+                    }
+                    if v.weight < 0 {
+                        return Err(format!(
+                            "event '{name}': variant '{}' has a negative weight",
+                            v.name
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (name, template) in &self.templates {
+            let format = template
+                .format
+                .parse::<ContentFormat>()
+                .map_err(|e| format!("template '{name}': {e}"))?;
+            if template.versions.keys().any(|&n| n < 1) {
+                return Err(format!("template '{name}': version numbers start at 1"));
+            }
+            let drafts = template
+                .versions
+                .values()
+                .filter(|v| v.status == "draft")
+                .count();
+            if drafts > 1 {
+                return Err(format!("template '{name}': only one draft is allowed"));
+            }
+            for (number, version) in &template.versions {
+                for (locale, body) in &version.contents {
+                    let errors = validate_content(registry, &template.channel, format, body);
+                    if !errors.is_empty() {
+                        let messages: Vec<_> = errors
+                            .iter()
+                            .map(|e| match e.field.as_str() {
+                                "" => e.message.clone(),
+                                field => format!("{field}: {}", e.message),
+                            })
+                            .collect();
+                        return Err(format!(
+                            "template '{name}' v{number} ({locale}): {}",
+                            messages.join("; ")
+                        ));
+                    }
+                }
+            }
+        }
+
+        let sources = self
+            .partials
+            .iter()
+            .filter(|(_, p)| p.kind != partial::STYLESHEET)
+            .map(|(name, p)| (name.clone(), p.body.clone()))
+            .collect();
+        notifico_template::Partials::new(sources).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// A rule's variants in export form, ordered by name.
+async fn variant_specs(
+    db: &impl ConnectionTrait,
+    rule_id: Uuid,
+    template_names: &HashMap<Uuid, &str>,
+) -> Result<Vec<VariantSpec>, DbErr> {
+    Ok(variant::list_by_rule(db, rule_id)
+        .await?
+        .into_iter()
+        .filter_map(|v| {
+            Some(VariantSpec {
+                template: template_names.get(&v.template_id)?.to_string(),
+                name: v.name,
+                weight: v.weight,
+            })
+        })
+        .collect())
+}
+
+/// Names that become a single path segment.
+fn check_segment(what: &str, name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\'])
+        || name.chars().any(char::is_control)
+    {
+        return Err(format!(
+            "{what} name '{name}' cannot be used as a file name"
+        ));
+    }
+    Ok(())
+}
+
+/// Layout, partial and stylesheet names, which may contain `/` to nest
+/// directories.
+fn check_path(name: &str) -> Result<(), String> {
+    name.split('/')
+        .try_for_each(|segment| check_segment("layout, partial or stylesheet", segment))
+}
+
+/// Content fields that get their own `.j2` file; the rest go to `content.json`.
+fn is_field_file_name(field: &str) -> bool {
+    !field.is_empty()
+        && !field.starts_with('.')
+        && field
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// TOML has no null; middleware treat a missing key the same way.
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            fields.retain(|_, v| !v.is_null());
+            fields.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => {
+            items.retain(|v| !v.is_null());
+            items.iter_mut().for_each(strip_nulls);
+        }
+        _ => {}
+    }
+}
+
+fn pretty_json(value: &Value) -> String {
+    let mut text = serde_json::to_string_pretty(value).unwrap_or_default();
+    text.push('\n');
+    text
+}
+
+/// Read every file under the managed directories of `dir`. Hidden files
+/// (`.gitkeep` and the like) are skipped.
+pub(crate) fn read_dir(dir: &Path) -> std::io::Result<BTreeMap<String, String>> {
+    fn walk(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                walk(root, &path, files)?;
+            } else {
+                let relative = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.insert(relative, std::fs::read_to_string(&path)?);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = BTreeMap::new();
+    for managed in MANAGED_DIRS {
+        let path = dir.join(managed);
+        if path.is_dir() {
+            walk(dir, &path, &mut files)?;
+        }
+    }
+    Ok(files)
+}
+
+/// Write files under `dir`, first removing the managed directories so files
+/// for deleted objects do not linger.
+pub(crate) fn write_dir(dir: &Path, files: &BTreeMap<String, String>) -> std::io::Result<()> {
+    for managed in MANAGED_DIRS {
+        let path = dir.join(managed);
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)?;
+        }
+    }
+    for (relative, text) in files {
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, text)?;
+    }
+    Ok(())
+}
+
+// ── Export ───────────────────────────────────────────────────────────
+
+/// Read a project's events, rules, middleware, templates and layouts.
+pub(crate) async fn export(db: &DatabaseConnection, project_id: Uuid) -> Result<Bundle, DbErr> {
+    let mut bundle = Bundle::default();
+
+    let templates = admin::list_templates(db, project_id).await?;
+    let template_names: HashMap<Uuid, &str> =
+        templates.iter().map(|t| (t.id, t.name.as_str())).collect();
+    for template in &templates {
+        let mut versions = BTreeMap::new();
+        for version in template_version::list_versions(db, template.id).await? {
+            let contents = template_version::get_contents(db, version.id).await?;
+            versions.insert(
+                version.version,
+                VersionSpec {
+                    status: version.status(),
+                    contents,
+                },
+            );
+        }
+        bundle.templates.insert(
+            template.name.clone(),
+            TemplateSpec {
+                channel: template.channel.clone(),
+                format: template.format.clone(),
+                versions,
+            },
+        );
+    }
+
+    for event in admin::list_events(db, project_id).await? {
+        let mut rules = Vec::new();
+        for rule in admin::list_rules(db, event.id).await? {
+            let Some(template) = template_names.get(&rule.template_id) else {
+                tracing::warn!(rule_id = %rule.id, "Skipping rule whose template no longer exists");
+                continue;
+            };
+            let mut middleware: Vec<MiddlewareSpec> = middleware::list_all_by_rule(db, rule.id)
+                .await?
+                .into_iter()
+                .map(|m| MiddlewareSpec {
+                    config: m.config_value().unwrap_or_default(),
+                    name: m.middleware_name,
+                    priority: m.priority,
+                    enabled: m.enabled,
+                })
+                .collect();
+            middleware.sort_by(|a, b| (a.priority, &a.name).cmp(&(b.priority, &b.name)));
+            let variants = variant_specs(db, rule.id, &template_names).await?;
+            rules.push(RuleSpec {
+                channel: rule.channel,
+                template: template.to_string(),
+                priority: rule.priority,
+                enabled: rule.enabled,
+                middleware,
+                experiment: rule.experiment.filter(|_| !variants.is_empty()),
+                variants,
+                credential: rule.credential,
+            });
+        }
+        // Stable order keeps exported files diff-friendly
+        rules.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| (&a.channel, &a.template).cmp(&(&b.channel, &b.template)))
+        });
+        bundle.events.insert(
+            event.name,
+            EventSpec {
+                category: event.category,
+                description: event.description,
+                data_schema: event.data_schema,
+                rules,
+            },
+        );
+    }
+
+    for p in partial::list_partials(db, project_id).await? {
+        bundle.partials.insert(
+            p.name,
+            PartialSpec {
+                kind: p.kind,
+                body: p.body,
+            },
+        );
+    }
+
+    Ok(bundle)
+}
+
+// ── Sync ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Action {
+    Create,
+    Update,
+    Delete,
+}
+
+/// One difference between the bundle and the database.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Change {
+    pub(crate) action: Action,
+    /// `event`, `rule`, `middleware`, `template`, `version`, `layout`,
+    /// `partial` or `stylesheet`.
+    pub(crate) kind: &'static str,
+    pub(crate) name: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            Action::Create => "create",
+            Action::Update => "update",
+            Action::Delete => "delete",
+        };
+        write!(f, "{action} {} {}", self.kind, self.name)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum SyncError {
+    /// The bundle is inconsistent or conflicts with the database.
+    Invalid(String),
+    Db(DbErr),
+}
+
+impl From<DbErr> for SyncError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(message) => f.write_str(message),
+            Self::Db(e) => write!(f, "database error: {e}"),
+        }
+    }
+}
+
+/// Make the project match `bundle` and return what changed. With `dry_run`
+/// nothing is written and the result is what a real sync would change.
+///
+/// Changes are applied in one transaction, so a sync that fails leaves the
+/// project as it was.
+pub(crate) async fn sync(
+    db: &DatabaseConnection,
+    registry: &TransportRegistry,
+    project_id: Uuid,
+    bundle: &Bundle,
+    dry_run: bool,
+) -> Result<Vec<Change>, SyncError> {
+    bundle.validate(registry).map_err(SyncError::Invalid)?;
+
+    let txn = db.begin().await?;
+    let changes = apply(&txn, project_id, bundle, dry_run).await?;
+    txn.commit().await?;
+    Ok(changes)
+}
+
+/// [`sync`] against an open transaction.
+async fn apply(
+    db: &impl ConnectionTrait,
+    project_id: Uuid,
+    bundle: &Bundle,
+    dry_run: bool,
+) -> Result<Vec<Change>, SyncError> {
+    let existing_templates = admin::list_templates(db, project_id).await?;
+    for template in &existing_templates {
+        if let Some(spec) = bundle.templates.get(&template.name)
+            && spec.channel != template.channel
+        {
+            return Err(SyncError::Invalid(format!(
+                "template '{}' is on channel '{}' and cannot move to '{}'; give it a new name instead",
+                template.name, template.channel, spec.channel
+            )));
+        }
+    }
+    // As in the admin API: a rule naming a missing credential fails every send
+    for (name, event) in &bundle.events {
+        for rule in &event.rules {
+            let Some(credential) = rule.credential.as_deref() else {
+                continue;
+            };
+            if !credential::credential_exists(db, project_id, &rule.channel, credential).await? {
+                return Err(SyncError::Invalid(format!(
+                    "event '{name}': no credential named '{credential}' for channel '{}'",
+                    rule.channel
+                )));
+            }
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut change = |action, kind, name: String| changes.push(Change { action, kind, name });
+
+    // Layouts, partials and stylesheets
+    let existing = partial::list_partials(db, project_id).await?;
+    for (name, spec) in &bundle.partials {
+        let kind = partial_kind(&spec.kind);
+        match existing.iter().find(|p| &p.name == name) {
+            None => {
+                change(Action::Create, kind, name.clone());
+                if !dry_run {
+                    partial::insert_partial(
+                        db,
+                        Uuid::now_v7(),
+                        project_id,
+                        name,
+                        &spec.kind,
+                        &spec.body,
+                    )
+                    .await?;
+                }
+            }
+            Some(p) if p.kind != spec.kind || p.body != spec.body => {
+                change(Action::Update, kind, name.clone());
+                if !dry_run {
+                    partial::update_partial(db, project_id, p.id, name, &spec.kind, &spec.body)
+                        .await?;
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    for p in existing
+        .iter()
+        .filter(|p| !bundle.partials.contains_key(&p.name))
+    {
+        change(Action::Delete, partial_kind(&p.kind), p.name.clone());
+        if !dry_run {
+            partial::delete_partial(db, project_id, p.id).await?;
+        }
+    }
+
+    // Templates and their versions
+    for (name, spec) in &bundle.templates {
+        let template_id = match existing_templates.iter().find(|t| &t.name == name) {
+            Some(t) => {
+                if t.format != spec.format {
+                    change(Action::Update, "template", name.clone());
+                    if !dry_run {
+                        admin::update_template(db, t.id, name, &spec.format).await?;
+                    }
+                }
+                Some(t.id)
+            }
+            None => {
+                change(Action::Create, "template", name.clone());
+                let id = Uuid::now_v7();
+                if !dry_run {
+                    admin::insert_template(db, id, project_id, name, &spec.channel, &spec.format)
+                        .await?;
+                }
+                (!dry_run).then_some(id)
+            }
+        };
+
+        let rows = match template_id {
+            Some(id) => template_version::list_versions(db, id).await?,
+            None => Vec::new(),
+        };
+        for (&number, version) in &spec.versions {
+            let label = format!("{name} v{number}");
+            match rows.iter().find(|r| r.version == number) {
+                None => {
+                    change(Action::Create, "version", label);
+                    if !dry_run && let Some(id) = template_id {
+                        let version_id = template_version::insert_version(db, id, number).await?;
+                        template_version::replace_contents(db, version_id, &version.contents)
+                            .await?;
+                    }
+                }
+                Some(row) => {
+                    let contents = template_version::get_contents(db, row.id).await?;
+                    if !row.is_draft() && contents != version.contents {
+                        return Err(SyncError::Invalid(format!(
+                            "template '{name}': v{number} is {} and its content cannot change; \
+                             add the changes as a new draft version",
+                            row.status()
+                        )));
+                    }
+                    if !row.is_draft() && version.status == "draft" {
+                        return Err(SyncError::Invalid(format!(
+                            "template '{name}': v{number} is {} and cannot become a draft again",
+                            row.status()
+                        )));
+                    }
+                    if contents != version.contents || row.status() != version.status {
+                        change(Action::Update, "version", label);
+                    }
+                    if contents != version.contents && !dry_run {
+                        template_version::replace_contents(db, row.id, &version.contents).await?;
+                    }
+                }
+            }
+        }
+        for row in rows
+            .iter()
+            .filter(|r| !spec.versions.contains_key(&r.version))
+        {
+            change(
+                Action::Delete,
+                "version",
+                format!("{name} v{}", row.version),
+            );
+            if !dry_run {
+                template_version::delete_version(db, row.id).await?;
+            }
+        }
+        if !dry_run && let Some(id) = template_id {
+            let status = |wanted| {
+                spec.versions
+                    .iter()
+                    .filter(move |(_, v)| v.status == wanted)
+            };
+            let current = status("current").map(|(&n, _)| n).next();
+            let drafts: Vec<i32> = status("draft").map(|(&n, _)| n).collect();
+            template_version::set_statuses(db, id, current, &drafts).await?;
+        }
+    }
+
+    // Events, rules and middleware. Existing rules are matched by the
+    // template names they had before this sync; new rules need the ids
+    // templates have after it.
+    let template_names: HashMap<Uuid, &str> = existing_templates
+        .iter()
+        .map(|t| (t.id, t.name.as_str()))
+        .collect();
+    let template_ids: HashMap<String, Uuid> = if dry_run {
+        HashMap::new()
+    } else {
+        admin::list_templates(db, project_id)
+            .await?
+            .into_iter()
+            .map(|t| (t.name, t.id))
+            .collect()
+    };
+
+    let existing_events = admin::list_events(db, project_id).await?;
+    for (name, spec) in &bundle.events {
+        let event_id = match existing_events.iter().find(|e| &e.name == name) {
+            Some(e) => {
+                let fields_changed =
+                    e.category != spec.category || e.description != spec.description;
+                let schema_changed = e.data_schema != spec.data_schema;
+                if fields_changed || schema_changed {
+                    change(Action::Update, "event", name.clone());
+                }
+                if fields_changed && !dry_run {
+                    admin::update_event(db, e.id, name, &spec.category, &spec.description).await?;
+                }
+                if schema_changed && !dry_run {
+                    admin::update_event_schema(db, e.id, spec.data_schema.as_ref()).await?;
+                }
+                Some(e.id)
+            }
+            None => {
+                change(Action::Create, "event", name.clone());
+                let id = Uuid::now_v7();
+                if !dry_run {
+                    admin::create_event(db, id, project_id, name, &spec.category).await?;
+                    admin::update_event(db, id, name, &spec.category, &spec.description).await?;
+                    admin::update_event_schema(db, id, spec.data_schema.as_ref()).await?;
+                }
+                (!dry_run).then_some(id)
+            }
+        };
+
+        let mut unmatched = match event_id {
+            Some(id) => admin::list_rules(db, id).await?,
+            None => Vec::new(),
+        };
+        for rule in &spec.rules {
+            let label = format!("{name} → {}:{}", rule.channel, rule.template);
+            let position = unmatched.iter().position(|r| {
+                r.channel == rule.channel
+                    && template_names.get(&r.template_id) == Some(&rule.template.as_str())
+            });
+            let rule_id = match position.map(|i| unmatched.remove(i)) {
+                Some(row) => {
+                    if row.enabled != rule.enabled
+                        || row.priority != rule.priority
+                        || row.credential != rule.credential
+                    {
+                        change(Action::Update, "rule", label.clone());
+                        if !dry_run {
+                            admin::update_rule(
+                                db,
+                                row.id,
+                                &row.channel,
+                                row.template_id,
+                                rule.enabled,
+                                rule.priority,
+                                rule.credential.as_deref(),
+                            )
+                            .await?;
+                        }
+                    }
+                    Some(row.id)
+                }
+                None => {
+                    change(Action::Create, "rule", label.clone());
+                    let id = Uuid::now_v7();
+                    if !dry_run
+                        && let (Some(event_id), Some(&template_id)) =
+                            (event_id, template_ids.get(&rule.template))
+                    {
+                        admin::create_rule(
+                            db,
+                            id,
+                            event_id,
+                            &rule.channel,
+                            template_id,
+                            rule.priority,
+                            rule.credential.as_deref(),
+                        )
+                        .await?;
+                        if !rule.enabled {
+                            admin::update_rule(
+                                db,
+                                id,
+                                &rule.channel,
+                                template_id,
+                                false,
+                                rule.priority,
+                                rule.credential.as_deref(),
+                            )
+                            .await?;
+                        }
+                    }
+                    (!dry_run).then_some(id)
+                }
+            };
+
+            let mut unmatched = match rule_id {
+                Some(id) => middleware::list_all_by_rule(db, id).await?,
+                None => Vec::new(),
+            };
+            for spec in &rule.middleware {
+                let label = format!("{label} / {}", spec.name);
+                match unmatched
+                    .iter()
+                    .position(|m| m.middleware_name == spec.name)
+                    .map(|i| unmatched.remove(i))
+                {
+                    Some(row) => {
+                        let config = row.config_value().unwrap_or_default();
+                        if config != spec.config
+                            || row.priority != spec.priority
+                            || row.enabled != spec.enabled
+                        {
+                            change(Action::Update, "middleware", label);
+                            if !dry_run {
+                                middleware::update(
+                                    db,
+                                    row.id,
+                                    &spec.config,
+                                    spec.priority,
+                                    spec.enabled,
+                                )
+                                .await?;
+                            }
+                        }
+                    }
+                    None => {
+                        change(Action::Create, "middleware", label);
+                        if !dry_run && let Some(rule_id) = rule_id {
+                            let id = Uuid::now_v7();
+                            middleware::insert(
+                                db,
+                                id,
+                                rule_id,
+                                &spec.name,
+                                &spec.config,
+                                spec.priority,
+                            )
+                            .await?;
+                            if !spec.enabled {
+                                middleware::update(db, id, &spec.config, spec.priority, false)
+                                    .await?;
+                            }
+                        }
+                    }
+                }
+            }
+            for row in unmatched {
+                change(
+                    Action::Delete,
+                    "middleware",
+                    format!("{label} / {}", row.middleware_name),
+                );
+                if !dry_run {
+                    middleware::delete(db, row.id).await?;
+                }
+            }
+
+            let (experiment, variants) = match (rule_id, position) {
+                (Some(id), Some(_)) => {
+                    let variants = variant_specs(db, id, &template_names).await?;
+                    let experiment = admin::get_rule(db, id).await?.and_then(|r| r.experiment);
+                    (experiment.filter(|_| !variants.is_empty()), variants)
+                }
+                _ => (None, Vec::new()),
+            };
+            let mut wanted = rule.variants.clone();
+            wanted.sort_by(|a, b| a.name.cmp(&b.name));
+            let wanted_experiment = rule.experiment.clone().filter(|_| !wanted.is_empty());
+            if (experiment, variants) != (wanted_experiment.clone(), wanted.clone()) {
+                change(Action::Update, "variants", label.clone());
+                if !dry_run && let Some(rule_id) = rule_id {
+                    let variants: Vec<_> = wanted
+                        .iter()
+                        .filter_map(|v| {
+                            let id = *template_ids.get(&v.template)?;
+                            Some((v.name.as_str(), id, v.weight))
+                        })
+                        .collect();
+                    variant::replace(db, rule_id, wanted_experiment.as_deref(), &variants)
+                        .await?;
+                }
+            }
+        }
+        for row in unmatched {
+            let template = template_names.get(&row.template_id).copied().unwrap_or("?");
+            change(
+                Action::Delete,
+                "rule",
+                format!("{name} → {}:{template}", row.channel),
+            );
+            if !dry_run {
+                admin::delete_rule(db, row.id).await?;
+            }
+        }
+    }
+
+    // Deleted last: rules kept above may still point at these until then
+    for e in existing_events
+        .iter()
+        .filter(|e| !bundle.events.contains_key(&e.name))
+    {
+        change(Action::Delete, "event", e.name.clone());
+        if !dry_run {
+            admin::delete_event(db, e.id).await?;
+        }
+    }
+    for t in existing_templates
+        .iter()
+        .filter(|t| !bundle.templates.contains_key(&t.name))
+    {
+        change(Action::Delete, "template", t.name.clone());
+        if !dry_run {
+            admin::delete_template(db, t.id).await?;
+        }
+    }
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn setup() -> (DatabaseConnection, Uuid) {
+        let db = notifico_db::connect("sqlite::memory:").await.unwrap();
+        notifico_db::run_migrations(&db).await.unwrap();
+        let project_id = Uuid::now_v7();
+        admin::create_project(&db, project_id, "P1", "en")
+            .await
+            .unwrap();
+        (db, project_id)
+    }
+
+    fn sample_files() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            (
+                "events/order.shipped.toml".to_string(),
+                r#"category = "transactional"
+description = "Order left the warehouse"
+
+[[rules]]
+channel = "email"
+template = "shipped"
+priority = 5
+experiment = "shipped-subject"
+
+[[rules.middleware]]
+name = "utm_params"
+config = { utm_source = "notifico" }
+
+[[rules.variants]]
+name = "control"
+template = "shipped"
+weight = 3
+"#
+                .to_string(),
+            ),
+            (
+                "events/order.shipped.schema.json".to_string(),
+                r#"{"type": "object", "required": ["order_id"]}"#.to_string(),
+            ),
+            (
+                "templates/shipped/template.toml".to_string(),
+                "channel = \"email\"\ncurrent = 1\ndrafts = [2]\n".to_string(),
+            ),
+            (
+                "templates/shipped/v1/en/subject.j2".to_string(),
+                "Order {{ order_id }} shipped".to_string(),
+            ),
+            (
+                "templates/shipped/v1/en/body.j2".to_string(),
+                "{% extends \"base\" %}".to_string(),
+            ),
+            (
+                "templates/shipped/v1/en/content.json".to_string(),
+                r#"{"tags": ["order"]}"#.to_string(),
+            ),
+            (
+                "layouts/base.j2".to_string(),
+                "<html>{% block content %}{% endblock %}</html>".to_string(),
+            ),
+            ("partials/email/footer.j2".to_string(), "Thanks".to_string()),
+            (
+                "stylesheets/brand.css".to_string(),
+                "h1 { color: #333 }".to_string(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn files_round_trip() {
+        let bundle = Bundle::from_files(&sample_files()).unwrap();
+        let shipped = &bundle.templates["shipped"];
+        assert_eq!(shipped.format, "html");
+        assert_eq!(shipped.versions[&1].status, "current");
+        assert_eq!(
+            shipped.versions[&1].contents["en"],
+            json!({
+                "subject": "Order {{ order_id }} shipped",
+                "body": "{% extends \"base\" %}",
+                "tags": ["order"],
+            })
+        );
+        // An empty draft survives through template.toml alone
+        assert!(shipped.versions[&2].contents.is_empty());
+        assert_eq!(bundle.partials["email/footer"].kind, "partial");
+        assert_eq!(bundle.partials["brand.css"].kind, "stylesheet");
+
+        let files = bundle.to_files().unwrap();
+        assert_eq!(Bundle::from_files(&files).unwrap(), bundle);
+    }
+
+    #[test]
+    fn rejects_unknown_files_and_unsafe_names() {
+        let mut files = sample_files();
+        files.insert("templates/shipped/v3/en/subject.j2".into(), "x".into());
+        let err = Bundle::from_files(&files).unwrap_err();
+        assert!(err.contains("v3 is not listed"), "{err}");
+
+        let mut files = sample_files();
+        files.insert("notes.txt".into(), String::new());
+        assert!(
+            Bundle::from_files(&files)
+                .unwrap_err()
+                .contains("Unexpected file")
+        );
+
+        let mut bundle = Bundle::from_files(&sample_files()).unwrap();
+        let event = bundle.events["order.shipped"].clone();
+        bundle.events.insert("../escape".into(), event);
+        assert!(bundle.to_files().is_err());
+    }
+
+    #[tokio::test]
+    async fn sync_is_idempotent_and_export_matches() {
+        let (db, project_id) = setup().await;
+        let bundle = Bundle::from_files(&sample_files()).unwrap();
+
+        let planned = sync(&db, &TransportRegistry::new(), project_id, &bundle, true)
+            .await
+            .unwrap();
+        assert!(
+            admin::list_events(&db, project_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let applied = sync(&db, &TransportRegistry::new(), project_id, &bundle, false)
+            .await
+            .unwrap();
+        assert_eq!(planned, applied);
+        assert!(applied.contains(&Change {
+            action: Action::Create,
+            kind: "middleware",
+            name: "order.shipped → email:shipped / utm_params".into(),
+        }));
+
+        assert!(
+            sync(&db, &TransportRegistry::new(), project_id, &bundle, false)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(export(&db, project_id).await.unwrap(), bundle);
+    }
+
+    #[tokio::test]
+    async fn sync_updates_and_deletes() {
+        let (db, project_id) = setup().await;
+        let bundle = Bundle::from_files(&sample_files()).unwrap();
+        sync(&db, &TransportRegistry::new(), project_id, &bundle, false)
+            .await
+            .unwrap();
+
+        let mut files = sample_files();
+        files.remove("partials/email/footer.j2");
+        files.insert(
+            "templates/shipped/template.toml".into(),
+            "channel = \"email\"\narchived = [1]\ncurrent = 2\n".into(),
+        );
+        files.insert("templates/shipped/v2/en/subject.j2".into(), "v2".into());
+        files.insert(
+            "events/order.shipped.toml".into(),
+            "category = \"transactional\"\n\n[[rules]]\nchannel = \"email\"\ntemplate = \"shipped\"\nenabled = false\n"
+                .into(),
+        );
+        let bundle = Bundle::from_files(&files).unwrap();
+
+        let changes: Vec<String> = sync(&db, &TransportRegistry::new(), project_id, &bundle, false)
+            .await
+            .unwrap()
+            .iter()
+            .map(Change::to_string)
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                "delete partial email/footer",
+                "update version shipped v1",
+                "update version shipped v2",
+                "update event order.shipped",
+                "update rule order.shipped → email:shipped",
+                "delete middleware order.shipped → email:shipped / utm_params",
+                "update variants order.shipped → email:shipped",
+            ]
+        );
+        assert_eq!(export(&db, project_id).await.unwrap(), bundle);
+
+        // Templates still in use cannot move channels
+        files.insert(
+            "templates/shipped/template.toml".into(),
+            "channel = \"sms\"\ncurrent = 2\narchived = [1]\n".into(),
+        );
+        let bundle = Bundle::from_files(&files).unwrap();
+        assert!(matches!(
+            sync(&db, &TransportRegistry::new(), project_id, &bundle, false).await,
+            Err(SyncError::Invalid(_))
+        ));
+
+        assert_eq!(
+            sync(
+                &db,
+                &TransportRegistry::new(),
+                project_id,
+                &Bundle::default(),
+                false
+            )
+            .await
+            .unwrap()
+            .len(),
+            4
+        );
+        assert_eq!(export(&db, project_id).await.unwrap(), Bundle::default());
+    }
+
+    #[tokio::test]
+    async fn sync_rejects_invalid_content_and_published_edits() {
+        let (db, project_id) = setup().await;
+        let bundle = Bundle::from_files(&sample_files()).unwrap();
+
+        // The email channel requires a `text` field the sample doesn't have
+        let registry = crate::transport_registry();
+        match sync(&db, &registry, project_id, &bundle, true).await {
+            Err(SyncError::Invalid(message)) => assert_eq!(
+                message,
+                "template 'shipped' v1 (en): text: 'text' is required"
+            ),
+            other => panic!("expected invalid bundle, got {other:?}"),
+        }
+
+        let mut files = sample_files();
+        files.insert(
+            "templates/shipped/v1/en/subject.j2".into(),
+            "Order {{ order_id".into(),
+        );
+        let bundle = Bundle::from_files(&files).unwrap();
+        assert!(matches!(
+            sync(&db, &TransportRegistry::new(), project_id, &bundle, false).await,
+            Err(SyncError::Invalid(message)) if message.starts_with("template 'shipped' v1 (en): subject:")
+        ));
+
+        let bundle = Bundle::from_files(&sample_files()).unwrap();
+        sync(&db, &TransportRegistry::new(), project_id, &bundle, false)
+            .await
+            .unwrap();
+
+        // Editing the current version fails, and the partial changed before
+        // it is rolled back
+        let mut files = sample_files();
+        files.insert("partials/email/footer.j2".into(), "Cheers".into());
+        files.insert(
+            "templates/shipped/v1/en/subject.j2".into(),
+            "Shipped".into(),
+        );
+        let edited = Bundle::from_files(&files).unwrap();
+        match sync(&db, &TransportRegistry::new(), project_id, &edited, false).await {
+            Err(SyncError::Invalid(message)) => assert!(
+                message.contains("v1 is current and its content cannot change"),
+                "{message}"
+            ),
+            other => panic!("expected invalid bundle, got {other:?}"),
+        }
+        assert_eq!(export(&db, project_id).await.unwrap(), bundle);
+
+        let mut files = sample_files();
+        files.insert(
+            "templates/shipped/template.toml".into(),
+            "channel = \"email\"\ncurrent = 2\ndrafts = [1]\n".into(),
+        );
+        let bundle = Bundle::from_files(&files).unwrap();
+        assert!(matches!(
+            sync(&db, &TransportRegistry::new(), project_id, &bundle, false).await,
+            Err(SyncError::Invalid(message)) if message.contains("cannot become a draft again")
+        ));
+    }
+
+    #[tokio::test]
+    async fn sync_rejects_unknown_credentials() {
+        let (db, project_id) = setup().await;
+        let mut files = sample_files();
+        let event = files["events/order.shipped.toml"]
+            .replace("priority = 5\n", "priority = 5\ncredential = \"eu\"\n");
+        files.insert("events/order.shipped.toml".into(), event);
+        let bundle = Bundle::from_files(&files).unwrap();
+
+        match sync(&db, &TransportRegistry::new(), project_id, &bundle, true).await {
+            Err(SyncError::Invalid(message)) => assert_eq!(
+                message,
+                "event 'order.shipped': no credential named 'eu' for channel 'email'"
+            ),
+            other => panic!("expected invalid bundle, got {other:?}"),
+        }
+
+        notifico_db::repo::credential::insert_credential(
+            &db,
+            Uuid::now_v7(),
+            project_id,
+            "eu",
+            "email",
+            &json!({}),
+            &[7; 32],
+        )
+        .await
+        .unwrap();
+        sync(&db, &TransportRegistry::new(), project_id, &bundle, false)
+            .await
+            .unwrap();
+        assert_eq!(export(&db, project_id).await.unwrap(), bundle);
+    }
+}
@@ -5,14 +5,11 @@ use axum::{
     http::{StatusCode, header},
     response::{IntoResponse, Redirect, Response},
 };
-use base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD};
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
+use notifico_core::tracking::verify_tracking_token;
 use uuid::Uuid;
 
 use crate::AppState;
-
-type HmacSha256 = Hmac<Sha256>;
+use crate::webhooks;
 
 /// 1x1 transparent GIF (43 bytes).
 const TRANSPARENT_GIF: [u8; 43] = [
@@ -21,47 +18,6 @@ const TRANSPARENT_GIF: [u8; 43] = [
     0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
 ];
 
-/// Create a signed tracking token.
-///
-/// Token format: `base64url(json_payload).base64url(hmac_signature)`
-pub fn create_tracking_token(delivery_log_id: &str, url: Option<&str>, key: &[u8; 32]) -> String {
-    let payload = match url {
-        Some(u) => serde_json::json!({"d": delivery_log_id, "u": u}),
-        None => serde_json::json!({"d": delivery_log_id}),
-    };
-    let payload_bytes = serde_json::to_vec(&payload).expect("JSON serialization cannot fail");
-    let encoded_payload = URL_SAFE_NO_PAD.encode(&payload_bytes);
-
-    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC key length is always valid");
-    mac.update(encoded_payload.as_bytes());
-    let signature = mac.finalize().into_bytes();
-    let encoded_sig = URL_SAFE_NO_PAD.encode(signature);
-
-    format!("{encoded_payload}.{encoded_sig}")
-}
-
-/// Verify a tracking token and return (delivery_log_id, optional_url).
-pub fn verify_tracking_token(token: &str, key: &[u8; 32]) -> Option<(String, Option<String>)> {
-    let (encoded_payload, encoded_sig) = token.split_once('.')?;
-
-    let signature = URL_SAFE_NO_PAD.decode(encoded_sig).ok()?;
-
-    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC key length is always valid");
-    mac.update(encoded_payload.as_bytes());
-    mac.verify_slice(&signature).ok()?;
-
-    let payload_bytes = URL_SAFE_NO_PAD.decode(encoded_payload).ok()?;
-    let payload: serde_json::Value = serde_json::from_slice(&payload_bytes).ok()?;
-
-    let delivery_log_id = payload.get("d")?.as_str()?.to_string();
-    let url = payload
-        .get("u")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    Some((delivery_log_id, url))
-}
-
 /// Handle open-tracking pixel requests.
 ///
 /// Always returns the 1x1 GIF regardless of token validity (don't leak info).
@@ -70,16 +26,20 @@ pub async fn handle_open(
     Path(token): Path<String>,
 ) -> Response {
     if let Some(key) = &state.encryption_key {
-        if let Some((delivery_log_id, _)) = verify_tracking_token(&token, key) {
+        if let Some((task_id, _)) = verify_tracking_token(&token, key) {
             let id = Uuid::now_v7();
-            let _ = notifico_db::repo::tracking::insert_tracking_event(
+            if notifico_db::repo::tracking::insert_tracking_event(
                 &state.db,
                 id,
-                &delivery_log_id,
+                &task_id,
                 "open",
                 None,
             )
-            .await;
+            .await
+            .is_ok()
+            {
+                webhooks::emit_tracking(&state.db, &task_id, "open", None).await;
+            }
         }
     }
 
@@ -103,81 +63,24 @@ pub async fn handle_click(
         None => return StatusCode::BAD_REQUEST.into_response(),
     };
 
-    let (delivery_log_id, url) = match verify_tracking_token(&token, key) {
+    let (task_id, url) = match verify_tracking_token(&token, key) {
         Some((d, Some(u))) => (d, u),
         _ => return StatusCode::BAD_REQUEST.into_response(),
     };
 
     let id = Uuid::now_v7();
-    let _ = notifico_db::repo::tracking::insert_tracking_event(
+    if notifico_db::repo::tracking::insert_tracking_event(
         &state.db,
         id,
-        &delivery_log_id,
+        &task_id,
         "click",
         Some(&url),
     )
-    .await;
-
-    Redirect::temporary(&url).into_response()
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test_key() -> [u8; 32] {
-        [0xAB; 32]
-    }
-
-    #[test]
-    fn token_roundtrip_open() {
-        let key = test_key();
-        let token = create_tracking_token("delivery-123", None, &key);
-        let result = verify_tracking_token(&token, &key);
-        assert!(result.is_some());
-        let (d, u) = result.unwrap();
-        assert_eq!(d, "delivery-123");
-        assert!(u.is_none());
-    }
-
-    #[test]
-    fn token_roundtrip_click() {
-        let key = test_key();
-        let url = "https://example.com/landing?ref=abc";
-        let token = create_tracking_token("delivery-456", Some(url), &key);
-        let result = verify_tracking_token(&token, &key);
-        assert!(result.is_some());
-        let (d, u) = result.unwrap();
-        assert_eq!(d, "delivery-456");
-        assert_eq!(u.unwrap(), url);
+    .await
+    .is_ok()
+    {
+        webhooks::emit_tracking(&state.db, &task_id, "click", Some(&url)).await;
     }
 
-    #[test]
-    fn tampered_token_fails() {
-        let key = test_key();
-        let token = create_tracking_token("delivery-123", None, &key);
-        // Tamper with the payload
-        let tampered = format!("x{token}");
-        assert!(verify_tracking_token(&tampered, &key).is_none());
-    }
-
-    #[test]
-    fn wrong_key_fails() {
-        let key = test_key();
-        let token = create_tracking_token("delivery-123", None, &key);
-        let wrong_key = [0xCD; 32];
-        assert!(verify_tracking_token(&token, &wrong_key).is_none());
-    }
-
-    #[test]
-    fn missing_dot_fails() {
-        let key = test_key();
-        assert!(verify_tracking_token("nodothere", &key).is_none());
-    }
-
-    #[test]
-    fn empty_token_fails() {
-        let key = test_key();
-        assert!(verify_tracking_token("", &key).is_none());
-    }
+    Redirect::temporary(&url).into_response()
 }
@@ -0,0 +1,371 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sea_orm::DatabaseConnection;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use notifico_db::repo::{self, delivery_log::DeliveryLogRow, webhook::WebhookDeliveryRow};
+
+use crate::AppState;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Attempts per webhook delivery before it is marked failed.
+const MAX_ATTEMPTS: i32 = 5;
+
+/// Event types clients can subscribe to.
+pub const EVENT_TYPES: &[&str] = &[
+    "delivery.delivered",
+    "delivery.failed",
+    "delivery.undelivered",
+    "delivery.bounced",
+    "tracking.open",
+    "tracking.click",
+];
+
+/// Map a delivery log status onto a webhook event type.
+///
+/// Only terminal or provider-reported statuses notify subscribers; "queued"
+/// (pending retry) does not.
+fn delivery_event_type(status: &str) -> Option<&'static str> {
+    match status {
+        "delivered" => Some("delivery.delivered"),
+        "failed" => Some("delivery.failed"),
+        "undelivered" => Some("delivery.undelivered"),
+        "bounced" => Some("delivery.bounced"),
+        _ => None,
+    }
+}
+
+/// Queue a webhook delivery for every subscription of the project that wants
+/// this event type. Errors are logged, never propagated.
+pub(crate) async fn emit(db: &DatabaseConnection, project_id: Uuid, event_type: &str, data: Value) {
+    let subscriptions = match repo::webhook::list_active_for_event(db, project_id, event_type).await
+    {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load webhook subscriptions");
+            return;
+        }
+    };
+
+    for subscription in subscriptions {
+        let id = Uuid::now_v7();
+        let payload = json!({
+            "id": id,
+            "type": event_type,
+            "project_id": project_id,
+            "data": data,
+        });
+        if let Err(e) = repo::webhook::enqueue_delivery(
+            db,
+            id,
+            subscription.id,
+            event_type,
+            &payload,
+            MAX_ATTEMPTS,
+        )
+        .await
+        {
+            tracing::error!(
+                subscription_id = %subscription.id,
+                error = %e,
+                "Failed to enqueue webhook delivery"
+            );
+        }
+    }
+}
+
+/// Notify subscribers about a delivery log status change.
+pub(crate) async fn emit_delivery_log(db: &DatabaseConnection, log: &DeliveryLogRow) {
+    let Some(event_type) = delivery_event_type(&log.status) else {
+        return;
+    };
+    let data = json!({
+        "delivery_log_id": log.id,
+        "event_name": log.event_name,
+        "recipient_id": log.recipient_id,
+        "channel": log.channel,
+        "status": log.status,
+        "error_message": log.error_message,
+        "provider_message_id": log.provider_message_id,
+    });
+    emit(db, log.project_id, event_type, data).await;
+}
+
+/// Notify subscribers about an open/click tracking event.
+///
+/// Tracking tokens carry the delivery task ID, so the project is resolved
+/// from the delivery log if one matches, otherwise from the task.
+pub(crate) async fn emit_tracking(
+    db: &DatabaseConnection,
+    delivery_id: &str,
+    tracking_type: &str,
+    url: Option<&str>,
+) {
+    let Ok(id) = Uuid::parse_str(delivery_id) else {
+        return;
+    };
+
+    let project_id = match repo::delivery_log::get_log(db, id).await {
+        Ok(Some(log)) => Some(log.project_id),
+        _ => match repo::queue::get_task(db, id).await {
+            Ok(Some(task)) => Some(task.project_id),
+            _ => None,
+        },
+    };
+    let Some(project_id) = project_id else {
+        return;
+    };
+
+    let data = json!({
+        "delivery_id": delivery_id,
+        "url": url,
+    });
+    emit(db, project_id, &format!("tracking.{tracking_type}"), data).await;
+}
+
+/// Compute the `X-Notifico-Signature` header value for a payload.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Run the webhook dispatcher loop: claim due deliveries, POST them, and
+/// record the outcome with retry/backoff.
+pub async fn run_webhook_dispatcher(state: Arc<AppState>) {
+    let poll_interval = Duration::from_secs(2);
+    let client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build HTTP client");
+
+    tracing::info!("Webhook dispatcher started");
+
+    loop {
+        let deliveries = match repo::webhook::claim_pending_deliveries(&state.db, 20).await {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to claim webhook deliveries");
+                tokio::time::sleep(poll_interval).await;
+                continue;
+            }
+        };
+
+        if deliveries.is_empty() {
+            tokio::time::sleep(poll_interval).await;
+            continue;
+        }
+
+        for delivery in &deliveries {
+            dispatch(&state.db, &client, delivery, state.encryption_key.as_ref()).await;
+        }
+    }
+}
+
+/// Send one webhook delivery and record the result. The subscription's
+/// secret is decrypted with the server key to sign the payload.
+pub(crate) async fn dispatch(
+    db: &DatabaseConnection,
+    client: &Client,
+    delivery: &WebhookDeliveryRow,
+    key: Option<&[u8; 32]>,
+) {
+    let secret = match key {
+        Some(key) => repo::webhook::decrypt_secret(&delivery.encrypted_secret, key)
+            .map_err(|e| format!("Cannot decrypt webhook secret: {e}")),
+        None => Err("Encryption key not configured".to_string()),
+    };
+    let result = match secret {
+        Ok(secret) => {
+            let body = delivery.payload.to_string();
+            let signature = sign_payload(&secret, body.as_bytes());
+            client
+                .post(&delivery.url)
+                .header("content-type", "application/json")
+                .header("X-Notifico-Event", &delivery.event_type)
+                .header("X-Notifico-Delivery", delivery.id.to_string())
+                .header("X-Notifico-Signature", signature)
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| e.to_string())
+        }
+        Err(error) => Err(error),
+    };
+
+    let outcome = match result {
+        Ok(resp) if resp.status().is_success() => {
+            repo::webhook::mark_delivery_succeeded(db, delivery.id, resp.status().as_u16() as i32)
+                .await
+        }
+        Ok(resp) => {
+            let status = resp.status().as_u16();
+            tracing::warn!(
+                delivery_id = %delivery.id,
+                url = %delivery.url,
+                status = status,
+                "Webhook endpoint returned error"
+            );
+            repo::webhook::mark_delivery_failed(
+                db,
+                delivery.id,
+                Some(status as i32),
+                &format!("HTTP {status}"),
+                delivery.attempt,
+                delivery.max_attempts,
+            )
+            .await
+        }
+        Err(e) => {
+            tracing::warn!(
+                delivery_id = %delivery.id,
+                url = %delivery.url,
+                error = %e,
+                "Webhook request failed"
+            );
+            repo::webhook::mark_delivery_failed(
+                db,
+                delivery.id,
+                None,
+                &e,
+                delivery.attempt,
+                delivery.max_attempts,
+            )
+            .await
+        }
+    };
+
+    if let Err(e) = outcome {
+        tracing::error!(delivery_id = %delivery.id, error = %e, "Failed to record webhook result");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delivery_status_mapping() {
+        assert_eq!(delivery_event_type("delivered"), Some("delivery.delivered"));
+        assert_eq!(delivery_event_type("bounced"), Some("delivery.bounced"));
+        assert_eq!(delivery_event_type("queued"), None);
+        for event_type in ["delivered", "failed", "undelivered", "bounced"]
+            .iter()
+            .filter_map(|s| delivery_event_type(s))
+        {
+            assert!(EVENT_TYPES.contains(&event_type));
+        }
+    }
+
+    #[test]
+    fn signature_matches_webhook_transport_format() {
+        let signature = sign_payload("test-secret", br#"{"hello":"world"}"#);
+        let hex = signature.strip_prefix("sha256=").unwrap();
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(
+            signature,
+            sign_payload("test-secret", br#"{"hello":"world"}"#)
+        );
+        assert_ne!(signature, sign_payload("other", br#"{"hello":"world"}"#));
+    }
+
+    const KEY: [u8; 32] = [0x24; 32];
+
+    #[tokio::test]
+    async fn dispatch_signs_payload_and_records_result() {
+        use axum::{Router, http::HeaderMap, routing::post};
+        use sea_orm::ConnectionTrait;
+        use std::sync::Mutex;
+
+        let received: Arc<Mutex<Vec<(HeaderMap, String)>>> = Arc::default();
+        let sink = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| async move {
+                sink.lock().unwrap().push((headers, body));
+                axum::http::StatusCode::NO_CONTENT
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let db = notifico_db::connect("sqlite::memory:").await.unwrap();
+        notifico_db::run_migrations(&db).await.unwrap();
+        let project_id = Uuid::now_v7();
+        db.execute_unprepared(&format!(
+            "INSERT INTO project (id, name) VALUES ('{project_id}', 'test')"
+        ))
+        .await
+        .unwrap();
+        let sub_id = Uuid::now_v7();
+        repo::webhook::insert_subscription(
+            &db,
+            sub_id,
+            project_id,
+            &format!("http://{addr}/hook"),
+            &["tracking.click".to_string()],
+            "whsec_test",
+            &KEY,
+        )
+        .await
+        .unwrap();
+
+        // Not subscribed to deliveries: nothing is queued
+        emit(&db, project_id, "delivery.delivered", json!({})).await;
+        emit(
+            &db,
+            project_id,
+            "tracking.click",
+            json!({"url": "https://example.com"}),
+        )
+        .await;
+
+        let claimed = repo::webhook::claim_pending_deliveries(&db, 10)
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+        dispatch(&db, &Client::new(), &claimed[0], Some(&KEY)).await;
+
+        let (headers, body) = received.lock().unwrap().pop().unwrap();
+        assert_eq!(headers["x-notifico-event"], "tracking.click");
+        assert_eq!(
+            headers["x-notifico-signature"].to_str().unwrap(),
+            sign_payload("whsec_test", body.as_bytes())
+        );
+        let payload: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(payload["type"], "tracking.click");
+        assert_eq!(payload["data"]["url"], "https://example.com");
+
+        let history = repo::webhook::list_deliveries(&db, sub_id, 50, 0)
+            .await
+            .unwrap();
+        assert_eq!(history[0].status, "delivered");
+        assert_eq!(history[0].response_status, Some(204));
+
+        // Without the key the secret cannot be read, so nothing is sent
+        emit(&db, project_id, "tracking.click", json!({})).await;
+        let claimed = repo::webhook::claim_pending_deliveries(&db, 10)
+            .await
+            .unwrap();
+        dispatch(&db, &Client::new(), &claimed[0], None).await;
+        assert!(received.lock().unwrap().is_empty());
+        let history = repo::webhook::list_deliveries(&db, sub_id, 50, 0)
+            .await
+            .unwrap();
+        let failed = history.iter().find(|d| d.id == claimed[0].id).unwrap();
+        assert_eq!(failed.status, "pending");
+        assert_eq!(
+            failed.error_message.as_deref(),
+            Some("Encryption key not configured")
+        );
+    }
+}
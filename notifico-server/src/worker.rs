@@ -6,6 +6,7 @@ use uuid::Uuid;
 use notifico_core::channel::ChannelId;
 use notifico_core::middleware::MiddlewareRegistry;
 use notifico_core::registry::TransportRegistry;
+use notifico_core::storage::Storage;
 use notifico_core::transport::{DeliveryResult, RenderedMessage};
 use notifico_db::repo;
 use notifico_queue::DeliveryTask;
@@ -49,7 +50,7 @@ pub async fn run_worker_loop(state: Arc<AppState>) {
                 for task_row in &tasks {
                     let delivery_task = task_row_to_delivery_task(task_row);
 
-                    match process_delivery(&delivery_task, &state.registry, &state.middleware_registry, &state.db, state.encryption_key.as_ref()).await {
+                    match process_delivery(&delivery_task, &state.registry, &state.middleware_registry, &state.db, state.storage.as_ref(), state.encryption_key.as_ref()).await {
                         Ok(()) => {
                             if let Err(e) =
                                 repo::queue::mark_completed(&state.db, task_row.id).await
@@ -102,7 +103,7 @@ async fn shutdown_signal() {
     }
 }
 
-fn task_row_to_delivery_task(row: &repo::queue::TaskRow) -> DeliveryTask {
+pub(crate) fn task_row_to_delivery_task(row: &repo::queue::TaskRow) -> DeliveryTask {
     DeliveryTask {
         id: row.id,
         project_id: row.project_id,
@@ -115,13 +116,21 @@ fn task_row_to_delivery_task(row: &repo::queue::TaskRow) -> DeliveryTask {
         rule_id: row.rule_id,
         attempt: row.attempt as u32,
         max_attempts: row.max_attempts as u32,
+        attachments: serde_json::from_value(row.attachments.clone()).unwrap_or_else(|e| {
+            tracing::warn!(task_id = %row.id, error = %e, "Invalid attachment references, ignoring");
+            vec![]
+        }),
+        template_version: row.template_version,
+        experiment: row.experiment.clone(),
+        variant: row.variant.clone(),
+        credential: row.credential.clone(),
     }
 }
 
 /// Process a single delivery task.
 #[tracing::instrument(
     name = "process_delivery",
-    skip(task, registry, middleware_registry, db, encryption_key),
+    skip(task, registry, middleware_registry, db, storage, encryption_key),
     fields(task_id = %task.id, channel = %task.channel, recipient_id = %task.recipient_id)
 )]
 pub async fn process_delivery(
@@ -129,6 +138,7 @@ pub async fn process_delivery(
     registry: &TransportRegistry,
     middleware_registry: &MiddlewareRegistry,
     db: &DatabaseConnection,
+    storage: &dyn Storage,
     encryption_key: Option<&[u8; 32]>,
 ) -> Result<(), String> {
     tracing::info!(
@@ -145,11 +155,23 @@ pub async fn process_delivery(
         .get(&channel_id)
         .ok_or_else(|| format!("Transport not found for channel: {}", task.channel))?;
 
-    // Resolve credentials for this transport
-    let credentials = if let Some(key) = encryption_key {
-        match repo::credential::find_credential(db, task.project_id, &task.channel, key).await {
-            Ok(Some(cred)) => cred.data,
+    // Resolve credentials for this transport: the one the rule names, or the
+    // channel's project default
+    let (credentials, credential_name) = if let Some(key) = encryption_key {
+        let name = task.credential.as_deref();
+        match repo::credential::find_credential(db, task.project_id, &task.channel, name, key)
+            .await
+        {
+            Ok(Some(cred)) => (cred.data, Some(cred.name)),
             Ok(None) => {
+                // A rule that names a credential never falls back to another one
+                if let Some(name) = name {
+                    return Err(format!(
+                        "Credential '{name}' not found for channel '{}' in project {}",
+                        task.channel, task.project_id
+                    ));
+                }
+
                 // Check if transport requires credentials
                 let schema = transport.credential_schema();
                 if schema.fields.iter().any(|f| f.required) {
@@ -158,16 +180,21 @@ pub async fn process_delivery(
                         task.channel, task.project_id
                     ));
                 }
-                serde_json::json!({})
+                (serde_json::json!({}), None)
+            }
+            // Retried later rather than sent without the credential the rule names
+            Err(e) if name.is_some() => {
+                return Err(format!("Failed to resolve credential: {e}"));
             }
             Err(e) => {
                 tracing::warn!(error = %e, "Failed to resolve credentials, proceeding without");
-                serde_json::json!({})
+                (serde_json::json!({}), None)
             }
         }
     } else {
-        serde_json::json!({})
+        (serde_json::json!({}), None)
     };
+    let credential_name = credential_name.as_deref();
 
     // Fetch middleware config if the task has a rule_id
     let mw_entries = if let Some(rule_id) = task.rule_id {
@@ -182,13 +209,20 @@ pub async fn process_delivery(
         vec![]
     };
 
+    // Load attachment content from storage
+    let attachments =
+        crate::attachments::load_attachments(db, storage, task.project_id, &task.attachments)
+            .await
+            .map_err(|e| format!("Failed to load attachments: {e}"))?;
+
     // Build RenderedMessage from task
     let mut message = RenderedMessage {
         channel: channel_id,
         recipient_contact: task.contact_value.clone(),
         content: task.rendered_body.clone(),
         credentials,
-        attachments: vec![],
+        attachments,
+        metadata: Default::default(),
     };
 
     // Run pre-send middleware
@@ -220,6 +254,9 @@ pub async fn process_delivery(
         }
     }
 
+    // Internal `_` fields never go out as content
+    message.split_metadata();
+
     // Send via transport
     let result = transport.send(&message).await;
 
@@ -254,7 +291,15 @@ pub async fn process_delivery(
             DeliveryResult::Delivered {
                 provider_message_id,
             } => {
-                log_delivery(db, task, "delivered", None).await;
+                log_delivery(
+                    db,
+                    task,
+                    credential_name,
+                    "delivered",
+                    None,
+                    provider_message_id.as_deref(),
+                )
+                .await;
                 tracing::info!(
                     task_id = %task.id,
                     provider_id = ?provider_message_id,
@@ -268,7 +313,7 @@ pub async fn process_delivery(
                 } else {
                     "failed"
                 };
-                log_delivery(db, task, status, Some(&error)).await;
+                log_delivery(db, task, credential_name, status, Some(&error), None).await;
 
                 if retryable && task.attempt < task.max_attempts {
                     Err(format!("Retryable failure: {error}"))
@@ -279,8 +324,14 @@ pub async fn process_delivery(
             }
         },
         Err(e) => {
+            // The task is retried, so the failure is only final on the last attempt
             let reason = e.to_string();
-            log_delivery(db, task, "failed", Some(&reason)).await;
+            let status = if task.attempt < task.max_attempts {
+                "queued"
+            } else {
+                "failed"
+            };
+            log_delivery(db, task, credential_name, status, Some(&reason), None).await;
             tracing::error!(task_id = %task.id, error = %reason, "Transport error");
             Err(reason)
         }
@@ -290,22 +341,34 @@ pub async fn process_delivery(
 async fn log_delivery(
     db: &DatabaseConnection,
     task: &DeliveryTask,
+    credential: Option<&str>,
     status: &str,
     error_message: Option<&str>,
+    provider_message_id: Option<&str>,
 ) {
-    if let Err(e) = repo::delivery_log::insert_log(
-        db,
-        Uuid::now_v7(),
-        task.project_id,
-        &task.event_name,
-        task.recipient_id,
-        &task.channel,
+    let log_id = Uuid::now_v7();
+    let log = repo::delivery_log::NewDeliveryLog {
+        id: log_id,
+        task_id: Some(task.id),
+        project_id: task.project_id,
+        event_name: &task.event_name,
+        recipient_id: task.recipient_id,
+        channel: &task.channel,
         status,
         error_message,
-        (task.attempt + 1) as i32,
-    )
-    .await
-    {
+        attempts: (task.attempt + 1) as i32,
+        provider_message_id,
+        template_version: task.template_version,
+        experiment: task.experiment.as_deref(),
+        variant: task.variant.as_deref(),
+        credential,
+    };
+    if let Err(e) = repo::delivery_log::insert_log(db, &log).await {
         tracing::error!(error = %e, "Failed to log delivery result");
+        return;
+    }
+
+    if let Ok(Some(log)) = repo::delivery_log::get_log(db, log_id).await {
+        crate::webhooks::emit_delivery_log(db, &log).await;
     }
 }
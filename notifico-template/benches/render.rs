@@ -0,0 +1,120 @@
+//! Rendering one template for a 10k-recipient broadcast, parsing every field
+//! per recipient versus reusing compiled fields from a `TemplateCache`.
+//!
+//! Run with `cargo bench -p notifico-template`.
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use notifico_template::{
+    Localization, Partials, RenderSettings, TemplateCache, TemplateKey, Translations, render_body,
+};
+use serde_json::{Value, json};
+
+const RECIPIENTS: usize = 10_000;
+
+fn body() -> Value {
+    json!({
+        "subject": "{{ t('order.subject', id=order.id) }}",
+        "html": "{% extends 'layout' %}{% block content %}\
+                 <p>Hi {{ user.name | title }},</p>\
+                 <ul>{% for item in order.items %}\
+                 <li>{{ item.qty }} x {{ item.name }}: {{ item.price | currency('EUR') }}</li>\
+                 {% endfor %}</ul>\
+                 <p>Total: {{ order.total | currency('EUR') }}, placed {{ order.placed_at | datetime }}</p>\
+                 {% endblock %}",
+        "text": "Hi {{ user.name }}, your order {{ order.id }} of \
+                 {{ order.items | length | plural(one='# item', other='# items') }} is confirmed.",
+        "blocks": [
+            {"type": "header", "text": "Order {{ order.id }}"},
+            {"type": "section", "text": "{{ order.total | number(2) }} EUR"}
+        ]
+    })
+}
+
+fn partials() -> Partials {
+    Partials::new(BTreeMap::from([
+        (
+            "layout".to_string(),
+            "<html><body>{% block content %}{% endblock %}\
+         <footer>{% include 'footer' %}</footer></body></html>"
+                .to_string(),
+        ),
+        ("footer".to_string(), "Sent to {{ user.email }}".to_string()),
+    ]))
+    .unwrap()
+}
+
+fn recipients(partials: &Partials) -> Vec<(Value, RenderSettings)> {
+    let translations = Translations::new(BTreeMap::from([
+        (
+            "en".to_string(),
+            json!({"order": {"subject": "Order {id} confirmed"}}),
+        ),
+        (
+            "de".to_string(),
+            json!({"order": {"subject": "Bestellung {id} bestätigt"}}),
+        ),
+    ]));
+    (0..RECIPIENTS)
+        .map(|i| {
+            let context = json!({
+                "user": {"name": format!("user {i}"), "email": format!("user{i}@example.com")},
+                "order": {
+                    "id": 10_000 + i,
+                    "total": 42.5 + i as f64,
+                    "placed_at": "2026-01-05T14:04:05Z",
+                    "items": [
+                        {"name": "Widget", "qty": 2, "price": 10.0},
+                        {"name": "Gadget", "qty": 1, "price": 22.5 + i as f64}
+                    ]
+                }
+            });
+            let (locale, timezone) = if i % 2 == 0 {
+                ("en", "UTC")
+            } else {
+                ("de", "+01:00")
+            };
+            let settings = RenderSettings {
+                partials: partials.clone(),
+                localization: Localization::new(locale)
+                    .with_timezone(timezone)
+                    .with_translations(translations.clone()),
+                ..RenderSettings::default()
+            };
+            (context, settings)
+        })
+        .collect()
+}
+
+fn broadcast(c: &mut Criterion) {
+    let body = body();
+    let recipients = recipients(&partials());
+
+    let mut group = c.benchmark_group("broadcast_10k");
+    group.sample_size(10);
+
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            for (context, settings) in &recipients {
+                black_box(render_body(&body, context, settings).unwrap());
+            }
+        })
+    });
+
+    group.bench_function("cached", |b| {
+        let cache = TemplateCache::default();
+        let key = TemplateKey::new("version", "en");
+        b.iter(|| {
+            for (context, settings) in &recipients {
+                black_box(cache.render_body(&key, &body, context, settings).unwrap());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, broadcast);
+criterion_main!(benches);
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use minijinja::Environment;
+use serde_json::Value;
+
+use crate::{Partials, RenderOptions, RenderSettings, TemplateError, add_body_templates};
+
+/// Identifies one template content: a template version in one locale.
+/// Together with a field path inside the body it names a compiled template.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TemplateKey {
+    pub version_id: String,
+    pub locale: String,
+}
+
+impl TemplateKey {
+    pub fn new(version_id: impl Into<String>, locale: impl Into<String>) -> Self {
+        Self {
+            version_id: version_id.into(),
+            locale: locale.into(),
+        }
+    }
+}
+
+/// Compiled template bodies, so rendering the same content for many
+/// recipients parses each field once instead of once per recipient.
+///
+/// Entries are keyed by [`TemplateKey`] and hold every field of the body
+/// compiled into one environment. An entry is only reused while the body,
+/// partials and render options it was built from are unchanged, so a stale
+/// entry is never rendered; [`TemplateCache::invalidate_version`] drops
+/// entries early when content is edited. Once `capacity` entries are held,
+/// the least recently used one is evicted.
+///
+/// Cheap to clone; clones share the entries.
+#[derive(Clone)]
+pub struct TemplateCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    entries: Mutex<HashMap<TemplateKey, Arc<Compiled>>>,
+    capacity: usize,
+    clock: AtomicU64,
+}
+
+struct Compiled {
+    body: Value,
+    partials: Partials,
+    options: RenderOptions,
+    env: Environment<'static>,
+    last_used: AtomicU64,
+}
+
+impl Compiled {
+    fn matches(&self, body: &Value, partials: &Partials, options: &RenderOptions) -> bool {
+        self.options == *options && self.partials.same_sources(partials) && self.body == *body
+    }
+}
+
+impl Default for TemplateCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl fmt::Debug for TemplateCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TemplateCache")
+            .field("len", &self.len())
+            .field("capacity", &self.inner.capacity)
+            .finish()
+    }
+}
+
+impl TemplateCache {
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                entries: Mutex::new(HashMap::new()),
+                capacity: capacity.max(1),
+                clock: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Like [`crate::render_body`], compiling `body` on first use under `key`
+    /// and reusing the compiled fields afterwards.
+    pub fn render_body(
+        &self,
+        key: &TemplateKey,
+        body: &Value,
+        context: &Value,
+        settings: &RenderSettings,
+    ) -> Result<serde_json::Map<String, Value>, TemplateError> {
+        let RenderSettings {
+            partials,
+            localization,
+            options,
+        } = settings;
+        let obj = body
+            .as_object()
+            .ok_or_else(|| TemplateError::InvalidBody("body must be a JSON object".to_string()))?;
+
+        let compiled = match self.get(key, body, partials, options) {
+            Some(compiled) => compiled,
+            None => {
+                let mut env = partials.environment(options);
+                if add_body_templates(&mut env, obj).is_err() {
+                    // Leave reporting the syntax error to the uncached path
+                    return crate::render_body(body, context, settings);
+                }
+                self.insert(
+                    key.clone(),
+                    Compiled {
+                        body: body.clone(),
+                        partials: partials.clone(),
+                        options: *options,
+                        env,
+                        last_used: AtomicU64::new(0),
+                    },
+                )
+            }
+        };
+
+        localization.scope(|| {
+            crate::render_fields(
+                &compiled.env,
+                options,
+                crate::Source::Compiled,
+                obj,
+                context,
+            )
+        })
+    }
+
+    /// Drop the entries of a template version in every locale.
+    pub fn invalidate_version(&self, version_id: &str) {
+        self.entries().retain(|key, _| key.version_id != version_id);
+    }
+
+    /// Number of compiled template contents held.
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get(
+        &self,
+        key: &TemplateKey,
+        body: &Value,
+        partials: &Partials,
+        options: &RenderOptions,
+    ) -> Option<Arc<Compiled>> {
+        let compiled = self.entries().get(key).cloned()?;
+        if !compiled.matches(body, partials, options) {
+            return None;
+        }
+        compiled.last_used.store(self.tick(), Ordering::Relaxed);
+        Some(compiled)
+    }
+
+    fn insert(&self, key: TemplateKey, compiled: Compiled) -> Arc<Compiled> {
+        compiled.last_used.store(self.tick(), Ordering::Relaxed);
+        let compiled = Arc::new(compiled);
+        let mut entries = self.entries();
+        if entries.len() >= self.inner.capacity && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, c)| c.last_used.load(Ordering::Relaxed))
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, Arc::clone(&compiled));
+        compiled
+    }
+
+    fn tick(&self) -> u64 {
+        self.inner.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<TemplateKey, Arc<Compiled>>> {
+        // A panic while holding the lock leaves the map itself intact
+        self.inner
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Localization, Translations, render_body};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn render(
+        cache: &TemplateCache,
+        key: &TemplateKey,
+        body: &Value,
+        context: Value,
+    ) -> Result<Value, TemplateError> {
+        cache
+            .render_body(key, body, &context, &RenderSettings::default())
+            .map(Value::Object)
+    }
+
+    #[test]
+    fn cached_render_matches_uncached() {
+        let cache = TemplateCache::default();
+        let key = TemplateKey::new("v1", "en");
+        let body = json!({
+            "subject": "Hi {{ name }}",
+            "blocks": [{"text": "{{ items | length }} items"}],
+            "data": {"$json": "{{ items | tojson }}"},
+            "count": 3
+        });
+        for name in ["Ann", "Bob"] {
+            let context = json!({"name": name, "items": [1, 2]});
+            let cached = render(&cache, &key, &body, context.clone()).unwrap();
+            let uncached = render_body(&body, &context, &RenderSettings::default()).unwrap();
+            assert_eq!(cached, Value::Object(uncached));
+        }
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn localization_is_per_render() {
+        let cache = TemplateCache::default();
+        let key = TemplateKey::new("v1", "en");
+        let body = json!({"text": "{{ ts | time }} {{ t('hello') }}"});
+        let translations = Translations::new(BTreeMap::from([
+            ("en".to_string(), json!({"hello": "Hello"})),
+            ("de".to_string(), json!({"hello": "Hallo"})),
+        ]));
+        let context = json!({"ts": "2026-01-05T14:04:05Z"});
+
+        let render_for = |locale: &str, tz: &str| {
+            let settings = RenderSettings {
+                localization: Localization::new(locale)
+                    .with_timezone(tz)
+                    .with_translations(translations.clone()),
+                ..RenderSettings::default()
+            };
+            let cached = cache.render_body(&key, &body, &context, &settings).unwrap();
+            let uncached = render_body(&body, &context, &settings).unwrap();
+            assert_eq!(cached, uncached);
+            cached["text"].clone()
+        };
+        assert_eq!(render_for("en", "UTC"), "2:04\u{202f}PM Hello");
+        assert_eq!(render_for("de", "+01:00"), "15:04 Hallo");
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn changed_content_is_recompiled() {
+        let cache = TemplateCache::default();
+        let key = TemplateKey::new("v1", "en");
+        let context = json!({"name": "Ann"});
+
+        let out = render(
+            &cache,
+            &key,
+            &json!({"text": "Hi {{ name }}"}),
+            context.clone(),
+        );
+        assert_eq!(out.unwrap()["text"], "Hi Ann");
+        // Same key, edited body: the stale entry is not used
+        let out = render(
+            &cache,
+            &key,
+            &json!({"text": "Bye {{ name }}"}),
+            context.clone(),
+        );
+        assert_eq!(out.unwrap()["text"], "Bye Ann");
+
+        // Partials and options are part of what was compiled
+        let partials = Partials::new(BTreeMap::from([("sig".into(), "-- A".into())])).unwrap();
+        let body = json!({"text": "{% include 'sig' %}"});
+        let settings = RenderSettings {
+            partials,
+            ..RenderSettings::default()
+        };
+        let out = cache.render_body(&key, &body, &context, &settings);
+        assert_eq!(out.unwrap()["text"], "-- A");
+        let partials = Partials::new(BTreeMap::from([("sig".into(), "-- B".into())])).unwrap();
+        let settings = RenderSettings {
+            partials,
+            ..RenderSettings::default()
+        };
+        let out = cache.render_body(&key, &body, &context, &settings);
+        assert_eq!(out.unwrap()["text"], "-- B");
+    }
+
+    #[test]
+    fn invalidate_version_drops_all_locales() {
+        let cache = TemplateCache::default();
+        let body = json!({"text": "Hi"});
+        for key in [
+            TemplateKey::new("v1", "en"),
+            TemplateKey::new("v1", "de"),
+            TemplateKey::new("v2", "en"),
+        ] {
+            render(&cache, &key, &body, json!({})).unwrap();
+        }
+        assert_eq!(cache.len(), 3);
+        cache.invalidate_version("v1");
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let cache = TemplateCache::new(2);
+        let body = json!({"text": "Hi"});
+        let (a, b, c) = (
+            TemplateKey::new("a", "en"),
+            TemplateKey::new("b", "en"),
+            TemplateKey::new("c", "en"),
+        );
+        render(&cache, &a, &body, json!({})).unwrap();
+        render(&cache, &b, &body, json!({})).unwrap();
+        render(&cache, &a, &body, json!({})).unwrap();
+        render(&cache, &c, &body, json!({})).unwrap();
+
+        let entries = cache.entries();
+        assert!(entries.contains_key(&a));
+        assert!(!entries.contains_key(&b));
+        assert!(entries.contains_key(&c));
+    }
+
+    #[test]
+    fn syntax_errors_are_reported_and_not_cached() {
+        let cache = TemplateCache::default();
+        let key = TemplateKey::new("v1", "en");
+        let err = render(&cache, &key, &json!({"text": "{{ oops"}), json!({})).unwrap_err();
+        assert!(matches!(err, TemplateError::Render(_)));
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,880 @@
+//! Locale-aware template filters and the `t()` translation function.
+//!
+//! Registered on every rendering environment and driven by the recipient's
+//! locale and time zone, which are set per render with [`Localization::scope`]
+//! so compiled environments can be shared between recipients:
+//!
+//! - `value | datetime(style="medium", tz=none)`, `value | date(style)`,
+//!   `value | time(style="short")` — `style` is `short`, `medium`, `long`,
+//!   `full`, or a strftime pattern (`%B`, `%b`, `%A`, `%a`, `%p` are
+//!   localized). Values are RFC 3339 strings, `YYYY-MM-DD` dates, unix
+//!   timestamps or `"now"`.
+//! - `value | number(decimals=none)` and `value | currency("EUR")`.
+//! - `count | plural(one="# item", other="# items")` — CLDR plural categories,
+//!   `#` is replaced with the formatted count.
+//! - `value | relative_time(now=none)` — "in 3 days", "2 hours ago".
+//! - `t("key", name=..., count=...)` — looks `key` up in the project
+//!   translation catalogs and formats it as an ICU message (`{name}`,
+//!   `{count, plural, =0 {none} one {# item} other {# items}}`,
+//!   `{kind, select, a {...} other {...}}`, `{n, number}`).
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike, Utc};
+use minijinja::value::{Kwargs, ValueKind};
+use minijinja::{Environment, Error, ErrorKind, Value};
+
+use crate::locale_data::{self, Length, LocaleData};
+use crate::tz;
+
+/// Per-project translation catalogs, keyed by locale, with flattened
+/// dot-separated message keys.
+///
+/// Cheap to clone; the catalogs are shared.
+#[derive(Debug, Clone, Default)]
+pub struct Translations {
+    catalogs: Arc<BTreeMap<String, BTreeMap<String, String>>>,
+    fallback: Vec<String>,
+}
+
+impl Translations {
+    /// Build from per-locale catalogs. Nested objects are flattened into
+    /// dotted keys (`{"order": {"shipped": "..."}}` → `order.shipped`);
+    /// non-string leaves are ignored.
+    pub fn new(catalogs: BTreeMap<String, serde_json::Value>) -> Self {
+        let catalogs = catalogs
+            .into_iter()
+            .map(|(locale, messages)| (locale_key(&locale), flatten_catalog(&messages)))
+            .collect();
+        Self {
+            catalogs: Arc::new(catalogs),
+            fallback: Vec::new(),
+        }
+    }
+
+    /// Locales to try after the recipient locale and its parents, typically
+    /// the project and server defaults.
+    pub fn with_fallback(mut self, locales: Vec<String>) -> Self {
+        self.fallback = locales;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.catalogs.is_empty()
+    }
+
+    /// Look a message up for `locale`, walking its parents and then the
+    /// fallback locales.
+    pub fn lookup(&self, locale: &str, key: &str) -> Option<&str> {
+        std::iter::once(locale)
+            .chain(self.fallback.iter().map(String::as_str))
+            .flat_map(parents)
+            .find_map(|l| self.catalogs.get(&l)?.get(key))
+            .map(String::as_str)
+    }
+}
+
+/// Flatten a nested JSON catalog into dotted keys.
+pub fn flatten_catalog(messages: &serde_json::Value) -> BTreeMap<String, String> {
+    fn walk(prefix: &str, value: &serde_json::Value, out: &mut BTreeMap<String, String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (k, v) in map {
+                    let key = match prefix {
+                        "" => k.clone(),
+                        p => format!("{p}.{k}"),
+                    };
+                    walk(&key, v, out);
+                }
+            }
+            serde_json::Value::String(s) => {
+                out.insert(prefix.to_string(), s.clone());
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    walk("", messages, &mut out);
+    out
+}
+
+/// Check every message of a catalog for ICU syntax errors. Returns the
+/// offending keys with their errors.
+pub fn check_catalog(messages: &serde_json::Value) -> Vec<(String, String)> {
+    let data = locale_data::for_locale(["en"]).ok();
+    flatten_catalog(messages)
+        .into_iter()
+        .filter_map(|(key, message)| {
+            parse_message(&message, &BTreeMap::new(), data.as_deref(), true)
+                .err()
+                .map(|e| (key, e))
+        })
+        .collect()
+}
+
+fn locale_key(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+/// `pt-BR` → `pt-br`, `pt`.
+fn parents(locale: &str) -> Vec<String> {
+    let key = locale_key(locale);
+    let mut subtags: Vec<&str> = key.split('-').filter(|s| !s.is_empty()).collect();
+    let mut result = Vec::new();
+    while !subtags.is_empty() {
+        result.push(subtags.join("-"));
+        subtags.pop();
+    }
+    result
+}
+
+thread_local! {
+    /// Localization of the render running on this thread.
+    static CURRENT: RefCell<Option<Localization>> = const { RefCell::new(None) };
+}
+
+/// Recipient locale, time zone and project catalogs used by the filters.
+#[derive(Debug, Clone)]
+pub struct Localization {
+    pub locale: String,
+    /// IANA zone name or fixed offset; unknown zones are a render error.
+    pub timezone: String,
+    pub translations: Translations,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new("en")
+    }
+}
+
+impl Localization {
+    pub fn new(locale: impl Into<String>) -> Self {
+        Self {
+            locale: locale.into(),
+            timezone: "UTC".to_string(),
+            translations: Translations::default(),
+        }
+    }
+
+    pub fn with_timezone(mut self, timezone: impl Into<String>) -> Self {
+        self.timezone = timezone.into();
+        self
+    }
+
+    pub fn with_translations(mut self, translations: Translations) -> Self {
+        self.translations = translations;
+        self
+    }
+
+    /// CLDR formatting data for the locale. A tag that is not a well-formed
+    /// locale falls back to the translation fallback locales (the project
+    /// and server defaults), then to the CLDR root locale.
+    fn data(&self) -> Result<Rc<LocaleData>, Error> {
+        let candidates = std::iter::once(self.locale.as_str())
+            .chain(self.translations.fallback.iter().map(String::as_str));
+        locale_data::for_locale(candidates).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidOperation,
+                format!("no formatting data for locale '{}': {e}", self.locale),
+            )
+        })
+    }
+
+    /// Make this the localization used by the filters and `t()` while `f` runs
+    /// on the current thread.
+    pub(crate) fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Restore(Option<Localization>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+            }
+        }
+
+        let _restore = Restore(CURRENT.with(|current| current.replace(Some(self.clone()))));
+        f()
+    }
+
+    /// Run `f` with the localization of the enclosing [`Localization::scope`],
+    /// or the default one outside any scope.
+    fn with_current<R>(f: impl FnOnce(&Localization) -> R) -> R {
+        CURRENT.with(|current| match current.borrow().as_ref() {
+            Some(l) => f(l),
+            None => f(&Localization::default()),
+        })
+    }
+
+    /// Register the filters and `t()` on a rendering environment.
+    pub(crate) fn register(env: &mut Environment<'static>) {
+        fn datetime_filter(
+            part: Part,
+        ) -> impl Fn(Value, Option<String>, Kwargs) -> Result<String, Error> + Send + Sync + 'static
+        {
+            move |value, style, kwargs| {
+                let tz: Option<String> = kwargs.get("tz")?;
+                kwargs.assert_all_used()?;
+                Localization::with_current(|l| {
+                    l.format_datetime(&value, style.as_deref(), tz.as_deref(), part)
+                })
+            }
+        }
+        fn current_data() -> Result<Rc<LocaleData>, Error> {
+            Localization::with_current(Localization::data)
+        }
+
+        env.add_filter("datetime", datetime_filter(Part::DateTime));
+        env.add_filter("date", datetime_filter(Part::Date));
+        env.add_filter("time", datetime_filter(Part::Time));
+        env.add_filter("number", |value: Value, decimals: Option<usize>| {
+            let n = to_f64(&value)?;
+            let data = current_data()?;
+            Ok::<_, Error>(match decimals {
+                Some(d) => data.format_number(n, d, d),
+                None => data.format_number(n, 0, 3),
+            })
+        });
+        env.add_filter("currency", |value: Value, code: String| {
+            Ok::<_, Error>(current_data()?.format_currency(to_f64(&value)?, &code))
+        });
+        env.add_filter("plural", |value: Value, kwargs: Kwargs| {
+            let n = to_f64(&value)?;
+            let data = current_data()?;
+            let category = data.plural(n);
+            let form: Option<String> = match kwargs.get::<Option<String>>(category)? {
+                Some(form) => Some(form),
+                None => kwargs.get("other")?,
+            };
+            // Unused categories are fine here.
+            let form = form.ok_or_else(|| {
+                Error::new(
+                    ErrorKind::MissingArgument,
+                    format!("plural form for '{category}' or 'other' is required"),
+                )
+            })?;
+            Ok::<_, Error>(form.replace('#', &data.format_number(n, 0, 3)))
+        });
+        env.add_filter("relative_time", |value: Value, kwargs: Kwargs| {
+            let now: Option<Value> = kwargs.get("now")?;
+            kwargs.assert_all_used()?;
+            let target = parse_instant(&value)?.to_utc();
+            let now = match now {
+                Some(now) => parse_instant(&now)?.to_utc(),
+                None => Utc::now(),
+            };
+            current_data()?
+                .format_relative(target.timestamp() - now.timestamp())
+                .map_err(|e| Error::new(ErrorKind::InvalidOperation, e))
+        });
+        env.add_function("t", |key: String, kwargs: Kwargs| {
+            let args: BTreeMap<String, Value> = kwargs
+                .args()
+                .map(|name| Ok((name.to_string(), kwargs.get::<Value>(name)?)))
+                .collect::<Result<_, Error>>()?;
+            Localization::with_current(|l| match l.translations.lookup(&l.locale, &key) {
+                Some(message) => {
+                    format_message(message, &args, l.data().ok().as_deref()).map_err(|e| {
+                        Error::new(
+                            ErrorKind::InvalidOperation,
+                            format!("translation '{key}': {e}"),
+                        )
+                    })
+                }
+                None => Ok(key),
+            })
+        });
+    }
+
+    fn format_datetime(
+        &self,
+        value: &Value,
+        style: Option<&str>,
+        tz: Option<&str>,
+        part: Part,
+    ) -> Result<String, Error> {
+        let local = match parse_instant(value)? {
+            Instant::At(at) => {
+                let name = tz.unwrap_or(&self.timezone);
+                let zone = tz::resolve(name).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidOperation,
+                        format!("unknown time zone '{name}'"),
+                    )
+                })?;
+                at.with_timezone(&zone.offset_at(at)).naive_local()
+            }
+            Instant::Date(date) => date.and_hms_opt(0, 0, 0).expect("midnight"),
+        };
+        let data = self.data()?;
+
+        let length = match style.unwrap_or(match part {
+            Part::Time => "short",
+            _ => "medium",
+        }) {
+            "short" => Length::Short,
+            "medium" => Length::Medium,
+            "long" => Length::Long,
+            "full" => Length::Full,
+            custom => return format_strftime(&local, custom, &data),
+        };
+        let (date, time) = match part {
+            Part::Date => (Some(length), None),
+            Part::Time => (None, Some(length)),
+            Part::DateTime => (Some(length), Some(length)),
+        };
+        data.format_datetime(&local, date, time)
+            .map_err(|e| Error::new(ErrorKind::InvalidOperation, e))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Part {
+    Date,
+    Time,
+    DateTime,
+}
+
+enum Instant {
+    At(DateTime<Utc>),
+    /// A calendar date without a time, formatted as is in any zone.
+    Date(NaiveDate),
+}
+
+impl Instant {
+    fn to_utc(&self) -> DateTime<Utc> {
+        match self {
+            Instant::At(at) => *at,
+            Instant::Date(date) => date.and_hms_opt(0, 0, 0).expect("midnight").and_utc(),
+        }
+    }
+}
+
+fn parse_instant(value: &Value) -> Result<Instant, Error> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("cannot interpret {value} as a date or time"),
+        )
+    };
+
+    if value.kind() == ValueKind::Number {
+        let secs = f64::try_from(value.clone()).map_err(|_| invalid())?;
+        return DateTime::from_timestamp_millis((secs * 1000.0) as i64)
+            .map(Instant::At)
+            .ok_or_else(invalid);
+    }
+
+    let s = value.as_str().ok_or_else(invalid)?.trim();
+    if s == "now" {
+        return Ok(Instant::At(Utc::now()));
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+        return Ok(Instant::At(at.to_utc()));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(Instant::At(naive.and_utc()));
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(Instant::Date)
+        .map_err(|_| invalid())
+}
+
+fn to_f64(value: &Value) -> Result<f64, Error> {
+    if let Some(n) = value.as_str().and_then(|s| s.trim().parse::<f64>().ok()) {
+        return Ok(n);
+    }
+    f64::try_from(value.clone()).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("cannot interpret {value} as a number"),
+        )
+    })
+}
+
+/// strftime with month, weekday and AM/PM names taken from the locale.
+fn format_strftime(dt: &NaiveDateTime, pattern: &str, data: &LocaleData) -> Result<String, Error> {
+    let mut localized = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            localized.push(c);
+            continue;
+        }
+        let Some(spec) = chars.next() else {
+            localized.push('%');
+            break;
+        };
+        let name = match spec {
+            'B' => data.month(dt.month0(), false),
+            'b' | 'h' => data.month(dt.month0(), true),
+            'A' => data.weekday(dt.weekday().num_days_from_sunday(), false),
+            'a' => data.weekday(dt.weekday().num_days_from_sunday(), true),
+            'p' => data.day_period(dt.hour() >= 12),
+            _ => {
+                localized.push('%');
+                localized.push(spec);
+                continue;
+            }
+        };
+        localized.push_str(&name.replace('%', "%%"));
+    }
+
+    let items: Vec<Item> = StrftimeItems::new(&localized).collect();
+    if items.iter().any(|i| matches!(i, Item::Error)) {
+        return Err(Error::new(
+            ErrorKind::InvalidOperation,
+            format!("invalid date format '{pattern}'"),
+        ));
+    }
+    use std::fmt::Write;
+    let mut out = String::new();
+    write!(out, "{}", dt.format_with_items(items.into_iter())).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("invalid date format '{pattern}'"),
+        )
+    })?;
+    Ok(out)
+}
+
+/// Format an ICU MessageFormat message (simple, `number`, `plural` and
+/// `select` arguments; apostrophe quoting). Without locale `data` only
+/// simple and `select` arguments can be formatted.
+fn format_message(
+    message: &str,
+    args: &BTreeMap<String, Value>,
+    data: Option<&LocaleData>,
+) -> Result<String, String> {
+    parse_message(message, args, data, false)
+}
+
+/// With `check`, missing arguments count as `0` so only syntax errors fail.
+fn parse_message(
+    message: &str,
+    args: &BTreeMap<String, Value>,
+    data: Option<&LocaleData>,
+    check: bool,
+) -> Result<String, String> {
+    let chars: Vec<char> = message.chars().collect();
+    let mut parser = MessageParser {
+        chars: &chars,
+        pos: 0,
+        args,
+        data,
+        check,
+    };
+    let out = parser.message(None, false)?;
+    if parser.pos < chars.len() {
+        return Err(format!("unexpected '}}' at {}", parser.pos));
+    }
+    Ok(out)
+}
+
+struct MessageParser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    args: &'a BTreeMap<String, Value>,
+    data: Option<&'a LocaleData>,
+    check: bool,
+}
+
+impl MessageParser<'_> {
+    fn data(&self) -> Result<&LocaleData, String> {
+        self.data
+            .ok_or_else(|| "no number or plural data for this locale".to_string())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Text up to the end, or up to the closing `}` of a branch when `nested`.
+    fn message(&mut self, count: Option<f64>, nested: bool) -> Result<String, String> {
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    self.pos += 1;
+                    out.push_str(&self.argument()?);
+                }
+                '}' if nested => return Ok(out),
+                '}' => return Ok(out),
+                '#' if count.is_some() => {
+                    self.pos += 1;
+                    out.push_str(&self.data()?.format_number(count.unwrap_or_default(), 0, 3));
+                }
+                '\'' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\'') => {
+                            self.pos += 1;
+                            out.push('\'');
+                        }
+                        Some('{' | '}' | '#') => {
+                            while let Some(q) = self.peek() {
+                                self.pos += 1;
+                                if q == '\'' {
+                                    break;
+                                }
+                                out.push(q);
+                            }
+                        }
+                        _ => out.push('\''),
+                    }
+                }
+                _ => {
+                    self.pos += 1;
+                    out.push(c);
+                }
+            }
+        }
+        if nested {
+            return Err("unclosed '{'".to_string());
+        }
+        Ok(out)
+    }
+
+    fn word(&mut self) -> String {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | '{' | '}'))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!(
+                "expected '{expected}', found '{c}' at {}",
+                self.pos
+            )),
+            None => Err(format!("expected '{expected}', found end of message")),
+        }
+    }
+
+    /// After an opening `{`: `name}`, `name, number}`, `name, plural, ...}`
+    /// or `name, select, ...}`.
+    fn argument(&mut self) -> Result<String, String> {
+        let name = self.word();
+        if name.is_empty() {
+            return Err(format!("missing argument name at {}", self.pos));
+        }
+        let value = match self.args.get(&name) {
+            Some(value) => value.clone(),
+            None if self.check => Value::from(0),
+            None => Value::UNDEFINED,
+        };
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(display(&value));
+        }
+        self.expect(',')?;
+        let kind = self.word();
+        self.skip_whitespace();
+
+        let result = match kind.as_str() {
+            "number" => {
+                let n = to_f64(&value).map_err(|_| format!("argument '{name}' is not a number"))?;
+                self.data()?.format_number(n, 0, 3)
+            }
+            "plural" => {
+                self.expect(',')?;
+                let n = to_f64(&value).map_err(|_| format!("argument '{name}' is not a number"))?;
+                let exact = format!("={}", display(&value));
+                let category = self.data()?.plural(n);
+                let branches = self.branches(Some(n))?;
+                pick(&branches, &[exact.as_str(), category, "other"])
+                    .ok_or_else(|| format!("no plural branch for '{name}'"))?
+            }
+            "select" => {
+                self.expect(',')?;
+                let key = display(&value);
+                let branches = self.branches(None)?;
+                pick(&branches, &[key.as_str(), "other"])
+                    .ok_or_else(|| format!("no select branch for '{name}'"))?
+            }
+            other => return Err(format!("unsupported argument type '{other}'")),
+        };
+        self.expect('}')?;
+        Ok(result)
+    }
+
+    /// `key {message} key {message} ...` up to (not including) the closing `}`.
+    fn branches(&mut self, count: Option<f64>) -> Result<Vec<(String, String)>, String> {
+        let mut branches = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => return Ok(branches),
+                None => return Err("unclosed '{'".to_string()),
+                _ => {}
+            }
+            let key = self.word();
+            self.expect('{')?;
+            let text = self.message(count, true)?;
+            self.expect('}')?;
+            branches.push((key, text));
+        }
+    }
+}
+
+fn pick(branches: &[(String, String)], keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| branches.iter().find(|(k, _)| k == key))
+        .map(|(_, text)| text.clone())
+}
+
+fn display(value: &Value) -> String {
+    if value.is_undefined() || value.is_none() {
+        String::new()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, localization: &Localization) -> String {
+        let mut env = Environment::new();
+        Localization::register(&mut env);
+        localization
+            .scope(|| env.render_str(template, json!({"ts": "2026-01-05T14:04:05Z"})))
+            .unwrap()
+    }
+
+    #[test]
+    fn dates_follow_locale_and_timezone() {
+        let en = Localization::new("en-US").with_timezone("America/New_York");
+        let de = Localization::new("de-DE").with_timezone("+01:00");
+        let fr = Localization::new("fr");
+
+        assert_eq!(
+            render("{{ ts | datetime }}", &en),
+            "Jan 5, 2026, 9:04:05\u{202f}AM"
+        );
+        assert_eq!(
+            render("{{ ts | date('full') }}", &en),
+            "Monday, January 5, 2026"
+        );
+        assert_eq!(render("{{ ts | date('short') }}", &de), "05.01.26");
+        assert_eq!(
+            render("{{ ts | datetime('long') }}", &de),
+            "5. Januar 2026, 15:04:05"
+        );
+        assert_eq!(render("{{ ts | time }}", &fr), "14:04");
+        assert_eq!(
+            render("{{ ts | date('%A %d %B') }}", &fr),
+            "lundi 05 janvier"
+        );
+        assert_eq!(render("{{ ts | time(tz='+05:30') }}", &fr), "19:34");
+        // Plain dates are not shifted by the time zone
+        assert_eq!(
+            render("{{ '2026-03-01' | date('long') }}", &en),
+            "March 1, 2026"
+        );
+    }
+
+    #[test]
+    fn numbers_and_currency() {
+        let en = Localization::new("en");
+        let de = Localization::new("de");
+        let es = Localization::new("es");
+        let pt = Localization::new("pt-BR");
+
+        assert_eq!(render("{{ 1234567.891 | number }}", &en), "1,234,567.891");
+        assert_eq!(render("{{ 1234.5 | number(2) }}", &de), "1.234,50");
+        assert_eq!(render("{{ 1234 | number }}", &es), "1234");
+        assert_eq!(render("{{ 12345 | number }}", &es), "12.345");
+        assert_eq!(render("{{ -9.5 | currency('USD') }}", &en), "-$9.50");
+        assert_eq!(
+            render("{{ 1234.5 | currency('EUR') }}", &de),
+            "1.234,50\u{a0}€"
+        );
+        assert_eq!(render("{{ 10 | currency('BRL') }}", &pt), "R$\u{a0}10,00");
+        assert_eq!(render("{{ 1000 | currency('jpy') }}", &en), "¥1,000");
+        assert_eq!(render("{{ 5 | currency('CHF') }}", &en), "CHF\u{a0}5.00");
+    }
+
+    #[test]
+    fn plural_and_relative_time() {
+        let en = Localization::new("en");
+        let ru = Localization::new("ru");
+
+        assert_eq!(
+            render("{{ 1 | plural(one='# item', other='# items') }}", &en),
+            "1 item"
+        );
+        assert_eq!(
+            render("{{ 1500 | plural(one='# item', other='# items') }}", &en),
+            "1,500 items"
+        );
+        let ru_forms = "plural(one='# файл', few='# файла', many='# файлов', other='# файла')";
+        assert_eq!(
+            render(&format!("{{{{ 22 | {ru_forms} }}}}"), &ru),
+            "22 файла"
+        );
+        assert_eq!(
+            render(&format!("{{{{ 11 | {ru_forms} }}}}"), &ru),
+            "11 файлов"
+        );
+
+        let now = "now='2026-01-05T12:00:00Z'";
+        assert_eq!(
+            render(
+                &format!("{{{{ '2026-01-08T12:00:00Z' | relative_time({now}) }}}}"),
+                &en
+            ),
+            "in 3 days"
+        );
+        assert_eq!(
+            render(
+                &format!("{{{{ '2026-01-05T10:00:00Z' | relative_time({now}) }}}}"),
+                &en
+            ),
+            "2 hours ago"
+        );
+        assert_eq!(
+            render(
+                &format!("{{{{ '2026-01-05T11:59:00Z' | relative_time({now}) }}}}"),
+                &ru
+            ),
+            "1 минуту назад"
+        );
+        assert_eq!(
+            render(
+                &format!("{{{{ '2026-01-10T12:00:00Z' | relative_time({now}) }}}}"),
+                &ru
+            ),
+            "через 5 дней"
+        );
+    }
+
+    #[test]
+    fn translations_with_icu_messages_and_fallback() {
+        let translations = Translations::new(BTreeMap::from([
+            (
+                "en".to_string(),
+                json!({
+                    "greeting": "Hello, {name}!",
+                    "cart": {"items": "{count, plural, =0 {Your cart is empty} one {# item} other {# items}}"},
+                    "only_en": "English only",
+                }),
+            ),
+            (
+                "pt".to_string(),
+                json!({"greeting": "Olá, {name}!", "quoted": "It''s '{literal}'"}),
+            ),
+        ]))
+        .with_fallback(vec!["en".to_string()]);
+
+        let pt = Localization::new("pt-BR").with_translations(translations.clone());
+        assert_eq!(render("{{ t('greeting', name='Ana') }}", &pt), "Olá, Ana!");
+        assert_eq!(render("{{ t('only_en') }}", &pt), "English only");
+        assert_eq!(render("{{ t('quoted') }}", &pt), "It's {literal}");
+        assert_eq!(render("{{ t('missing.key') }}", &pt), "missing.key");
+
+        let en = Localization::new("en").with_translations(translations);
+        assert_eq!(
+            render("{{ t('cart.items', count=0) }}", &en),
+            "Your cart is empty"
+        );
+        assert_eq!(render("{{ t('cart.items', count=1) }}", &en), "1 item");
+        assert_eq!(
+            render("{{ t('cart.items', count=1234) }}", &en),
+            "1,234 items"
+        );
+    }
+
+    #[test]
+    fn any_locale_formats_and_unknown_zones_are_errors() {
+        let mut env = Environment::new();
+        Localization::register(&mut env);
+        let render = |template: &str, localization: &Localization| {
+            localization.scope(|| env.render_str(template, json!({"ts": "2026-01-05T14:04:05Z"})))
+        };
+
+        let ja =
+            Localization::new("ja-JP").with_translations(Translations::new(BTreeMap::from([(
+                "ja".to_string(),
+                json!({"hello": "こんにちは、{name}", "items": "{n, plural, other {# 件}}"}),
+            )])));
+        for (template, expected) in [
+            ("{{ ts | date }}", "2026/01/05"),
+            ("{{ 1234 | number }}", "1,234"),
+            ("{{ 5 | currency('JPY') }}", "￥5"),
+            ("{{ 2 | plural(other='# items') }}", "2 items"),
+            ("{{ t('items', n=2) }}", "2 件"),
+            ("{{ t('hello', name='Ana') }}", "こんにちは、Ana"),
+        ] {
+            assert_eq!(render(template, &ja).unwrap(), expected, "{template}");
+        }
+
+        // A malformed tag falls back to the project default locale
+        let broken = Localization::new("not a locale!")
+            .with_translations(Translations::default().with_fallback(vec!["de".to_string()]));
+        assert_eq!(render("{{ 1234.5 | number }}", &broken).unwrap(), "1.234,5");
+
+        let mars = Localization::new("en").with_timezone("Mars/Olympus");
+        let err = render("{{ ts | datetime }}", &mars).unwrap_err();
+        assert!(err.to_string().contains("unknown time zone 'Mars/Olympus'"));
+        let berlin = Localization::new("en").with_timezone("Europe/Berlin");
+        assert_eq!(
+            render("{{ ts | time }}", &berlin).unwrap(),
+            "3:04\u{202f}PM"
+        );
+    }
+
+    #[test]
+    fn icu_select_and_errors() {
+        let data = locale_data::for_locale(["en"]).ok();
+        let data = data.as_deref();
+        let args = BTreeMap::from([
+            ("gender".to_string(), Value::from("female")),
+            ("n".to_string(), Value::from(2)),
+        ]);
+        assert_eq!(
+            format_message(
+                "{gender, select, female {She has {n, plural, one {# reply} other {# replies}}} other {They}}",
+                &args,
+                data
+            )
+            .unwrap(),
+            "She has 2 replies"
+        );
+        assert!(format_message("{n, plural, one {x}", &args, data).is_err());
+        assert!(format_message("{n, date}", &args, data).is_err());
+
+        let errors = check_catalog(&json!({
+            "ok": "{count, plural, one {# item} other {# items}}",
+            "nested": {"broken": "{count, plural, one {# item}"},
+        }));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "nested.broken");
+    }
+}
@@ -1,7 +1,21 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
 use minijinja::Environment;
 use serde_json::Value;
 use thiserror::Error;
 
+mod cache;
+mod i18n;
+mod locale_data;
+mod options;
+mod tz;
+
+pub use cache::{TemplateCache, TemplateKey};
+pub use i18n::{Localization, Translations, check_catalog, flatten_catalog};
+use options::RenderContext;
+pub use options::RenderOptions;
+
 /// Errors that can occur during template rendering.
 #[derive(Debug, Error)]
 pub enum TemplateError {
@@ -16,49 +30,440 @@ pub enum TemplateError {
     /// The template body is not a valid JSON object.
     #[error("Invalid template body: {0}")]
     InvalidBody(String),
+
+    /// Layouts/partials reference each other in a loop.
+    #[error("Template include cycle: {0}")]
+    Cycle(String),
+
+    /// A `{"$json": ...}` field did not render to valid JSON.
+    #[error("Invalid JSON from template at {path}: {message}")]
+    InvalidJson { path: String, message: String },
+
+    /// Strict mode: the template used a variable missing from the context.
+    #[error("Undefined variable: {path}")]
+    Undefined { path: String },
+
+    /// The template ran more instructions than its fuel allows.
+    #[error("Template exceeded the fuel limit of {limit}")]
+    OutOfFuel { limit: u64 },
+
+    /// Blocks, loops, macros or includes nested too deeply.
+    #[error("Template exceeded the recursion limit of {limit}")]
+    RecursionLimit { limit: usize },
+
+    /// The rendered output grew past the size limit.
+    #[error("Rendered output exceeded {limit} bytes")]
+    OutputTooLarge { limit: usize },
+}
+
+/// Key of a single-entry object whose template renders to JSON, which is
+/// parsed and put in its place: `{"blocks": {"$json": "[{% for ... %}]"}}`.
+pub const JSON_TEMPLATE_KEY: &str = "$json";
+
+/// Named layouts and partials that templates can pull in with
+/// `{% extends %}`, `{% include %}`, `{% import %}` and `{% from %}`.
+///
+/// Cheap to clone; the sources are shared.
+#[derive(Debug, Clone, Default)]
+pub struct Partials {
+    sources: Arc<BTreeMap<String, String>>,
+}
+
+impl Partials {
+    /// Build a partial set, rejecting sources that reference each other in a cycle.
+    pub fn new(sources: BTreeMap<String, String>) -> Result<Self, TemplateError> {
+        if let Some(cycle) = find_cycle(&sources) {
+            return Err(TemplateError::Cycle(cycle.join(" -> ")));
+        }
+        Ok(Self {
+            sources: Arc::new(sources),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Whether both sets hold the same sources; cheap when one is a clone of the other.
+    fn same_sources(&self, other: &Partials) -> bool {
+        Arc::ptr_eq(&self.sources, &other.sources) || self.sources == other.sources
+    }
+
+    /// A rendering environment with these partials, the localization filters
+    /// (see [`Localization::scope`]) and `options` applied.
+    fn environment(&self, options: &RenderOptions) -> Environment<'static> {
+        let mut env = Environment::new();
+        options.apply(&mut env);
+        Localization::register(&mut env);
+        if !self.sources.is_empty() {
+            let sources = Arc::clone(&self.sources);
+            env.set_loader(move |name| Ok(sources.get(name).cloned()));
+        }
+        env
+    }
+}
+
+/// Names of other templates a source refers to through `extends`, `include`,
+/// `import` or `from` tags with string-literal names.
+pub fn referenced_templates(source: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{%") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("%}") else {
+            break;
+        };
+        let tag = after[..end].trim_matches(|c: char| c == '-' || c == '+' || c.is_whitespace());
+        rest = &after[end + 2..];
+
+        let keyword = tag.split_whitespace().next().unwrap_or("");
+        if !matches!(keyword, "extends" | "include" | "import" | "from") {
+            continue;
+        }
+        // For `from "x" import a, b` only the module name is a template.
+        let args = match keyword {
+            "from" => tag.split(" import ").next().unwrap_or(tag),
+            _ => tag,
+        };
+        names.extend(string_literals(&args[keyword.len()..]));
+    }
+
+    names
+}
+
+fn string_literals(s: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '"' || c == '\'' {
+            let body = &s[i + 1..];
+            if let Some(len) = body.find(c) {
+                literals.push(body[..len].to_string());
+                // Skip past the closing quote
+                for _ in 0..=body[..len].chars().count() {
+                    chars.next();
+                }
+            }
+        }
+    }
+    literals
+}
+
+/// Find a reference cycle among named sources. Returns the cycle as a path
+/// that starts and ends with the same name, e.g. `["a", "b", "a"]`.
+/// References to names outside `sources` are ignored.
+pub fn find_cycle(sources: &BTreeMap<String, String>) -> Option<Vec<String>> {
+    fn visit(
+        name: &str,
+        sources: &BTreeMap<String, String>,
+        path: &mut Vec<String>,
+        done: &mut BTreeSet<String>,
+    ) -> Option<Vec<String>> {
+        if let Some(pos) = path.iter().position(|n| n == name) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(name.to_string());
+            return Some(cycle);
+        }
+        if done.contains(name) {
+            return None;
+        }
+        let source = sources.get(name)?;
+
+        path.push(name.to_string());
+        for next in referenced_templates(source) {
+            if let Some(cycle) = visit(&next, sources, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(name.to_string());
+        None
+    }
+
+    let mut done = BTreeSet::new();
+    sources
+        .keys()
+        .find_map(|name| visit(name, sources, &mut Vec::new(), &mut done))
 }
 
 /// Render a single Jinja2 template string with the given context.
 ///
 /// The `context` value is passed directly to MiniJinja as the template context,
 /// so it can be an object, array, or any JSON value that the template expects.
+/// Default [`RenderOptions`] limits apply.
 pub fn render_string(template: &str, context: &Value) -> Result<String, TemplateError> {
-    let env = Environment::new();
-    let result = env.render_str(template, context)?;
-    Ok(result)
+    let options = RenderOptions::default();
+    let env = Partials::default().environment(&options);
+    options.render(&env, template, &RenderContext::new(context))
 }
 
-/// Render all string fields in a JSON object body, passing non-string values through unchanged.
+/// Project layouts and partials, recipient localization and render limits
+/// for [`render_body`] and [`TemplateCache::render_body`].
 ///
-/// Each string value in the top-level object is treated as a Jinja2 template and rendered
-/// with the provided context. Non-string values (arrays, numbers, booleans, nested objects, null)
-/// are included in the output without modification.
+/// The default has no partials, English in UTC without catalogs, and the
+/// default [`RenderOptions`].
+#[derive(Debug, Clone, Default)]
+pub struct RenderSettings {
+    pub partials: Partials,
+    pub localization: Localization,
+    pub options: RenderOptions,
+}
+
+/// Render all string values in a JSON object body, recursing into nested
+/// objects and arrays.
+///
+/// Each string is treated as a Jinja2 template and rendered with the provided
+/// context; numbers, booleans and null are passed through, and object keys are
+/// left as is. A `{"$json": "<template>"}` object is replaced by the parsed
+/// JSON its template renders to (see [`JSON_TEMPLATE_KEY`]), so payloads such
+/// as Slack blocks can be built with loops. `settings` supplies the partials
+/// available by name, the locale, time zone and catalogs behind the
+/// locale-aware filters and `t()`, and the undefined-variable mode and limits.
 ///
 /// Returns an error if `body` is not a JSON object.
 pub fn render_body(
     body: &Value,
     context: &Value,
+    settings: &RenderSettings,
 ) -> Result<serde_json::Map<String, Value>, TemplateError> {
     let obj = body
         .as_object()
         .ok_or_else(|| TemplateError::InvalidBody("body must be a JSON object".to_string()))?;
 
-    let env = Environment::new();
+    let env = settings.partials.environment(&settings.options);
+    settings
+        .localization
+        .scope(|| render_fields(&env, &settings.options, Source::Inline, obj, context))
+}
+
+/// Where [`render_value`] gets the template for a string field.
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    /// Compile the string itself.
+    Inline,
+    /// Use the template [`add_body_templates`] added for the field's path.
+    Compiled,
+}
+
+fn render_fields(
+    env: &Environment<'static>,
+    options: &RenderOptions,
+    source: Source,
+    obj: &serde_json::Map<String, Value>,
+    context: &Value,
+) -> Result<serde_json::Map<String, Value>, TemplateError> {
+    let context = RenderContext::new(context);
     let mut result = serde_json::Map::new();
 
     for (key, value) in obj {
+        result.insert(
+            key.clone(),
+            render_value(env, options, source, value, &context, key)?,
+        );
+    }
+
+    Ok(result)
+}
+
+/// Name of the compiled template for the string at `path` in a body. The
+/// angle brackets keep it apart from partial names, like MiniJinja's own
+/// `<string>`.
+fn field_template_name(path: &str) -> String {
+    format!("<{path}>")
+}
+
+/// Compile every template string in a body into `env`, named by
+/// [`field_template_name`] with the field paths [`render_value`] uses.
+fn add_body_templates(
+    env: &mut Environment<'static>,
+    obj: &serde_json::Map<String, Value>,
+) -> Result<(), minijinja::Error> {
+    fn walk(
+        env: &mut Environment<'static>,
+        value: &Value,
+        path: String,
+    ) -> Result<(), minijinja::Error> {
         match value {
             Value::String(template_str) => {
-                let rendered = env.render_str(template_str, context)?;
-                result.insert(key.clone(), Value::String(rendered));
+                env.add_template_owned(field_template_name(&path), template_str.clone())
             }
-            other => {
-                result.insert(key.clone(), other.clone());
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, item)| walk(env, item, format!("{path}[{i}]"))),
+            Value::Object(map) => match json_template(map) {
+                Some(template_str) => {
+                    env.add_template_owned(field_template_name(&path), template_str.to_string())
+                }
+                None => map
+                    .iter()
+                    .try_for_each(|(key, item)| walk(env, item, format!("{path}.{key}"))),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    obj.iter()
+        .try_for_each(|(key, value)| walk(env, value, key.clone()))
+}
+
+fn render_field(
+    env: &Environment<'static>,
+    options: &RenderOptions,
+    source: Source,
+    template_str: &str,
+    context: &RenderContext,
+    path: &str,
+) -> Result<String, TemplateError> {
+    match source {
+        Source::Inline => options.render(env, template_str, context),
+        Source::Compiled => {
+            let template = env.get_template(&field_template_name(path))?;
+            options.render_template(env, &template, context)
+        }
+    }
+}
+
+fn render_value(
+    env: &Environment<'static>,
+    options: &RenderOptions,
+    source: Source,
+    value: &Value,
+    context: &RenderContext,
+    path: &str,
+) -> Result<Value, TemplateError> {
+    match value {
+        Value::String(template_str) => Ok(Value::String(render_field(
+            env,
+            options,
+            source,
+            template_str,
+            context,
+            path,
+        )?)),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                render_value(env, options, source, item, context, &format!("{path}[{i}]"))
+            })
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Value::Object(map) => {
+            if let Some(template_str) = json_template(map) {
+                let rendered = render_field(env, options, source, template_str, context, path)?;
+                return serde_json::from_str(&rendered).map_err(|e| TemplateError::InvalidJson {
+                    path: path.to_string(),
+                    message: e.to_string(),
+                });
             }
+            map.iter()
+                .map(|(key, item)| {
+                    Ok((
+                        key.clone(),
+                        render_value(
+                            env,
+                            options,
+                            source,
+                            item,
+                            context,
+                            &format!("{path}.{key}"),
+                        )?,
+                    ))
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::Object)
         }
+        other => Ok(other.clone()),
     }
+}
 
-    Ok(result)
+/// A template string that fails to compile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// Field path within the body, e.g. `subject` or `blocks[0].text`.
+    pub path: String,
+    pub message: String,
+    pub line: Option<usize>,
+}
+
+/// Compile every template string in a body (recursively, like
+/// [`render_body`]) and report syntax errors. Nothing is rendered, so
+/// undefined variables, filters and partials are not checked.
+pub fn check_syntax(body: &Value) -> Vec<SyntaxError> {
+    fn walk(env: &Environment<'static>, value: &Value, path: String, out: &mut Vec<SyntaxError>) {
+        match value {
+            Value::String(source) => {
+                if let Err(e) = env.template_from_str(source) {
+                    out.push(SyntaxError {
+                        path,
+                        message: e.detail().unwrap_or("syntax error").to_string(),
+                        line: e.line(),
+                    });
+                }
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    walk(env, item, format!("{path}[{i}]"), out);
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map {
+                    let path = match path.as_str() {
+                        "" => key.clone(),
+                        p => format!("{p}.{key}"),
+                    };
+                    walk(env, item, path, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let env = Environment::new();
+    let mut errors = Vec::new();
+    walk(&env, body, String::new(), &mut errors);
+    errors
+}
+
+/// Context variables a body reads, as dotted paths such as `order.total`
+/// (recursively, like [`render_body`]). Loop and `set` variables, globals
+/// like `t()` and strings that fail to compile are skipped; variables used
+/// only inside layouts or partials are not seen.
+pub fn referenced_variables(body: &Value) -> BTreeSet<String> {
+    fn walk(env: &Environment<'static>, value: &Value, out: &mut BTreeSet<String>) {
+        match value {
+            Value::String(source) => {
+                if let Ok(template) = env.template_from_str(source) {
+                    out.extend(template.undeclared_variables(true));
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| walk(env, item, out)),
+            Value::Object(map) => map.values().for_each(|item| walk(env, item, out)),
+            _ => {}
+        }
+    }
+
+    let env = Partials::default().environment(&RenderOptions::default());
+    let mut variables = BTreeSet::new();
+    walk(&env, body, &mut variables);
+
+    let globals: BTreeSet<&str> = env.globals().map(|(name, _)| name).collect();
+    variables.retain(|path| {
+        let root = path.split('.').next().unwrap_or(path);
+        !globals.contains(root)
+    });
+    variables
+}
+
+fn json_template(map: &serde_json::Map<String, Value>) -> Option<&str> {
+    match map.iter().next() {
+        Some((key, Value::String(template_str))) if map.len() == 1 && key == JSON_TEMPLATE_KEY => {
+            Some(template_str)
+        }
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -97,7 +502,7 @@ mod tests {
             "text": "Dear {{ name }}, welcome!"
         });
         let context = json!({"name": "Alice"});
-        let result = render_body(&body, &context).unwrap();
+        let result = render_body(&body, &context, &RenderSettings::default()).unwrap();
         assert_eq!(result["subject"], "Hello Alice");
         assert_eq!(result["text"], "Dear Alice, welcome!");
     }
@@ -109,7 +514,7 @@ mod tests {
             "buttons": [{"label": "Click me", "url": "https://example.com"}]
         });
         let context = json!({"name": "Bob"});
-        let result = render_body(&body, &context).unwrap();
+        let result = render_body(&body, &context, &RenderSettings::default()).unwrap();
         assert_eq!(result["subject"], "Hello Bob");
         assert_eq!(
             result["buttons"],
@@ -117,13 +522,178 @@ mod tests {
         );
     }
 
+    #[test]
+    fn render_body_recurses_into_objects_and_arrays() {
+        let body = json!({
+            "text": "Order {{ id }}",
+            "blocks": [
+                {"type": "section", "text": {"type": "mrkdwn", "text": "*{{ name }}*"}},
+                {"type": "divider"}
+            ],
+            "data": {"order_id": "{{ id }}", "count": 3, "urgent": false, "note": null}
+        });
+        let result = render_body(
+            &body,
+            &json!({"id": 7, "name": "Ann"}),
+            &RenderSettings::default(),
+        )
+        .unwrap();
+        assert_eq!(result["blocks"][0]["text"]["text"], "*Ann*");
+        assert_eq!(result["blocks"][1], json!({"type": "divider"}));
+        assert_eq!(
+            result["data"],
+            json!({"order_id": "7", "count": 3, "urgent": false, "note": null})
+        );
+    }
+
+    #[test]
+    fn json_template_fields_render_then_parse() {
+        let body = json!({
+            "text": "Cart",
+            "blocks": {"$json": "[{% for item in items %}{\"type\": \"section\", \"text\": {{ item.name | tojson }}, \"qty\": {{ item.qty }}}{% if not loop.last %},{% endif %}{% endfor %}]"},
+            "embeds": [{"fields": {"$json": "{{ fields | tojson }}"}}]
+        });
+        let context = json!({
+            "items": [{"name": "Tea \"green\"", "qty": 2}, {"name": "Mug", "qty": 1}],
+            "fields": [{"name": "a", "value": "b"}]
+        });
+        let result = render_body(&body, &context, &RenderSettings::default()).unwrap();
+        assert_eq!(
+            result["blocks"],
+            json!([
+                {"type": "section", "text": "Tea \"green\"", "qty": 2},
+                {"type": "section", "text": "Mug", "qty": 1}
+            ])
+        );
+        assert_eq!(
+            result["embeds"][0]["fields"],
+            json!([{"name": "a", "value": "b"}])
+        );
+
+        let err = render_body(
+            &json!({"embeds": [{"fields": {"$json": "[{{ x }}"}}]}),
+            &json!({"x": 1}),
+            &RenderSettings::default(),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, TemplateError::InvalidJson { ref path, .. } if path == "embeds[0].fields")
+        );
+    }
+
+    #[test]
+    fn check_syntax_reports_paths_and_lines() {
+        let body = json!({
+            "subject": "Hi {{ name }",
+            "text": "ok {{ name | upper }} {% include \"footer\" %}",
+            "blocks": [{"text": "fine"}, {"text": "{% for x in y %}\nno end"}],
+            "data": {"$json": "{{ items | tojson }}"}
+        });
+        let errors = check_syntax(&body);
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["blocks[1].text", "subject"]);
+        assert_eq!(errors[1].line, Some(1));
+        assert!(check_syntax(&json!({"text": "{{ x }}"})).is_empty());
+    }
+
+    #[test]
+    fn referenced_variables_collects_nested_paths() {
+        let body = json!({
+            "subject": "Order #{{ order.id }} for {{ customer.name | upper }}",
+            "text": "{% for item in items %}{{ item.sku }}{% endfor %}{{ t('footer') }}",
+            "blocks": [{"text": "{% set total = order.total %}{{ total }}"}],
+            "data": {"$json": "{{ meta | tojson }}"},
+            "broken": "{{ ignored }"
+        });
+        let variables: Vec<String> = referenced_variables(&body).into_iter().collect();
+        assert_eq!(
+            variables,
+            vec!["customer.name", "items", "meta", "order.id", "order.total"]
+        );
+    }
+
     #[test]
     fn render_body_invalid_non_object() {
         let body = json!("not an object");
         let context = json!({"name": "Test"});
-        let result = render_body(&body, &context);
+        let result = render_body(&body, &context, &RenderSettings::default());
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(matches!(err, TemplateError::InvalidBody(_)));
     }
+
+    fn partials(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn render_body_with_layout_and_partial() {
+        let partials = Partials::new(partials(&[
+            (
+                "base_email",
+                "<html>{% block content %}{% endblock %}{% include \"footer\" %}</html>",
+            ),
+            ("footer", "<p>Bye {{ name }}</p>"),
+        ]))
+        .unwrap();
+        let body = json!({
+            "subject": "Hi {{ name }}",
+            "html": "{% extends \"base_email\" %}{% block content %}<h1>Hello {{ name }}</h1>{% endblock %}"
+        });
+        let settings = RenderSettings {
+            partials,
+            ..RenderSettings::default()
+        };
+        let result = render_body(&body, &json!({"name": "Ann"}), &settings).unwrap();
+        assert_eq!(result["subject"], "Hi Ann");
+        assert_eq!(
+            result["html"],
+            "<html><h1>Hello Ann</h1><p>Bye Ann</p></html>"
+        );
+    }
+
+    #[test]
+    fn unknown_partial_is_a_render_error() {
+        let body = json!({"html": "{% include \"missing\" %}"});
+        let err = render_body(&body, &json!({}), &RenderSettings::default()).unwrap_err();
+        assert!(matches!(err, TemplateError::Render(_)));
+    }
+
+    #[test]
+    fn referenced_templates_finds_all_tag_kinds() {
+        let source = r#"{% extends "base" %}{%- include 'a' -%}{% include ["b", "c"] ignore missing %}
+            {% import "macros" as m %}{% from "forms" import input %}{% if x %}"not"{% endif %}"#;
+        assert_eq!(
+            referenced_templates(source),
+            vec!["base", "a", "b", "c", "macros", "forms"]
+        );
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let sources = partials(&[
+            ("a", "{% include \"b\" %}"),
+            ("b", "{% extends \"c\" %}"),
+            ("c", "{% include \"a\" %}"),
+            ("d", "{% include \"a\" %}"),
+        ]);
+        assert_eq!(find_cycle(&sources).unwrap(), vec!["a", "b", "c", "a"]);
+        let err = Partials::new(sources).unwrap_err();
+        assert_eq!(err.to_string(), "Template include cycle: a -> b -> c -> a");
+
+        let self_ref = partials(&[("x", "{% include \"x\" %}")]);
+        assert_eq!(find_cycle(&self_ref).unwrap(), vec!["x", "x"]);
+
+        // Shared dependencies are not cycles
+        let diamond = partials(&[
+            ("a", "{% include \"b\" %}{% include \"c\" %}"),
+            ("b", "{% include \"d\" %}"),
+            ("c", "{% include \"d\" %}"),
+            ("d", "leaf {% include \"external\" %}"),
+        ]);
+        assert!(find_cycle(&diamond).is_none());
+    }
 }
@@ -0,0 +1,364 @@
+//! CLDR formatting data for the template filters, from the ICU4X compiled
+//! data. Locales without data of their own fall back to their parents and
+//! finally to the CLDR root locale, so every well-formed locale can format.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use fixed_decimal::{FixedDecimal, Sign};
+use icu_calendar::{DateTime, Gregorian};
+use icu_datetime::options::length;
+use icu_datetime::provider::calendar::{GregorianDateSymbolsV1Marker, TimeSymbolsV1Marker};
+use icu_datetime::{TimeFormatter, TypedDateFormatter, TypedDateTimeFormatter};
+use icu_decimal::FixedDecimalFormatter;
+use icu_decimal::options::FixedDecimalFormatterOptions;
+use icu_experimental::dimension::provider::currency::{
+    CurrencyEssentialsV1Marker, PatternSelection, PlaceholderValue,
+};
+use icu_experimental::relativetime::options::{Numeric, RelativeTimeFormatterOptions};
+use icu_experimental::relativetime::{RelativeTimeError, RelativeTimeFormatter};
+use icu_locid::Locale;
+use icu_plurals::{PluralCategory, PluralOperands, PluralRules};
+use icu_provider::{DataLocale, DataPayload, DataProvider, DataRequest};
+use tinystr::TinyAsciiStr;
+
+/// Distinct locales kept per thread before the cache starts over.
+const CACHE_CAPACITY: usize = 64;
+
+thread_local! {
+    static CACHE: RefCell<HashMap<String, Rc<LocaleData>>> = RefCell::new(HashMap::new());
+}
+
+/// Date and time format length, as in CLDR.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Length {
+    Short,
+    Medium,
+    Long,
+    Full,
+}
+
+impl Length {
+    fn date(self) -> length::Date {
+        match self {
+            Length::Short => length::Date::Short,
+            Length::Medium => length::Date::Medium,
+            Length::Long => length::Date::Long,
+            Length::Full => length::Date::Full,
+        }
+    }
+
+    /// Long and full times would need zone names; use medium for those.
+    fn time(self) -> length::Time {
+        match self {
+            Length::Short => length::Time::Short,
+            _ => length::Time::Medium,
+        }
+    }
+}
+
+/// Formatters and names for one locale.
+pub(crate) struct LocaleData {
+    locale: DataLocale,
+    plurals: PluralRules,
+    decimal: FixedDecimalFormatter,
+    date_symbols: DataPayload<GregorianDateSymbolsV1Marker>,
+    time_symbols: DataPayload<TimeSymbolsV1Marker>,
+    currency: DataPayload<CurrencyEssentialsV1Marker>,
+}
+
+/// Data for the first well-formed locale among `candidates`, or for the
+/// root locale when none is. Cached per thread.
+pub(crate) fn for_locale<'a>(
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Result<Rc<LocaleData>, String> {
+    let locale = candidates
+        .into_iter()
+        .find_map(|tag| Locale::try_from_bytes(tag.trim().replace('_', "-").as_bytes()).ok())
+        .unwrap_or(Locale::UND);
+    let key = locale.to_string();
+
+    if let Some(data) = CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+        return Ok(data);
+    }
+    let data = Rc::new(LocaleData::load((&locale).into())?);
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(key, data.clone());
+    });
+    Ok(data)
+}
+
+fn load<M>(provider: &impl DataProvider<M>, locale: &DataLocale) -> Result<DataPayload<M>, String>
+where
+    M: icu_provider::KeyedDataMarker,
+{
+    provider
+        .load(DataRequest {
+            locale,
+            metadata: Default::default(),
+        })
+        .and_then(|response| response.take_payload())
+        .map_err(|e| e.to_string())
+}
+
+impl LocaleData {
+    fn load(locale: DataLocale) -> Result<Self, String> {
+        Ok(Self {
+            plurals: PluralRules::try_new_cardinal(&locale).map_err(|e| e.to_string())?,
+            decimal: FixedDecimalFormatter::try_new(
+                &locale,
+                FixedDecimalFormatterOptions::default(),
+            )
+            .map_err(|e| e.to_string())?,
+            date_symbols: load(&icu_datetime::provider::Baked, &locale)?,
+            time_symbols: load(&icu_datetime::provider::Baked, &locale)?,
+            currency: load(&icu_experimental::provider::Baked, &locale)?,
+            locale,
+        })
+    }
+
+    /// CLDR plural category of `n`: `zero`, `one`, `two`, `few`, `many` or `other`.
+    pub(crate) fn plural(&self, n: f64) -> &'static str {
+        if !n.is_finite() {
+            return "other";
+        }
+        let operands = PluralOperands::from(&decimal(n, 0, 3));
+        match self.plurals.category_for(operands) {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+
+    /// Format with locale separators, rounding to `max_frac` digits and
+    /// keeping at least `min_frac`.
+    pub(crate) fn format_number(&self, n: f64, min_frac: usize, max_frac: usize) -> String {
+        if !n.is_finite() {
+            return n.to_string();
+        }
+        self.decimal
+            .format_to_string(&decimal(n, min_frac, max_frac))
+    }
+
+    /// Format an amount in the currency with ISO 4217 `code`, using the
+    /// locale's currency pattern and symbol and the currency's minor units.
+    pub(crate) fn format_currency(&self, n: f64, code: &str) -> String {
+        let code = code.trim().to_ascii_uppercase();
+        let digits = currency_digits(&code);
+        let amount = self.format_number(n.abs(), digits, digits);
+        let sign = if n < 0.0 && !decimal(n, digits, digits).is_zero() {
+            "-"
+        } else {
+            ""
+        };
+
+        let data = self.currency.get();
+        let config = TinyAsciiStr::<3>::from_str(&code)
+            .ok()
+            .and_then(|c| data.pattern_config_map.get_copied(&c.to_unvalidated()))
+            .unwrap_or(data.default_pattern_config);
+        let symbol = match config.short_placeholder_value {
+            Some(PlaceholderValue::Index(i)) => data.placeholders.get(i.into()).unwrap_or(&code),
+            Some(PlaceholderValue::ISO) | None => &code,
+        };
+        let pattern = match config.short_pattern_selection {
+            PatternSelection::Standard => data.standard_pattern.as_ref(),
+            PatternSelection::StandardAlphaNextToNumber => {
+                data.standard_alpha_next_to_number_pattern.as_ref()
+            }
+        };
+        match pattern {
+            Some(pattern) => format!(
+                "{sign}{}",
+                pattern.interpolate_to_string((amount.as_str(), symbol))
+            ),
+            None => format!("{sign}{code}\u{a0}{amount}"),
+        }
+    }
+
+    /// Format a date, a time or both with the locale's CLDR patterns for
+    /// the given lengths.
+    pub(crate) fn format_datetime(
+        &self,
+        dt: &NaiveDateTime,
+        date: Option<Length>,
+        time: Option<Length>,
+    ) -> Result<String, String> {
+        let value = DateTime::try_new_gregorian_datetime(
+            dt.year(),
+            dt.month() as u8,
+            dt.day() as u8,
+            dt.hour() as u8,
+            dt.minute() as u8,
+            dt.second() as u8,
+        )
+        .map_err(|e| e.to_string())?;
+        let locale = &self.locale;
+
+        match (date, time) {
+            (Some(date), Some(time)) => {
+                let options = length::Bag::from_date_time_style(date.date(), time.time());
+                TypedDateTimeFormatter::<Gregorian>::try_new(locale, options.into())
+                    .map(|f| f.format_to_string(&value))
+            }
+            (Some(date), None) => {
+                TypedDateFormatter::<Gregorian>::try_new_with_length(locale, date.date())
+                    .map(|f| f.format_to_string(&value.date))
+            }
+            (None, Some(time)) => TimeFormatter::try_new_with_length(locale, time.time())
+                .map(|f| f.format_to_string(&value)),
+            (None, None) => Ok(String::new()),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    /// Month name for a zero-based month, as used in dates.
+    pub(crate) fn month(&self, month0: u32, abbreviated: bool) -> &str {
+        let months = &self.date_symbols.get().months.format;
+        let symbols = if abbreviated {
+            &months.abbreviated
+        } else {
+            &months.wide
+        };
+        let code = TinyAsciiStr::<4>::from_str(&format!("M{:02}", month0 + 1))
+            .expect("month code is ASCII");
+        symbols
+            .get(icu_calendar::types::MonthCode(code))
+            .unwrap_or_default()
+    }
+
+    /// Weekday name, counting from Sunday.
+    pub(crate) fn weekday(&self, from_sunday: u32, abbreviated: bool) -> &str {
+        let weekdays = &self.date_symbols.get().weekdays.format;
+        let symbols = if abbreviated {
+            &weekdays.abbreviated
+        } else {
+            &weekdays.wide
+        };
+        &symbols.0[from_sunday as usize % 7]
+    }
+
+    /// AM or PM marker.
+    pub(crate) fn day_period(&self, pm: bool) -> &str {
+        let periods = &self.time_symbols.get().day_periods.format.abbreviated;
+        if pm { &periods.pm } else { &periods.am }
+    }
+
+    /// "in 3 days" / "3 days ago" for a difference in seconds.
+    pub(crate) fn format_relative(&self, diff: i64) -> Result<String, String> {
+        const UNITS: [i64; 7] = [1, 60, 3600, 86_400, 604_800, 2_592_000, 31_536_000];
+        type Constructor = fn(
+            &DataLocale,
+            RelativeTimeFormatterOptions,
+        ) -> Result<RelativeTimeFormatter, RelativeTimeError>;
+        const CONSTRUCTORS: [Constructor; 7] = [
+            RelativeTimeFormatter::try_new_long_second,
+            RelativeTimeFormatter::try_new_long_minute,
+            RelativeTimeFormatter::try_new_long_hour,
+            RelativeTimeFormatter::try_new_long_day,
+            RelativeTimeFormatter::try_new_long_week,
+            RelativeTimeFormatter::try_new_long_month,
+            RelativeTimeFormatter::try_new_long_year,
+        ];
+
+        let secs = diff.abs();
+        let unit = match secs {
+            s if s < 60 => 0,
+            s if s < 3600 => 1,
+            s if s < 86_400 => 2,
+            s if s < 604_800 => 3,
+            s if s < 2_592_000 => 4,
+            s if s < 31_536_000 => 5,
+            _ => 6,
+        };
+        // Only "now" uses the locale's words instead of a number
+        let options = RelativeTimeFormatterOptions {
+            numeric: if secs == 0 {
+                Numeric::Auto
+            } else {
+                Numeric::Always
+            },
+        };
+        let formatter = CONSTRUCTORS[unit](&self.locale, options).map_err(|e| e.to_string())?;
+
+        let count = match secs {
+            0 => 0,
+            s => ((s + UNITS[unit] / 2) / UNITS[unit]).max(1),
+        };
+        let value = FixedDecimal::from(count).with_sign(match diff < 0 {
+            true => Sign::Negative,
+            false => Sign::None,
+        });
+        Ok(formatter.format(value).to_string())
+    }
+}
+
+/// `n` rounded to `max_frac` digits, without trailing zeros beyond `min_frac`.
+fn decimal(n: f64, min_frac: usize, max_frac: usize) -> FixedDecimal {
+    let mut d: FixedDecimal = format!("{:.*}", max_frac, n.abs())
+        .parse()
+        .expect("formatted float is a decimal");
+    d.trim_end();
+    d.pad_end(-(min_frac as i16));
+    if n < 0.0 && !d.is_zero() {
+        d.set_sign(Sign::Negative);
+    }
+    d
+}
+
+/// Minor-unit digits for an ISO 4217 code.
+fn currency_digits(code: &str) -> usize {
+    match code {
+        "JPY" | "KRW" | "CLP" | "ISK" | "VND" => 0,
+        "BHD" | "KWD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plural_rules() {
+        let en = for_locale(["en-US"]).unwrap();
+        assert_eq!(en.plural(1.0), "one");
+        assert_eq!(en.plural(0.0), "other");
+        assert_eq!(en.plural(1.5), "other");
+
+        let fr = for_locale(["fr"]).unwrap();
+        assert_eq!(fr.plural(0.0), "one");
+        assert_eq!(fr.plural(1.5), "one");
+        assert_eq!(fr.plural(2.0), "other");
+
+        let ru = for_locale(["ru_RU"]).unwrap();
+        assert_eq!(ru.plural(1.0), "one");
+        assert_eq!(ru.plural(21.0), "one");
+        assert_eq!(ru.plural(11.0), "many");
+        assert_eq!(ru.plural(3.0), "few");
+        assert_eq!(ru.plural(13.0), "many");
+        assert_eq!(ru.plural(25.0), "many");
+        assert_eq!(ru.plural(2.5), "other");
+
+        let ar = for_locale(["ar"]).unwrap();
+        assert_eq!(ar.plural(0.0), "zero");
+        assert_eq!(ar.plural(2.0), "two");
+    }
+
+    #[test]
+    fn malformed_locales_fall_back() {
+        let data = for_locale(["not a locale!", "de"]).unwrap();
+        assert_eq!(data.format_number(1234.5, 0, 3), "1.234,5");
+        let root = for_locale(["", "??"]).unwrap();
+        assert_eq!(root.plural(1.0), "other");
+    }
+}
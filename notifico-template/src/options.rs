@@ -0,0 +1,380 @@
+use std::io;
+use std::sync::OnceLock;
+
+use minijinja::{Environment, ErrorKind, Template, UndefinedBehavior};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::TemplateError;
+
+/// Undefined-variable handling and resource limits for rendering, stored
+/// per project under `render` in the project's `settings` JSON.
+///
+/// Limits apply to each template string separately, so a body with several
+/// fields may use the fuel and output budget once per field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderOptions {
+    /// Fail the render when a template prints, iterates or looks up an
+    /// attribute of an undefined variable. `{% if x %}` and `x is defined`
+    /// still work on undefined values.
+    pub strict_undefined: bool,
+    /// Maximum number of engine instructions per template string.
+    pub fuel: u64,
+    /// Maximum nesting depth of blocks, loops, macros and includes.
+    pub max_recursion: usize,
+    /// Maximum size of a rendered template string, in bytes.
+    pub max_output_bytes: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            strict_undefined: false,
+            fuel: 1_000_000,
+            max_recursion: 100,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Read the options from project settings. Each field is read on its
+    /// own, so an absent or invalid field only falls back to its own default.
+    pub fn from_settings(settings: &Value) -> Self {
+        fn field<T: DeserializeOwned>(
+            render: &Value,
+            name: &str,
+            valid: impl Fn(&T) -> bool,
+            default: T,
+        ) -> T {
+            render
+                .get(name)
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .filter(valid)
+                .unwrap_or(default)
+        }
+
+        let default = Self::default();
+        let Some(render) = settings.get("render") else {
+            return default;
+        };
+        Self {
+            strict_undefined: field(
+                render,
+                "strict_undefined",
+                |_| true,
+                default.strict_undefined,
+            ),
+            fuel: field(render, "fuel", |&n| n > 0, default.fuel),
+            max_recursion: field(render, "max_recursion", |&n| n > 0, default.max_recursion),
+            max_output_bytes: field(
+                render,
+                "max_output_bytes",
+                |&n| n > 0,
+                default.max_output_bytes,
+            ),
+        }
+    }
+
+    /// Check the limits before they are saved; every limit must be positive.
+    pub fn validate(&self) -> Result<(), String> {
+        let zero = [
+            ("fuel", self.fuel == 0),
+            ("max_recursion", self.max_recursion == 0),
+            ("max_output_bytes", self.max_output_bytes == 0),
+        ];
+        match zero.iter().find(|(_, zero)| *zero) {
+            Some((name, _)) => Err(format!("{name} must be greater than 0")),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn apply(&self, env: &mut Environment<'static>) {
+        env.set_undefined_behavior(if self.strict_undefined {
+            UndefinedBehavior::SemiStrict
+        } else {
+            UndefinedBehavior::Lenient
+        });
+        env.set_fuel(Some(self.fuel));
+        env.set_recursion_limit(self.max_recursion);
+    }
+
+    /// Render a template string within these limits.
+    pub(crate) fn render(
+        &self,
+        env: &Environment<'static>,
+        source: &str,
+        context: &RenderContext,
+    ) -> Result<String, TemplateError> {
+        let template = env.template_from_str(source)?;
+        self.render_template(env, &template, context)
+    }
+
+    /// Render an already compiled template within these limits.
+    pub(crate) fn render_template(
+        &self,
+        env: &Environment<'static>,
+        template: &Template,
+        context: &RenderContext,
+    ) -> Result<String, TemplateError> {
+        let mut out = LimitedWriter {
+            buf: Vec::new(),
+            limit: self.max_output_bytes,
+            exceeded: false,
+        };
+        match template.render_to_write(&context.value, &mut out) {
+            Ok(_) => String::from_utf8(out.buf)
+                .map_err(|e| TemplateError::InvalidBody(format!("rendered output: {e}"))),
+            Err(_) if out.exceeded => Err(TemplateError::OutputTooLarge {
+                limit: self.max_output_bytes,
+            }),
+            Err(e) => Err(self.classify(e, env, template, context.data)),
+        }
+    }
+
+    fn classify(
+        &self,
+        e: minijinja::Error,
+        env: &Environment<'static>,
+        template: &Template,
+        context: &Value,
+    ) -> TemplateError {
+        match e.kind() {
+            ErrorKind::OutOfFuel => TemplateError::OutOfFuel { limit: self.fuel },
+            ErrorKind::InvalidOperation if is_recursion_limit(&e) => {
+                TemplateError::RecursionLimit {
+                    limit: self.max_recursion,
+                }
+            }
+            ErrorKind::UndefinedError => match undefined_path(env, template, context) {
+                Some(path) => TemplateError::Undefined { path },
+                None => TemplateError::Render(e),
+            },
+            _ => TemplateError::Render(e),
+        }
+    }
+}
+
+/// Whether `e` is MiniJinja's recursion limit error.
+///
+/// MiniJinja reports it as a generic [`ErrorKind::InvalidOperation`], so it
+/// is recognised by comparing with the error the engine itself raises when
+/// a template nests deeper than a zero limit, not by a copy of its wording.
+fn is_recursion_limit(e: &minijinja::Error) -> bool {
+    static REFERENCE: OnceLock<Option<String>> = OnceLock::new();
+    let reference = REFERENCE.get_or_init(|| {
+        let mut env = Environment::new();
+        env.set_recursion_limit(0);
+        let err = env.render_str("{% for x in [1] %}{% endfor %}", ()).err()?;
+        (err.kind() == ErrorKind::InvalidOperation)
+            .then(|| err.detail().map(String::from))
+            .flatten()
+    });
+    e.kind() == ErrorKind::InvalidOperation
+        && reference.is_some()
+        && e.detail() == reference.as_deref()
+}
+
+/// Render context converted for MiniJinja once and shared by every field
+/// of a body, so the data is not re-serialized per field.
+pub(crate) struct RenderContext<'a> {
+    data: &'a Value,
+    value: minijinja::Value,
+}
+
+impl<'a> RenderContext<'a> {
+    pub(crate) fn new(data: &'a Value) -> Self {
+        Self {
+            data,
+            value: minijinja::Value::from_serialize(data),
+        }
+    }
+}
+
+/// The first variable path the template reads that `context` lacks,
+/// cut at the first missing segment: `user.name` with no `user` gives `user`.
+/// Globals such as `t` are skipped.
+fn undefined_path(
+    env: &Environment<'static>,
+    template: &Template,
+    context: &Value,
+) -> Option<String> {
+    let mut variables: Vec<String> = template.undeclared_variables(true).into_iter().collect();
+    variables.sort();
+    variables.iter().find_map(|path| {
+        let root = path.split('.').next().unwrap_or(path);
+        if env.globals().any(|(name, _)| name == root) {
+            return None;
+        }
+        let mut node = context;
+        let mut resolved = Vec::new();
+        for segment in path.split('.') {
+            resolved.push(segment);
+            match node.get(segment) {
+                Some(next) => node = next,
+                None => return Some(resolved.join(".")),
+            }
+        }
+        None
+    })
+}
+
+/// Collects rendered output and fails the render once it outgrows `limit`.
+struct LimitedWriter {
+    buf: Vec<u8>,
+    limit: usize,
+    exceeded: bool,
+}
+
+impl io::Write for LimitedWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > self.limit {
+            self.exceeded = true;
+            return Err(io::Error::other("output size limit exceeded"));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RenderSettings, render_body};
+    use serde_json::json;
+
+    fn render(body: Value, context: Value, options: RenderOptions) -> Result<Value, TemplateError> {
+        let settings = RenderSettings {
+            options,
+            ..RenderSettings::default()
+        };
+        render_body(&body, &context, &settings).map(Value::Object)
+    }
+
+    fn strict() -> RenderOptions {
+        RenderOptions {
+            strict_undefined: true,
+            ..RenderOptions::default()
+        }
+    }
+
+    #[test]
+    fn lenient_by_default() {
+        let out = render(
+            json!({"text": "Hi {{ nmae }}!"}),
+            json!({"name": "Ann"}),
+            RenderOptions::default(),
+        );
+        assert_eq!(out.unwrap()["text"], "Hi !");
+    }
+
+    #[test]
+    fn strict_undefined_reports_variable_path() {
+        let context = json!({"order": {"id": 7}});
+        let err = render(
+            json!({"text": "{{ order.id }} {{ order.total }}"}),
+            context.clone(),
+            strict(),
+        )
+        .unwrap_err();
+        assert!(matches!(&err, TemplateError::Undefined { path } if path == "order.total"));
+        assert_eq!(err.to_string(), "Undefined variable: order.total");
+
+        let err = render(
+            json!({"text": "{{ customer.name }}"}),
+            context.clone(),
+            strict(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, TemplateError::Undefined { path } if path == "customer"));
+
+        // Tests on undefined values are still allowed
+        let out = render(
+            json!({"text": "{% if coupon %}{{ coupon }}{% endif %}{{ t('x') }}"}),
+            context,
+            strict(),
+        );
+        assert_eq!(out.unwrap()["text"], "x");
+    }
+
+    #[test]
+    fn fuel_limit_stops_runaway_loops() {
+        let options = RenderOptions {
+            fuel: 1_000,
+            ..RenderOptions::default()
+        };
+        let err = render(
+            json!({"text": "{% for i in range(100000) %}{{ i }}{% endfor %}"}),
+            json!({}),
+            options,
+        )
+        .unwrap_err();
+        assert!(matches!(err, TemplateError::OutOfFuel { limit: 1_000 }));
+    }
+
+    #[test]
+    fn recursion_limit_stops_deep_nesting() {
+        let options = RenderOptions {
+            max_recursion: 3,
+            ..RenderOptions::default()
+        };
+        let source = "{% for a in x %}{% for b in x %}{% for c in x %}{% for d in x %}.\
+                      {% endfor %}{% endfor %}{% endfor %}{% endfor %}";
+        let err = render(json!({"text": source}), json!({"x": [1]}), options).unwrap_err();
+        assert!(matches!(err, TemplateError::RecursionLimit { limit: 3 }));
+    }
+
+    #[test]
+    fn output_size_limit() {
+        let options = RenderOptions {
+            max_output_bytes: 16,
+            ..RenderOptions::default()
+        };
+        let err = render(json!({"text": "{{ 'x' * 17 }}"}), json!({}), options).unwrap_err();
+        assert!(matches!(err, TemplateError::OutputTooLarge { limit: 16 }));
+        let out = render(json!({"text": "{{ 'x' * 16 }}"}), json!({}), options);
+        assert_eq!(out.unwrap()["text"], "x".repeat(16));
+    }
+
+    #[test]
+    fn from_settings_falls_back_per_field() {
+        let options = RenderOptions::from_settings(&json!({"render": {"strict_undefined": true}}));
+        assert_eq!(options, strict());
+        assert_eq!(
+            RenderOptions::from_settings(&json!({})),
+            RenderOptions::default()
+        );
+
+        // A bad field keeps its default without resetting the others
+        let options = RenderOptions::from_settings(&json!({
+            "render": {"strict_undefined": true, "fuel": "lots", "max_recursion": 0, "max_output_bytes": 64}
+        }));
+        assert_eq!(
+            options,
+            RenderOptions {
+                max_output_bytes: 64,
+                ..strict()
+            }
+        );
+    }
+
+    #[test]
+    fn validate_rejects_zero_limits_and_unknown_fields() {
+        assert!(RenderOptions::default().validate().is_ok());
+        let options = RenderOptions {
+            max_recursion: 0,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            options.validate().unwrap_err(),
+            "max_recursion must be greater than 0"
+        );
+        assert!(serde_json::from_value::<RenderOptions>(json!({"strict_undefind": true})).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! Time zone resolution for template date filters.
+//!
+//! Supports `UTC`, fixed offsets (`+02:00`, `-0530`, `UTC+3`) and IANA names
+//! from the tz database bundled by `chrono-tz`.
+
+use chrono::{DateTime, FixedOffset, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Zone {
+    Fixed(FixedOffset),
+    Iana(Tz),
+}
+
+impl Zone {
+    pub(crate) fn utc() -> Self {
+        Zone::Fixed(FixedOffset::east_opt(0).expect("zero offset"))
+    }
+
+    /// UTC offset in effect at the given instant.
+    pub(crate) fn offset_at(&self, instant: DateTime<Utc>) -> FixedOffset {
+        match self {
+            Zone::Fixed(offset) => *offset,
+            Zone::Iana(tz) => tz.offset_from_utc_datetime(&instant.naive_utc()).fix(),
+        }
+    }
+}
+
+/// Resolve a zone name; `None` when it is neither an offset nor a known
+/// IANA zone.
+pub(crate) fn resolve(name: &str) -> Option<Zone> {
+    let name = name.trim();
+    if name.is_empty() || name.eq_ignore_ascii_case("utc") || name.eq_ignore_ascii_case("z") {
+        return Some(Zone::utc());
+    }
+    if let Some(offset) = parse_offset(name) {
+        return Some(Zone::Fixed(offset));
+    }
+    name.parse::<Tz>().ok().map(Zone::Iana)
+}
+
+/// `+02:00`, `-0530`, `+3`, optionally prefixed with `UTC`/`GMT`.
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    let s = s
+        .strip_prefix("UTC")
+        .or_else(|| s.strip_prefix("GMT"))
+        .unwrap_or(s);
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    if hours.is_empty() || hours.len() > 2 || minutes.is_empty() || minutes.len() > 2 {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 18 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset(zone: &str, at: DateTime<Utc>) -> Option<i32> {
+        resolve(zone).map(|z| z.offset_at(at).local_minus_utc())
+    }
+
+    #[test]
+    fn fixed_offsets_and_unknown_zones() {
+        let at = Utc.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        assert_eq!(offset("+02:00", at), Some(7200));
+        assert_eq!(offset("UTC-0530", at), Some(-19800));
+        assert_eq!(offset("GMT+3", at), Some(10800));
+        assert_eq!(offset("Mars/Olympus", at), None);
+        assert_eq!(offset("../../etc/passwd", at), None);
+    }
+
+    #[test]
+    fn iana_zone_follows_dst() {
+        let winter = Utc.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        let summer = Utc.with_ymd_and_hms(2026, 7, 5, 12, 0, 0).unwrap();
+        assert_eq!(offset("Europe/Berlin", winter), Some(3600));
+        assert_eq!(offset("Europe/Berlin", summer), Some(7200));
+
+        // Far past the last explicit transition, DST still follows the rules.
+        let future = Utc.with_ymd_and_hms(2090, 7, 5, 12, 0, 0).unwrap();
+        assert_eq!(offset("America/New_York", future), Some(-4 * 3600));
+    }
+}
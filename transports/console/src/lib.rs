@@ -67,6 +67,7 @@ mod tests {
             content: serde_json::json!({"text": "Hello, world!"}),
             credentials: serde_json::json!({}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await.unwrap();
@@ -171,6 +171,7 @@ mod tests {
             content: serde_json::json!({"text": "Hello!"}),
             credentials: serde_json::json!({}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
@@ -186,6 +187,7 @@ mod tests {
             content: serde_json::json!({}),
             credentials: serde_json::json!({"bot_token": "test-token"}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
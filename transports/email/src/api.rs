@@ -0,0 +1,679 @@
+//! HTTP API providers for the email channel, picked with the `provider`
+//! credential. SES and Mailgun accept the same raw MIME message the SMTP
+//! path sends (DKIM-signed when configured); SendGrid and Postmark get the
+//! parsed parts as JSON and sign with their own domain keys.
+//!
+//! Replies map to [`DeliveryResult`]: 2xx is delivered with the provider's
+//! message id; throttling (HTTP 429) and server errors are retryable, other
+//! rejections are not. SES and Postmark also report an error code, and the
+//! codes for quotas and maintenance are retryable whatever the status.
+//! SendGrid and Mailgun only have the status to go by.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use lettre::message::{Mailbox, Mailboxes};
+use reqwest::header::HeaderMap;
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use notifico_core::error::CoreError;
+use notifico_core::sigv4;
+use notifico_core::transport::{Attachment, AttachmentDisposition, DeliveryResult};
+
+use crate::{Email, signed_mime};
+
+/// Amazon SES v2 `SendEmail` with raw content, signed with SigV4.
+pub(crate) async fn send_ses(
+    client: &Client,
+    creds: &Value,
+    email: &Email<'_>,
+) -> Result<DeliveryResult, CoreError> {
+    let access_key_id = credential(creds, "aws_access_key_id")?;
+    let secret_access_key = credential(creds, "aws_secret_access_key")?;
+    let region = credential(creds, "aws_region")?;
+    let base = base_url(creds, || format!("https://email.{region}.amazonaws.com"));
+    let url = reqwest::Url::parse(&format!("{base}/v2/email/outbound-emails"))
+        .map_err(|e| CoreError::InvalidConfig(format!("Invalid api_base_url: {e}")))?;
+
+    // Bcc is not in the MIME headers, so the destination is explicit
+    let raw = signed_mime(email, creds)?.formatted();
+    let body = serde_json::to_vec(&json!({
+        "Destination": {
+            "ToAddresses": [email.to.email.to_string()],
+            "CcAddresses": addresses(&email.cc),
+            "BccAddresses": addresses(&email.bcc),
+        },
+        "Content": {"Raw": {"Data": BASE64.encode(raw)}},
+    }))
+    .map_err(|e| CoreError::Transport(format!("SES request encoding error: {e}")))?;
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let authorization = sigv4::authorization(
+        "POST",
+        url.path(),
+        &[
+            ("content-type", "application/json"),
+            ("host", &host),
+            ("x-amz-date", &amz_date),
+        ],
+        &hex::encode(Sha256::digest(&body)),
+        &sigv4::SigningKey {
+            access_key_id,
+            secret_access_key,
+            region,
+            service: "ses",
+        },
+        &amz_date,
+    );
+
+    let request = client
+        .post(url)
+        .header("content-type", "application/json")
+        .header("x-amz-date", amz_date)
+        .header("authorization", authorization)
+        .body(body);
+    let (status, headers, body) = call(request, "SES").await?;
+    if status.is_success() {
+        return Ok(delivered(body["MessageId"].as_str()));
+    }
+
+    // e.g. "TooManyRequestsException:http://internal.amazon.com/..."
+    let error_type = headers
+        .get("x-amzn-errortype")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(':').next())
+        .unwrap_or_default();
+    let retryable = matches!(
+        error_type,
+        "TooManyRequestsException" | "LimitExceededException"
+    );
+    let message = body["message"].as_str().or(body["Message"].as_str());
+    Ok(failed("SES", status, message, retryable))
+}
+
+/// SendGrid v3 `mail/send`.
+pub(crate) async fn send_sendgrid(
+    client: &Client,
+    creds: &Value,
+    email: &Email<'_>,
+) -> Result<DeliveryResult, CoreError> {
+    let api_key = credential(creds, "api_key")?;
+    let base = base_url(creds, || "https://api.sendgrid.com".into());
+
+    let sendgrid_address = |mailbox: &Mailbox| match &mailbox.name {
+        Some(name) => json!({"email": mailbox.email.to_string(), "name": name}),
+        None => json!({"email": mailbox.email.to_string()}),
+    };
+    let sendgrid_addresses =
+        |mailboxes: &Mailboxes| mailboxes.iter().map(sendgrid_address).collect::<Vec<_>>();
+
+    let mut personalization = json!({"to": [sendgrid_address(&email.to)]});
+    if email.cc.iter().next().is_some() {
+        personalization["cc"] = sendgrid_addresses(&email.cc).into();
+    }
+    if email.bcc.iter().next().is_some() {
+        personalization["bcc"] = sendgrid_addresses(&email.bcc).into();
+    }
+    let mut content = vec![json!({"type": "text/plain", "value": email.text})];
+    if let Some(html) = email.html {
+        content.push(json!({"type": "text/html", "value": html}));
+    }
+    let mut body = json!({
+        "personalizations": [personalization],
+        "from": sendgrid_address(&email.from),
+        "subject": email.subject,
+        "content": content,
+    });
+    if email.reply_to.iter().next().is_some() {
+        body["reply_to_list"] = sendgrid_addresses(&email.reply_to).into();
+    }
+    if !email.headers.is_empty() {
+        body["headers"] = email
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::from(value.as_str())))
+            .collect::<serde_json::Map<_, _>>()
+            .into();
+    }
+    if !email.attachments.is_empty() {
+        body["attachments"] = email
+            .attachments
+            .iter()
+            .map(|a| {
+                let mut attachment = json!({
+                    "content": BASE64.encode(&a.data),
+                    "filename": a.filename,
+                    "type": a.content_type,
+                    "disposition": "attachment",
+                });
+                if let Some(cid) = inline_content_id(email, a) {
+                    attachment["disposition"] = "inline".into();
+                    attachment["content_id"] = cid.into();
+                }
+                attachment
+            })
+            .collect::<Vec<_>>()
+            .into();
+    }
+
+    let request = client
+        .post(format!("{base}/v3/mail/send"))
+        .bearer_auth(api_key)
+        .json(&body);
+    let (status, headers, body) = call(request, "SendGrid").await?;
+    if status.is_success() {
+        let id = headers.get("x-message-id").and_then(|v| v.to_str().ok());
+        return Ok(delivered(id));
+    }
+    let message = body["errors"][0]["message"].as_str();
+    Ok(failed("SendGrid", status, message, false))
+}
+
+/// Mailgun `messages.mime`: the raw message plus the envelope recipients.
+pub(crate) async fn send_mailgun(
+    client: &Client,
+    creds: &Value,
+    email: &Email<'_>,
+) -> Result<DeliveryResult, CoreError> {
+    let api_key = credential(creds, "api_key")?;
+    let domain = credential(creds, "mailgun_domain")?;
+    let base = base_url(creds, || "https://api.mailgun.net".into());
+
+    let to = email
+        .recipients()
+        .map(|m| m.email.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let raw = signed_mime(email, creds)?.formatted();
+    let form = Form::new()
+        .text("to", to)
+        .part("message", Part::bytes(raw).file_name("message.mime"));
+
+    let request = client
+        .post(format!("{base}/v3/{domain}/messages.mime"))
+        .basic_auth("api", Some(api_key))
+        .multipart(form);
+    let (status, _, body) = call(request, "Mailgun").await?;
+    if status.is_success() {
+        // Mailgun returns the Message-ID in angle brackets
+        let id = body["id"].as_str().map(|id| id.trim_matches(['<', '>']));
+        return Ok(delivered(id));
+    }
+    let message = body["message"].as_str();
+    Ok(failed("Mailgun", status, message, false))
+}
+
+/// Postmark `email` endpoint.
+pub(crate) async fn send_postmark(
+    client: &Client,
+    creds: &Value,
+    email: &Email<'_>,
+) -> Result<DeliveryResult, CoreError> {
+    let server_token = credential(creds, "api_key")?;
+    let base = base_url(creds, || "https://api.postmarkapp.com".into());
+
+    let mut body = json!({
+        "From": email.from.to_string(),
+        "To": email.to.to_string(),
+        "Subject": email.subject,
+        "TextBody": email.text,
+    });
+    if let Some(html) = email.html {
+        body["HtmlBody"] = html.into();
+    }
+    for (field, mailboxes) in [
+        ("Cc", &email.cc),
+        ("Bcc", &email.bcc),
+        ("ReplyTo", &email.reply_to),
+    ] {
+        if mailboxes.iter().next().is_some() {
+            body[field] = mailboxes.to_string().into();
+        }
+    }
+    if !email.headers.is_empty() {
+        body["Headers"] = email
+            .headers
+            .iter()
+            .map(|(name, value)| json!({"Name": name.to_string(), "Value": value}))
+            .collect::<Vec<_>>()
+            .into();
+    }
+    if !email.attachments.is_empty() {
+        body["Attachments"] = email
+            .attachments
+            .iter()
+            .map(|a| {
+                let mut attachment = json!({
+                    "Name": a.filename,
+                    "Content": BASE64.encode(&a.data),
+                    "ContentType": a.content_type,
+                });
+                if let Some(cid) = inline_content_id(email, a) {
+                    attachment["ContentID"] = format!("cid:{cid}").into();
+                }
+                attachment
+            })
+            .collect::<Vec<_>>()
+            .into();
+    }
+
+    let request = client
+        .post(format!("{base}/email"))
+        .header("X-Postmark-Server-Token", server_token)
+        .header("Accept", "application/json")
+        .json(&body);
+    let (status, _, body) = call(request, "Postmark").await?;
+    // Postmark reports some rejections with HTTP 200 and a non-zero ErrorCode
+    if status.is_success() && body["ErrorCode"].as_i64().unwrap_or(0) == 0 {
+        return Ok(delivered(body["MessageID"].as_str()));
+    }
+    // 100: server maintenance, 405: out of credits until the account is topped up
+    let retryable = matches!(body["ErrorCode"].as_i64(), Some(100 | 405));
+    let message = body["Message"].as_str();
+    Ok(failed("Postmark", status, message, retryable))
+}
+
+fn credential<'a>(creds: &'a Value, name: &str) -> Result<&'a str, CoreError> {
+    creds
+        .get(name)
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| CoreError::InvalidConfig(format!("Missing {name} credential")))
+}
+
+/// `api_base_url` when set (regional endpoints such as Mailgun EU), else
+/// the provider's default.
+fn base_url(creds: &Value, default: impl FnOnce() -> String) -> String {
+    creds
+        .get("api_base_url")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(default)
+}
+
+fn addresses(mailboxes: &Mailboxes) -> Vec<String> {
+    mailboxes.iter().map(|m| m.email.to_string()).collect()
+}
+
+/// The content id an attachment is embedded under, mirroring the MIME
+/// layout: inline parts only make sense next to an HTML body.
+fn inline_content_id<'a>(email: &Email<'_>, attachment: &'a Attachment) -> Option<&'a str> {
+    attachment
+        .content_id
+        .as_deref()
+        .filter(|_| email.html.is_some())
+        .filter(|_| matches!(attachment.disposition, AttachmentDisposition::Inline))
+}
+
+/// Send the request and read the reply. Bodies that are not JSON (some
+/// gateway errors) come back as a JSON string.
+async fn call(
+    request: RequestBuilder,
+    provider: &str,
+) -> Result<(StatusCode, HeaderMap, Value), CoreError> {
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| CoreError::Transport(format!("{provider} HTTP error: {e}")))?;
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| CoreError::Transport(format!("{provider} response read error: {e}")))?;
+    let body = serde_json::from_slice(&bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+    Ok((status, headers, body))
+}
+
+fn delivered(provider_message_id: Option<&str>) -> DeliveryResult {
+    tracing::info!(provider_message_id, "Email accepted by provider");
+    DeliveryResult::Delivered {
+        provider_message_id: provider_message_id.map(str::to_string),
+    }
+}
+
+/// A rejection. Throttling and server errors are always retryable;
+/// `retryable` adds provider error codes that are.
+fn failed(
+    provider: &str,
+    status: StatusCode,
+    message: Option<&str>,
+    retryable: bool,
+) -> DeliveryResult {
+    let error = match message {
+        Some(message) => format!("{provider} error (HTTP {}): {message}", status.as_u16()),
+        None => format!("{provider} API error (HTTP {})", status.as_u16()),
+    };
+    let retryable =
+        retryable || status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+    tracing::warn!(
+        provider,
+        status = %status.as_u16(),
+        error = %error,
+        retryable = retryable,
+        "Email API delivery failed"
+    );
+    DeliveryResult::Failed { error, retryable }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::Router;
+    use axum::body::Bytes;
+    use axum::http::{self, Uri};
+    use axum::response::IntoResponse;
+    use notifico_core::channel::ChannelId;
+    use notifico_core::transport::{RenderedMessage, Transport};
+
+    use super::*;
+    use crate::EmailTransport;
+
+    #[derive(Default)]
+    struct Captured {
+        path: String,
+        headers: http::HeaderMap,
+        body: Vec<u8>,
+    }
+
+    /// A provider stand-in that records the request and answers every one
+    /// with `status`, `headers` and `body`. Returns its base URL.
+    async fn mock_api(
+        status: u16,
+        headers: &'static [(&'static str, &'static str)],
+        body: &'static str,
+    ) -> (String, Arc<Mutex<Captured>>) {
+        let captured = Arc::new(Mutex::new(Captured::default()));
+        let app = Router::new().fallback({
+            let captured = captured.clone();
+            move |uri: Uri, request_headers: http::HeaderMap, request_body: Bytes| async move {
+                *captured.lock().unwrap() = Captured {
+                    path: uri.path().into(),
+                    headers: request_headers,
+                    body: request_body.to_vec(),
+                };
+                let mut response =
+                    (http::StatusCode::from_u16(status).unwrap(), body).into_response();
+                for (name, value) in headers {
+                    response
+                        .headers_mut()
+                        .insert(*name, http::HeaderValue::from_static(value));
+                }
+                response
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), captured)
+    }
+
+    fn message(credentials: Value) -> RenderedMessage {
+        RenderedMessage {
+            channel: ChannelId::new("email"),
+            recipient_contact: "Rita <rcpt@example.com>".into(),
+            content: json!({
+                "subject": "Hi",
+                "text": "Hello",
+                "html": "<p>Hello <img src=\"cid:logo\"></p>",
+                "cc": "Carl <cc@example.com>",
+                "bcc": "bcc@example.com",
+                "reply_to": "replies@example.com",
+                "headers": {"X-Campaign": "october"},
+            }),
+            credentials,
+            attachments: vec![
+                Attachment {
+                    filename: "logo.png".into(),
+                    content_type: "image/png".into(),
+                    data: b"png".to_vec(),
+                    disposition: AttachmentDisposition::Inline,
+                    content_id: Some("logo".into()),
+                },
+                Attachment {
+                    filename: "invoice.pdf".into(),
+                    content_type: "application/pdf".into(),
+                    data: b"pdf".to_vec(),
+                    disposition: AttachmentDisposition::Attachment,
+                    content_id: None,
+                },
+            ],
+            metadata: Default::default(),
+        }
+    }
+
+    fn credentials(provider: &str, base: &str) -> Value {
+        json!({
+            "provider": provider,
+            "api_base_url": base,
+            "from_address": "noreply@example.com",
+            "from_name": "Shop",
+            "api_key": "key-123",
+            "mailgun_domain": "mg.example.com",
+            "aws_access_key_id": "AKID",
+            "aws_secret_access_key": "secret",
+            "aws_region": "us-east-1",
+        })
+    }
+
+    async fn send(provider: &str, base: &str) -> Result<DeliveryResult, CoreError> {
+        EmailTransport::new()
+            .send(&message(credentials(provider, base)))
+            .await
+    }
+
+    fn delivered_id(result: DeliveryResult) -> Option<String> {
+        match result {
+            DeliveryResult::Delivered {
+                provider_message_id,
+            } => provider_message_id,
+            other => panic!("expected delivery, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn sendgrid_request_and_message_id() {
+        let (base, captured) = mock_api(202, &[("x-message-id", "sg-1")], "").await;
+        let id = delivered_id(send("sendgrid", &base).await.unwrap());
+        assert_eq!(id.as_deref(), Some("sg-1"));
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(captured.path, "/v3/mail/send");
+        assert_eq!(captured.headers["authorization"], "Bearer key-123");
+        let body: Value = serde_json::from_slice(&captured.body).unwrap();
+        assert_eq!(
+            body["personalizations"][0],
+            json!({
+                "to": [{"email": "rcpt@example.com", "name": "Rita"}],
+                "cc": [{"email": "cc@example.com", "name": "Carl"}],
+                "bcc": [{"email": "bcc@example.com"}],
+            })
+        );
+        assert_eq!(
+            body["from"],
+            json!({"email": "noreply@example.com", "name": "Shop"})
+        );
+        assert_eq!(
+            body["reply_to_list"],
+            json!([{"email": "replies@example.com"}])
+        );
+        assert_eq!(body["content"][0]["type"], "text/plain");
+        assert_eq!(body["content"][1]["type"], "text/html");
+        assert_eq!(body["headers"], json!({"X-Campaign": "october"}));
+        assert_eq!(body["attachments"][0]["disposition"], "inline");
+        assert_eq!(body["attachments"][0]["content_id"], "logo");
+        assert_eq!(body["attachments"][1]["content"], BASE64.encode("pdf"));
+        assert_eq!(body["attachments"][1]["disposition"], "attachment");
+    }
+
+    #[tokio::test]
+    async fn postmark_request_and_error_codes() {
+        let ok = r#"{"ErrorCode": 0, "Message": "OK", "MessageID": "pm-1"}"#;
+        let (base, captured) = mock_api(200, &[], ok).await;
+        let id = delivered_id(send("postmark", &base).await.unwrap());
+        assert_eq!(id.as_deref(), Some("pm-1"));
+
+        {
+            let captured = captured.lock().unwrap();
+            assert_eq!(captured.path, "/email");
+            assert_eq!(captured.headers["x-postmark-server-token"], "key-123");
+            let body: Value = serde_json::from_slice(&captured.body).unwrap();
+            assert_eq!(body["From"], "Shop <noreply@example.com>");
+            assert_eq!(body["To"], "Rita <rcpt@example.com>");
+            assert_eq!(body["Cc"], "Carl <cc@example.com>");
+            assert_eq!(body["Bcc"], "bcc@example.com");
+            assert_eq!(body["ReplyTo"], "replies@example.com");
+            assert_eq!(body["TextBody"], "Hello");
+            assert_eq!(
+                body["Headers"],
+                json!([{"Name": "X-Campaign", "Value": "october"}])
+            );
+            assert_eq!(body["Attachments"][0]["ContentID"], "cid:logo");
+            assert!(body["Attachments"][1].get("ContentID").is_none());
+        }
+
+        let inactive = r#"{"ErrorCode": 406, "Message": "Inactive recipient"}"#;
+        let (base, _) = mock_api(422, &[], inactive).await;
+        match send("postmark", &base).await.unwrap() {
+            DeliveryResult::Failed { error, retryable } => {
+                assert!(error.contains("Inactive recipient"));
+                assert!(!retryable);
+            }
+            other => panic!("expected failure, got {other:?}"),
+        }
+
+        let credits = r#"{"ErrorCode": 405, "Message": "You have run out of credits"}"#;
+        let (base, _) = mock_api(422, &[], credits).await;
+        match send("postmark", &base).await.unwrap() {
+            DeliveryResult::Failed { retryable, .. } => assert!(retryable),
+            other => panic!("expected failure, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn mailgun_sends_raw_mime_to_all_recipients() {
+        let reply = r#"{"id": "<mg-1@mg.example.com>", "message": "Queued. Thank you."}"#;
+        let (base, captured) = mock_api(200, &[], reply).await;
+        let id = delivered_id(send("mailgun", &base).await.unwrap());
+        assert_eq!(id.as_deref(), Some("mg-1@mg.example.com"));
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(captured.path, "/v3/mg.example.com/messages.mime");
+        assert_eq!(
+            captured.headers["authorization"],
+            format!("Basic {}", BASE64.encode("api:key-123"))
+        );
+        let form = String::from_utf8_lossy(&captured.body);
+        assert!(form.contains("rcpt@example.com,cc@example.com,bcc@example.com"));
+        assert!(form.contains("Subject: Hi\r\n"));
+        assert!(form.contains("X-Campaign: october\r\n"));
+        assert!(!form.contains("Bcc:"));
+    }
+
+    #[tokio::test]
+    async fn ses_sends_signed_raw_request() {
+        let (base, captured) = mock_api(200, &[], r#"{"MessageId": "ses-1"}"#).await;
+        let id = delivered_id(send("ses", &base).await.unwrap());
+        assert_eq!(id.as_deref(), Some("ses-1"));
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(captured.path, "/v2/email/outbound-emails");
+        let body: Value = serde_json::from_slice(&captured.body).unwrap();
+        assert_eq!(
+            body["Destination"],
+            json!({
+                "ToAddresses": ["rcpt@example.com"],
+                "CcAddresses": ["cc@example.com"],
+                "BccAddresses": ["bcc@example.com"],
+            })
+        );
+        let raw = BASE64
+            .decode(body["Content"]["Raw"]["Data"].as_str().unwrap())
+            .unwrap();
+        let raw = String::from_utf8(raw).unwrap();
+        assert!(raw.contains("Subject: Hi\r\n"));
+        assert!(raw.contains("multipart/related"));
+
+        // The signature covers the headers that were actually sent
+        let header = |name: &str| captured.headers[name].to_str().unwrap();
+        let expected = sigv4::authorization(
+            "POST",
+            "/v2/email/outbound-emails",
+            &[
+                ("content-type", header("content-type")),
+                ("host", header("host")),
+                ("x-amz-date", header("x-amz-date")),
+            ],
+            &hex::encode(Sha256::digest(&captured.body)),
+            &sigv4::SigningKey {
+                access_key_id: "AKID",
+                secret_access_key: "secret",
+                region: "us-east-1",
+                service: "ses",
+            },
+            header("x-amz-date"),
+        );
+        assert_eq!(header("authorization"), expected);
+    }
+
+    #[tokio::test]
+    async fn throttling_and_server_errors_are_retryable() {
+        let retryable = |result: DeliveryResult| match result {
+            DeliveryResult::Failed { retryable, .. } => retryable,
+            other => panic!("expected failure, got {other:?}"),
+        };
+
+        let (base, _) = mock_api(429, &[], r#"{"errors": [{"message": "slow down"}]}"#).await;
+        assert!(retryable(send("sendgrid", &base).await.unwrap()));
+
+        let (base, _) = mock_api(503, &[], "Service Unavailable").await;
+        assert!(retryable(send("mailgun", &base).await.unwrap()));
+
+        let invalid = r#"{"message": "to parameter is not a valid address"}"#;
+        let (base, _) = mock_api(400, &[], invalid).await;
+        assert!(!retryable(send("mailgun", &base).await.unwrap()));
+
+        let bad = r#"{"errors": [{"message": "The from address does not match"}]}"#;
+        let (base, _) = mock_api(403, &[], bad).await;
+        match send("sendgrid", &base).await.unwrap() {
+            DeliveryResult::Failed { error, retryable } => {
+                assert!(error.contains("HTTP 403"));
+                assert!(error.contains("does not match"));
+                assert!(!retryable);
+            }
+            other => panic!("expected failure, got {other:?}"),
+        }
+
+        let quota = &[("x-amzn-errortype", "LimitExceededException:http://internal")];
+        let (base, _) = mock_api(400, quota, r#"{"message": "Daily quota exceeded"}"#).await;
+        assert!(retryable(send("ses", &base).await.unwrap()));
+
+        let rejected = &[("x-amzn-errortype", "MessageRejected:http://internal")];
+        let (base, _) = mock_api(
+            400,
+            rejected,
+            r#"{"message": "Email address is not verified"}"#,
+        )
+        .await;
+        assert!(!retryable(send("ses", &base).await.unwrap()));
+    }
+
+    #[tokio::test]
+    async fn invalid_provider_configuration() {
+        assert!(send("sparkpost", "http://127.0.0.1:9").await.is_err());
+
+        let mut creds = credentials("sendgrid", "http://127.0.0.1:9");
+        creds["api_key"] = "".into();
+        let transport = EmailTransport::new();
+        assert!(transport.send(&message(creds)).await.is_err());
+    }
+}
@@ -365,6 +365,7 @@ mod tests {
             content: serde_json::json!({"title": "Hello", "body": "World"}),
             credentials: serde_json::json!({}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
@@ -174,6 +174,7 @@ mod tests {
             content: serde_json::json!({"text": "Hello!"}),
             credentials: serde_json::json!({}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
@@ -189,6 +190,7 @@ mod tests {
             content: serde_json::json!({}),
             credentials: serde_json::json!({"bot_token": "xoxb-test"}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
@@ -179,6 +179,7 @@ mod tests {
             content: serde_json::json!({"text": "Hello!"}),
             credentials: serde_json::json!({}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
@@ -191,6 +191,7 @@ mod tests {
             content: serde_json::json!({"text": "Hello!"}),
             credentials: serde_json::json!({}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
@@ -210,6 +211,7 @@ mod tests {
                 "from_number": "+15559876543"
             }),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
@@ -352,6 +352,7 @@ mod tests {
                 "subject": "mailto:test@example.com"
             }),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;
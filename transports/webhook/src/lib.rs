@@ -198,6 +198,7 @@ mod tests {
             content: serde_json::json!({"body": {"event": "test"}}),
             credentials: serde_json::json!({}),
             attachments: vec![],
+            metadata: Default::default(),
         };
 
         let result = transport.send(&message).await;